@@ -0,0 +1,9 @@
+(program) @local.scope
+
+(variable_assignment name: (variable_name) @local.definition)
+(declaration_command (variable_name) @local.definition)
+(for_statement variable: (variable_name) @local.definition)
+(function_definition name: (word) @local.definition)
+
+(variable_name) @local.reference
+(command_name (word) @local.reference)
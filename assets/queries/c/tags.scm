@@ -0,0 +1,5 @@
+(function_definition
+  declarator: (function_declarator declarator: (_) @name)) @definition.function
+(struct_specifier name: (type_identifier) @name body: (_)) @definition.struct
+(enum_specifier name: (type_identifier) @name body: (_)) @definition.enum
+(type_definition declarator: (type_identifier) @name) @definition.type
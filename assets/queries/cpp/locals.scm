@@ -0,0 +1,15 @@
+[
+  (compound_statement)
+  (function_definition)
+  (for_statement)
+] @local.scope
+
+(declaration declarator: (identifier) @local.definition)
+(init_declarator declarator: (identifier) @local.definition)
+(pointer_declarator declarator: (identifier) @local.definition)
+(array_declarator declarator: (identifier) @local.definition)
+(parameter_declaration declarator: (identifier) @local.definition)
+
+(identifier) @local.reference
+(type_identifier) @local.reference
+(field_identifier) @local.reference
@@ -0,0 +1,7 @@
+(namespace_definition name: (_) @name) @definition.module
+(class_specifier name: (type_identifier) @name body: (_)) @definition.class
+(struct_specifier name: (type_identifier) @name body: (_)) @definition.struct
+(enum_specifier name: (type_identifier) @name body: (_)) @definition.enum
+(function_definition
+  declarator: (function_declarator declarator: (_) @name)) @definition.function
+(type_definition declarator: (type_identifier) @name) @definition.type
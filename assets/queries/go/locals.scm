@@ -0,0 +1,18 @@
+[
+  (block)
+  (function_declaration)
+  (method_declaration)
+  (func_literal)
+  (for_statement)
+  (if_statement)
+] @local.scope
+
+(short_var_declaration left: (expression_list (identifier) @local.definition))
+(var_spec name: (identifier) @local.definition)
+(const_spec name: (identifier) @local.definition)
+(parameter_declaration name: (identifier) @local.definition)
+(range_clause left: (expression_list (identifier) @local.definition))
+
+(identifier) @local.reference
+(type_identifier) @local.reference
+(field_identifier) @local.reference
@@ -0,0 +1,7 @@
+(class_declaration name: (_) @name) @definition.class
+(method_definition name: (_) @name) @definition.method
+(function_declaration name: (identifier) @name) @definition.function
+(generator_function_declaration name: (identifier) @name) @definition.function
+(variable_declarator
+  name: (identifier) @name
+  value: [(arrow_function) (function_expression)]) @definition.function
@@ -0,0 +1,15 @@
+[
+  (chunk)
+  (block)
+  (function_declaration)
+  (function_definition)
+  (for_statement)
+] @local.scope
+
+(variable_declaration (variable_list (identifier) @local.definition))
+(variable_declaration (assignment_statement (variable_list (identifier) @local.definition)))
+(for_generic_clause (variable_list (identifier) @local.definition))
+(for_numeric_clause name: (identifier) @local.definition)
+(parameters name: (identifier) @local.definition)
+
+(identifier) @local.reference
@@ -0,0 +1 @@
+(function_declaration name: (_) @name) @definition.function
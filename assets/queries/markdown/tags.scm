@@ -0,0 +1 @@
+(section (atx_heading heading_content: (inline) @name)) @definition.heading
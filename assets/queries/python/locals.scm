@@ -0,0 +1,18 @@
+[
+  (module)
+  (function_definition)
+  (lambda)
+] @local.scope
+
+(assignment left: (identifier) @local.definition)
+(augmented_assignment left: (identifier) @local.definition)
+(parameters (identifier) @local.definition)
+(default_parameter name: (identifier) @local.definition)
+(typed_parameter (identifier) @local.definition)
+(typed_default_parameter name: (identifier) @local.definition)
+(lambda_parameters (identifier) @local.definition)
+(for_statement left: (identifier) @local.definition)
+(for_in_clause left: (identifier) @local.definition)
+(pattern_list (identifier) @local.definition)
+
+(identifier) @local.reference
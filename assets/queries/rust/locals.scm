@@ -0,0 +1,19 @@
+[
+  (block)
+  (function_item)
+  (closure_expression)
+  (for_expression)
+  (match_arm)
+] @local.scope
+
+(let_declaration pattern: (identifier) @local.definition)
+(let_declaration pattern: (mut_pattern (identifier) @local.definition))
+(tuple_pattern (identifier) @local.definition)
+(parameter pattern: (identifier) @local.definition)
+(parameter pattern: (mut_pattern (identifier) @local.definition))
+(closure_parameters (identifier) @local.definition)
+(for_expression pattern: (identifier) @local.definition)
+
+(identifier) @local.reference
+(type_identifier) @local.reference
+(field_identifier) @local.reference
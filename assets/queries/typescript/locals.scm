@@ -0,0 +1,16 @@
+[
+  (statement_block)
+  (function_expression)
+  (arrow_function)
+  (function_declaration)
+  (method_definition)
+] @local.scope
+
+(pattern/identifier) @local.definition
+(variable_declarator name: (identifier) @local.definition)
+(required_parameter (identifier) @local.definition)
+(optional_parameter (identifier) @local.definition)
+
+(identifier) @local.reference
+(property_identifier) @local.reference
+(type_identifier) @local.reference
@@ -0,0 +1,5 @@
+(document) @local.scope
+
+(anchor (anchor_name) @local.definition)
+
+(alias (alias_name) @local.reference)
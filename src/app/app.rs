@@ -2,16 +2,17 @@ use std::cell::RefCell;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
-use crate::config::Theme;
-use crate::core::{Document, History};
-use crate::file::{ExplorerState, FileFinder, FileWatcher, RecentFiles};
-use crate::lsp::LspWorkspace;
+use crate::config::{SaveConfig, SnippetLibrary, Theme};
+use crate::core::{Document, HexDocument, History};
+use crate::editor::{SnippetSession, WordIndex};
+use crate::file::{ExplorerState, FileFinder, FileWatcher, LoadedDocument, RecentFiles};
+use crate::lsp::{DocumentHighlights, EditOrigin, LspWorkspace, WorkspaceEditPlan};
 use crate::syntax::{
-    compute_folds, markdown_code_block_spans_for_line, spans_for_line, HighlightSpan, LanguageId,
-    LanguageRegistry, SyntaxLayer,
+    compute_folds, language_for_path, markdown_code_block_spans_for_line, spans_for_line,
+    HighlightSpan, LanguageId, LanguageRegistry, SyntaxLayer,
 };
-use crate::ui::{LayoutState, PickerState};
-use crate::util::{Clipboard, DetectedEncoding};
+use crate::ui::{HierarchyTree, LayoutState, OutlineState, OutlineSymbol, PickerState};
+use crate::util::{Clipboard, DetectedEncoding, LineEnding};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AppMode {
@@ -19,6 +20,8 @@ pub enum AppMode {
     ConfirmQuit,
     ConfirmDeleteExplorerEntry,
     ExternalChangeConflict,
+    /// A server edit that deletes a tree or overwrites a file awaits y/n.
+    ConfirmWorkspaceEdit,
     CommandBar(CommandBarMode),
 }
 
@@ -45,6 +48,8 @@ pub enum MessageKind {
 pub enum FocusTarget {
     Editor,
     Explorer,
+    Outline,
+    Hierarchy,
 }
 
 #[derive(Debug, Default)]
@@ -65,9 +70,73 @@ pub struct BufferState {
     pub history: History,
     pub saved_snapshot: String,
     pub encoding: DetectedEncoding,
+    /// Encoding and BOM of the file on disk, compared like the line ending.
+    pub saved_encoding: DetectedEncoding,
+    pub line_ending: LineEnding,
+    /// Line ending of the file on disk, so switching it counts as a change.
+    pub saved_line_ending: LineEnding,
+    pub hex: Option<HexDocument>,
+    /// Streamed from a file above `LARGE_FILE_THRESHOLD`; syntax, folds and
+    /// LSP stay off and no full-text snapshot is kept.
+    pub large_file: bool,
+    /// Title of a generated read-only view such as the LSP log.
+    pub scratch_name: Option<String>,
     pub syntax: SyntaxLayer,
     pub line_highlight_cache: RefCell<LineHighlightCache>,
     pub fold_cache: RefCell<FoldCache>,
+    pub outline_cache: RefCell<OutlineCache>,
+    pub locals_cache: RefCell<LocalsCache>,
+}
+
+impl BufferState {
+    /// Swaps in freshly loaded file contents, keeping the buffer id and history.
+    pub fn reload_from(&mut self, loaded: LoadedDocument) {
+        self.document = loaded.document;
+        self.encoding = loaded.encoding;
+        self.saved_encoding = loaded.encoding;
+        self.line_ending = loaded.line_ending;
+        self.saved_line_ending = loaded.line_ending;
+        self.hex = loaded.binary.map(HexDocument::from_bytes);
+        self.large_file = loaded.large_file;
+        self.history.mark_saved();
+        self.saved_snapshot = if self.large_file {
+            String::new()
+        } else {
+            self.document.text()
+        };
+        let language_id = self
+            .document
+            .path()
+            .and_then(language_for_path)
+            .filter(|_| !self.large_file);
+        let _ = self.syntax.set_language_id(language_id);
+        self.syntax.mark_dirty();
+    }
+
+    /// File name, scratch title or `[No Name]` for tabs and pickers.
+    pub fn display_name(&self) -> String {
+        if let Some(name) = &self.scratch_name {
+            return format!("[{name}]");
+        }
+        self.document
+            .path()
+            .and_then(|path| path.file_name())
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| String::from("[No Name]"))
+    }
+
+    /// Large files keep no snapshot, so they compare history revisions.
+    pub fn refresh_dirty(&mut self) {
+        let dirty = if self.large_file {
+            !self.history.is_at_saved()
+        } else {
+            self.document.text() != self.saved_snapshot
+        };
+        let dirty = dirty
+            || self.line_ending != self.saved_line_ending
+            || !self.encoding.saves_like(self.saved_encoding);
+        self.document.set_dirty(dirty);
+    }
 }
 
 #[derive(Default)]
@@ -82,6 +151,28 @@ pub struct FoldCache {
     folds: Vec<crate::syntax::FoldRange>,
 }
 
+/// Tags-query outline of a buffer, rebuilt when the syntax tree changes.
+#[derive(Default)]
+pub struct OutlineCache {
+    pub(crate) revision: u64,
+    pub(crate) symbols: Option<Vec<OutlineSymbol>>,
+}
+
+/// Highlighted detail and documentation of the selected completion item,
+/// rebuilt when the item's text, the language or the theme changes.
+#[derive(Default)]
+pub struct CompletionDocsCache {
+    pub(crate) key: Option<(String, String, Option<LanguageId>, String)>,
+    pub(crate) lines: Vec<ratatui::text::Line<'static>>,
+}
+
+/// Locals-query scopes of a buffer, rebuilt when the syntax tree changes.
+#[derive(Default)]
+pub struct LocalsCache {
+    pub(crate) revision: u64,
+    pub(crate) bindings: Option<crate::syntax::LocalBindings>,
+}
+
 pub struct App {
     pub(crate) workspace_root: PathBuf,
     pub(crate) buffers: Vec<BufferState>,
@@ -89,11 +180,17 @@ pub struct App {
     pub(crate) next_buffer_id: u64,
     pub(crate) layout: LayoutState,
     pub(crate) explorer: ExplorerState,
+    pub(crate) outline: OutlineState,
+    pub(crate) hierarchy: Option<HierarchyTree>,
+    pub(crate) document_highlights: DocumentHighlights,
+    pub(crate) completion_docs: RefCell<CompletionDocsCache>,
+    pub(crate) snippet: Option<SnippetSession>,
     pub(crate) file_finder: FileFinder,
     pub(crate) recent_files: RecentFiles,
     pub(crate) watcher: Option<FileWatcher>,
     pub(crate) picker: Option<PickerState>,
     pub(crate) pending_conflict_paths: Vec<PathBuf>,
+    pub(crate) pending_workspace_edit: Option<(WorkspaceEditPlan, EditOrigin)>,
     pub(crate) clipboard: Option<Clipboard>,
     pub(crate) focus: FocusTarget,
     pub(crate) mode: AppMode,
@@ -105,6 +202,9 @@ pub struct App {
     pub(crate) message: Option<Message>,
     pub(crate) command_bar: CommandBarState,
     pub(crate) active_theme: Theme,
+    pub(crate) save_config: SaveConfig,
+    pub(crate) snippets: SnippetLibrary,
+    pub(crate) words: WordIndex,
     pub(crate) lsp: LspWorkspace,
 }
 
@@ -114,7 +214,9 @@ impl App {
     }
 
     pub fn run(&mut self) -> Result<(), crate::app::AppError> {
-        crate::app::run_app(self)
+        let result = crate::app::run_app(self);
+        self.lsp.shutdown_all();
+        result
     }
 
     pub fn active_buffer(&self) -> &BufferState {
@@ -178,6 +280,14 @@ impl App {
         &self.explorer
     }
 
+    pub fn outline(&self) -> &OutlineState {
+        &self.outline
+    }
+
+    pub fn hierarchy(&self) -> Option<&HierarchyTree> {
+        self.hierarchy.as_ref()
+    }
+
     pub fn focus(&self) -> FocusTarget {
         self.focus
     }
@@ -205,6 +315,10 @@ impl App {
             if !buffer.syntax.is_dirty() {
                 continue;
             }
+            if buffer.syntax.language_id().is_none() {
+                let _ = buffer.syntax.reparse(&[]);
+                continue;
+            }
 
             let source = buffer.document.text();
             let _ = buffer.syntax.reparse(source.as_bytes());
@@ -227,7 +341,15 @@ impl App {
         ids
     }
 
+    /// Tree-sitter spans with semantic token spans after them, so the
+    /// server's tokens win where both colour the same text.
     pub fn syntax_spans_for_line(&self, buffer_id: u64, line_index: usize) -> Vec<HighlightSpan> {
+        let mut spans = self.tree_sitter_spans_for_line(buffer_id, line_index);
+        spans.extend(self.semantic_spans_for_line(buffer_id, line_index));
+        spans
+    }
+
+    fn tree_sitter_spans_for_line(&self, buffer_id: u64, line_index: usize) -> Vec<HighlightSpan> {
         let Some(buffer) = self.buffer_by_id(buffer_id) else {
             return Vec::new();
         };
@@ -1,7 +1,9 @@
+#[allow(clippy::module_inception)]
 mod app;
 mod error;
 mod startup;
 mod terminal;
+#[cfg(test)]
 mod tests;
 
 pub use crate::config::Theme;
@@ -14,10 +16,13 @@ pub(crate) use app::BufferState;
 pub use app::CommandBarMode;
 pub(crate) use app::CommandBarState;
 pub use app::FocusTarget;
+pub(crate) use app::CompletionDocsCache;
 pub(crate) use app::FoldCache;
 pub(crate) use app::LineHighlightCache;
+pub(crate) use app::LocalsCache;
 pub(crate) use app::Message;
 pub use app::MessageKind;
+pub(crate) use app::OutlineCache;
 pub use error::AppError;
 pub use startup::run;
 pub(crate) use terminal::TerminalSession;
@@ -39,7 +39,7 @@ impl TerminalSession {
 
 impl Drop for TerminalSession {
     fn drop(&mut self) {
-        let _ = ratatui::restore();
+        ratatui::restore();
         let _ = execute!(
             io::stdout(),
             DisableBracketedPaste,
@@ -1,45 +1,122 @@
-#[cfg(test)]
-mod tests {
-    use std::path::PathBuf;
+use std::path::PathBuf;
 
-    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
-    use ratatui::layout::Size;
+use ratatui::layout::Size;
 
-    use crate::app::{App, AppMode};
+use crate::app::{App, AppMode};
 
-    #[test]
-    fn dirty_document_requires_confirmation() {
-        let mut app = App::open(None).unwrap_or_else(|error| panic!("{error}"));
-        app.set_terminal_size(Size {
-            width: 120,
-            height: 30,
-        });
-        app.handle_key_event(KeyEvent::from(KeyCode::Char('a')))
-            .unwrap_or_else(|error| panic!("{error}"));
-        app.handle_key_event(KeyEvent::new(KeyCode::Char('q'), KeyModifiers::CONTROL))
-            .unwrap_or_else(|error| panic!("{error}"));
-        assert!(matches!(app.mode(), AppMode::ConfirmQuit));
-    }
+#[test]
+fn dirty_document_requires_confirmation() {
+    let mut app = App::open(None).unwrap_or_else(|error| panic!("{error}"));
+    app.set_terminal_size(Size {
+        width: 120,
+        height: 30,
+    });
+    app.handle_key_event(KeyEvent::from(KeyCode::Char('a')))
+        .unwrap_or_else(|error| panic!("{error}"));
+    app.handle_key_event(KeyEvent::new(KeyCode::Char('q'), KeyModifiers::CONTROL))
+        .unwrap_or_else(|error| panic!("{error}"));
+    assert!(matches!(app.mode(), AppMode::ConfirmQuit));
+}
 
-    #[test]
-    fn open_new_path_creates_unsaved_document() {
-        let app =
-            App::open(Some(PathBuf::from("new.txt"))).unwrap_or_else(|error| panic!("{error}"));
-        assert_eq!(
-            app.active_document()
-                .path()
-                .map(|path: &std::path::Path| path.to_string_lossy().into_owned()),
-            Some(String::from("new.txt"))
-        );
-        assert!(!app.active_document().is_dirty());
-    }
+#[test]
+fn open_new_path_creates_unsaved_document() {
+    let app =
+        App::open(Some(PathBuf::from("new.txt"))).unwrap_or_else(|error| panic!("{error}"));
+    assert_eq!(
+        app.active_document()
+            .path()
+            .map(|path: &std::path::Path| path.to_string_lossy().into_owned()),
+        Some(String::from("new.txt"))
+    );
+    assert!(!app.active_document().is_dirty());
+}
+
+#[test]
+fn command_mode_opens_from_colon() {
+    let mut app = App::open(None).unwrap_or_else(|error| panic!("{error}"));
+    app.handle_key_event(KeyEvent::from(KeyCode::Char(':')))
+        .unwrap_or_else(|error| panic!("{error}"));
+    assert!(matches!(app.mode(), AppMode::CommandBar(_)));
+}
+
+#[test]
+fn save_transforms_are_written_and_undoable() {
+    let suffix = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let path = std::env::temp_dir().join(format!("codx-save-{suffix}.txt"));
+    std::fs::write(&path, "keep  \nlast").unwrap_or_else(|error| panic!("{error}"));
+    let mut app = App::open(Some(path.clone())).unwrap_or_else(|error| panic!("{error}"));
+    app.save_config = crate::config::SaveConfig::parse(
+        "[save.default]\ntrim_trailing_whitespace = true\nfinal_newline = \"ensure\"\n",
+    );
 
-    #[test]
-    fn command_mode_opens_from_colon() {
-        let mut app = App::open(None).unwrap_or_else(|error| panic!("{error}"));
-        app.handle_key_event(KeyEvent::from(KeyCode::Char(':')))
+    app.save_to_path(&path)
+        .unwrap_or_else(|error| panic!("{error}"));
+    let written = std::fs::read_to_string(&path).unwrap_or_else(|error| panic!("{error}"));
+    assert_eq!(written, "keep\nlast\n");
+
+    app.undo();
+    app.undo();
+    assert_eq!(app.active_document().text(), "keep  \nlast");
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn text_edit_keys_leave_hex_buffers_alone() {
+    let suffix = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let path = std::env::temp_dir().join(format!("codx-hex-{suffix}.bin"));
+    std::fs::write(&path, [0x00, 0x01, 0x02, 0xff]).unwrap_or_else(|error| panic!("{error}"));
+    let mut app = App::open(Some(path.clone())).unwrap_or_else(|error| panic!("{error}"));
+    assert!(app.is_hex_view());
+
+    for key in ['v', 'x', 'z', 'y'] {
+        app.handle_key_event(KeyEvent::new(KeyCode::Char(key), KeyModifiers::CONTROL))
             .unwrap_or_else(|error| panic!("{error}"));
-        assert!(matches!(app.mode(), AppMode::CommandBar(_)));
     }
+    app.insert_text("text", false);
+    assert_eq!(app.active_document().text(), "");
+    assert!(!app.active_document().is_dirty());
+    assert!(app.active_buffer().history.last_applied().is_empty());
+    let bytes = app.active_buffer().hex.as_ref().map(|hex| hex.bytes().to_vec());
+    assert_eq!(bytes, Some(vec![0x00, 0x01, 0x02, 0xff]));
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn line_ending_change_stays_dirty_after_undoing_text() {
+    let mut app = App::open(None).unwrap_or_else(|error| panic!("{error}"));
+    app.set_line_ending(crate::util::LineEnding::Crlf);
+    app.handle_key_event(KeyEvent::from(KeyCode::Char('a')))
+        .unwrap_or_else(|error| panic!("{error}"));
+    app.handle_key_event(KeyEvent::from(KeyCode::Backspace))
+        .unwrap_or_else(|error| panic!("{error}"));
+    assert_eq!(app.active_document().text(), "");
+    assert!(app.active_document().is_dirty());
+}
+
+#[test]
+fn encoding_and_bom_changes_stay_dirty_until_reverted() {
+    let mut app = App::open(None).unwrap_or_else(|error| panic!("{error}"));
+    let latin1 = crate::util::DetectedEncoding::for_label("latin1")
+        .unwrap_or_else(|| panic!("latin1 is a known label"));
+    app.set_save_encoding(latin1);
+    app.handle_key_event(KeyEvent::from(KeyCode::Char('a')))
+        .unwrap_or_else(|error| panic!("{error}"));
+    app.handle_key_event(KeyEvent::from(KeyCode::Backspace))
+        .unwrap_or_else(|error| panic!("{error}"));
+    assert!(app.active_document().is_dirty());
+
+    app.set_save_encoding(crate::util::DetectedEncoding::default());
+    assert!(!app.active_document().is_dirty());
+    app.set_byte_order_mark(true);
+    assert!(app.active_document().is_dirty());
+    app.set_byte_order_mark(false);
+    assert!(!app.active_document().is_dirty());
 }
@@ -1,3 +1,7 @@
+mod save;
+mod snippets;
 mod theme;
 
+pub use save::{FinalNewline, SaveConfig};
+pub use snippets::SnippetLibrary;
 pub use theme::Theme;
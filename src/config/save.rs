@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::syntax::{LanguageId, language_for_name};
+
+const DEFAULT_FORMAT_TIMEOUT_MS: u64 = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FinalNewline {
+    #[default]
+    Keep,
+    Ensure,
+    Strip,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SaveRules {
+    pub trim_trailing_whitespace: bool,
+    pub final_newline: FinalNewline,
+    pub format_on_save: bool,
+    pub format_timeout: Duration,
+}
+
+impl Default for SaveRules {
+    fn default() -> Self {
+        Self {
+            trim_trailing_whitespace: false,
+            final_newline: FinalNewline::Keep,
+            format_on_save: false,
+            format_timeout: Duration::from_millis(DEFAULT_FORMAT_TIMEOUT_MS),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SaveConfig {
+    defaults: SaveRules,
+    languages: HashMap<LanguageId, SaveRules>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct SaveConfigFile {
+    #[serde(default)]
+    save: HashMap<String, SaveRulesToml>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+struct SaveRulesToml {
+    trim_trailing_whitespace: Option<bool>,
+    final_newline: Option<FinalNewline>,
+    format_on_save: Option<bool>,
+    format_timeout_ms: Option<u64>,
+}
+
+impl SaveRulesToml {
+    fn apply_to(self, base: SaveRules) -> SaveRules {
+        SaveRules {
+            trim_trailing_whitespace: self
+                .trim_trailing_whitespace
+                .unwrap_or(base.trim_trailing_whitespace),
+            final_newline: self.final_newline.unwrap_or(base.final_newline),
+            format_on_save: self.format_on_save.unwrap_or(base.format_on_save),
+            format_timeout: self
+                .format_timeout_ms
+                .map(Duration::from_millis)
+                .unwrap_or(base.format_timeout),
+        }
+    }
+}
+
+impl SaveConfig {
+    pub fn load(workspace_root: &Path) -> Self {
+        let config_path = workspace_root.join("assets/languages.toml");
+        std::fs::read_to_string(config_path)
+            .ok()
+            .map(|text| Self::parse(&text))
+            .unwrap_or_default()
+    }
+
+    pub fn parse(text: &str) -> Self {
+        let Ok(parsed) = toml::from_str::<SaveConfigFile>(text) else {
+            return Self::default();
+        };
+
+        let defaults = parsed
+            .save
+            .get("default")
+            .map(|rules| rules.apply_to(SaveRules::default()))
+            .unwrap_or_default();
+        let languages = parsed
+            .save
+            .iter()
+            .filter(|(name, _)| name.as_str() != "default")
+            .filter_map(|(name, rules)| Some((language_for_name(name)?, rules.apply_to(defaults))))
+            .collect();
+
+        Self {
+            defaults,
+            languages,
+        }
+    }
+
+    pub fn rules_for(&self, language: Option<LanguageId>) -> SaveRules {
+        language
+            .and_then(|language| self.languages.get(&language).copied())
+            .unwrap_or(self.defaults)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::{FinalNewline, SaveConfig};
+    use crate::syntax::LanguageId;
+
+    #[test]
+    fn language_rules_inherit_defaults() {
+        let config = SaveConfig::parse(
+            r#"
+[save.default]
+trim_trailing_whitespace = true
+final_newline = "ensure"
+
+[save.markdown]
+final_newline = "keep"
+format_on_save = true
+"#,
+        );
+        let rust = config.rules_for(Some(LanguageId::Rust));
+        assert!(rust.trim_trailing_whitespace);
+        assert_eq!(rust.final_newline, FinalNewline::Ensure);
+        let markdown = config.rules_for(Some(LanguageId::Markdown));
+        assert!(markdown.trim_trailing_whitespace);
+        assert_eq!(markdown.final_newline, FinalNewline::Keep);
+        assert!(markdown.format_on_save);
+    }
+}
@@ -0,0 +1,309 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::syntax::{LanguageId, language_for_name};
+
+/// A snippet from the user's or the workspace's snippet files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserSnippet {
+    pub name: String,
+    pub prefixes: Vec<String>,
+    pub body: String,
+    pub description: String,
+    /// `None` for snippets offered in every language.
+    pub language: Option<LanguageId>,
+}
+
+/// Snippets loaded from `<config>/codx/snippets/` and `.codx/snippets/`.
+///
+/// Files are VS Code snippet JSON (`rust.json`, `*.code-snippets`) or the
+/// same layout in TOML (`rust.toml`). The file name picks the language;
+/// `global` files and `.code-snippets` apply to every language unless an
+/// entry names its own `scope`.
+#[derive(Debug, Clone, Default)]
+pub struct SnippetLibrary {
+    snippets: Vec<UserSnippet>,
+    /// Files that could not be read and names that match no language.
+    warnings: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SnippetEntry {
+    #[serde(default)]
+    prefix: Lines,
+    body: Lines,
+    #[serde(default)]
+    description: Lines,
+    /// Comma separated language names.
+    scope: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Lines {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Default for Lines {
+    fn default() -> Self {
+        Self::Many(Vec::new())
+    }
+}
+
+impl Lines {
+    fn into_vec(self) -> Vec<String> {
+        match self {
+            Self::One(line) => vec![line],
+            Self::Many(lines) => lines,
+        }
+    }
+}
+
+impl SnippetLibrary {
+    pub fn load(workspace_root: &Path) -> Self {
+        let mut library = Self::default();
+        if let Some(dir) = user_snippet_dir() {
+            library.load_dir(&dir);
+        }
+        library.load_dir(&workspace_root.join(".codx/snippets"));
+        library
+    }
+
+    /// Adds every snippet file in `dir`; a snippet with the name and
+    /// language of an earlier one replaces it.
+    fn load_dir(&mut self, dir: &Path) {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return;
+        };
+        let mut paths = entries
+            .filter_map(|entry| Some(entry.ok()?.path()))
+            .collect::<Vec<_>>();
+        paths.sort();
+        for path in paths {
+            let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            if !is_snippet_file(file_name) {
+                continue;
+            }
+            let text = match std::fs::read_to_string(&path) {
+                Ok(text) => text,
+                Err(error) => {
+                    self.warnings.push(format!("{}: {error}", path.display()));
+                    continue;
+                }
+            };
+            let mut warnings = Vec::new();
+            let snippets = Self::parse_file(file_name, &text, &mut warnings);
+            self.warnings.extend(
+                warnings
+                    .into_iter()
+                    .map(|warning| format!("{}: {warning}", path.display())),
+            );
+            for snippet in snippets {
+                self.snippets.retain(|existing| {
+                    existing.name != snippet.name || existing.language != snippet.language
+                });
+                self.snippets.push(snippet);
+            }
+        }
+    }
+
+    /// Reads one snippet file. Malformed files and unknown languages give
+    /// no snippets and add to `warnings`.
+    pub fn parse_file(
+        file_name: &str,
+        text: &str,
+        warnings: &mut Vec<String>,
+    ) -> Vec<UserSnippet> {
+        let (stem, extension) = file_name.rsplit_once('.').unwrap_or((file_name, ""));
+        let global = extension == "code-snippets" || matches!(stem, "global" | "all");
+        let file_language = language_for_name(stem);
+        if !global && file_language.is_none() {
+            warnings.push(format!("no language is called `{stem}`"));
+            return Vec::new();
+        }
+        let entries = match extension {
+            "json" | "code-snippets" => {
+                serde_json::from_str::<BTreeMap<String, SnippetEntry>>(&strip_json_comments(text))
+                    .map_err(|error| error.to_string())
+            }
+            "toml" => toml::from_str::<BTreeMap<String, SnippetEntry>>(text)
+                .map_err(|error| error.message().to_owned()),
+            _ => Ok(BTreeMap::new()),
+        };
+        let entries = entries.unwrap_or_else(|error| {
+            warnings.push(error);
+            BTreeMap::new()
+        });
+
+        let mut snippets = Vec::new();
+        for (name, entry) in entries {
+            let languages = match entry.scope.as_deref() {
+                Some(scope) => scope
+                    .split(',')
+                    .filter_map(|language| {
+                        let found = language_for_name(language.trim());
+                        if found.is_none() {
+                            warnings.push(format!("`{name}` names unknown scope `{language}`"));
+                        }
+                        found.map(Some)
+                    })
+                    .collect(),
+                None => vec![file_language],
+            };
+            let prefixes = entry.prefix.into_vec();
+            let body = entry.body.into_vec().join("\n");
+            let description = entry.description.into_vec().join("\n");
+            for language in languages {
+                snippets.push(UserSnippet {
+                    name: name.clone(),
+                    prefixes: prefixes.clone(),
+                    body: body.clone(),
+                    description: description.clone(),
+                    language,
+                });
+            }
+        }
+        snippets
+    }
+
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    pub fn get(&self, index: usize) -> Option<&UserSnippet> {
+        self.snippets.get(index)
+    }
+
+    /// Snippets for `language` and for every language, with their index.
+    pub fn for_language(
+        &self,
+        language: Option<LanguageId>,
+    ) -> impl Iterator<Item = (usize, &UserSnippet)> {
+        self.snippets
+            .iter()
+            .enumerate()
+            .filter(move |(_, snippet)| snippet.language.is_none() || snippet.language == language)
+    }
+}
+
+fn is_snippet_file(file_name: &str) -> bool {
+    [".json", ".toml", ".code-snippets"]
+        .iter()
+        .any(|extension| file_name.ends_with(extension))
+}
+
+fn user_snippet_dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("codx").join("snippets"))
+}
+
+/// Drops `//` and `/* */` comments outside strings, which VS Code allows in
+/// snippet files.
+fn strip_json_comments(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    let mut in_string = false;
+    while let Some(ch) = chars.next() {
+        if in_string {
+            output.push(ch);
+            match ch {
+                '\\' => output.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match (ch, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                output.push(ch);
+            }
+            ('/', Some('/')) => {
+                while chars.next_if(|next| *next != '\n').is_some() {}
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                let mut previous = ' ';
+                for next in chars.by_ref() {
+                    if previous == '*' && next == '/' {
+                        break;
+                    }
+                    previous = next;
+                }
+            }
+            _ => output.push(ch),
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::SnippetLibrary;
+    use crate::syntax::LanguageId;
+
+    #[test]
+    fn parses_vscode_json_and_toml_snippets() {
+        let json = r##"{
+            // Line comment with a "quote".
+            "Test function": {
+                "prefix": ["test", "tfn"],
+                "body": ["#[test]", "fn ${1:name}() {", "\t$0", "}"],
+                "description": "A // test"
+            },
+            /* Applies to two languages. */
+            "Log": { "prefix": "log", "body": "console.log($1);", "scope": "javascript,ts" }
+        }"##;
+        let mut warnings = Vec::new();
+        let rust = SnippetLibrary::parse_file("rust.json", json, &mut warnings);
+        assert!(warnings.is_empty());
+        assert_eq!(rust.len(), 3);
+        // Entries come sorted by name, and a scoped entry once per language.
+        let languages = rust.iter().map(|snippet| snippet.language).collect::<Vec<_>>();
+        assert_eq!(
+            languages,
+            vec![
+                Some(LanguageId::JavaScript),
+                Some(LanguageId::TypeScript),
+                Some(LanguageId::Rust)
+            ]
+        );
+        let test = &rust[2];
+        assert_eq!(test.name, "Test function");
+        assert_eq!(test.prefixes, vec!["test", "tfn"]);
+        assert_eq!(test.body, "#[test]\nfn ${1:name}() {\n\t$0\n}");
+        assert_eq!(test.description, "A // test");
+
+        let toml = "[header]\nprefix = \"hdr\"\nbody = [\"# ${1:Title}\", \"\"]\n";
+        let global = SnippetLibrary::parse_file("global.toml", toml, &mut warnings);
+        assert_eq!(global[0].body, "# ${1:Title}\n");
+        assert_eq!(global[0].language, None);
+        assert!(SnippetLibrary::parse_file("notes.json", json, &mut warnings).is_empty());
+        assert_eq!(warnings, vec!["no language is called `notes`"]);
+    }
+
+    #[test]
+    fn reports_unreadable_files_and_unknown_scopes() {
+        let dir = tempfile::tempdir().unwrap_or_else(|error| panic!("{error}"));
+        let write = |name: &str, text: &str| {
+            std::fs::write(dir.path().join(name), text).unwrap_or_else(|error| panic!("{error}"))
+        };
+        write("rust.json", r#"{ "Log": { "prefix": "log", "body": "x", "scope": "rust,cobol" } }"#);
+        write("python.toml", "[broken\n");
+        write("README.md", "Not a snippet file.");
+        let mut library = SnippetLibrary::default();
+        library.load_dir(dir.path());
+        assert_eq!(library.for_language(Some(LanguageId::Rust)).count(), 1);
+        let warnings = library.warnings();
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].starts_with(&dir.path().join("python.toml").display().to_string()));
+        assert!(warnings[1].ends_with("`Log` names unknown scope `cobol`"));
+    }
+}
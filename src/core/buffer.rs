@@ -14,6 +14,10 @@ impl Buffer {
         }
     }
 
+    pub fn from_rope(rope: Rope) -> Self {
+        Self { rope }
+    }
+
     pub fn line_count(&self) -> usize {
         self.rope.len_lines().max(1)
     }
@@ -72,6 +76,11 @@ impl Buffer {
         Cursor::new(line, clamped.saturating_sub(line_start))
     }
 
+    pub fn byte_to_cursor(&self, byte_index: usize) -> Cursor {
+        let clamped = byte_index.min(self.rope.len_bytes());
+        self.char_to_cursor(self.rope.byte_to_char(clamped))
+    }
+
     pub fn insert(&mut self, cursor: Cursor, text: &str) -> Cursor {
         let char_index = self.cursor_to_char(cursor);
         self.rope.insert(char_index, text);
@@ -106,8 +115,8 @@ mod tests {
         let mut buffer = Buffer::new("bc");
         let cursor = buffer.insert(Cursor::new(0, 0), "a");
         assert_eq!(cursor, Cursor::new(0, 1));
-        let cursor = buffer.insert(Cursor::new(0, 2), "d");
-        assert_eq!(cursor, Cursor::new(0, 3));
+        let cursor = buffer.insert(Cursor::new(0, 3), "d");
+        assert_eq!(cursor, Cursor::new(0, 4));
         let cursor = buffer.insert(Cursor::new(0, 1), "X");
         assert_eq!(cursor, Cursor::new(0, 2));
         assert_eq!(buffer.text(), "aXbcd");
@@ -29,6 +29,14 @@ impl Document {
         }
     }
 
+    pub fn from_buffer(path: Option<PathBuf>, buffer: Buffer) -> Self {
+        Self {
+            buffer,
+            path,
+            dirty: false,
+        }
+    }
+
     pub fn path(&self) -> Option<&Path> {
         self.path.as_deref()
     }
@@ -41,6 +49,11 @@ impl Document {
         self.dirty = dirty;
     }
 
+    /// Points the document at a file that was moved on disk.
+    pub fn set_path(&mut self, path: PathBuf) {
+        self.path = Some(path);
+    }
+
     pub fn mark_saved(&mut self, path: PathBuf) {
         self.path = Some(path);
         self.dirty = false;
@@ -62,6 +75,10 @@ impl Document {
         self.buffer.cursor_to_byte(cursor)
     }
 
+    pub fn byte_to_cursor(&self, byte_index: usize) -> Cursor {
+        self.buffer.byte_to_cursor(byte_index)
+    }
+
     pub fn last_line_index(&self) -> usize {
         self.line_count().saturating_sub(1)
     }
@@ -256,6 +273,23 @@ impl Document {
         Cursor::new(cursor.line, col)
     }
 
+    /// Start and end of the identifier the cursor is in or right after.
+    pub fn word_range_at(&self, cursor: Cursor) -> Option<(Cursor, Cursor)> {
+        let chars: Vec<char> = self.line_text(cursor.line).chars().collect();
+        let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+        let column = cursor.column.min(chars.len());
+        let mut start = column;
+        while start > 0 && is_word_char(chars[start - 1]) {
+            start -= 1;
+        }
+        let mut end = column;
+        while end < chars.len() && is_word_char(chars[end]) {
+            end += 1;
+        }
+        (start < end && !chars[start].is_ascii_digit())
+            .then(|| (Cursor::new(cursor.line, start), Cursor::new(cursor.line, end)))
+    }
+
     pub fn previous_word_start(&self, cursor: Cursor) -> Cursor {
         if cursor.column == 0 {
             if cursor.line == 0 {
@@ -0,0 +1,214 @@
+pub const HEX_BYTES_PER_ROW: usize = 16;
+
+/// Raw byte buffer edited in place through the hex view. Edits overwrite
+/// nibbles; the file length never changes.
+#[derive(Debug, Clone)]
+pub struct HexDocument {
+    bytes: Vec<u8>,
+    saved: Vec<u8>,
+    cursor: usize,
+    low_nibble: bool,
+    top_row: usize,
+    last_search: Option<Vec<u8>>,
+}
+
+impl HexDocument {
+    pub fn from_bytes(bytes: Vec<u8>) -> Self {
+        Self {
+            saved: bytes.clone(),
+            bytes,
+            cursor: 0,
+            low_nibble: false,
+            top_row: 0,
+            last_search: None,
+        }
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn low_nibble(&self) -> bool {
+        self.low_nibble
+    }
+
+    pub fn top_row(&self) -> usize {
+        self.top_row
+    }
+
+    pub fn row_count(&self) -> usize {
+        self.bytes.len().div_ceil(HEX_BYTES_PER_ROW).max(1)
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.bytes != self.saved
+    }
+
+    pub fn mark_saved(&mut self) {
+        self.saved = self.bytes.clone();
+    }
+
+    pub fn last_search(&self) -> Option<&[u8]> {
+        self.last_search.as_deref()
+    }
+
+    pub fn move_to(&mut self, offset: usize) {
+        self.cursor = offset.min(self.bytes.len().saturating_sub(1));
+        self.low_nibble = false;
+    }
+
+    pub fn move_by(&mut self, delta: isize) {
+        self.move_to(self.cursor.saturating_add_signed(delta));
+    }
+
+    pub fn move_to_row_start(&mut self) {
+        self.move_to(self.cursor - self.cursor % HEX_BYTES_PER_ROW);
+    }
+
+    pub fn move_to_row_end(&mut self) {
+        self.move_to(self.cursor - self.cursor % HEX_BYTES_PER_ROW + HEX_BYTES_PER_ROW - 1);
+    }
+
+    /// Overwrites the nibble under the cursor and advances to the next one.
+    pub fn type_hex_digit(&mut self, digit: char) -> bool {
+        let Some(value) = digit.to_digit(16) else {
+            return false;
+        };
+        let Some(byte) = self.bytes.get_mut(self.cursor) else {
+            return false;
+        };
+        let value = value as u8;
+        if self.low_nibble {
+            *byte = (*byte & 0xF0) | value;
+            if self.cursor + 1 < self.bytes.len() {
+                self.cursor += 1;
+                self.low_nibble = false;
+            }
+        } else {
+            *byte = (*byte & 0x0F) | (value << 4);
+            self.low_nibble = true;
+        }
+        true
+    }
+
+    /// Finds `pattern` after the cursor, wrapping around to the start.
+    pub fn find_next(&mut self, pattern: Vec<u8>) -> Option<usize> {
+        if pattern.is_empty() || pattern.len() > self.bytes.len() {
+            self.last_search = Some(pattern);
+            return None;
+        }
+        let start = self.cursor + 1;
+        let found = find_bytes(&self.bytes[start.min(self.bytes.len())..], &pattern)
+            .map(|index| index + start)
+            .or_else(|| find_bytes(&self.bytes, &pattern));
+        self.last_search = Some(pattern);
+        if let Some(offset) = found {
+            self.move_to(offset);
+        }
+        found
+    }
+
+    pub fn scroll_to_cursor(&mut self, height: usize) {
+        let row = self.cursor / HEX_BYTES_PER_ROW;
+        let height = height.max(1);
+        if row < self.top_row {
+            self.top_row = row;
+        } else if row >= self.top_row + height {
+            self.top_row = row + 1 - height;
+        }
+    }
+}
+
+fn find_bytes(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// Parses `de ad be ef`, `deadbeef` or a quoted `"text"` into a byte pattern.
+pub fn parse_byte_pattern(input: &str) -> Option<Vec<u8>> {
+    let input = input.trim();
+    if let Some(text) = input
+        .strip_prefix('"')
+        .and_then(|rest| rest.strip_suffix('"'))
+    {
+        return Some(text.as_bytes().to_vec());
+    }
+
+    let digits = input
+        .chars()
+        .filter(|ch| !ch.is_whitespace())
+        .collect::<Vec<_>>();
+    if digits.is_empty() || digits.len() % 2 != 0 {
+        return None;
+    }
+    digits
+        .chunks(2)
+        .map(|pair| {
+            let high = pair[0].to_digit(16)?;
+            let low = pair[1].to_digit(16)?;
+            Some((high * 16 + low) as u8)
+        })
+        .collect()
+}
+
+/// Parses a decimal or `0x`-prefixed hexadecimal byte offset.
+pub fn parse_offset(input: &str) -> Option<usize> {
+    let input = input.trim();
+    match input
+        .strip_prefix("0x")
+        .or_else(|| input.strip_prefix("0X"))
+    {
+        Some(hex) => usize::from_str_radix(hex, 16).ok(),
+        None => input.parse().ok(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::{HexDocument, parse_byte_pattern, parse_offset};
+
+    #[test]
+    fn overwrite_edits_nibbles_and_tracks_dirty_state() {
+        let mut hex = HexDocument::from_bytes(vec![0x00, 0x11, 0x22]);
+        assert!(hex.type_hex_digit('a'));
+        assert!(hex.type_hex_digit('B'));
+        assert!(!hex.type_hex_digit('z'));
+        assert_eq!(hex.bytes(), &[0xAB, 0x11, 0x22]);
+        assert_eq!(hex.cursor(), 1);
+        assert!(hex.is_dirty());
+        hex.mark_saved();
+        assert!(!hex.is_dirty());
+    }
+
+    #[test]
+    fn finds_patterns_with_wraparound() {
+        let mut hex = HexDocument::from_bytes(b"abcabc".to_vec());
+        assert_eq!(hex.find_next(b"bc".to_vec()), Some(1));
+        assert_eq!(hex.find_next(b"bc".to_vec()), Some(4));
+        assert_eq!(hex.find_next(b"bc".to_vec()), Some(1));
+        assert_eq!(hex.find_next(b"zz".to_vec()), None);
+    }
+
+    #[test]
+    fn parses_patterns_and_offsets() {
+        assert_eq!(parse_byte_pattern("de ad BE ef"), Some(vec![0xDE, 0xAD, 0xBE, 0xEF]));
+        assert_eq!(parse_byte_pattern("\"PK\""), Some(b"PK".to_vec()));
+        assert_eq!(parse_byte_pattern("abc"), None);
+        assert_eq!(parse_offset("0x1f"), Some(31));
+        assert_eq!(parse_offset("42"), Some(42));
+        assert_eq!(parse_offset("x"), None);
+    }
+}
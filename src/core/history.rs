@@ -15,6 +15,10 @@ pub struct EditRecord {
     pub deleted_text: String,
     pub cursor_before: Cursor,
     pub cursor_after: Cursor,
+    /// Names the text this record leads to; set by `History`.
+    revision: u64,
+    /// Undone and redone together with the record before it.
+    joined: bool,
 }
 
 impl EditRecord {
@@ -39,6 +43,8 @@ impl EditRecord {
             deleted_text,
             cursor_before,
             cursor_after,
+            revision: 0,
+            joined: false,
         }
     }
 
@@ -80,34 +86,101 @@ pub struct History {
     undo_stack: Vec<EditRecord>,
     redo_stack: Vec<EditRecord>,
     coalescing_active: bool,
+    last_revision: u64,
+    saved_revision: u64,
 }
 
 impl History {
-    pub fn push_edit(&mut self, record: EditRecord, coalesce: bool) {
+    pub fn push_edit(&mut self, mut record: EditRecord, coalesce: bool) {
+        self.last_revision += 1;
         if coalesce && self.coalescing_active && self.try_coalesce_insert(&record) {
+            if let Some(previous) = self.undo_stack.last_mut() {
+                previous.revision = self.last_revision;
+            }
             self.redo_stack.clear();
             return;
         }
 
+        record.revision = self.last_revision;
         self.undo_stack.push(record);
         self.redo_stack.clear();
         self.coalescing_active = coalesce;
     }
 
+    /// Makes the latest record undo and redo together with the one before
+    /// it, e.g. for edits applied at several places at once.
+    pub fn join_last(&mut self) {
+        if self.undo_stack.len() > 1
+            && let Some(record) = self.undo_stack.last_mut()
+        {
+            record.joined = true;
+        }
+        self.coalescing_active = false;
+    }
+
     pub fn undo(&mut self, document: &mut Document) -> Option<Cursor> {
-        let record = self.undo_stack.pop()?;
-        let cursor = record.undo(document);
-        self.redo_stack.push(record);
+        let mut cursor = None;
+        while let Some(record) = self.undo_stack.pop() {
+            cursor = Some(record.undo(document));
+            let joined = record.joined;
+            self.redo_stack.push(record);
+            if !joined {
+                break;
+            }
+        }
         self.coalescing_active = false;
-        Some(cursor)
+        cursor
     }
 
     pub fn redo(&mut self, document: &mut Document) -> Option<Cursor> {
-        let record = self.redo_stack.pop()?;
-        let cursor = record.redo(document);
-        self.undo_stack.push(record);
+        let mut cursor = None;
+        while let Some(record) = self.redo_stack.pop() {
+            cursor = Some(record.redo(document));
+            self.undo_stack.push(record);
+            if !self.redo_stack.last().is_some_and(|next| next.joined) {
+                break;
+            }
+        }
         self.coalescing_active = false;
-        Some(cursor)
+        cursor
+    }
+
+    /// Names the current text: undoing and redoing back to a revision gives
+    /// the same text, while any new edit gets a new revision.
+    pub fn revision(&self) -> u64 {
+        self.undo_stack.last().map_or(0, |record| record.revision)
+    }
+
+    /// Records the current text as the one on disk.
+    pub fn mark_saved(&mut self) {
+        self.saved_revision = self.revision();
+    }
+
+    /// Whether undo and redo led back to the text last saved.
+    pub fn is_at_saved(&self) -> bool {
+        self.revision() == self.saved_revision
+    }
+
+    /// The records the last undo reverted, in the order it reverted them;
+    /// `redo` re-applies them.
+    pub fn last_undone(&self) -> &[EditRecord] {
+        let mut start = self.redo_stack.len().saturating_sub(1);
+        while start > 0 && self.redo_stack[start - 1].joined {
+            start -= 1;
+        }
+        &self.redo_stack[start..]
+    }
+
+    /// The records most recently applied or redone, in order.
+    pub fn last_applied(&self) -> &[EditRecord] {
+        let mut start = self.undo_stack.len();
+        while let Some(record) = start.checked_sub(1).map(|index| &self.undo_stack[index]) {
+            start -= 1;
+            if !record.joined {
+                break;
+            }
+        }
+        &self.undo_stack[start..]
     }
 
     fn try_coalesce_insert(&mut self, record: &EditRecord) -> bool {
@@ -188,4 +261,70 @@ mod tests {
         );
         assert!(history.redo(&mut document).is_none());
     }
+
+    #[test]
+    fn tracks_the_saved_revision_through_undo_and_coalescing() {
+        let insert = |column: usize, text: &str| {
+            EditRecord::new(
+                Cursor::new(0, column),
+                String::from(text),
+                String::new(),
+                Cursor::new(0, column),
+                Cursor::new(0, column + text.len()),
+            )
+        };
+        let mut document = Document::from_text(None, "");
+        let mut history = History::default();
+        assert!(history.is_at_saved());
+        history.push_edit(insert(0, "a"), true);
+        history.mark_saved();
+        // Typing on after the save grows the saved record but changes the text.
+        history.push_edit(insert(1, "b"), true);
+        assert!(!history.is_at_saved());
+        document.insert_text(Cursor::new(0, 0), "ab");
+        let _ = history.undo(&mut document);
+        assert!(!history.is_at_saved());
+
+        history.push_edit(insert(0, "a"), false);
+        history.mark_saved();
+        history.push_edit(insert(1, "c"), false);
+        let _ = history.undo(&mut document);
+        assert!(history.is_at_saved());
+        let _ = history.redo(&mut document);
+        assert!(!history.is_at_saved());
+    }
+
+    #[test]
+    fn joined_records_undo_and_redo_as_one_step() {
+        let insert = |column: usize, text: &str| {
+            EditRecord::new(
+                Cursor::new(0, column),
+                String::from(text),
+                String::new(),
+                Cursor::new(0, column),
+                Cursor::new(0, column + text.len()),
+            )
+        };
+        let mut document = Document::from_text(None, "ab-ab");
+        let mut history = History::default();
+        history.push_edit(insert(0, "ab"), false);
+        history.push_edit(insert(2, "-"), false);
+        history.push_edit(insert(3, "ab"), false);
+        history.join_last();
+        assert_eq!(history.last_applied().len(), 2);
+
+        let cursor = history.undo(&mut document);
+        assert_eq!(document.text(), "ab");
+        assert_eq!(cursor, Some(Cursor::new(0, 2)));
+        let undone = history.last_undone();
+        assert_eq!(undone.iter().map(|record| record.start.column).collect::<Vec<_>>(), [3, 2]);
+
+        let cursor = history.redo(&mut document);
+        assert_eq!(document.text(), "ab-ab");
+        assert_eq!(cursor, Some(Cursor::new(0, 5)));
+        let _ = history.undo(&mut document);
+        let _ = history.undo(&mut document);
+        assert_eq!(document.text(), "");
+        assert_eq!(history.last_undone().len(), 1);
+    }
 }
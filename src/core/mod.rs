@@ -1,12 +1,14 @@
 mod buffer;
 mod cursor;
 mod document;
+mod hex;
 mod history;
 mod selection;
 
 pub use buffer::Buffer;
 pub use cursor::Cursor;
 pub use document::Document;
+pub use hex::{HEX_BYTES_PER_ROW, HexDocument, parse_byte_pattern, parse_offset};
 #[allow(unused_imports)]
 pub use history::{EditKind, EditRecord, History};
 pub use selection::Selection;
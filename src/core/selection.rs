@@ -18,6 +18,10 @@ impl Selection {
         Self { active, ..self }
     }
 
+    pub fn anchor(&self) -> Cursor {
+        self.anchor
+    }
+
     pub fn is_empty(&self) -> bool {
         self.anchor == self.active
     }
@@ -17,6 +17,9 @@ impl App {
     }
 
     pub(crate) fn cut_selection(&mut self) {
+        if self.reject_hex_text_edit() {
+            return;
+        }
         if let Some(text) = self.selection_text() {
             match self.clipboard.as_mut() {
                 Some(clipboard) => match clipboard.copy(&text) {
@@ -40,10 +40,24 @@ pub enum Command {
     Hover,
     SignatureHelp,
     GotoDefinition,
+    GotoDeclaration,
+    GotoTypeDefinition,
+    GotoImplementation,
     GotoReferences,
     RenameSymbol,
     CodeActions,
     FormatDocument,
     WorkspaceSymbols,
     ToggleDiagnosticsPanel,
+    ToggleOutline,
+    IncomingCalls,
+    OutgoingCalls,
+    Supertypes,
+    Subtypes,
+    JumpBack,
+    JumpForward,
+    JumpList,
+    SnippetPicker,
+    NextHighlight,
+    PreviousHighlight,
 }
@@ -1,6 +1,7 @@
 use std::path::{Path, PathBuf};
 
 use crate::app::{App, AppError, AppMode, CommandBarMode, MessageKind};
+use crate::editor::Command;
 
 impl App {
     pub(crate) fn begin_save_as_prompt(&mut self) {
@@ -17,9 +18,12 @@ impl App {
     pub(crate) fn begin_search_prompt(&mut self) {
         self.command_bar.input = self.active_pane().search().confirmed_query().to_owned();
         self.command_bar.original_search_query = Some(self.command_bar.input.clone());
-        self.with_active_search_state(|search, document, cursor| {
-            search.begin_preview(search.confirmed_query().to_owned(), document, cursor);
-        });
+        // Large files keep their matches until submit; see `update_search_preview`.
+        if !self.active_buffer().large_file {
+            self.with_active_search_state(|search, document, cursor| {
+                search.begin_preview(search.confirmed_query().to_owned(), document, cursor);
+            });
+        }
         self.mode = AppMode::CommandBar(CommandBarMode::Search);
     }
 
@@ -30,7 +34,9 @@ impl App {
     }
 
     pub(crate) fn cancel_command_bar(&mut self) {
-        if matches!(self.mode, AppMode::CommandBar(CommandBarMode::Search)) {
+        if matches!(self.mode, AppMode::CommandBar(CommandBarMode::Search))
+            && !self.active_buffer().large_file
+        {
             if let Some(original) = self.command_bar.original_search_query.clone() {
                 self.with_active_search_state(|search, document, cursor| {
                     search.begin_preview(original.clone(), document, cursor);
@@ -61,12 +67,17 @@ impl App {
             CommandBarMode::Search => {
                 if self.command_bar.input.is_empty() {
                     self.active_pane_mut().search_mut().clear();
+                } else if self.active_buffer().large_file {
+                    let query = self.command_bar.input.clone();
+                    self.record_jump();
+                    self.active_pane_mut().search_mut().confirm_chunked(query);
                 } else {
                     let query = self.command_bar.input.clone();
                     self.with_active_search_state(|search, document, cursor| {
                         search.update_preview(query.clone(), document, cursor);
                         search.confirm_preview();
                     });
+                    self.record_jump();
                     self.jump_to_active_search_match();
                 }
                 self.mode = AppMode::Editing;
@@ -118,6 +129,10 @@ impl App {
     }
 
     pub(crate) fn update_search_preview(&mut self) {
+        // Large files only search on submit; rescanning every keystroke stalls.
+        if self.active_buffer().large_file {
+            return;
+        }
         if self.command_bar.input.is_empty() {
             self.active_pane_mut().search_mut().clear();
             return;
@@ -149,8 +164,84 @@ impl App {
                     search.begin_preview(query.clone(), document, cursor);
                     search.confirm_preview();
                 });
+                self.record_jump();
                 self.jump_to_active_search_match();
             }
+            "line-ending" => {
+                let label = self.active_buffer().line_ending.label();
+                self.set_message(&format!("Line ending: {label}"), MessageKind::Info);
+            }
+            _ if command.starts_with("line-ending ") => {
+                let label = command.trim_start_matches("line-ending ").trim();
+                match crate::util::LineEnding::from_label(label) {
+                    Some(line_ending) => self.set_line_ending(line_ending),
+                    None => self.set_message(
+                        &format!("Unknown line ending '{label}'. Use lf, crlf or cr"),
+                        MessageKind::Warning,
+                    ),
+                }
+            }
+            "encoding" => {
+                let label = self.active_buffer().encoding.status_label();
+                self.set_message(&format!("Encoding: {label}"), MessageKind::Info);
+            }
+            _ if command.starts_with("reopen-encoding ") => {
+                let label = command.trim_start_matches("reopen-encoding ").trim();
+                match crate::util::DetectedEncoding::for_label(label) {
+                    Some(encoding) => self.reopen_with_encoding(encoding),
+                    None => self.warn_unknown_encoding(label),
+                }
+            }
+            _ if command.starts_with("save-encoding ") => {
+                let label = command.trim_start_matches("save-encoding ").trim();
+                match crate::util::DetectedEncoding::for_label(label) {
+                    Some(encoding) => self.set_save_encoding(encoding),
+                    None => self.warn_unknown_encoding(label),
+                }
+            }
+            _ if command.starts_with("goto-offset ") => {
+                let input = command.trim_start_matches("goto-offset ");
+                match crate::core::parse_offset(input) {
+                    Some(offset) => self.hex_goto_offset(offset),
+                    None => self.set_message(
+                        &format!("Invalid offset '{}'", input.trim()),
+                        MessageKind::Warning,
+                    ),
+                }
+            }
+            "hex-find" => self.hex_find(None),
+            _ if command.starts_with("hex-find ") => {
+                let input = command.trim_start_matches("hex-find ");
+                match crate::core::parse_byte_pattern(input) {
+                    Some(pattern) => self.hex_find(Some(pattern)),
+                    None => self.set_message(
+                        "Byte pattern must be hex pairs like 'de ad' or quoted text",
+                        MessageKind::Warning,
+                    ),
+                }
+            }
+            "outline" => self.toggle_outline(),
+            "declaration" => self.apply_command(Command::GotoDeclaration)?,
+            "type-definition" => self.apply_command(Command::GotoTypeDefinition)?,
+            "implementation" => self.apply_command(Command::GotoImplementation)?,
+            "incoming-calls" => self.apply_command(Command::IncomingCalls)?,
+            "outgoing-calls" => self.apply_command(Command::OutgoingCalls)?,
+            "supertypes" => self.apply_command(Command::Supertypes)?,
+            "subtypes" => self.apply_command(Command::Subtypes)?,
+            "jumps" => self.apply_command(Command::JumpList)?,
+            "snippets" => self.apply_command(Command::SnippetPicker)?,
+            "next-occurrence" => self.apply_command(Command::NextHighlight)?,
+            "previous-occurrence" => self.apply_command(Command::PreviousHighlight)?,
+            "inlay-hints" => self.toggle_inlay_hints(),
+            "inlay-hint-tooltip" => self.show_inlay_hint_tooltip(),
+            "lsp-restart" => self.restart_language_servers(),
+            "lsp-status" => self.show_lsp_status(),
+            "lsp-log" => self.open_lsp_log(""),
+            _ if command.starts_with("lsp-log ") => {
+                self.open_lsp_log(command.trim_start_matches("lsp-log "));
+            }
+            "bom add" => self.set_byte_order_mark(true),
+            "bom remove" => self.set_byte_order_mark(false),
             "theme list" | "themes" => {
                 let names = crate::config::Theme::available_names().join(", ");
                 self.set_message(&format!("Themes: {names}"), MessageKind::Info);
@@ -192,4 +283,11 @@ impl App {
             apply(pane.search_mut(), &buffer.document, cursor);
         }
     }
+
+    fn warn_unknown_encoding(&mut self, label: &str) {
+        self.set_message(
+            &format!("Unknown encoding '{label}'. Try utf-8, utf-16le, shift_jis, gbk or iso-8859-2"),
+            MessageKind::Warning,
+        );
+    }
 }
@@ -1,47 +1,189 @@
-use crate::app::App;
+use std::path::PathBuf;
+
+use crate::app::{App, MessageKind};
 use crate::core::Cursor;
+use crate::lsp::{CompletionItemView, CompletionSource};
+
+const MAX_PATH_ITEMS: usize = 500;
 
 impl App {
     pub(crate) fn trigger_completion(&mut self, trigger: Option<char>) {
-        let Some(path) = self.active_document().path().map(|path| path.to_path_buf()) else {
-            return;
+        let cursor = self.active_pane().cursor();
+        match self.active_document().path().map(|path| path.to_path_buf()) {
+            Some(path) => self.lsp.request_completion(
+                &path,
+                &self.workspace_root,
+                cursor,
+                &self.buffers,
+                trigger,
+            ),
+            None => self.close_completion(),
+        }
+        // Inside a path string the file name is completed instead of the word.
+        let (start, local) = match self.path_completion_items(cursor) {
+            Some((start, items)) => (start, items),
+            None => {
+                let start = completion_prefix_start(self.active_document(), cursor);
+                let prefix = self.completion_prefix(start, cursor);
+                let mut items = self.snippet_completion_items(&prefix);
+                items.extend(self.word_completion_items(&prefix));
+                (start, items)
+            }
+        };
+        let prefix = self.completion_prefix(start, cursor);
+        let completion = &mut self.lsp.completion;
+        if !local.is_empty() {
+            completion.merge_items(cursor, local, false);
+        }
+        completion.start = start;
+        completion.set_filter(&prefix);
+    }
+
+    /// Entries of the directory typed so far when the cursor is inside a
+    /// quoted path, with the start of the file name they replace. Relative
+    /// paths start at the buffer's directory.
+    fn path_completion_items(&self, cursor: Cursor) -> Option<(Cursor, Vec<CompletionItemView>)> {
+        let line = self.active_document().line_text(cursor.line);
+        let before = line.chars().take(cursor.column).collect::<String>();
+        let typed = path_prefix(&before)?;
+        let (directory, name) = typed.rsplit_once('/')?;
+        let base = self
+            .active_document()
+            .path()
+            .and_then(|path| path.parent())
+            .map_or_else(|| self.workspace_root.clone(), |parent| parent.to_path_buf());
+        let directory = if directory.is_empty() && typed.starts_with('/') {
+            PathBuf::from("/")
+        } else if let Some(rest) = directory.strip_prefix('~') {
+            PathBuf::from(std::env::var_os("HOME")?).join(rest.trim_start_matches('/'))
+        } else {
+            base.join(directory)
         };
+        let mut entries = std::fs::read_dir(directory)
+            .ok()?
+            .filter_map(|entry| {
+                let entry = entry.ok()?;
+                let file_name = entry.file_name().into_string().ok()?;
+                let is_dir = entry.file_type().ok()?.is_dir();
+                Some((file_name, is_dir))
+            })
+            .filter(|(file_name, _)| !file_name.starts_with('.') || name.starts_with('.'))
+            .collect::<Vec<_>>();
+        entries.sort();
+        entries.truncate(MAX_PATH_ITEMS);
+        let items = entries
+            .into_iter()
+            .map(|(file_name, is_dir)| {
+                let label = if is_dir {
+                    format!("{file_name}/")
+                } else {
+                    file_name.clone()
+                };
+                CompletionItemView {
+                    label: label.clone(),
+                    resolved: true,
+                    kind: if is_dir { 19 } else { 17 },
+                    insert_text: label,
+                    // Directories first.
+                    sort_text: format!("{}{file_name}", u8::from(!is_dir)),
+                    filter_text: file_name,
+                    source: CompletionSource::Path,
+                    ..CompletionItemView::default()
+                }
+            })
+            .collect();
+        let start = Cursor::new(cursor.line, cursor.column - name.chars().count());
+        Some((start, items))
+    }
+
+    /// Narrows the open list after `ch` was typed, or asks the servers
+    /// again when the word ended or the list was incomplete.
+    pub(crate) fn update_completion(&mut self, ch: char) {
+        let completion = &self.lsp.completion;
+        if is_word_char(ch) && completion.active && !completion.incomplete {
+            self.filter_completion();
+        } else {
+            self.trigger_completion(Some(ch));
+        }
+    }
+
+    /// Refilters by the word before the cursor, closing the list once the
+    /// cursor left that word.
+    pub(crate) fn filter_completion(&mut self) {
+        let start = self.lsp.completion.start;
         let cursor = self.active_pane().cursor();
-        self.lsp.request_completion(
-            &path,
-            &self.workspace_root,
-            cursor.line,
-            cursor.column,
-            trigger,
-        );
+        if cursor.line != start.line || cursor.column < start.column {
+            self.close_completion();
+            return;
+        }
+        let prefix = self.completion_prefix(start, cursor);
+        self.lsp.completion.set_filter(&prefix);
+    }
+
+    /// Accepts the selected item when `ch` is one of its commit characters,
+    /// before `ch` itself is inserted.
+    pub(crate) fn commit_completion(&mut self, ch: char) {
+        if self.completion_active()
+            && self
+                .lsp
+                .completion
+                .selected_item()
+                .is_some_and(|item| item.commit_characters.contains(&ch))
+        {
+            self.accept_completion(false);
+        }
     }
 
-    pub(crate) fn accept_completion(&mut self) {
+    /// Applies the selected item's edit and its additional edits as one
+    /// undo step. `replace` also overwrites the rest of the word after the
+    /// cursor when the server sent a replace range.
+    pub(crate) fn accept_completion(&mut self, replace: bool) {
         let Some(item) = self.lsp.completion.selected_item().cloned() else {
             return;
         };
-        let text = if item.is_snippet {
-            crate::editor::snippet::expand_snippet_body(&item.insert_text)
-        } else {
-            item.insert_text
-        };
+        let snippet = item.is_snippet.then(|| self.expand_snippet(&item.insert_text));
+        let text = snippet
+            .as_ref()
+            .map_or_else(|| item.insert_text.clone(), |snippet| snippet.text.clone());
         let cursor = self.active_pane().cursor();
-        let replace_start = completion_prefix_start(self.active_document(), cursor);
-        if replace_start != cursor {
-            self.apply_edit(replace_start, cursor, &text, false);
-        } else {
-            self.insert_text(&text, false);
+        let line_text = self.active_document().line_text(cursor.line);
+        let completion = &self.lsp.completion;
+        let (request, start) = (completion.cursor, completion.start);
+        let edits = item.text_edits(text, &line_text, request, start, cursor, replace);
+        self.lsp.close_completion();
+        let buffer_id = self.active_buffer_id;
+        if let Err(error) = self.apply_buffer_text_edits(buffer_id, &edits, item.encoding) {
+            self.set_message(&format!("Completion failed: {error}"), MessageKind::Error);
+            return;
+        }
+        if let Some(snippet) = snippet {
+            // The cursor follows the inserted text, so the snippet ends there.
+            let document = self.active_document();
+            let end = document.cursor_to_byte(self.active_pane().cursor());
+            let start = document.byte_to_cursor(end.saturating_sub(snippet.text.len()));
+            self.start_snippet(&snippet, start);
         }
-        self.lsp.completion.close();
     }
 
     pub(crate) fn close_completion(&mut self) {
-        self.lsp.completion.close();
+        self.lsp.close_completion();
     }
 
     pub(crate) fn completion_active(&self) -> bool {
         self.lsp.completion.active
     }
+
+    fn completion_prefix(&self, start: Cursor, cursor: Cursor) -> String {
+        let line = self.active_document().line_text(cursor.line);
+        line.chars()
+            .skip(start.column)
+            .take(cursor.column.saturating_sub(start.column))
+            .collect()
+    }
+}
+
+pub(super) fn is_word_char(character: char) -> bool {
+    character.is_alphanumeric() || character == '_'
 }
 
 fn completion_prefix_start(document: &crate::core::Document, cursor: Cursor) -> Cursor {
@@ -51,7 +193,7 @@ fn completion_prefix_start(document: &crate::core::Document, cursor: Cursor) ->
 
     while column > 0 {
         let character = chars[column - 1];
-        if character.is_alphanumeric() || character == '_' {
+        if is_word_char(character) {
             column -= 1;
             continue;
         }
@@ -60,3 +202,37 @@ fn completion_prefix_start(document: &crate::core::Document, cursor: Cursor) ->
 
     Cursor::new(cursor.line, column)
 }
+
+/// The path typed so far when `before`, the line up to the cursor, ends
+/// inside a quoted string holding a path without spaces.
+fn path_prefix(before: &str) -> Option<&str> {
+    let mut quote: Option<(char, usize)> = None;
+    let mut escaped = false;
+    for (index, ch) in before.char_indices() {
+        match quote {
+            Some(_) if escaped => escaped = false,
+            Some(_) if ch == '\\' => escaped = true,
+            Some((open, _)) if ch == open => quote = None,
+            None if matches!(ch, '"' | '\'' | '`') => quote = Some((ch, index + ch.len_utf8())),
+            _ => {}
+        }
+    }
+    let text = &before[quote?.1..];
+    (text.contains('/') && !text.contains(char::is_whitespace)).then_some(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::path_prefix;
+
+    #[test]
+    fn finds_paths_inside_open_strings() {
+        assert_eq!(path_prefix("import x from './lib/ut"), Some("./lib/ut"));
+        assert_eq!(path_prefix("open(\"/etc/"), Some("/etc/"));
+        assert_eq!(path_prefix("let s = \"a \\\" b\"; load(\"~/"), Some("~/"));
+        // Closed strings, prose and plain words are not paths.
+        assert_eq!(path_prefix("f(\"./a\") + x/y"), None);
+        assert_eq!(path_prefix("don't see a/b"), None);
+        assert_eq!(path_prefix("\"name"), None);
+    }
+}
@@ -0,0 +1,113 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::app::{App, MessageKind};
+use crate::core::{HEX_BYTES_PER_ROW, HexDocument};
+
+impl App {
+    pub(crate) fn is_hex_view(&self) -> bool {
+        self.active_buffer().hex.is_some()
+    }
+
+    /// Warns and returns `true` when the active buffer is a hex view, whose
+    /// text document stays empty and must not be edited.
+    pub(crate) fn reject_hex_text_edit(&mut self) -> bool {
+        if !self.is_hex_view() {
+            return false;
+        }
+        self.set_message(
+            "Hex view overwrites bytes: type 0-9 or a-f",
+            MessageKind::Warning,
+        );
+        true
+    }
+
+    /// Handles navigation and overwrite keys for the hex view. Returns `false`
+    /// for keys that should fall through to the regular keymap.
+    pub(crate) fn handle_hex_key(&mut self, key_event: KeyEvent) -> bool {
+        let page = self.active_pane().viewport().text_height().max(1) * HEX_BYTES_PER_ROW;
+        let row = HEX_BYTES_PER_ROW as isize;
+        let control = key_event
+            .modifiers
+            .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+        let mut rejected = false;
+        let Some(hex) = self.active_hex_mut() else {
+            return false;
+        };
+
+        match key_event.code {
+            KeyCode::Left | KeyCode::Backspace => hex.move_by(-1),
+            KeyCode::Right => hex.move_by(1),
+            KeyCode::Up => hex.move_by(-row),
+            KeyCode::Down => hex.move_by(row),
+            KeyCode::PageUp => hex.move_by(-(page as isize)),
+            KeyCode::PageDown => hex.move_by(page as isize),
+            KeyCode::Home => hex.move_to_row_start(),
+            KeyCode::End => hex.move_to_row_end(),
+            KeyCode::Char(ch) if !control => rejected = !hex.type_hex_digit(ch),
+            KeyCode::Enter | KeyCode::Tab | KeyCode::Delete => rejected = true,
+            _ => return false,
+        }
+
+        if rejected {
+            self.set_message(
+                "Hex view overwrites bytes: type 0-9 or a-f",
+                MessageKind::Warning,
+            );
+        }
+        self.sync_hex_view();
+        true
+    }
+
+    pub(crate) fn hex_goto_offset(&mut self, offset: usize) {
+        let Some(hex) = self.active_hex_mut() else {
+            self.set_message("goto-offset needs a hex view buffer", MessageKind::Warning);
+            return;
+        };
+        if offset >= hex.len() {
+            let len = hex.len();
+            self.set_message(
+                &format!("Offset 0x{offset:x} is past the end (0x{len:x} bytes)"),
+                MessageKind::Warning,
+            );
+            return;
+        }
+        hex.move_to(offset);
+        self.sync_hex_view();
+    }
+
+    /// Searches for `pattern`, or repeats the previous search when `None`.
+    pub(crate) fn hex_find(&mut self, pattern: Option<Vec<u8>>) {
+        let Some(hex) = self.active_hex_mut() else {
+            self.set_message("hex-find needs a hex view buffer", MessageKind::Warning);
+            return;
+        };
+        let Some(pattern) = pattern.or_else(|| hex.last_search().map(<[u8]>::to_vec)) else {
+            self.set_message("No previous byte search", MessageKind::Warning);
+            return;
+        };
+        match hex.find_next(pattern) {
+            Some(offset) => {
+                self.set_message(&format!("Match at 0x{offset:08x}"), MessageKind::Info);
+            }
+            None => self.set_message("Byte pattern not found", MessageKind::Warning),
+        }
+        self.sync_hex_view();
+    }
+
+    fn active_hex_mut(&mut self) -> Option<&mut HexDocument> {
+        self.buffer_by_id_mut(self.active_buffer_id)?.hex.as_mut()
+    }
+
+    fn sync_hex_view(&mut self) {
+        let height = self.active_pane().viewport().text_height();
+        let Some(buffer) = self.buffer_by_id_mut(self.active_buffer_id) else {
+            return;
+        };
+        let Some(hex) = buffer.hex.as_mut() else {
+            return;
+        };
+        hex.scroll_to_cursor(height);
+        let dirty = hex.is_dirty();
+        buffer.document.set_dirty(dirty);
+    }
+}
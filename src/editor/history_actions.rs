@@ -11,6 +11,10 @@ impl App {
     }
 
     fn apply_history_step(&mut self, undo: bool) {
+        if self.reject_hex_text_edit() {
+            return;
+        }
+        self.end_snippet();
         let pane_id = self.active_pane_id();
         let buffer_id = self.active_buffer_id;
         let Some(buffer_index) = self
@@ -38,9 +42,7 @@ impl App {
             };
             cursor.map(|cursor| {
                 let preferred = buffer.document.display_column(cursor);
-                buffer
-                    .document
-                    .set_dirty(buffer.document.text() != buffer.saved_snapshot);
+                buffer.refresh_dirty();
                 cursor.with_preferred_column(preferred)
             })
         };
@@ -57,6 +59,31 @@ impl App {
             return;
         };
 
+        let history = &self.buffers[buffer_index].history;
+        // Undo replays records backwards, so their inserted text is what goes away.
+        let changes = if undo {
+            history
+                .last_undone()
+                .iter()
+                .map(|record| {
+                    (record.start, record.inserted_text.clone(), record.deleted_text.clone())
+                })
+                .collect::<Vec<_>>()
+        } else {
+            history
+                .last_applied()
+                .iter()
+                .map(|record| {
+                    (record.start, record.deleted_text.clone(), record.inserted_text.clone())
+                })
+                .collect()
+        };
+        for (start, deleted, inserted) in changes {
+            self.notify_lsp_edit(start, &deleted, &inserted);
+            self.shift_jumps(buffer_id, start, &deleted, &inserted);
+            self.index_buffer_edit(buffer_id, start, &deleted, &inserted);
+        }
+
         if let Some(pane) = self.layout.pane_mut(pane_id) {
             pane.set_cursor(cursor);
             pane.set_selection(Selection::caret(pane.cursor()));
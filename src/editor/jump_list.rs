@@ -0,0 +1,254 @@
+use crate::app::{App, MessageKind};
+use crate::core::{Cursor, Selection};
+use crate::ui::{PickerItem, PickerKind, PickerState};
+
+const MAX_JUMPS: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Jump {
+    pub buffer_id: u64,
+    pub cursor: Cursor,
+}
+
+/// Positions a pane jumped away from, walked with back and forward.
+#[derive(Debug, Default)]
+pub struct JumpList {
+    entries: Vec<Jump>,
+    /// Entry the pane is at; `entries.len()` when past the newest one.
+    index: usize,
+}
+
+impl JumpList {
+    pub fn entries(&self) -> &[Jump] {
+        &self.entries
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Remembers `from` before a jump, dropping the entries ahead of it.
+    pub fn record(&mut self, from: Jump) {
+        self.entries.truncate(self.index);
+        if self.entries.last().is_some_and(|last| {
+            last.buffer_id == from.buffer_id && last.cursor.line == from.cursor.line
+        }) {
+            self.entries.pop();
+        }
+        self.entries.push(from);
+        if self.entries.len() > MAX_JUMPS {
+            self.entries.remove(0);
+        }
+        self.index = self.entries.len();
+    }
+
+    pub fn back(&mut self, current: Jump) -> Option<Jump> {
+        let index = self.index.checked_sub(1)?;
+        self.go_to(index, current)
+    }
+
+    pub fn forward(&mut self) -> Option<Jump> {
+        if self.index + 1 >= self.entries.len() {
+            return None;
+        }
+        self.index += 1;
+        Some(self.entries[self.index])
+    }
+
+    /// Moves to entry `index`. Leaving the newest position keeps `current`
+    /// so forward can come back to it.
+    pub fn go_to(&mut self, index: usize, current: Jump) -> Option<Jump> {
+        if index >= self.entries.len() {
+            return None;
+        }
+        if self.index >= self.entries.len() {
+            self.entries.push(current);
+        }
+        self.index = index;
+        Some(self.entries[index])
+    }
+
+    /// Keeps entries of `buffer_id` on the same text after `deleted` was
+    /// replaced by `inserted` at `start`.
+    pub fn apply_edit(&mut self, buffer_id: u64, start: Cursor, deleted: &str, inserted: &str) {
+        let removed = deleted.matches('\n').count();
+        let added = inserted.matches('\n').count();
+        for jump in self
+            .entries
+            .iter_mut()
+            .filter(|jump| jump.buffer_id == buffer_id && jump.cursor.line > start.line)
+        {
+            let line = jump.cursor.line;
+            jump.cursor.line = if line <= start.line + removed {
+                start.line + (line - start.line).min(added)
+            } else {
+                line - removed + added
+            };
+        }
+    }
+}
+
+impl App {
+    /// Records the active pane's position before a large cursor move.
+    pub(crate) fn record_jump(&mut self) {
+        let from = Jump {
+            buffer_id: self.active_buffer_id,
+            cursor: self.active_pane().cursor(),
+        };
+        self.active_pane_mut().jumps_mut().record(from);
+    }
+
+    pub(crate) fn jump_back(&mut self) {
+        let current = self.current_jump();
+        loop {
+            let Some(jump) = self.active_pane_mut().jumps_mut().back(current) else {
+                self.set_message("Jump list start", MessageKind::Info);
+                return;
+            };
+            if self.restore_jump(jump) {
+                return;
+            }
+        }
+    }
+
+    pub(crate) fn jump_forward(&mut self) {
+        loop {
+            let Some(jump) = self.active_pane_mut().jumps_mut().forward() else {
+                self.set_message("Jump list end", MessageKind::Info);
+                return;
+            };
+            if self.restore_jump(jump) {
+                return;
+            }
+        }
+    }
+
+    pub(crate) fn open_jump_list_picker(&mut self) {
+        if self.active_pane().jumps().entries().is_empty() {
+            self.set_message("Jump list is empty", MessageKind::Info);
+            return;
+        }
+        let mut picker = PickerState::new(PickerKind::Jumps);
+        picker.set_buffer_items(self.jump_picker_items(""));
+        let index = self.active_pane().jumps().index();
+        picker.set_selected(index.saturating_sub(1));
+        self.picker = Some(picker);
+    }
+
+    pub(crate) fn jump_picker_items(&self, query: &str) -> Vec<PickerItem> {
+        let query = query.to_lowercase();
+        let jumps = self.active_pane().jumps();
+        jumps
+            .entries()
+            .iter()
+            .enumerate()
+            .filter_map(|(index, jump)| {
+                let buffer = self.buffer_by_id(jump.buffer_id)?;
+                let name = buffer.document.path().map_or_else(
+                    || String::from("[No Name]"),
+                    |path| {
+                        let shown = path.strip_prefix(&self.workspace_root).unwrap_or(path);
+                        shown.display().to_string()
+                    },
+                );
+                let marker = if index == jumps.index() { "> " } else { "  " };
+                let title = format!("{marker}{name}:{}", jump.cursor.line + 1);
+                let preview = buffer.document.line_text(jump.cursor.line).trim().to_owned();
+                Some(PickerItem {
+                    title,
+                    subtitle: preview,
+                    path: None,
+                    buffer_id: Some(jump.buffer_id),
+                    line: Some(jump.cursor.line),
+                    column: Some(jump.cursor.column),
+                    entry: Some(index),
+                })
+            })
+            .filter(|item| item.title.to_lowercase().contains(&query))
+            .collect()
+    }
+
+    /// Jumps to entry `index` of the active pane's jump list.
+    pub(crate) fn go_to_jump(&mut self, index: usize) {
+        let current = self.current_jump();
+        if let Some(jump) = self.active_pane_mut().jumps_mut().go_to(index, current) {
+            self.restore_jump(jump);
+        }
+    }
+
+    /// Moves every pane's jump entries along with an edit of `buffer_id`.
+    pub(crate) fn shift_jumps(
+        &mut self,
+        buffer_id: u64,
+        start: Cursor,
+        deleted: &str,
+        inserted: &str,
+    ) {
+        for pane_id in self.layout.pane_ids() {
+            if let Some(pane) = self.layout.pane_mut(pane_id) {
+                pane.jumps_mut().apply_edit(buffer_id, start, deleted, inserted);
+            }
+        }
+    }
+
+    fn current_jump(&self) -> Jump {
+        Jump {
+            buffer_id: self.active_buffer_id,
+            cursor: self.active_pane().cursor(),
+        }
+    }
+
+    /// Shows a jump's buffer and cursor; `false` when the buffer was closed.
+    fn restore_jump(&mut self, jump: Jump) -> bool {
+        let Some(buffer) = self.buffer_by_id(jump.buffer_id) else {
+            return false;
+        };
+        let line = jump.cursor.line.min(buffer.document.last_line_index());
+        let column = jump.cursor.column.min(buffer.document.line_end(line).column);
+        let cursor = Cursor::new(line, column);
+        self.switch_to_buffer(jump.buffer_id);
+        let pane = self.active_pane_mut();
+        pane.set_cursor(cursor);
+        pane.set_selection(Selection::caret(cursor));
+        self.ensure_cursor_visible();
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Jump, JumpList};
+    use crate::core::Cursor;
+
+    fn jump(buffer_id: u64, line: usize) -> Jump {
+        Jump {
+            buffer_id,
+            cursor: Cursor::new(line, 0),
+        }
+    }
+
+    #[test]
+    fn walks_back_and_forward_and_follows_edits() {
+        let mut jumps = JumpList::default();
+        jumps.record(jump(1, 10));
+        jumps.record(jump(2, 4));
+        assert_eq!(jumps.back(jump(1, 30)), Some(jump(2, 4)));
+        assert_eq!(jumps.back(jump(2, 4)), Some(jump(1, 10)));
+        assert_eq!(jumps.back(jump(1, 10)), None);
+        assert_eq!(jumps.forward(), Some(jump(2, 4)));
+        assert_eq!(jumps.forward(), Some(jump(1, 30)));
+        assert_eq!(jumps.forward(), None);
+
+        // A new jump from the middle drops the entries ahead.
+        jumps.go_to(1, jump(1, 30));
+        jumps.record(jump(1, 12));
+        assert_eq!(jumps.entries(), &[jump(1, 10), jump(1, 12)]);
+
+        // Two lines inserted above both entries, then lines 3..=12 joined.
+        jumps.apply_edit(1, Cursor::new(2, 0), "", "a\nb\n");
+        jumps.apply_edit(2, Cursor::new(0, 0), "", "x\n");
+        assert_eq!(jumps.entries(), &[jump(1, 12), jump(1, 14)]);
+        jumps.apply_edit(1, Cursor::new(3, 0), &"\n".repeat(9), "");
+        assert_eq!(jumps.entries(), &[jump(1, 3), jump(1, 5)]);
+    }
+}
@@ -1,13 +1,20 @@
 mod clipboard;
 mod command;
 mod completion;
+mod hex_actions;
 mod command_bar;
 mod history_actions;
+mod jump_list;
 mod movement;
+mod save_pipeline;
 mod search;
 mod search_actions;
 mod snippet;
 mod text_edit;
+mod word_index;
 
 pub use command::Command;
+pub use jump_list::JumpList;
 pub use search::{SearchMatch, SearchState};
+pub use snippet::SnippetSession;
+pub use word_index::WordIndex;
@@ -59,10 +59,16 @@ impl App {
     }
 
     pub(crate) fn move_document_start(&mut self, extend: bool) {
+        if !extend {
+            self.record_jump();
+        }
         self.update_cursor(Cursor::default(), extend, false);
     }
 
     pub(crate) fn move_document_end(&mut self, extend: bool) {
+        if !extend {
+            self.record_jump();
+        }
         let last_line = self.active_document().last_line_index();
         let target = self.active_document().line_end(last_line);
         self.update_cursor(target, extend, false);
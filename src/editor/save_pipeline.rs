@@ -0,0 +1,101 @@
+use std::path::Path;
+
+use crate::app::App;
+use crate::config::FinalNewline;
+use crate::syntax::{LanguageId, language_for_path};
+
+impl App {
+    pub(crate) fn apply_save_transforms(&mut self, path: &Path) {
+        let language = language_for_path(path);
+        let rules = self.save_config.rules_for(language);
+
+        if rules.format_on_save
+            && let Some((edits, encoding)) =
+                self.lsp.format_edits_within(path, &self.workspace_root, rules.format_timeout)
+        {
+            let _ = self.apply_buffer_text_edits(self.active_buffer_id, &edits, encoding);
+        }
+
+        if rules.trim_trailing_whitespace {
+            let markdown = language == Some(LanguageId::Markdown);
+            let trimmed = trim_trailing_whitespace(&self.active_document().text(), markdown);
+            self.replace_active_text(&trimmed);
+        }
+
+        let text = self.active_document().text();
+        match rules.final_newline {
+            FinalNewline::Ensure => {
+                self.replace_active_text(&ensure_final_newline(&text));
+            }
+            FinalNewline::Strip => {
+                self.replace_active_text(strip_final_newlines(&text));
+            }
+            FinalNewline::Keep => {}
+        }
+    }
+}
+
+fn trim_trailing_whitespace(text: &str, markdown: bool) -> String {
+    let mut output = String::with_capacity(text.len());
+    for segment in text.split_inclusive('\n') {
+        let (line, newline) = match segment.strip_suffix('\n') {
+            Some(line) => (line, "\n"),
+            None => (segment, ""),
+        };
+        let trimmed = line.trim_end_matches([' ', '\t']);
+        if markdown && is_markdown_hard_break(line, trimmed) {
+            output.push_str(line);
+        } else {
+            output.push_str(trimmed);
+        }
+        output.push_str(newline);
+    }
+    output
+}
+
+/// Two or more trailing spaces after content form a Markdown hard line break.
+fn is_markdown_hard_break(line: &str, trimmed: &str) -> bool {
+    let trailing = &line[trimmed.len()..];
+    !trimmed.is_empty() && trailing.len() >= 2 && trailing.bytes().all(|byte| byte == b' ')
+}
+
+fn ensure_final_newline(text: &str) -> String {
+    if text.is_empty() || text.ends_with('\n') {
+        text.to_owned()
+    } else {
+        format!("{text}\n")
+    }
+}
+
+fn strip_final_newlines(text: &str) -> &str {
+    text.trim_end_matches('\n')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ensure_final_newline, strip_final_newlines, trim_trailing_whitespace};
+
+    #[test]
+    fn trims_whitespace_but_keeps_markdown_hard_breaks() {
+        let text = "fn main() {  \n\tlet a = 1;\t\n}   ";
+        assert_eq!(
+            trim_trailing_whitespace(text, false),
+            "fn main() {\n\tlet a = 1;\n}"
+        );
+
+        let markdown = "first line  \nsecond line \n   \nthird\t\n";
+        assert_eq!(
+            trim_trailing_whitespace(markdown, true),
+            "first line  \nsecond line\n\nthird\n"
+        );
+    }
+
+    #[test]
+    fn ensures_or_strips_final_newline() {
+        assert_eq!(ensure_final_newline("a\nb"), "a\nb\n");
+        assert_eq!(ensure_final_newline("a\n"), "a\n");
+        assert_eq!(ensure_final_newline(""), "");
+        assert_eq!(strip_final_newlines("a\n\n"), "a");
+        assert_eq!(strip_final_newlines("a"), "a");
+    }
+}
@@ -13,6 +13,8 @@ pub struct SearchState {
     preview_query: Option<String>,
     matches: Vec<SearchMatch>,
     active_index: Option<usize>,
+    /// Next line of a confirmed query still being scanned in chunks.
+    scan_line: Option<usize>,
 }
 
 impl SearchState {
@@ -55,6 +57,40 @@ impl SearchState {
         self.preview_query = None;
         self.matches.clear();
         self.active_index = None;
+        self.scan_line = None;
+    }
+
+    /// Confirms `query` without scanning yet; `continue_scan` then finds
+    /// the matches a chunk of lines at a time.
+    pub fn confirm_chunked(&mut self, query: String) {
+        self.confirmed_query = query;
+        self.preview_query = None;
+        self.matches.clear();
+        self.active_index = None;
+        self.scan_line = Some(0);
+    }
+
+    pub fn is_scanning(&self) -> bool {
+        self.scan_line.is_some()
+    }
+
+    /// Scans up to `max_lines` more lines of a chunked search. Returns
+    /// `true` once the scan finished, with the active match picked.
+    pub fn continue_scan(&mut self, document: &Document, cursor: Cursor, max_lines: usize) -> bool {
+        let Some(start) = self.scan_line else {
+            return false;
+        };
+        let end = start.saturating_add(max_lines).min(document.line_count());
+        for line in start..end {
+            collect_line_matches(document, line, &self.confirmed_query, &mut self.matches);
+        }
+        if end < document.line_count() {
+            self.scan_line = Some(end);
+            return false;
+        }
+        self.scan_line = None;
+        self.active_index = find_active_match_index(&self.matches, cursor);
+        true
     }
 
     pub fn refresh_for_document(&mut self, document: &Document, cursor: Cursor) {
@@ -65,6 +101,56 @@ impl SearchState {
         self.recompute(&query, document, cursor);
     }
 
+    /// Rescans only the lines touched by an edit of `start_line..=old_end_line`
+    /// that now spans `start_line..=new_end_line`, shifting later matches.
+    pub fn refresh_lines(
+        &mut self,
+        document: &Document,
+        start_line: usize,
+        old_end_line: usize,
+        new_end_line: usize,
+        cursor: Cursor,
+    ) {
+        let query = self
+            .preview_query
+            .clone()
+            .unwrap_or_else(|| self.confirmed_query.clone());
+        if query.is_empty() {
+            self.matches.clear();
+            self.active_index = None;
+            return;
+        }
+        if self.scan_line.is_some() {
+            // Lines before the scan position moved; scan again from the top.
+            self.matches.clear();
+            self.scan_line = Some(0);
+            return;
+        }
+
+        let line_delta = new_end_line as isize - old_end_line as isize;
+        let mut matches = Vec::with_capacity(self.matches.len());
+        matches.extend(
+            self.matches
+                .iter()
+                .copied()
+                .take_while(|item| item.line < start_line),
+        );
+        for line in start_line..=new_end_line.min(document.last_line_index()) {
+            collect_line_matches(document, line, &query, &mut matches);
+        }
+        matches.extend(
+            self.matches
+                .iter()
+                .filter(|item| item.line > old_end_line)
+                .map(|item| SearchMatch {
+                    line: item.line.saturating_add_signed(line_delta),
+                    ..*item
+                }),
+        );
+        self.matches = matches;
+        self.active_index = find_active_match_index(&self.matches, cursor);
+    }
+
     pub fn select_next(&mut self) -> Option<SearchMatch> {
         let len = self.matches.len();
         if len == 0 {
@@ -108,6 +194,7 @@ impl SearchState {
     }
 
     fn recompute(&mut self, query: &str, document: &Document, cursor: Cursor) {
+        self.scan_line = None;
         self.matches = if query.is_empty() {
             Vec::new()
         } else {
@@ -118,41 +205,49 @@ impl SearchState {
 }
 
 fn collect_matches(document: &Document, query: &str) -> Vec<SearchMatch> {
+    let mut matches = Vec::new();
+    for line in 0..document.line_count() {
+        collect_line_matches(document, line, query, &mut matches);
+    }
+    matches
+}
+
+fn collect_line_matches(
+    document: &Document,
+    line: usize,
+    query: &str,
+    matches: &mut Vec<SearchMatch>,
+) {
     let case_sensitive = query.chars().any(char::is_uppercase);
     let query_lower = query.to_lowercase();
     let needle_chars: Vec<char> = query.chars().collect();
     let needle_len = needle_chars.len();
     if needle_len == 0 {
-        return Vec::new();
+        return;
     }
 
-    let mut matches = Vec::new();
-    for line in 0..document.line_count() {
-        let line_text = document.line_text(line);
-        let chars: Vec<char> = line_text.chars().collect();
-        let mut index = 0usize;
-        while index + needle_len <= chars.len() {
-            let hay = chars[index..index + needle_len].iter().collect::<String>();
-            let matched = if case_sensitive {
-                hay == query
-            } else {
-                hay.to_lowercase() == query_lower
-            };
-
-            if matched {
-                matches.push(SearchMatch {
-                    line,
-                    start_column: index,
-                    end_column: index + needle_len,
-                });
-                index += needle_len;
-            } else {
-                index += 1;
-            }
+    let line_text = document.line_text(line);
+    let chars: Vec<char> = line_text.chars().collect();
+    let mut index = 0usize;
+    while index + needle_len <= chars.len() {
+        let hay = chars[index..index + needle_len].iter().collect::<String>();
+        let matched = if case_sensitive {
+            hay == query
+        } else {
+            hay.to_lowercase() == query_lower
+        };
+
+        if matched {
+            matches.push(SearchMatch {
+                line,
+                start_column: index,
+                end_column: index + needle_len,
+            });
+            index += needle_len;
+        } else {
+            index += 1;
         }
     }
-
-    matches
 }
 
 fn find_active_match_index(matches: &[SearchMatch], cursor: Cursor) -> Option<usize> {
@@ -189,4 +284,37 @@ mod tests {
         state.begin_preview(String::from("Hello"), &document, Cursor::new(0, 0));
         assert_eq!(state.matches().len(), 1);
     }
+
+    #[test]
+    fn refresh_lines_rescans_edit_and_shifts_later_matches() {
+        let mut document = Document::from_text(None, "foo\nbar\nfoo");
+        let mut state = SearchState::default();
+        state.begin_preview(String::from("foo"), &document, Cursor::new(0, 0));
+        assert_eq!(state.matches().len(), 2);
+
+        document.insert_text(Cursor::new(1, 0), "foo\n");
+        state.refresh_lines(&document, 1, 1, 2, Cursor::new(0, 0));
+        let lines = state
+            .matches()
+            .iter()
+            .map(|item| item.line)
+            .collect::<Vec<_>>();
+        assert_eq!(lines, vec![0, 1, 3]);
+    }
+
+    #[test]
+    fn chunked_scan_finds_matches_over_several_calls() {
+        let document = Document::from_text(None, "foo\nbar\nfoo\nbaz\nfoo");
+        let mut state = SearchState::default();
+        state.confirm_chunked(String::from("foo"));
+        assert!(!state.continue_scan(&document, Cursor::new(1, 0), 2));
+        assert_eq!(state.matches().len(), 1);
+        assert_eq!(state.active_match(), None);
+        assert!(!state.continue_scan(&document, Cursor::new(1, 0), 2));
+        assert!(state.continue_scan(&document, Cursor::new(1, 0), 2));
+        assert!(!state.is_scanning());
+        assert_eq!(state.matches().len(), 3);
+        assert_eq!(state.active_match().map(|item| item.line), Some(2));
+        assert_eq!(state.confirmed_query(), "foo");
+    }
 }
@@ -2,7 +2,40 @@ use crate::app::App;
 use crate::core::{Cursor, Selection};
 use crate::editor::SearchMatch;
 
+/// Lines a chunked search scans per event loop iteration.
+const SEARCH_SCAN_LINES: usize = 20_000;
+
 impl App {
+    pub(crate) fn search_scan_pending(&self) -> bool {
+        self.layout.pane_ids().into_iter().any(|pane_id| {
+            self.layout
+                .pane(pane_id)
+                .is_some_and(|pane| pane.search().is_scanning())
+        })
+    }
+
+    /// Advances the chunked searches of large files, moving the active
+    /// pane's cursor to its first match once its scan finishes.
+    pub(crate) fn continue_search_scans(&mut self) {
+        let active_pane_id = self.active_pane_id();
+        for pane_id in self.layout.pane_ids() {
+            let (layout, buffers) = (&mut self.layout, &self.buffers);
+            let Some(pane) = layout.pane_mut(pane_id) else {
+                continue;
+            };
+            let cursor = pane.cursor();
+            let Some(buffer) = buffers.iter().find(|buffer| buffer.id == pane.buffer_id()) else {
+                continue;
+            };
+            let finished = pane
+                .search_mut()
+                .continue_scan(&buffer.document, cursor, SEARCH_SCAN_LINES);
+            if finished && pane_id == active_pane_id {
+                self.jump_to_active_search_match();
+            }
+        }
+    }
+
     pub(crate) fn search_next(&mut self) {
         let next_match = self.active_pane_mut().search_mut().select_next();
         if let Some(item) = next_match {
@@ -1,14 +1,890 @@
-pub fn expand_snippet_body(body: &str) -> String {
-    let mut output = String::with_capacity(body.len());
-    let mut chars = body.chars().peekable();
-    while let Some(ch) = chars.next() {
-        if ch == '$' {
-            while chars.peek().is_some_and(|next| next.is_ascii_digit()) {
-                let _ = chars.next();
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::Path;
+
+use chrono::{DateTime, TimeZone};
+
+use crate::app::{App, MessageKind};
+use crate::core::{Cursor, Selection};
+use crate::lsp::{CompletionItemView, CompletionSource};
+use crate::ui::{PickerItem, PickerKind, PickerState};
+
+/// Parsed LSP snippet syntax, before variables and mirrors are resolved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Node {
+    Text(String),
+    /// `$1`, `${1}` or `${1:placeholder}`; `children` holds the placeholder.
+    Tabstop { number: u32, children: Vec<Node> },
+    /// `${1|one,two|}`.
+    Choice { number: u32, options: Vec<String> },
+    /// `$NAME`, `${NAME}` or `${NAME:default}`; transforms are dropped.
+    Variable { name: String, default: Vec<Node> },
+}
+
+/// Text of an expanded snippet and where its tabstops ended up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snippet {
+    pub text: String,
+    /// In visiting order with `$0` last.
+    pub tabstops: Vec<SnippetTabstop>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnippetTabstop {
+    pub number: u32,
+    /// Byte ranges in `Snippet::text`; the first one is edited and the
+    /// others mirror it.
+    pub ranges: Vec<Range<usize>>,
+    pub choices: Vec<String>,
+}
+
+impl Snippet {
+    /// Expands `body`, asking `variable` for the value of `$NAME`s.
+    /// Unknown variables insert their name, empty ones their default.
+    pub fn parse(body: &str, variable: &dyn Fn(&str) -> Option<String>) -> Self {
+        let chars = body.chars().collect::<Vec<_>>();
+        let mut position = 0;
+        let nodes = parse_nodes(&chars, &mut position, false);
+
+        let mut values = HashMap::new();
+        collect_values(&nodes, variable, &mut values);
+        let mut expander = Expander {
+            variable,
+            values: &values,
+            text: String::new(),
+            ranges: Vec::new(),
+            choices: HashMap::new(),
+            defined: Vec::new(),
+        };
+        expander.expand(&nodes);
+
+        let Expander {
+            text,
+            ranges,
+            mut choices,
+            ..
+        } = expander;
+        let mut tabstops: Vec<SnippetTabstop> = Vec::new();
+        for (number, range, primary) in ranges {
+            let index = match tabstops.iter().position(|stop| stop.number == number) {
+                Some(index) => index,
+                None => {
+                    tabstops.push(SnippetTabstop {
+                        number,
+                        ranges: Vec::new(),
+                        choices: choices.remove(&number).unwrap_or_default(),
+                    });
+                    tabstops.len() - 1
+                }
+            };
+            let ranges = &mut tabstops[index].ranges;
+            if primary {
+                ranges.insert(0, range);
+            } else {
+                ranges.push(range);
+            }
+        }
+        if !tabstops.iter().any(|stop| stop.number == 0) {
+            tabstops.push(SnippetTabstop {
+                number: 0,
+                ranges: std::iter::once(text.len()..text.len()).collect(),
+                choices: Vec::new(),
+            });
+        }
+        tabstops.sort_by_key(|stop| if stop.number == 0 { u32::MAX } else { stop.number });
+        Self { text, tabstops }
+    }
+}
+
+fn parse_nodes(chars: &[char], position: &mut usize, nested: bool) -> Vec<Node> {
+    let mut nodes = Vec::new();
+    let mut text = String::new();
+    while let Some(&ch) = chars.get(*position) {
+        if nested && ch == '}' {
+            break;
+        }
+        *position += 1;
+        match ch {
+            '\\' => match chars.get(*position) {
+                Some(&next @ ('$' | '}' | '\\')) => {
+                    text.push(next);
+                    *position += 1;
+                }
+                _ => text.push('\\'),
+            },
+            '$' => {
+                let start = *position;
+                match parse_dollar(chars, position) {
+                    Some(node) => {
+                        if !text.is_empty() {
+                            nodes.push(Node::Text(std::mem::take(&mut text)));
+                        }
+                        nodes.push(node);
+                    }
+                    None => {
+                        *position = start;
+                        text.push('$');
+                    }
+                }
+            }
+            _ => text.push(ch),
+        }
+    }
+    if !text.is_empty() {
+        nodes.push(Node::Text(text));
+    }
+    nodes
+}
+
+/// Parses what follows a `$`; `None` leaves it as literal text.
+fn parse_dollar(chars: &[char], position: &mut usize) -> Option<Node> {
+    if let Some(number) = parse_number(chars, position) {
+        return Some(Node::Tabstop {
+            number,
+            children: Vec::new(),
+        });
+    }
+    if let Some(name) = parse_name(chars, position) {
+        return Some(Node::Variable {
+            name,
+            default: Vec::new(),
+        });
+    }
+    if chars.get(*position) != Some(&'{') {
+        return None;
+    }
+    *position += 1;
+
+    if let Some(number) = parse_number(chars, position) {
+        return match chars.get(*position)? {
+            '}' => {
+                *position += 1;
+                Some(Node::Tabstop {
+                    number,
+                    children: Vec::new(),
+                })
+            }
+            ':' => {
+                *position += 1;
+                let children = parse_nodes(chars, position, true);
+                expect(chars, position, '}')?;
+                Some(Node::Tabstop { number, children })
+            }
+            '|' => {
+                *position += 1;
+                let options = parse_choices(chars, position)?;
+                Some(Node::Choice { number, options })
+            }
+            _ => None,
+        };
+    }
+
+    let name = parse_name(chars, position)?;
+    match chars.get(*position)? {
+        '}' => {
+            *position += 1;
+            Some(Node::Variable {
+                name,
+                default: Vec::new(),
+            })
+        }
+        ':' => {
+            *position += 1;
+            let default = parse_nodes(chars, position, true);
+            expect(chars, position, '}')?;
+            Some(Node::Variable { name, default })
+        }
+        '/' => {
+            skip_transform(chars, position)?;
+            Some(Node::Variable {
+                name,
+                default: Vec::new(),
+            })
+        }
+        _ => None,
+    }
+}
+
+fn parse_number(chars: &[char], position: &mut usize) -> Option<u32> {
+    let start = *position;
+    while chars.get(*position).is_some_and(char::is_ascii_digit) {
+        *position += 1;
+    }
+    chars[start..*position].iter().collect::<String>().parse().ok()
+}
+
+fn parse_name(chars: &[char], position: &mut usize) -> Option<String> {
+    let first = chars.get(*position)?;
+    if !(first.is_ascii_alphabetic() || *first == '_') {
+        return None;
+    }
+    let start = *position;
+    while chars
+        .get(*position)
+        .is_some_and(|ch| ch.is_ascii_alphanumeric() || *ch == '_')
+    {
+        *position += 1;
+    }
+    Some(chars[start..*position].iter().collect())
+}
+
+fn parse_choices(chars: &[char], position: &mut usize) -> Option<Vec<String>> {
+    let mut options = Vec::new();
+    let mut option = String::new();
+    loop {
+        let ch = *chars.get(*position)?;
+        *position += 1;
+        match ch {
+            '\\' => match chars.get(*position) {
+                Some(&next @ ('$' | '}' | '\\' | ',' | '|')) => {
+                    option.push(next);
+                    *position += 1;
+                }
+                _ => option.push('\\'),
+            },
+            ',' => options.push(std::mem::take(&mut option)),
+            '|' => {
+                options.push(option);
+                expect(chars, position, '}')?;
+                return Some(options);
+            }
+            _ => option.push(ch),
+        }
+    }
+}
+
+/// Skips `/regex/format/options}` after a variable name.
+fn skip_transform(chars: &[char], position: &mut usize) -> Option<()> {
+    let mut depth = 0usize;
+    loop {
+        let ch = *chars.get(*position)?;
+        *position += 1;
+        match ch {
+            '\\' => *position += 1,
+            '{' => depth += 1,
+            '}' if depth == 0 => return Some(()),
+            '}' => depth -= 1,
+            _ => {}
+        }
+    }
+}
+
+fn expect(chars: &[char], position: &mut usize, expected: char) -> Option<()> {
+    (chars.get(*position) == Some(&expected)).then(|| *position += 1)
+}
+
+/// Text of each tabstop's first placeholder, which its mirrors repeat.
+fn collect_values(
+    nodes: &[Node],
+    variable: &dyn Fn(&str) -> Option<String>,
+    values: &mut HashMap<u32, String>,
+) {
+    for node in nodes {
+        match node {
+            Node::Tabstop { number, children } if !children.is_empty() => {
+                collect_values(children, variable, values);
+                if !values.contains_key(number) {
+                    let value = plain_text(children, variable, values);
+                    values.insert(*number, value);
+                }
+            }
+            Node::Choice { number, options } => {
+                let first = options.first().cloned().unwrap_or_default();
+                values.entry(*number).or_insert(first);
+            }
+            Node::Variable { default, .. } => collect_values(default, variable, values),
+            _ => {}
+        }
+    }
+}
+
+fn plain_text(
+    nodes: &[Node],
+    variable: &dyn Fn(&str) -> Option<String>,
+    values: &HashMap<u32, String>,
+) -> String {
+    let mut expander = Expander {
+        variable,
+        values,
+        text: String::new(),
+        ranges: Vec::new(),
+        choices: HashMap::new(),
+        defined: Vec::new(),
+    };
+    expander.expand(nodes);
+    expander.text
+}
+
+struct Expander<'a> {
+    variable: &'a dyn Fn(&str) -> Option<String>,
+    values: &'a HashMap<u32, String>,
+    text: String,
+    /// Tabstop number, byte range and whether it is the edited occurrence.
+    ranges: Vec<(u32, Range<usize>, bool)>,
+    choices: HashMap<u32, Vec<String>>,
+    /// Tabstops whose placeholder was already written out.
+    defined: Vec<u32>,
+}
+
+impl Expander<'_> {
+    fn expand(&mut self, nodes: &[Node]) {
+        for node in nodes {
+            let start = self.text.len();
+            match node {
+                Node::Text(text) => self.text.push_str(text),
+                Node::Tabstop { number, children } => {
+                    let primary = !self.defined.contains(number)
+                        && (!children.is_empty() || !self.values.contains_key(number));
+                    if primary {
+                        self.defined.push(*number);
+                        self.expand(children);
+                    } else if let Some(value) = self.values.get(number) {
+                        self.text.push_str(value);
+                    }
+                    let range = start..self.text.len();
+                    self.ranges.push((*number, range, primary));
+                }
+                Node::Choice { number, options } => {
+                    let primary = !self.defined.contains(number);
+                    self.defined.push(*number);
+                    self.text.push_str(options.first().map_or("", String::as_str));
+                    self.choices.entry(*number).or_insert_with(|| options.clone());
+                    let range = start..self.text.len();
+                    self.ranges.push((*number, range, primary));
+                }
+                Node::Variable { name, default } => match (self.variable)(name) {
+                    Some(value) if !value.is_empty() => self.text.push_str(&value),
+                    Some(_) => self.expand(default),
+                    None if default.is_empty() => self.text.push_str(name),
+                    None => self.expand(default),
+                },
+            }
+        }
+    }
+}
+
+/// Where `position` ends up after `deleted` at `start` was replaced by
+/// `inserted`. With `after`, positions at the edit move past the inserted
+/// text; otherwise they stay before it.
+fn shift_position(
+    position: Cursor,
+    start: Cursor,
+    deleted: &str,
+    inserted: &str,
+    after: bool,
+) -> Cursor {
+    let old_end = text_end(start, deleted);
+    let new_end = text_end(start, inserted);
+    if position < start || (position == start && !after) {
+        return position;
+    }
+    if position < old_end || (position == old_end && position == start) {
+        return if after { new_end } else { start };
+    }
+    if position.line == old_end.line {
+        Cursor::new(new_end.line, new_end.column + position.column - old_end.column)
+    } else {
+        Cursor::new(position.line - old_end.line + new_end.line, position.column)
+    }
+}
+
+fn text_end(start: Cursor, text: &str) -> Cursor {
+    match text.rsplit_once('\n') {
+        Some((before, last)) => Cursor::new(
+            start.line + before.matches('\n').count() + 1,
+            last.chars().count(),
+        ),
+        None => Cursor::new(start.line, start.column + text.chars().count()),
+    }
+}
+
+/// Tabstops of an inserted snippet, walked with Tab and Shift+Tab.
+#[derive(Debug, Clone)]
+pub struct SnippetSession {
+    buffer_id: u64,
+    /// Document ranges of each tabstop, in visiting order with `$0` last.
+    tabstops: Vec<Vec<(Cursor, Cursor)>>,
+    choices: Vec<Vec<String>>,
+    active: usize,
+}
+
+impl SnippetSession {
+    fn new(buffer_id: u64, snippet: &Snippet, start: Cursor) -> Self {
+        let at = |offset: usize| text_end(start, &snippet.text[..offset]);
+        Self {
+            buffer_id,
+            tabstops: snippet
+                .tabstops
+                .iter()
+                .map(|stop| {
+                    stop.ranges
+                        .iter()
+                        .map(|range| (at(range.start), at(range.end)))
+                        .collect()
+                })
+                .collect(),
+            choices: snippet
+                .tabstops
+                .iter()
+                .map(|stop| stop.choices.clone())
+                .collect(),
+            active: 0,
+        }
+    }
+
+    /// Range of the tabstop being edited.
+    fn current(&self) -> Option<(Cursor, Cursor)> {
+        self.tabstops.get(self.active)?.first().copied()
+    }
+
+    fn is_last(&self) -> bool {
+        self.active + 1 >= self.tabstops.len()
+    }
+
+    /// First and last position covered by any tabstop.
+    fn extent(&self) -> Option<(Cursor, Cursor)> {
+        let ranges = self.tabstops.iter().flatten();
+        let start = ranges.clone().map(|(start, _)| *start).min()?;
+        let end = ranges.map(|(_, end)| *end).max()?;
+        Some((start, end))
+    }
+
+    /// Keeps tabstops on their text after an edit. Edits at the edge of the
+    /// active tabstop grow it, while other tabstops do not take them in.
+    fn apply_edit(&mut self, start: Cursor, deleted: &str, inserted: &str) {
+        for (index, ranges) in self.tabstops.iter_mut().enumerate() {
+            let active = index == self.active;
+            for (range_start, range_end) in ranges {
+                *range_start = shift_position(*range_start, start, deleted, inserted, !active);
+                *range_end = shift_position(*range_end, start, deleted, inserted, active);
+                *range_end = (*range_end).max(*range_start);
             }
-            continue;
         }
-        output.push(ch);
     }
-    output
+}
+
+impl App {
+    /// Starts a session on `snippet`, already inserted at `start` of the
+    /// active buffer. Snippets without tabstops just move to `$0`.
+    pub(crate) fn start_snippet(&mut self, snippet: &Snippet, start: Cursor) {
+        let session = SnippetSession::new(self.active_buffer_id, snippet, start);
+        self.snippet = Some(session);
+        self.select_snippet_tabstop(0);
+    }
+
+    /// Expands `body` for the active buffer, reading the clipboard and the
+    /// local time only when the body refers to them.
+    pub(crate) fn expand_snippet(&mut self, body: &str) -> Snippet {
+        let clipboard = if body.contains("CLIPBOARD") {
+            self.clipboard
+                .as_mut()
+                .and_then(|clipboard| clipboard.paste().ok())
+        } else {
+            None
+        };
+        let now = body.contains("CURRENT_").then(chrono::Local::now);
+        Snippet::parse(body, &|name| match name {
+            "CLIPBOARD" => Some(clipboard.clone().unwrap_or_default()),
+            _ if name.starts_with("CURRENT_") => {
+                now.as_ref().and_then(|now| date_variable(name, now))
+            }
+            _ => self.snippet_variable(name),
+        })
+    }
+
+    /// Replaces `start..end` of the active buffer with the expanded `body`
+    /// and starts its session.
+    pub(crate) fn insert_snippet(&mut self, body: &str, start: Cursor, end: Cursor) {
+        let snippet = self.expand_snippet(body);
+        self.apply_edit(start, end, &snippet.text, false);
+        // Read-only buffers refuse the edit, leaving the cursor behind.
+        if self.active_pane().cursor() == text_end(start, &snippet.text) {
+            self.start_snippet(&snippet, start);
+        }
+    }
+
+    pub(crate) fn open_snippet_picker(&mut self) {
+        let items = self.snippet_picker_items("");
+        if items.is_empty() {
+            self.set_message("No snippets for this buffer", MessageKind::Info);
+            return;
+        }
+        let mut picker = PickerState::new(PickerKind::Snippets);
+        picker.set_buffer_items(items);
+        self.picker = Some(picker);
+    }
+
+    pub(crate) fn snippet_picker_items(&self, query: &str) -> Vec<PickerItem> {
+        let query = query.to_lowercase();
+        let language = self.active_buffer().syntax.language_id();
+        self.snippets
+            .for_language(language)
+            .map(|(index, snippet)| PickerItem {
+                title: format!("{}  {}", snippet.prefixes.join(", "), snippet.name),
+                subtitle: snippet.description.clone(),
+                path: None,
+                buffer_id: None,
+                line: None,
+                column: None,
+                entry: Some(index),
+            })
+            .filter(|item| {
+                item.title.to_lowercase().contains(&query)
+                    || item.subtitle.to_lowercase().contains(&query)
+            })
+            .collect()
+    }
+
+    /// Inserts library snippet `index` over the selection or at the cursor.
+    pub(crate) fn insert_user_snippet(&mut self, index: usize) {
+        let Some(body) = self.snippets.get(index).map(|snippet| snippet.body.clone()) else {
+            return;
+        };
+        let cursor = self.active_pane().cursor();
+        let (start, end) = self
+            .active_pane()
+            .selection()
+            .normalized()
+            .unwrap_or((cursor, cursor));
+        self.insert_snippet(&body, start, end);
+    }
+
+    /// Library snippets whose prefix starts with `prefix`, as completion
+    /// items.
+    pub(crate) fn snippet_completion_items(&self, prefix: &str) -> Vec<CompletionItemView> {
+        if prefix.is_empty() {
+            return Vec::new();
+        }
+        let language = self.active_buffer().syntax.language_id();
+        let mut items = Vec::new();
+        for (index, snippet) in self.snippets.for_language(language) {
+            let documentation = format!("{}\n\n```\n{}\n```", snippet.description, snippet.body);
+            for label in &snippet.prefixes {
+                if !label.starts_with(prefix) {
+                    continue;
+                }
+                items.push(CompletionItemView {
+                    id: index,
+                    label: label.clone(),
+                    detail: snippet.name.clone(),
+                    documentation: documentation.trim_start().to_owned(),
+                    resolved: true,
+                    kind: 15,
+                    insert_text: snippet.body.clone(),
+                    is_snippet: true,
+                    filter_text: label.clone(),
+                    sort_text: label.clone(),
+                    source: CompletionSource::Snippet,
+                    ..CompletionItemView::default()
+                });
+            }
+        }
+        items
+    }
+
+    pub(crate) fn snippet_active(&self) -> bool {
+        self.snippet.is_some()
+    }
+
+    pub(crate) fn end_snippet(&mut self) {
+        self.snippet = None;
+    }
+
+    pub(crate) fn next_snippet_tabstop(&mut self) {
+        if let Some(session) = self.snippet.as_ref() {
+            self.select_snippet_tabstop(session.active + 1);
+        }
+    }
+
+    pub(crate) fn previous_snippet_tabstop(&mut self) {
+        if let Some(session) = self.snippet.as_ref() {
+            self.select_snippet_tabstop(session.active.saturating_sub(1));
+        }
+    }
+
+    /// Selects tabstop `index` and offers its choices; reaching `$0` ends
+    /// the session.
+    fn select_snippet_tabstop(&mut self, index: usize) {
+        let Some(session) = self.snippet.as_mut() else {
+            return;
+        };
+        session.active = index.min(session.tabstops.len().saturating_sub(1));
+        let Some((start, end)) = session.current() else {
+            self.snippet = None;
+            return;
+        };
+        let choices = session.choices[session.active].clone();
+        if session.is_last() {
+            self.snippet = None;
+        }
+        let pane = self.active_pane_mut();
+        pane.set_cursor(end);
+        pane.set_selection(Selection::caret(start).with_active(end));
+        self.ensure_cursor_visible();
+        self.close_completion();
+        if choices.len() > 1 {
+            self.show_snippet_choices(start, end, choices);
+        }
+    }
+
+    /// Lists a choice tabstop's options in the completion popup.
+    fn show_snippet_choices(&mut self, start: Cursor, end: Cursor, choices: Vec<String>) {
+        let items = choices
+            .into_iter()
+            .enumerate()
+            .map(|(index, choice)| CompletionItemView {
+                id: index,
+                label: choice.clone(),
+                insert_text: choice.clone(),
+                filter_text: choice,
+                sort_text: format!("{index:04}"),
+                resolved: true,
+                source: CompletionSource::Snippet,
+                ..CompletionItemView::default()
+            })
+            .collect();
+        let completion = &mut self.lsp.completion;
+        completion.set_items(end, items, false);
+        completion.start = start;
+    }
+
+    /// Moves the session's tabstops along with an edit of `buffer_id`.
+    pub(crate) fn shift_snippet(
+        &mut self,
+        buffer_id: u64,
+        start: Cursor,
+        deleted: &str,
+        inserted: &str,
+    ) {
+        if let Some(session) = self.snippet.as_mut()
+            && session.buffer_id == buffer_id
+        {
+            session.apply_edit(start, deleted, inserted);
+        }
+    }
+
+    /// Ends the session once the cursor left the snippet, and otherwise
+    /// copies the edited tabstop's text into its mirrors.
+    pub(crate) fn update_snippet(&mut self) {
+        let Some(session) = self.snippet.as_ref() else {
+            return;
+        };
+        let cursor = self.active_pane().cursor();
+        let inside = session
+            .extent()
+            .is_some_and(|(start, end)| start <= cursor && cursor <= end);
+        if session.buffer_id != self.active_buffer_id || !inside {
+            self.snippet = None;
+            return;
+        }
+        let Some((start, end)) = session.current() else {
+            return;
+        };
+        let text = self.active_document().slice_string(start, end);
+        let mut mirrors = session.tabstops[session.active][1..].to_vec();
+        mirrors.sort_by(|left, right| right.cmp(left));
+        for (mirror, _) in mirrors {
+            self.update_snippet_mirror(mirror, &text);
+        }
+    }
+
+    /// Replaces the mirror starting at `mirror` with `text`, as part of the
+    /// undo step of the edit to the tabstop it copies.
+    fn update_snippet_mirror(&mut self, mirror: Cursor, text: &str) {
+        let buffer_id = self.active_buffer_id;
+        let Some(end) = self.snippet.as_ref().and_then(|session| {
+            session.tabstops[session.active][1..]
+                .iter()
+                .find(|(start, _)| *start == mirror)
+                .map(|(_, end)| *end)
+        }) else {
+            return;
+        };
+        if self.active_document().slice_string(mirror, end) == text {
+            return;
+        }
+        let Some(buffer_index) = self.buffers.iter().position(|buffer| buffer.id == buffer_id)
+        else {
+            return;
+        };
+        let cursor = self.active_pane().cursor();
+        let anchor = self.active_pane().selection().anchor();
+        let Some((deleted, _)) = self.edit_buffer(buffer_index, mirror, end, text, cursor, false)
+        else {
+            return;
+        };
+        self.buffers[buffer_index].history.join_last();
+        self.notify_lsp_buffer_edit(buffer_id, mirror, &deleted, text);
+        let cursor = shift_position(cursor, mirror, &deleted, text, true);
+        let anchor = shift_position(anchor, mirror, &deleted, text, false);
+        let pane = self.active_pane_mut();
+        pane.set_cursor(cursor);
+        pane.set_selection(Selection::caret(anchor).with_active(cursor));
+    }
+
+    /// Value of a snippet `$NAME` variable; `None` for unknown names.
+    pub(crate) fn snippet_variable(&self, name: &str) -> Option<String> {
+        let cursor = self.active_pane().cursor();
+        let document = self.active_document();
+        let path = document.path();
+        let file_name = |path: &Path| {
+            path.file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        };
+        let value = match name {
+            "TM_SELECTED_TEXT" => self.selection_text().unwrap_or_default(),
+            "TM_CURRENT_LINE" => document.line_text(cursor.line),
+            "TM_CURRENT_WORD" => document
+                .word_range_at(cursor)
+                .map(|(start, end)| document.slice_string(start, end))
+                .unwrap_or_default(),
+            "TM_LINE_INDEX" => cursor.line.to_string(),
+            "TM_LINE_NUMBER" => (cursor.line + 1).to_string(),
+            "TM_FILENAME" => path.map(file_name).unwrap_or_default(),
+            "TM_FILENAME_BASE" => path
+                .and_then(Path::file_stem)
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            "TM_DIRECTORY" => path
+                .and_then(Path::parent)
+                .map(|parent| parent.display().to_string())
+                .unwrap_or_default(),
+            "TM_FILEPATH" => path.map(|path| path.display().to_string()).unwrap_or_default(),
+            "RELATIVE_FILEPATH" => path
+                .map(|path| {
+                    let shown = path.strip_prefix(&self.workspace_root).unwrap_or(path);
+                    shown.display().to_string()
+                })
+                .unwrap_or_default(),
+            "WORKSPACE_NAME" => file_name(&self.workspace_root),
+            "WORKSPACE_FOLDER" => self.workspace_root.display().to_string(),
+            _ => return None,
+        };
+        Some(value)
+    }
+}
+
+/// `$CURRENT_*` date variables for `now`, in its time zone.
+fn date_variable<Tz: TimeZone>(name: &str, now: &DateTime<Tz>) -> Option<String>
+where
+    Tz::Offset: std::fmt::Display,
+{
+    let format = match name {
+        "CURRENT_YEAR" => "%Y",
+        "CURRENT_YEAR_SHORT" => "%y",
+        "CURRENT_MONTH" => "%m",
+        "CURRENT_MONTH_NAME" => "%B",
+        "CURRENT_MONTH_NAME_SHORT" => "%b",
+        "CURRENT_DATE" => "%d",
+        "CURRENT_DAY_NAME" => "%A",
+        "CURRENT_DAY_NAME_SHORT" => "%a",
+        "CURRENT_HOUR" => "%H",
+        "CURRENT_MINUTE" => "%M",
+        "CURRENT_SECOND" => "%S",
+        "CURRENT_SECONDS_UNIX" => "%s",
+        _ => return None,
+    };
+    Some(now.format(format).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, FixedOffset};
+    use crossterm::event::{KeyCode, KeyEvent};
+
+    use super::{Snippet, SnippetSession, date_variable};
+    use crate::app::App;
+    use crate::core::Cursor;
+
+    #[test]
+    fn expands_placeholders_mirrors_choices_and_variables() {
+        let variable = |name: &str| match name {
+            "TM_FILENAME_BASE" => Some(String::from("lib")),
+            "TM_SELECTED_TEXT" => Some(String::new()),
+            _ => None,
+        };
+        let body = concat!(
+            "fn ${1:name}(${2:arg: ${3:u8}}) -> ${4|u8,u16|} {\n",
+            "\t$1 \\$ $0${TM_SELECTED_TEXT:todo!()}\n}\n",
+            "// $TM_FILENAME_BASE $UNKNOWN ${5",
+        );
+        let snippet = Snippet::parse(body, &variable);
+        let expected = "fn name(arg: u8) -> u8 {\n\tname $ todo!()\n}\n// lib UNKNOWN ${5";
+        assert_eq!(snippet.text, expected);
+        let stops = snippet
+            .tabstops
+            .iter()
+            .map(|stop| {
+                let texts = stop.ranges.iter().map(|range| &snippet.text[range.clone()]);
+                (stop.number, texts.collect::<Vec<_>>())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            stops,
+            vec![
+                (1, vec!["name", "name"]),
+                (2, vec!["arg: u8"]),
+                (3, vec!["u8"]),
+                (4, vec!["u8"]),
+                (0, vec![""]),
+            ]
+        );
+        assert_eq!(snippet.tabstops[3].choices, vec!["u8", "u16"]);
+
+        // Typing over the first tabstop grows it and shifts the rest.
+        let mut session = SnippetSession::new(7, &snippet, Cursor::new(2, 4));
+        assert_eq!(session.current(), Some((Cursor::new(2, 7), Cursor::new(2, 11))));
+        session.apply_edit(Cursor::new(2, 7), "name", "run");
+        session.apply_edit(Cursor::new(2, 10), "", "s");
+        assert_eq!(session.current(), Some((Cursor::new(2, 7), Cursor::new(2, 11))));
+        assert_eq!(session.tabstops[1], vec![(Cursor::new(2, 12), Cursor::new(2, 19))]);
+        assert_eq!(session.tabstops[0][1], (Cursor::new(3, 1), Cursor::new(3, 5)));
+        assert_eq!(session.extent(), Some((Cursor::new(2, 7), Cursor::new(3, 8))));
+
+        // 2024-02-29T13:05:09Z, a Thursday.
+        let leap_day = 1_709_211_909;
+        let at = |offset| {
+            let zone = FixedOffset::east_opt(offset).unwrap_or_else(|| panic!("bad offset"));
+            DateTime::from_timestamp(leap_day, 0)
+                .unwrap_or_else(|| panic!("bad timestamp"))
+                .with_timezone(&zone)
+        };
+        let date = ["CURRENT_YEAR", "CURRENT_MONTH_NAME_SHORT", "CURRENT_DATE", "CURRENT_HOUR"]
+            .map(|name| date_variable(name, &at(0)).unwrap_or_default());
+        assert_eq!(date, ["2024", "Feb", "29", "13"]);
+        assert_eq!(date_variable("CURRENT_DAY_NAME", &at(0)).as_deref(), Some("Thursday"));
+        // Twelve hours ahead it is already Friday, March 1st, at 01:05.
+        let ahead = ["CURRENT_MONTH", "CURRENT_DATE", "CURRENT_DAY_NAME_SHORT", "CURRENT_HOUR"]
+            .map(|name| date_variable(name, &at(12 * 3600)).unwrap_or_default());
+        assert_eq!(ahead, ["03", "01", "Fri", "01"]);
+        let behind = date_variable("CURRENT_MINUTE", &at(-5 * 3600 - 1800));
+        assert_eq!(behind.as_deref(), Some("35"));
+        let unix = date_variable("CURRENT_SECONDS_UNIX", &at(3600));
+        assert_eq!(unix, Some(leap_day.to_string()));
+        assert_eq!(date_variable("CURRENT_WEEK", &at(0)), None);
+    }
+
+    #[test]
+    fn undo_reverts_a_mirrored_tabstop_with_its_mirrors() {
+        let mut app = App::open(None).unwrap_or_else(|error| panic!("{error}"));
+        let start = Cursor::new(0, 0);
+        app.insert_snippet("let ${1:x} = 1; use($1);", start, start);
+        let type_char = |app: &mut App, ch| {
+            app.handle_key_event(KeyEvent::from(KeyCode::Char(ch)))
+                .unwrap_or_else(|error| panic!("{error}"));
+            app.update_snippet();
+        };
+        type_char(&mut app, 'a');
+        type_char(&mut app, 'b');
+        assert_eq!(app.active_document().text(), "let ab = 1; use(ab);");
+
+        app.undo();
+        assert_eq!(app.active_document().text(), "let a = 1; use(a);");
+        assert_eq!(app.active_pane().cursor(), Cursor::new(0, 5));
+        app.undo();
+        assert_eq!(app.active_document().text(), "let x = 1; use(x);");
+        app.redo();
+        assert_eq!(app.active_document().text(), "let a = 1; use(a);");
+    }
 }
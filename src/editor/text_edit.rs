@@ -1,4 +1,4 @@
-use crate::app::App;
+use crate::app::{App, MessageKind};
 use crate::core::{Cursor, EditRecord, Selection};
 use crate::syntax::compute_indent;
 use tree_sitter::Point;
@@ -18,11 +18,12 @@ fn cursor_to_point(document: &crate::core::Document, cursor: Cursor) -> Point {
 
 impl App {
     pub(crate) fn handle_editor_char_input(&mut self, typed: char) {
-        if is_auto_close_char(typed) {
-            if self.selection_text().is_none() && self.char_at_cursor() == Some(typed) {
-                self.move_right(false);
-                return;
-            }
+        if is_auto_close_char(typed)
+            && self.selection_text().is_none()
+            && self.char_at_cursor() == Some(typed)
+        {
+            self.move_right(false);
+            return;
         }
 
         if is_symmetric_pair_char(typed) && self.selection_text().is_none() {
@@ -70,19 +71,18 @@ impl App {
         }
 
         let cursor = self.active_pane().cursor();
-        if let Some(previous) = self.active_document().previous_char(cursor) {
-            if let Some(next) = self.char_at_cursor() {
-                if matching_pair(previous).map(|(_, close)| close) == Some(next) {
-                    let after_next = self.active_document().next_position(cursor);
-                    self.apply_edit(
-                        self.active_document().previous_position(cursor),
-                        after_next,
-                        "",
-                        false,
-                    );
-                    return;
-                }
-            }
+        if let Some(previous) = self.active_document().previous_char(cursor)
+            && let Some(next) = self.char_at_cursor()
+            && matching_pair(previous).map(|(_, close)| close) == Some(next)
+        {
+            let after_next = self.active_document().next_position(cursor);
+            self.apply_edit(
+                self.active_document().previous_position(cursor),
+                after_next,
+                "",
+                false,
+            );
+            return;
         }
 
         let previous = self.active_document().previous_position(cursor);
@@ -149,6 +149,13 @@ impl App {
         let pane_id = self.active_pane_id();
         let buffer_id = self.active_buffer_id;
         let cursor_before = self.active_pane().cursor();
+        if self.active_buffer().scratch_name.is_some() {
+            self.set_message("Buffer is read-only", MessageKind::Warning);
+            return;
+        }
+        if self.reject_hex_text_edit() {
+            return;
+        }
 
         let Some(buffer_index) = self
             .buffers
@@ -157,64 +164,131 @@ impl App {
         else {
             return;
         };
-        let (cursor_after, document_ref) = {
-            let buffer = &mut self.buffers[buffer_index];
-            let start_byte = buffer.document.cursor_to_byte(start);
-            let old_end_byte = buffer.document.cursor_to_byte(end);
-            let start_position = cursor_to_point(&buffer.document, start);
-            let old_end_position = cursor_to_point(&buffer.document, end);
-            let deleted_text = buffer.document.slice_string(start, end);
-            if deleted_text.is_empty() && inserted_text.is_empty() {
-                return;
-            }
-
-            buffer.document.delete_range(start, end);
-            let new_cursor = if inserted_text.is_empty() {
-                start
-            } else {
-                buffer.document.insert_text(start, inserted_text)
-            };
-            let cursor_after =
-                new_cursor.with_preferred_column(buffer.document.display_column(new_cursor));
-
-            buffer.history.push_edit(
-                EditRecord::new(
-                    start,
-                    inserted_text.to_owned(),
-                    deleted_text,
-                    cursor_before,
-                    cursor_after,
-                ),
-                coalesce,
-            );
-            buffer
-                .document
-                .set_dirty(buffer.document.text() != buffer.saved_snapshot);
-            let new_end_position = cursor_to_point(&buffer.document, cursor_after);
-            let new_end_byte = start_byte + inserted_text.len();
-            buffer.syntax.apply_edit(
-                start_byte,
-                old_end_byte,
-                new_end_byte,
-                start_position,
-                old_end_position,
-                new_end_position,
-            );
-            (cursor_after, &buffer.document as *const _)
+        let Some((deleted_text, cursor_after)) =
+            self.edit_buffer(buffer_index, start, end, inserted_text, cursor_before, coalesce)
+        else {
+            return;
         };
 
         if let Some(pane) = self.layout.pane_mut(pane_id) {
             pane.set_cursor(cursor_after);
             pane.set_selection(Selection::caret(cursor_after));
-            let document = unsafe { &*document_ref };
-            pane.search_mut()
-                .refresh_for_document(document, cursor_after);
+            pane.search_mut().refresh_lines(
+                &self.buffers[buffer_index].document,
+                start.line,
+                end.line,
+                cursor_after.line,
+                cursor_after,
+            );
+        }
+        self.notify_lsp_edit(start, &deleted_text, inserted_text);
+        self.ensure_cursor_visible();
+    }
+
+    /// Replaces `start..end` in one buffer and records it in that buffer's
+    /// history and syntax tree. Panes and the language server are left to the
+    /// caller. Returns the deleted text and the cursor after the insertion.
+    pub(crate) fn edit_buffer(
+        &mut self,
+        buffer_index: usize,
+        start: Cursor,
+        end: Cursor,
+        inserted_text: &str,
+        cursor_before: Cursor,
+        coalesce: bool,
+    ) -> Option<(String, Cursor)> {
+        let buffer = self.buffers.get_mut(buffer_index)?;
+        if buffer.hex.is_some() {
+            return None;
+        }
+        let buffer_id = buffer.id;
+        let start_byte = buffer.document.cursor_to_byte(start);
+        let old_end_byte = buffer.document.cursor_to_byte(end);
+        let start_position = cursor_to_point(&buffer.document, start);
+        let old_end_position = cursor_to_point(&buffer.document, end);
+        let deleted_text = buffer.document.slice_string(start, end);
+        if deleted_text.is_empty() && inserted_text.is_empty() {
+            return None;
         }
-        if let Some(path) = self.active_document().path().map(|path| path.to_path_buf()) {
-            self.lsp
-                .did_change(&path, &self.active_document().text(), &self.workspace_root);
+
+        buffer.document.delete_range(start, end);
+        let new_cursor = if inserted_text.is_empty() {
+            start
+        } else {
+            buffer.document.insert_text(start, inserted_text)
+        };
+        let cursor_after =
+            new_cursor.with_preferred_column(buffer.document.display_column(new_cursor));
+
+        buffer.history.push_edit(
+            EditRecord::new(
+                start,
+                inserted_text.to_owned(),
+                deleted_text.clone(),
+                cursor_before,
+                cursor_after,
+            ),
+            coalesce,
+        );
+        buffer.refresh_dirty();
+        let new_end_position = cursor_to_point(&buffer.document, cursor_after);
+        let new_end_byte = start_byte + inserted_text.len();
+        buffer.syntax.apply_edit(
+            start_byte,
+            old_end_byte,
+            new_end_byte,
+            start_position,
+            old_end_position,
+            new_end_position,
+        );
+        self.shift_jumps(buffer_id, start, &deleted_text, inserted_text);
+        self.shift_snippet(buffer_id, start, &deleted_text, inserted_text);
+        self.index_buffer_edit(buffer_id, start, &deleted_text, inserted_text);
+        Some((deleted_text, cursor_after))
+    }
+
+    /// Replaces the whole active document with `new_text` as a single undo step,
+    /// touching only the span between the common prefix and suffix.
+    pub(crate) fn replace_active_text(&mut self, new_text: &str) -> bool {
+        let old_text = self.active_document().text();
+        if old_text == new_text {
+            return false;
+        }
+
+        let prefix_len = old_text
+            .char_indices()
+            .zip(new_text.chars())
+            .take_while(|((_, old), new)| old == new)
+            .last()
+            .map(|((index, old), _)| index + old.len_utf8())
+            .unwrap_or(0);
+        let suffix_len = old_text[prefix_len..]
+            .chars()
+            .rev()
+            .zip(new_text[prefix_len..].chars().rev())
+            .take_while(|(old, new)| old == new)
+            .map(|(old, _)| old.len_utf8())
+            .sum::<usize>();
+
+        let removed = &old_text[prefix_len..old_text.len() - suffix_len];
+        let inserted = &new_text[prefix_len..new_text.len() - suffix_len];
+        let document = self.active_document();
+        let start = document.advance_cursor(Cursor::default(), &old_text[..prefix_len]);
+        let end = document.advance_cursor(start, removed);
+
+        let cursor = self.active_pane().cursor();
+        self.apply_edit(start, end, inserted, false);
+
+        let document = self.active_document();
+        let line = cursor.line.min(document.last_line_index());
+        let column = cursor.column.min(document.line_text(line).chars().count());
+        let restored = Cursor::new(line, column);
+        if let Some(pane) = self.layout.pane_mut(self.active_pane_id()) {
+            pane.set_cursor(restored);
+            pane.set_selection(Selection::caret(restored));
         }
         self.ensure_cursor_visible();
+        true
     }
 
     fn current_edit_range(&self) -> (Cursor, Cursor) {
@@ -330,7 +404,10 @@ impl App {
 
         let new_indent = {
             let buffer = self.buffer_by_id(buffer_id);
-            let source = buffer.map(|b| b.document.text()).unwrap_or_default();
+            let source = buffer
+                .filter(|_| tree_ref.is_some())
+                .map(|b| b.document.text())
+                .unwrap_or_default();
             let tree = tree_ref.map(|ptr| unsafe { &*ptr });
             compute_indent(tree, source.as_bytes(), cursor_byte, &current_indent)
         };
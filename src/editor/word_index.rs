@@ -0,0 +1,251 @@
+use std::collections::HashMap;
+
+use super::completion::is_word_char;
+use crate::app::App;
+use crate::core::{Cursor, Document};
+use crate::lsp::{CompletionItemView, CompletionSource};
+
+const MIN_WORD_CHARS: usize = 3;
+const MAX_WORD_CHARS: usize = 64;
+
+/// Words of the open buffers, offered as completions where no server
+/// answers. Edits re-read only the lines they touched.
+#[derive(Debug, Default)]
+pub struct WordIndex {
+    buffers: HashMap<u64, IndexedBuffer>,
+    /// Occurrences of each word across all buffers.
+    counts: HashMap<String, usize>,
+}
+
+#[derive(Debug, Default)]
+struct IndexedBuffer {
+    lines: Vec<Vec<String>>,
+    /// Line count and byte length when last indexed; a mismatch means the
+    /// buffer changed without `apply_edit`, as on reload.
+    stamp: (usize, usize),
+}
+
+impl WordIndex {
+    /// Indexes new and changed buffers and forgets the ones not listed.
+    pub fn sync<'a>(&mut self, documents: impl Iterator<Item = (u64, &'a Document)>) {
+        let mut seen = Vec::new();
+        for (buffer_id, document) in documents {
+            seen.push(buffer_id);
+            let stamp = document_stamp(document);
+            if self
+                .buffers
+                .get(&buffer_id)
+                .is_some_and(|indexed| indexed.stamp == stamp)
+            {
+                continue;
+            }
+            self.remove_buffer(buffer_id);
+            let lines = (0..document.line_count())
+                .map(|line| line_words(&document.raw_line_text(line)))
+                .collect::<Vec<_>>();
+            for words in &lines {
+                add_words(&mut self.counts, words);
+            }
+            self.buffers.insert(buffer_id, IndexedBuffer { lines, stamp });
+        }
+        let closed = self
+            .buffers
+            .keys()
+            .copied()
+            .filter(|buffer_id| !seen.contains(buffer_id))
+            .collect::<Vec<_>>();
+        for buffer_id in closed {
+            self.remove_buffer(buffer_id);
+        }
+    }
+
+    /// Re-reads the lines of `document` that replaced `deleted` at `line`.
+    pub fn apply_edit(
+        &mut self,
+        buffer_id: u64,
+        document: &Document,
+        line: usize,
+        deleted: &str,
+        inserted: &str,
+    ) {
+        let Some(indexed) = self.buffers.get_mut(&buffer_id) else {
+            return;
+        };
+        if line >= indexed.lines.len() {
+            // Out of step; the next `sync` indexes the buffer again.
+            indexed.stamp = (0, 0);
+            return;
+        }
+        let removed_end = (line + deleted.matches('\n').count() + 1).min(indexed.lines.len());
+        let added = (line..line + inserted.matches('\n').count() + 1)
+            .map(|line| line_words(&document.raw_line_text(line)))
+            .collect::<Vec<_>>();
+        for words in &added {
+            add_words(&mut self.counts, words);
+        }
+        for words in indexed.lines.splice(line..removed_end, added) {
+            remove_words(&mut self.counts, &words);
+        }
+        indexed.stamp = if indexed.lines.len() == document.line_count() {
+            document_stamp(document)
+        } else {
+            (0, 0)
+        };
+    }
+
+    fn remove_buffer(&mut self, buffer_id: u64) {
+        if let Some(indexed) = self.buffers.remove(&buffer_id) {
+            for words in &indexed.lines {
+                remove_words(&mut self.counts, words);
+            }
+        }
+    }
+
+    /// Words sharing `prefix`'s first letter, ignoring case, for the
+    /// fuzzy filter to rank. The word being typed is left out unless it
+    /// also occurs elsewhere.
+    pub fn candidates(&self, prefix: &str) -> Vec<&str> {
+        let Some(first) = prefix.chars().next() else {
+            return Vec::new();
+        };
+        let mut words = self
+            .counts
+            .iter()
+            .filter(|(word, count)| **count > 1 || word.as_str() != prefix)
+            .map(|(word, _)| word.as_str())
+            .filter(|word| {
+                word.chars()
+                    .next()
+                    .is_some_and(|letter| letter.to_lowercase().eq(first.to_lowercase()))
+            })
+            .collect::<Vec<_>>();
+        words.sort_unstable();
+        words
+    }
+}
+
+fn document_stamp(document: &Document) -> (usize, usize) {
+    let end = document.line_end(document.last_line_index());
+    (document.line_count(), document.cursor_to_byte(end))
+}
+
+fn line_words(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    for ch in line.chars().chain(std::iter::once(' ')) {
+        if is_word_char(ch) {
+            word.push(ch);
+            continue;
+        }
+        let chars = word.chars().count();
+        let numeric = word.starts_with(|first: char| first.is_ascii_digit());
+        if (MIN_WORD_CHARS..=MAX_WORD_CHARS).contains(&chars) && !numeric {
+            words.push(word.clone());
+        }
+        word.clear();
+    }
+    words
+}
+
+fn add_words(counts: &mut HashMap<String, usize>, words: &[String]) {
+    for word in words {
+        *counts.entry(word.clone()).or_default() += 1;
+    }
+}
+
+fn remove_words(counts: &mut HashMap<String, usize>, words: &[String]) {
+    for word in words {
+        if let Some(count) = counts.get_mut(word) {
+            *count -= 1;
+            if *count == 0 {
+                counts.remove(word);
+            }
+        }
+    }
+}
+
+impl App {
+    /// Brings the word index in line with the open buffers; large files,
+    /// hex views and scratch buffers are left out.
+    pub(crate) fn sync_word_index(&mut self) {
+        let documents = self
+            .buffers
+            .iter()
+            .filter(|buffer| {
+                !buffer.large_file && buffer.hex.is_none() && buffer.scratch_name.is_none()
+            })
+            .map(|buffer| (buffer.id, &buffer.document));
+        self.words.sync(documents);
+    }
+
+    /// Re-indexes the lines an edit of `buffer_id` at `start` touched.
+    pub(crate) fn index_buffer_edit(
+        &mut self,
+        buffer_id: u64,
+        start: Cursor,
+        deleted: &str,
+        inserted: &str,
+    ) {
+        if let Some(buffer) = self.buffers.iter().find(|buffer| buffer.id == buffer_id) {
+            self.words
+                .apply_edit(buffer_id, &buffer.document, start.line, deleted, inserted);
+        }
+    }
+
+    /// Open-buffer words for `prefix`, as completion items.
+    pub(crate) fn word_completion_items(&self, prefix: &str) -> Vec<CompletionItemView> {
+        self.words
+            .candidates(prefix)
+            .into_iter()
+            .map(|word| CompletionItemView {
+                label: word.to_owned(),
+                resolved: true,
+                kind: 1,
+                insert_text: word.to_owned(),
+                filter_text: word.to_owned(),
+                sort_text: word.to_owned(),
+                source: CompletionSource::Word,
+                ..CompletionItemView::default()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WordIndex;
+    use crate::core::{Cursor, Document};
+
+    #[test]
+    fn reindexes_only_edited_lines_and_forgets_closed_buffers() {
+        let mut first = Document::from_text(None, "let counter = 1;\nprint(counter)\n");
+        let second = Document::from_text(None, "Counting cows 42 x1\n");
+        let mut index = WordIndex::default();
+        index.sync([(1, &first), (2, &second)].into_iter());
+        // The first letter matches in either case; short words are skipped.
+        assert_eq!(index.candidates("co"), vec!["Counting", "counter", "cows"]);
+        assert_eq!(index.candidates("x"), Vec::<&str>::new());
+        assert_eq!(index.counts.get("counter"), Some(&2));
+        // The word under the cursor only counts once it occurs elsewhere.
+        assert!(!index.candidates("cows").contains(&"cows"));
+
+        // Replace "1;\nprint(" with "total;\nlet totals = (".
+        let start = Cursor::new(0, 14);
+        let end = Cursor::new(1, 6);
+        let deleted = first.slice_string(start, end);
+        let inserted = "total;\nlet totals = (";
+        first.delete_range(start, end);
+        first.insert_text(start, inserted);
+        index.apply_edit(1, &first, start.line, &deleted, inserted);
+        assert_eq!(index.candidates("tot"), vec!["total", "totals"]);
+        assert_eq!(index.counts.get("print"), None);
+        assert_eq!(index.counts.get("let"), Some(&2));
+        // The edit kept the stamp current, so syncing changes nothing.
+        index.sync([(1, &first), (2, &second)].into_iter());
+        assert_eq!(index.counts.get("let"), Some(&2));
+
+        index.sync([(1, &first)].into_iter());
+        assert_eq!(index.candidates("co"), vec!["counter"]);
+        assert_eq!(index.counts.get("Counting"), None);
+    }
+}
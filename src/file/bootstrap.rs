@@ -2,9 +2,10 @@ use std::path::{Path, PathBuf};
 
 use crate::app::{App, AppError, AppMode, BufferState, FocusTarget, MessageKind, Theme};
 use crate::file::{load_document, ExplorerState, FileFinder, FileWatcher, RecentFiles};
-use crate::lsp::LspWorkspace;
+use crate::lsp::{DocumentHighlights, LspWorkspace};
 use crate::syntax::{language_for_path, SyntaxLayer};
-use crate::util::{Clipboard, DetectedEncoding};
+use crate::ui::OutlineState;
+use crate::util::{Clipboard, DetectedEncoding, LineEnding};
 use std::cell::RefCell;
 
 pub(crate) fn open_app(path: Option<PathBuf>) -> Result<App, AppError> {
@@ -13,35 +14,35 @@ pub(crate) fn open_app(path: Option<PathBuf>) -> Result<App, AppError> {
     let recent_files = RecentFiles::load();
     let watcher = FileWatcher::new(&workspace_root).ok();
 
-    let (document, encoding) = match path {
-        Some(path) if path.exists() => {
-            let loaded = load_document(&path)?;
-            (loaded.document, loaded.encoding)
-        }
-        Some(path) => (
-            crate::core::Document::new_empty(Some(path)),
-            DetectedEncoding::default(),
-        ),
-        None => (
-            crate::core::Document::new_empty(None),
-            DetectedEncoding::default(),
-        ),
+    let loaded = match path.as_deref() {
+        Some(path) if path.exists() => Some(load_document(path)?),
+        _ => None,
     };
-
-    let saved_snapshot = document.text();
+    let document = crate::core::Document::new_empty(path);
     let language_id = document.path().and_then(language_for_path);
     let syntax = SyntaxLayer::new(language_id);
 
-    let initial_buffer = BufferState {
+    let mut initial_buffer = BufferState {
         id: 1,
         document,
         history: crate::core::History::default(),
-        saved_snapshot,
-        encoding,
+        saved_snapshot: String::new(),
+        encoding: DetectedEncoding::default(),
+        saved_encoding: DetectedEncoding::default(),
+        line_ending: LineEnding::default(),
+        saved_line_ending: LineEnding::default(),
+        hex: None,
+        large_file: false,
+        scratch_name: None,
         syntax,
         line_highlight_cache: RefCell::new(crate::app::LineHighlightCache::default()),
         fold_cache: RefCell::new(crate::app::FoldCache::default()),
+        outline_cache: RefCell::new(crate::app::OutlineCache::default()),
+        locals_cache: RefCell::new(crate::app::LocalsCache::default()),
     };
+    if let Some(loaded) = loaded {
+        initial_buffer.reload_from(loaded);
+    }
 
     let active_theme = Theme::default_theme();
 
@@ -51,10 +52,16 @@ pub(crate) fn open_app(path: Option<PathBuf>) -> Result<App, AppError> {
         next_buffer_id: 2,
         layout: crate::ui::LayoutState::new(1),
         explorer: ExplorerState::new(workspace_root.clone()),
+        outline: OutlineState::default(),
+        hierarchy: None,
+        document_highlights: DocumentHighlights::default(),
+        completion_docs: RefCell::new(crate::app::CompletionDocsCache::default()),
+        snippet: None,
         file_finder: FileFinder::new(workspace_root.clone()),
         recent_files,
         watcher,
         pending_conflict_paths: Vec::new(),
+        pending_workspace_edit: None,
         buffers: vec![initial_buffer],
         picker: None,
         clipboard,
@@ -68,6 +75,9 @@ pub(crate) fn open_app(path: Option<PathBuf>) -> Result<App, AppError> {
         message: None,
         command_bar: crate::app::CommandBarState::default(),
         active_theme,
+        save_config: crate::config::SaveConfig::load(&workspace_root),
+        snippets: crate::config::SnippetLibrary::load(&workspace_root),
+        words: crate::editor::WordIndex::default(),
         lsp: LspWorkspace::new(&workspace_root),
     };
 
@@ -82,12 +92,21 @@ pub(crate) fn open_app(path: Option<PathBuf>) -> Result<App, AppError> {
         );
     }
 
+    if let Some(warning) = app.snippets.warnings().first() {
+        let more = app.snippets.warnings().len() - 1;
+        let more = if more > 0 { format!(" (+{more} more)") } else { String::new() };
+        app.set_message(&format!("Snippets: {warning}{more}"), MessageKind::Warning);
+    }
+
     app.lsp.bootstrap_workspace(&workspace_root);
 
-    if let Some(path) = app.active_document().path().map(Path::to_path_buf) {
+    if !app.active_buffer().large_file
+        && let Some(path) = app.active_document().path().map(Path::to_path_buf)
+    {
         let text = app.active_document().text();
         app.lsp.did_open(&path, &text, &workspace_root);
     }
+    app.warn_about_load();
 
     Ok(app)
 }
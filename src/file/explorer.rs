@@ -136,16 +136,15 @@ impl ExplorerState {
         self.entries.clear();
         let root = self.root.clone();
         self.walk_directory(&root, 0);
-        if let Some(selected_path) = selected_path {
-            if let Some(index) = self
+        if let Some(selected_path) = selected_path
+            && let Some(index) = self
                 .entries
                 .iter()
                 .position(|entry| entry.path == selected_path)
-            {
-                self.selected = index;
-                self.scroll_offset = self.scroll_offset.min(self.selected);
-                return;
-            }
+        {
+            self.selected = index;
+            self.scroll_offset = self.scroll_offset.min(self.selected);
+            return;
         }
         self.selected = self.selected.min(self.entries.len().saturating_sub(1));
         self.scroll_offset = self.scroll_offset.min(self.selected);
@@ -1,23 +1,40 @@
 use std::fs;
-use std::io::Write;
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 
+use ropey::RopeBuilder;
 use tempfile::NamedTempFile;
 use thiserror::Error;
 
-use crate::core::Document;
-use crate::util::{DetectedEncoding, EncodingError, decode_text, encode_text};
+use crate::core::{Buffer, Document};
+use crate::util::{
+    DetectedEncoding, EncodingError, LineEnding, StreamDecoder, apply_line_ending, decode_text,
+    decode_with_encoding, detect_line_ending, detect_prefix_encoding, encode_text,
+    normalize_line_endings,
+};
 
 #[derive(Debug)]
 pub struct LoadedDocument {
     pub document: Document,
     pub encoding: DetectedEncoding,
+    pub line_ending: LineEnding,
+    /// Raw file contents when the file looks binary and should open in the hex view.
+    pub binary: Option<Vec<u8>>,
+    pub large_file: bool,
 }
 
+/// Files at least this big are streamed into the rope and open with syntax,
+/// folds and LSP turned off.
+pub const LARGE_FILE_THRESHOLD: u64 = 32 * 1024 * 1024;
+
+const STREAM_CHUNK_LEN: usize = 1024 * 1024;
+
 #[derive(Debug, Error)]
 pub enum FileError {
     #[error("path is a directory: {0}")]
     Directory(String),
+    #[error("binary file is too large for the hex view: {0}")]
+    BinaryTooLarge(String),
     #[error("io error: {0}")]
     Io(#[from] std::io::Error),
     #[error("encoding error for {path}: {source}")]
@@ -31,18 +48,123 @@ pub enum FileError {
 }
 
 pub fn load_document(path: &Path) -> Result<LoadedDocument, FileError> {
+    if is_large_file(path)? {
+        return stream_large_document(path, None);
+    }
+
+    let bytes = read_document_bytes(path)?;
+    if looks_binary(&bytes) {
+        return Ok(LoadedDocument {
+            document: Document::new_empty(Some(path.to_path_buf())),
+            encoding: DetectedEncoding::default(),
+            line_ending: LineEnding::default(),
+            binary: Some(bytes),
+            large_file: false,
+        });
+    }
+    let (text, encoding) = decode_text(&bytes);
+    Ok(loaded_document(path, &text, encoding))
+}
+
+/// NUL bytes near the start mark a file as binary unless a UTF-16 BOM explains them.
+fn looks_binary(bytes: &[u8]) -> bool {
+    const SNIFF_LEN: usize = 8192;
+
+    if bytes.starts_with(&[0xFF, 0xFE]) || bytes.starts_with(&[0xFE, 0xFF]) {
+        return false;
+    }
+    bytes[..bytes.len().min(SNIFF_LEN)].contains(&0)
+}
+
+pub fn load_document_with_encoding(
+    path: &Path,
+    encoding: DetectedEncoding,
+) -> Result<LoadedDocument, FileError> {
+    if is_large_file(path)? {
+        return stream_large_document(path, Some(encoding));
+    }
+    let bytes = read_document_bytes(path)?;
+    let (text, encoding) = decode_with_encoding(&bytes, encoding);
+    Ok(loaded_document(path, &text, encoding))
+}
+
+fn is_large_file(path: &Path) -> Result<bool, FileError> {
+    Ok(!path.is_dir() && fs::metadata(path)?.len() >= LARGE_FILE_THRESHOLD)
+}
+
+fn read_document_bytes(path: &Path) -> Result<Vec<u8>, FileError> {
     if path.is_dir() {
         return Err(FileError::Directory(path.display().to_string()));
     }
+    Ok(fs::read(path)?)
+}
 
-    let bytes = fs::read(path)?;
-    let (text, encoding) = decode_text(&bytes).map_err(|source| FileError::Encoding {
-        path: path.display().to_string(),
-        source,
-    })?;
-    Ok(LoadedDocument {
+fn loaded_document(path: &Path, text: &str, encoding: DetectedEncoding) -> LoadedDocument {
+    let line_ending = detect_line_ending(text);
+    let text = normalize_line_endings(text);
+    LoadedDocument {
         document: Document::from_text(Some(path.to_path_buf()), &text),
         encoding,
+        line_ending,
+        binary: None,
+        large_file: false,
+    }
+}
+
+/// Streams a file into a rope chunk by chunk without holding the whole file
+/// as one `String`, decoding with `chosen` or else the encoding detected
+/// from the first chunk. Binaries this big are refused rather than copied
+/// into the hex view.
+fn stream_large_document(
+    path: &Path,
+    chosen: Option<DetectedEncoding>,
+) -> Result<LoadedDocument, FileError> {
+    let mut file = fs::File::open(path)?;
+    let mut chunk = Vec::with_capacity(STREAM_CHUNK_LEN);
+    (&mut file)
+        .take(STREAM_CHUNK_LEN as u64)
+        .read_to_end(&mut chunk)?;
+    if chosen.is_none() && looks_binary(&chunk) {
+        return Err(FileError::BinaryTooLarge(path.display().to_string()));
+    }
+    let encoding = chosen.unwrap_or_else(|| detect_prefix_encoding(&chunk));
+    let mut decoder = StreamDecoder::new(encoding, &chunk);
+    let mut builder = RopeBuilder::new();
+    let mut text = String::new();
+    let mut line_ending = None;
+
+    loop {
+        let last = chunk.is_empty();
+        decoder.decode(&chunk, last, &mut text);
+        // Hold back a trailing '\r' so a CRLF split across chunks normalizes once.
+        let take = if !last && text.ends_with('\r') {
+            text.len() - 1
+        } else {
+            text.len()
+        };
+        if line_ending.is_none() && text[..take].contains(['\n', '\r']) {
+            line_ending = Some(detect_line_ending(&text[..take]));
+        }
+        builder.append(&normalize_line_endings(&text[..take]));
+        text.drain(..take);
+        if last {
+            break;
+        }
+        chunk.clear();
+        (&mut file)
+            .take(STREAM_CHUNK_LEN as u64)
+            .read_to_end(&mut chunk)?;
+    }
+
+    Ok(LoadedDocument {
+        document: Document::from_buffer(
+            Some(path.to_path_buf()),
+            Buffer::from_rope(builder.finish()),
+        ),
+        encoding: decoder.finish(),
+        line_ending: line_ending.unwrap_or_default(),
+        binary: None,
+        large_file: true,
     })
 }
 
@@ -50,6 +172,7 @@ pub fn save_document(
     path: &Path,
     document: &Document,
     encoding: DetectedEncoding,
+    line_ending: LineEnding,
 ) -> Result<(), FileError> {
     if path.is_dir() {
         return Err(FileError::Directory(path.display().to_string()));
@@ -59,7 +182,8 @@ pub fn save_document(
         fs::create_dir_all(parent)?;
     }
 
-    let bytes = encode_text(&document.text(), encoding).map_err(|source| FileError::Encoding {
+    let text = apply_line_ending(&document.text(), line_ending);
+    let bytes = encode_text(&text, encoding).map_err(|source| FileError::Encoding {
         path: path.display().to_string(),
         source,
     })?;
@@ -67,6 +191,14 @@ pub fn save_document(
     Ok(())
 }
 
+/// Writes raw bytes untouched, bypassing line ending and encoding conversion.
+pub fn save_bytes(path: &Path, bytes: &[u8]) -> Result<(), FileError> {
+    if path.is_dir() {
+        return Err(FileError::Directory(path.display().to_string()));
+    }
+    atomic_write(path, bytes)
+}
+
 fn atomic_write(path: &Path, bytes: &[u8]) -> Result<(), FileError> {
     let parent = path
         .parent()
@@ -86,8 +218,8 @@ mod tests {
     use std::time::{SystemTime, UNIX_EPOCH};
 
     use crate::core::{Cursor, Document};
-    use crate::file::{load_document, save_document};
-    use crate::util::DetectedEncoding;
+    use crate::file::{load_document, save_bytes, save_document};
+    use crate::util::{DetectedEncoding, LineEnding};
 
     #[test]
     fn load_and_save_round_trip_utf8() {
@@ -98,10 +230,101 @@ mod tests {
         let path = std::env::temp_dir().join(format!("codx-{suffix}.txt"));
         let mut document = Document::new_empty(Some(path.clone()));
         document.insert_text(Cursor::new(0, 0), "hello\nworld");
-        save_document(&path, &document, DetectedEncoding::default())
-            .unwrap_or_else(|error| panic!("{error}"));
+        save_document(
+            &path,
+            &document,
+            DetectedEncoding::default(),
+            LineEnding::Lf,
+        )
+        .unwrap_or_else(|error| panic!("{error}"));
         let loaded = load_document(&path).unwrap_or_else(|error| panic!("{error}"));
         assert_eq!(loaded.document.text(), "hello\nworld");
         let _ = fs::remove_file(path);
     }
+
+    #[test]
+    fn crlf_files_are_normalized_and_written_back() {
+        let suffix = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let path = std::env::temp_dir().join(format!("codx-crlf-{suffix}.txt"));
+        fs::write(&path, "one\r\ntwo\r\n").unwrap_or_else(|error| panic!("{error}"));
+        let loaded = load_document(&path).unwrap_or_else(|error| panic!("{error}"));
+        assert_eq!(loaded.line_ending, LineEnding::Crlf);
+        assert_eq!(loaded.document.text(), "one\ntwo\n");
+        save_document(&path, &loaded.document, loaded.encoding, loaded.line_ending)
+            .unwrap_or_else(|error| panic!("{error}"));
+        let raw = fs::read(&path).unwrap_or_else(|error| panic!("{error}"));
+        assert_eq!(raw, b"one\r\ntwo\r\n");
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn binary_files_load_raw_bytes_and_save_exactly() {
+        let suffix = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let path = std::env::temp_dir().join(format!("codx-bin-{suffix}.bin"));
+        let bytes = vec![0x7F, b'E', b'L', b'F', 0x00, 0xFF, 0x0D, 0x0A];
+        fs::write(&path, &bytes).unwrap_or_else(|error| panic!("{error}"));
+        let loaded = load_document(&path).unwrap_or_else(|error| panic!("{error}"));
+        assert_eq!(loaded.binary.as_deref(), Some(bytes.as_slice()));
+        save_bytes(&path, &bytes).unwrap_or_else(|error| panic!("{error}"));
+        assert_eq!(fs::read(&path).unwrap_or_else(|error| panic!("{error}")), bytes);
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn large_files_stream_into_the_rope() {
+        let suffix = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let path = std::env::temp_dir().join(format!("codx-large-{suffix}.log"));
+        fs::write(&path, "\u{FEFF}first\r\nsecond\r\n").unwrap_or_else(|error| panic!("{error}"));
+        let loaded = super::stream_large_document(&path, None)
+            .unwrap_or_else(|error| panic!("{error}"));
+        assert!(loaded.large_file);
+        assert!(loaded.encoding.has_bom());
+        assert_eq!(loaded.line_ending, LineEnding::Crlf);
+        assert_eq!(loaded.document.text(), "first\nsecond\n");
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn large_files_decode_any_encoding_and_keep_bad_bytes() {
+        let suffix = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let path = std::env::temp_dir().join(format!("codx-large-utf16-{suffix}.txt"));
+        let utf16: Vec<u8> = [0xFF, 0xFE]
+            .into_iter()
+            .chain("caf\u{e9}\r\n".encode_utf16().flat_map(u16::to_le_bytes))
+            .collect();
+        fs::write(&path, utf16).unwrap_or_else(|error| panic!("{error}"));
+        let loaded = super::stream_large_document(&path, None)
+            .unwrap_or_else(|error| panic!("{error}"));
+        assert_eq!(loaded.encoding.label(), "UTF-16LE");
+        assert_eq!(loaded.document.text(), "caf\u{e9}\n");
+
+        // Valid UTF-8 for the whole first chunk, then one stray byte.
+        let mut bytes = vec![b'a'; super::STREAM_CHUNK_LEN + 10];
+        bytes.extend_from_slice(b"\xFFtail\r\nend");
+        fs::write(&path, &bytes).unwrap_or_else(|error| panic!("{error}"));
+        let loaded = super::stream_large_document(&path, None)
+            .unwrap_or_else(|error| panic!("{error}"));
+        assert_eq!(loaded.encoding.label(), "UTF-8");
+        assert!(loaded.encoding.is_lossy());
+        assert!(loaded.document.text().ends_with("a\u{FFFD}tail\nend"));
+
+        fs::write(&path, [0u8; 16]).unwrap_or_else(|error| panic!("{error}"));
+        assert!(matches!(
+            super::stream_large_document(&path, None),
+            Err(super::FileError::BinaryTooLarge(_))
+        ));
+        let _ = fs::remove_file(path);
+    }
 }
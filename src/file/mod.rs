@@ -9,6 +9,9 @@ mod workspace;
 pub(crate) use bootstrap::open_app;
 pub use explorer::ExplorerState;
 pub use finder::{FileFinder, FinderItem};
-pub use io::{FileError, load_document, save_document};
+pub use io::{
+    FileError, LoadedDocument, load_document, load_document_with_encoding, save_bytes,
+    save_document,
+};
 pub use recent::RecentFiles;
 pub use watcher::FileWatcher;
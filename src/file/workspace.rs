@@ -1,8 +1,8 @@
 use std::path::Path;
 
 use crate::app::{App, AppError, AppMode, CommandBarMode, FocusTarget, MessageKind};
-use crate::core::History;
-use crate::syntax::language_for_path;
+use crate::core::{Cursor, Document, History, Selection};
+use crate::util::{DetectedEncoding, LineEnding};
 
 impl App {
     pub(crate) fn request_quit(&mut self) {
@@ -18,6 +18,10 @@ impl App {
     }
 
     pub(crate) fn save_or_prompt(&mut self) -> Result<(), AppError> {
+        if self.active_buffer().scratch_name.is_some() {
+            self.set_message("Buffer is read-only", MessageKind::Warning);
+            return Ok(());
+        }
         let path = self.active_document().path().map(Path::to_path_buf);
         if let Some(path) = path {
             self.save_to_path(&path)
@@ -28,15 +32,56 @@ impl App {
     }
 
     pub(crate) fn save_to_path(&mut self, path: &Path) -> Result<(), AppError> {
+        if self.is_hex_view() {
+            let buffer = self
+                .buffer_by_id_mut(self.active_buffer_id)
+                .ok_or_else(|| AppError::Invariant(String::from("active buffer is missing")))?;
+            if let Some(hex) = buffer.hex.as_mut() {
+                crate::file::save_bytes(path, hex.bytes())?;
+                hex.mark_saved();
+            }
+            buffer.document.mark_saved(path.to_path_buf());
+            return self.finish_save(path, String::new());
+        }
+        if self.active_buffer().encoding.is_lossy() {
+            self.pending_quit_after_save = false;
+            let label = self.active_buffer().encoding.label();
+            self.set_message(
+                &format!(
+                    "Not saved: file had bytes invalid in {label}. Use :reopen-encoding or :save-encoding"
+                ),
+                MessageKind::Warning,
+            );
+            return Ok(());
+        }
+        if !self.active_buffer().large_file {
+            self.apply_save_transforms(path);
+        }
         let saved_text = {
             let buffer = self
                 .buffer_by_id_mut(self.active_buffer_id)
                 .ok_or_else(|| AppError::Invariant(String::from("active buffer is missing")))?;
-            crate::file::save_document(path, &buffer.document, buffer.encoding)?;
+            crate::file::save_document(
+                path,
+                &buffer.document,
+                buffer.encoding,
+                buffer.line_ending,
+            )?;
             buffer.document.mark_saved(path.to_path_buf());
-            buffer.saved_snapshot = buffer.document.text();
-            buffer.document.text()
+            buffer.history.mark_saved();
+            buffer.saved_encoding = buffer.encoding;
+            buffer.saved_line_ending = buffer.line_ending;
+            if buffer.large_file {
+                String::new()
+            } else {
+                buffer.saved_snapshot = buffer.document.text();
+                buffer.document.text()
+            }
         };
+        self.finish_save(path, saved_text)
+    }
+
+    fn finish_save(&mut self, path: &Path, saved_text: String) -> Result<(), AppError> {
         self.recent_files.record(path);
         self.file_finder.refresh();
         self.explorer.refresh();
@@ -46,42 +91,194 @@ impl App {
         } else {
             self.set_message(&format!("Saved {}", path.display()), MessageKind::Info);
         }
-        self.lsp.did_save(path, &saved_text, &self.workspace_root);
+        if !self.active_buffer().large_file && !self.is_hex_view() {
+            self.lsp.did_save(path, &saved_text, &self.workspace_root);
+        }
         self.pending_quit_after_save = false;
         Ok(())
     }
 
-    pub(crate) fn open_path_in_active_pane(&mut self, path: &Path) -> Result<(), AppError> {
-        let buffer_id = if let Some(existing) = self
-            .buffers
-            .iter()
-            .find(|buffer| buffer.document.path().is_some_and(|item| item == path))
-            .map(|buffer| buffer.id)
-        {
-            existing
+    pub(crate) fn set_line_ending(&mut self, line_ending: LineEnding) {
+        let Some(buffer) = self.buffer_by_id_mut(self.active_buffer_id) else {
+            return;
+        };
+        if buffer.line_ending == line_ending {
+            let label = line_ending.label();
+            self.set_message(&format!("Line ending is already {label}"), MessageKind::Info);
+            return;
+        }
+        buffer.line_ending = line_ending;
+        buffer.refresh_dirty();
+        let label = line_ending.label();
+        self.set_message(
+            &format!("Line ending set to {label}; save to write it"),
+            MessageKind::Info,
+        );
+    }
+
+    pub(crate) fn reopen_with_encoding(&mut self, encoding: DetectedEncoding) {
+        let Some(path) = self.active_document().path().map(Path::to_path_buf) else {
+            self.set_message("Buffer has no file to reopen", MessageKind::Warning);
+            return;
+        };
+        if self.active_document().is_dirty() {
+            self.set_message(
+                "Buffer has unsaved changes; save or discard them before reopening",
+                MessageKind::Warning,
+            );
+            return;
+        }
+        let loaded = match crate::file::load_document_with_encoding(&path, encoding) {
+            Ok(loaded) => loaded,
+            Err(error) => {
+                self.set_message(&error.to_string(), MessageKind::Error);
+                return;
+            }
+        };
+
+        let encoding = loaded.encoding;
+        if let Some(buffer) = self.buffer_by_id_mut(self.active_buffer_id) {
+            buffer.reload_from(loaded);
+            buffer.history = History::default();
+        }
+
+        let cursor = self.active_pane().cursor();
+        let line = cursor.line.min(self.active_document().last_line_index());
+        let column = cursor
+            .column
+            .min(self.active_document().line_text(line).chars().count());
+        let cursor = Cursor::new(line, column);
+        if let Some(pane) = self.layout.focused_pane_mut() {
+            pane.set_cursor(cursor);
+            pane.set_selection(Selection::caret(cursor));
+        }
+        self.ensure_cursor_visible();
+        self.lsp
+            .did_change(&path, &self.active_document().text(), &self.workspace_root);
+
+        let label = encoding.label();
+        if encoding.is_lossy() {
+            self.set_message(
+                &format!("Reopened as {label}; undecodable bytes shown as U+FFFD"),
+                MessageKind::Warning,
+            );
         } else {
-            let loaded = crate::file::load_document(path)?;
-            let saved_snapshot = loaded.document.text();
-            self.push_buffer(
-                loaded.document,
-                History::default(),
-                saved_snapshot,
-                loaded.encoding,
-            )
+            self.set_message(&format!("Reopened as {label}"), MessageKind::Info);
+        }
+    }
+
+    pub(crate) fn set_save_encoding(&mut self, encoding: DetectedEncoding) {
+        let Some(buffer) = self.buffer_by_id_mut(self.active_buffer_id) else {
+            return;
         };
+        let bom = buffer.encoding.has_bom();
+        buffer.encoding = encoding.with_bom(bom);
+        buffer.refresh_dirty();
+        let label = encoding.label();
+        self.set_message(
+            &format!("Encoding set to {label}; save to write it"),
+            MessageKind::Info,
+        );
+    }
+
+    pub(crate) fn set_byte_order_mark(&mut self, bom: bool) {
+        let Some(buffer) = self.buffer_by_id_mut(self.active_buffer_id) else {
+            return;
+        };
+        let label = buffer.encoding.label();
+        if bom && !buffer.encoding.supports_bom() {
+            self.set_message(
+                &format!("{label} has no byte order mark"),
+                MessageKind::Warning,
+            );
+            return;
+        }
+        if buffer.encoding.has_bom() == bom {
+            let state = if bom { "has" } else { "has no" };
+            self.set_message(&format!("File already {state} a BOM"), MessageKind::Info);
+            return;
+        }
+        buffer.encoding = buffer.encoding.with_bom(bom);
+        buffer.refresh_dirty();
+        let action = if bom { "added" } else { "removed" };
+        self.set_message(
+            &format!("BOM {action}; save to write it"),
+            MessageKind::Info,
+        );
+    }
+
+    pub(crate) fn open_path_in_active_pane(&mut self, path: &Path) -> Result<(), AppError> {
+        let buffer_id = self.load_buffer(path)?;
         self.switch_to_buffer(buffer_id);
         self.recent_files.record(path);
         if let Some(text) = self
             .buffer_by_id(buffer_id)
+            .filter(|buffer| !buffer.large_file)
             .map(|buffer| buffer.document.text())
         {
             self.lsp.did_open(path, &text, &self.workspace_root);
         }
+        self.warn_about_load();
         Ok(())
     }
 
+    /// Returns the buffer for `path`, loading the file into a new one if it
+    /// is not open yet. Does not change the focused buffer.
+    pub(crate) fn load_buffer(&mut self, path: &Path) -> Result<u64, AppError> {
+        if let Some(existing) = self
+            .buffers
+            .iter()
+            .find(|buffer| buffer.document.path().is_some_and(|item| item == path))
+            .map(|buffer| buffer.id)
+        {
+            return Ok(existing);
+        }
+        let loaded = crate::file::load_document(path)?;
+        let buffer_id = self.push_buffer(
+            Document::new_empty(Some(path.to_path_buf())),
+            History::default(),
+            String::new(),
+            DetectedEncoding::default(),
+            LineEnding::default(),
+        );
+        if let Some(buffer) = self.buffer_by_id_mut(buffer_id) {
+            buffer.reload_from(loaded);
+        }
+        Ok(buffer_id)
+    }
+
+    pub(crate) fn warn_about_load(&mut self) {
+        if self.active_buffer().large_file {
+            self.set_message(
+                "Large file: syntax highlighting, folds and LSP are disabled",
+                MessageKind::Warning,
+            );
+            return;
+        }
+        let encoding = self.active_buffer().encoding;
+        if encoding.is_lossy() {
+            let label = encoding.label();
+            self.set_message(
+                &format!(
+                    "File is not valid {label}; undecodable bytes shown as U+FFFD. Try :reopen-encoding"
+                ),
+                MessageKind::Warning,
+            );
+        }
+    }
+
     pub(crate) fn poll_background_tasks(&mut self) {
-        self.lsp.poll_server_messages();
+        self.lsp.poll_server_messages(&self.buffers);
+        self.handle_lsp_responses();
+        self.lsp.resolve_completion_selection();
+        self.update_snippet();
+        self.sync_word_index();
+        self.continue_search_scans();
+        self.refresh_code_action_hint();
+        self.refresh_document_highlights();
+        self.refresh_inlay_hints();
+        self.refresh_semantic_tokens();
+        self.refresh_outline();
         let watched = match self.watcher.as_mut() {
             Some(watcher) => watcher.poll_paths(),
             None => return,
@@ -105,16 +302,13 @@ impl App {
                 }
                 Some(idx) if !self.buffers[idx].document.is_dirty() => {
                     // Open but clean — silently reload
-                    if let Ok(loaded) = crate::file::load_document(&path) {
-                        self.buffers[idx].document = loaded.document;
-                        self.buffers[idx].encoding = loaded.encoding;
-                        self.buffers[idx].saved_snapshot = self.buffers[idx].document.text();
-                        let language_id = self.buffers[idx]
-                            .document
-                            .path()
-                            .and_then(language_for_path);
-                        let _ = self.buffers[idx].syntax.set_language_id(language_id);
-                        self.buffers[idx].syntax.mark_dirty();
+                    if let Ok(loaded) = crate::file::load_document(&path)
+                        && self.buffers[idx]
+                            .hex
+                            .as_ref()
+                            .is_none_or(|hex| loaded.binary.as_deref() != Some(hex.bytes()))
+                    {
+                        self.buffers[idx].reload_from(loaded);
                     }
                     need_refresh = true;
                 }
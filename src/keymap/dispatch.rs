@@ -10,14 +10,28 @@ pub fn map_key_event(key_event: KeyEvent) -> Option<Command> {
     match key_event.code {
         KeyCode::PageUp if control => Some(Command::PreviousBuffer),
         KeyCode::PageDown if control => Some(Command::NextBuffer),
-        KeyCode::Left if modifiers.contains(KeyModifiers::ALT) => Some(Command::ResizePaneLeft),
-        KeyCode::Right if modifiers.contains(KeyModifiers::ALT) => Some(Command::ResizePaneRight),
+        // Alt+Left/Right walk the jump list; resizing panes, which used to
+        // own them, moved to Shift+Alt+Left/Right.
+        KeyCode::Left if extend && modifiers.contains(KeyModifiers::ALT) => {
+            Some(Command::ResizePaneLeft)
+        }
+        KeyCode::Right if extend && modifiers.contains(KeyModifiers::ALT) => {
+            Some(Command::ResizePaneRight)
+        }
+        KeyCode::Left if modifiers.contains(KeyModifiers::ALT) => Some(Command::JumpBack),
+        KeyCode::Right if modifiers.contains(KeyModifiers::ALT) => Some(Command::JumpForward),
         KeyCode::Home if control => Some(Command::MoveDocumentStart { extend: false }),
         KeyCode::End if control => Some(Command::MoveDocumentEnd { extend: false }),
         KeyCode::F(3) if extend => Some(Command::SearchPrevious),
         KeyCode::F(3) => Some(Command::SearchNext),
         KeyCode::F(1) => Some(Command::SignatureHelp),
+        KeyCode::F(7) if extend => Some(Command::PreviousHighlight),
+        KeyCode::F(7) => Some(Command::NextHighlight),
         KeyCode::F(12) if extend => Some(Command::GotoReferences),
+        KeyCode::F(12) if control => Some(Command::GotoImplementation),
+        KeyCode::F(12) if modifiers.contains(KeyModifiers::ALT) => {
+            Some(Command::GotoTypeDefinition)
+        }
         KeyCode::F(12) => Some(Command::GotoDefinition),
         KeyCode::F(2) => Some(Command::RenameSymbol),
         KeyCode::Left if control => Some(Command::MoveWordLeft { extend }),
@@ -46,6 +60,10 @@ pub fn map_key_event(key_event: KeyEvent) -> Option<Command> {
         KeyCode::Char('f') if control => Some(Command::OpenSearch),
         KeyCode::Char(' ') if control => Some(Command::TriggerCompletion),
         KeyCode::Char('b') if control => Some(Command::ToggleExplorer),
+        KeyCode::Char('o') if control => Some(Command::ToggleOutline),
+        KeyCode::Char('H') if modifiers.contains(KeyModifiers::ALT) => {
+            Some(Command::IncomingCalls)
+        }
         KeyCode::Char('t') if control => Some(Command::WorkspaceSymbols),
         KeyCode::Char('.') if control => Some(Command::CodeActions),
         KeyCode::Char('i') if control && modifiers.contains(KeyModifiers::SHIFT) => {
@@ -93,5 +111,13 @@ mod tests {
 
         let undo = map_key_event(KeyEvent::new(KeyCode::Char('z'), KeyModifiers::CONTROL));
         assert_eq!(undo, Some(Command::Undo));
+
+        let back = map_key_event(KeyEvent::new(KeyCode::Left, KeyModifiers::ALT));
+        assert_eq!(back, Some(Command::JumpBack));
+        let resize = map_key_event(KeyEvent::new(
+            KeyCode::Right,
+            KeyModifiers::ALT | KeyModifiers::SHIFT,
+        ));
+        assert_eq!(resize, Some(Command::ResizePaneRight));
     }
 }
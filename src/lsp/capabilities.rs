@@ -1,31 +1,145 @@
 use lsp_types::{
-    ClientCapabilities, CodeActionProviderCapability, HoverProviderCapability, InitializeResult,
-    OneOf, ServerCapabilities,
+    CallHierarchyServerCapability, ClientCapabilities, CodeActionProviderCapability,
+    DeclarationCapability, HoverProviderCapability, ImplementationProviderCapability,
+    InitializeResult, OneOf, SemanticTokensFullOptions, SemanticTokensServerCapabilities,
+    ServerCapabilities, TextDocumentSyncCapability, TextDocumentSyncKind,
+    TypeDefinitionProviderCapability,
 };
+use serde_json::Value;
+
+use crate::lsp::client::LspFeature;
+use crate::lsp::position::PositionEncoding;
+use crate::lsp::semantic_tokens::SemanticLegend;
 
 #[derive(Debug, Clone)]
 pub struct NegotiatedCapabilities {
     pub completion: bool,
     pub completion_trigger_characters: Vec<String>,
+    /// Server fills in item documentation lazily through `completionItem/resolve`.
+    pub completion_resolve: bool,
     pub hover: bool,
     pub signature_help: bool,
     pub goto_definition: bool,
+    pub declaration: bool,
+    pub type_definition: bool,
+    pub implementation: bool,
     pub references: bool,
     pub rename: bool,
     pub code_action: bool,
+    /// Server fills in code action edits lazily through `codeAction/resolve`.
+    pub code_action_resolve: bool,
     pub formatting: bool,
     pub workspace_symbols: bool,
+    pub inlay_hints: bool,
+    /// Server fills in inlay hint tooltips lazily through `inlayHint/resolve`.
+    pub inlay_hint_resolve: bool,
+    pub semantic_tokens: Option<SemanticTokensSupport>,
+    pub document_symbols: bool,
+    pub document_highlight: bool,
+    pub call_hierarchy: bool,
+    pub type_hierarchy: bool,
+    pub incremental_sync: bool,
+    pub position_encoding: PositionEncoding,
+}
+
+/// Which semantic token requests a server answers, and its legend.
+#[derive(Debug, Clone)]
+pub struct SemanticTokensSupport {
+    pub legend: SemanticLegend,
+    pub full: bool,
+    /// Server answers `semanticTokens/full/delta` against a previous result.
+    pub delta: bool,
+    pub range: bool,
+}
+
+impl NegotiatedCapabilities {
+    /// Diagnostics are pushed by the server, so every server supports them.
+    pub fn supports(&self, feature: LspFeature) -> bool {
+        match feature {
+            LspFeature::Completion => self.completion,
+            LspFeature::Hover => self.hover,
+            LspFeature::SignatureHelp => self.signature_help,
+            LspFeature::Definition => self.goto_definition,
+            LspFeature::Declaration => self.declaration,
+            LspFeature::TypeDefinition => self.type_definition,
+            LspFeature::Implementation => self.implementation,
+            LspFeature::References => self.references,
+            LspFeature::Rename => self.rename,
+            LspFeature::CodeAction => self.code_action,
+            LspFeature::Formatting => self.formatting,
+            LspFeature::WorkspaceSymbols => self.workspace_symbols,
+            LspFeature::InlayHints => self.inlay_hints,
+            LspFeature::SemanticTokens => self.semantic_tokens.is_some(),
+            LspFeature::DocumentSymbols => self.document_symbols,
+            LspFeature::DocumentHighlight => self.document_highlight,
+            LspFeature::CallHierarchy => self.call_hierarchy,
+            LspFeature::TypeHierarchy => self.type_hierarchy,
+            LspFeature::Diagnostics => true,
+        }
+    }
+
+    /// Short list of supported features for `:lsp-status`.
+    pub fn summary(&self) -> String {
+        let features = [
+            (self.completion, "completion"),
+            (self.hover, "hover"),
+            (self.signature_help, "signature"),
+            (self.goto_definition, "definition"),
+            (self.declaration, "declaration"),
+            (self.type_definition, "type-definition"),
+            (self.implementation, "implementation"),
+            (self.references, "references"),
+            (self.rename, "rename"),
+            (self.code_action, "code-actions"),
+            (self.formatting, "format"),
+            (self.workspace_symbols, "symbols"),
+            (self.inlay_hints, "inlay-hints"),
+            (self.semantic_tokens.is_some(), "semantic-tokens"),
+            (self.document_symbols, "outline"),
+            (self.document_highlight, "highlight"),
+            (self.call_hierarchy, "call-hierarchy"),
+            (self.type_hierarchy, "type-hierarchy"),
+        ];
+        let mut parts = features
+            .iter()
+            .filter(|(supported, _)| *supported)
+            .map(|(_, name)| *name)
+            .collect::<Vec<_>>();
+        parts.push(if self.incremental_sync {
+            "sync:incremental"
+        } else {
+            "sync:full"
+        });
+        let encoding = match self.position_encoding {
+            PositionEncoding::Utf8 => "utf-8",
+            PositionEncoding::Utf16 => "utf-16",
+            PositionEncoding::Utf32 => "utf-32",
+        };
+        format!("{} enc:{encoding}", parts.join(" "))
+    }
 }
 
 pub fn default_client_capabilities() -> ClientCapabilities {
     ClientCapabilities {
         workspace: Some(lsp_types::WorkspaceClientCapabilities {
             apply_edit: Some(true),
-            workspace_edit: None,
+            workspace_edit: Some(lsp_types::WorkspaceEditClientCapabilities {
+                document_changes: Some(true),
+                resource_operations: Some(vec![
+                    lsp_types::ResourceOperationKind::Create,
+                    lsp_types::ResourceOperationKind::Rename,
+                    lsp_types::ResourceOperationKind::Delete,
+                ]),
+                failure_handling: Some(lsp_types::FailureHandlingKind::Abort),
+                normalizes_line_endings: None,
+                change_annotation_support: None,
+            }),
             did_change_configuration: None,
             did_change_watched_files: None,
             symbol: None,
-            execute_command: None,
+            execute_command: Some(lsp_types::DynamicRegistrationClientCapabilities {
+                dynamic_registration: Some(false),
+            }),
             workspace_folders: Some(true),
             configuration: Some(true),
             semantic_tokens: None,
@@ -55,14 +169,24 @@ pub fn default_client_capabilities() -> ClientCapabilities {
                     preselect_support: Some(true),
                     tag_support: None,
                     insert_replace_support: Some(true),
-                    resolve_support: None,
+                    resolve_support: Some(lsp_types::CompletionItemCapabilityResolveSupport {
+                        properties: ["documentation", "detail", "additionalTextEdits"]
+                            .map(String::from)
+                            .to_vec(),
+                    }),
                     insert_text_mode_support: None,
                     label_details_support: Some(true),
                 }),
                 completion_item_kind: None,
                 context_support: Some(true),
                 insert_text_mode: Some(lsp_types::InsertTextMode::AS_IS),
-                completion_list: None,
+                completion_list: Some(lsp_types::CompletionListCapability {
+                    item_defaults: Some(
+                        ["editRange", "commitCharacters", "insertTextFormat"]
+                            .map(String::from)
+                            .to_vec(),
+                    ),
+                }),
             }),
             hover: Some(lsp_types::HoverClientCapabilities {
                 dynamic_registration: Some(false),
@@ -85,22 +209,47 @@ pub fn default_client_capabilities() -> ClientCapabilities {
                 }),
                 context_support: Some(true),
             }),
-            declaration: None,
-            definition: None,
-            type_definition: None,
-            implementation: None,
+            declaration: Some(goto_capability()),
+            definition: Some(goto_capability()),
+            type_definition: Some(goto_capability()),
+            implementation: Some(goto_capability()),
             references: Some(lsp_types::DynamicRegistrationClientCapabilities {
                 dynamic_registration: Some(false),
             }),
-            document_highlight: None,
-            document_symbol: None,
+            document_highlight: Some(lsp_types::DynamicRegistrationClientCapabilities {
+                dynamic_registration: Some(false),
+            }),
+            document_symbol: Some(lsp_types::DocumentSymbolClientCapabilities {
+                dynamic_registration: Some(false),
+                symbol_kind: None,
+                hierarchical_document_symbol_support: Some(true),
+                tag_support: None,
+            }),
             code_action: Some(lsp_types::CodeActionClientCapabilities {
                 dynamic_registration: Some(false),
-                code_action_literal_support: None,
+                code_action_literal_support: Some(lsp_types::CodeActionLiteralSupport {
+                    code_action_kind: lsp_types::CodeActionKindLiteralSupport {
+                        value_set: [
+                            "",
+                            "quickfix",
+                            "refactor",
+                            "refactor.extract",
+                            "refactor.inline",
+                            "refactor.rewrite",
+                            "source",
+                            "source.organizeImports",
+                        ]
+                        .into_iter()
+                        .map(String::from)
+                        .collect(),
+                    },
+                }),
                 is_preferred_support: Some(true),
                 disabled_support: Some(true),
                 data_support: Some(true),
-                resolve_support: None,
+                resolve_support: Some(lsp_types::CodeActionCapabilityResolveSupport {
+                    properties: vec![String::from("edit")],
+                }),
                 honors_change_annotations: Some(false),
             }),
             code_lens: None,
@@ -129,12 +278,75 @@ pub fn default_client_capabilities() -> ClientCapabilities {
             folding_range: None,
             selection_range: None,
             linked_editing_range: None,
-            call_hierarchy: None,
-            semantic_tokens: None,
+            call_hierarchy: Some(lsp_types::CallHierarchyClientCapabilities {
+                dynamic_registration: Some(false),
+            }),
+            semantic_tokens: Some(lsp_types::SemanticTokensClientCapabilities {
+                dynamic_registration: Some(false),
+                requests: lsp_types::SemanticTokensClientCapabilitiesRequests {
+                    range: Some(true),
+                    full: Some(lsp_types::SemanticTokensFullOptions::Delta { delta: Some(true) }),
+                },
+                token_types: [
+                    "namespace",
+                    "type",
+                    "class",
+                    "enum",
+                    "interface",
+                    "struct",
+                    "typeParameter",
+                    "parameter",
+                    "variable",
+                    "property",
+                    "enumMember",
+                    "event",
+                    "function",
+                    "method",
+                    "macro",
+                    "keyword",
+                    "modifier",
+                    "comment",
+                    "string",
+                    "number",
+                    "regexp",
+                    "operator",
+                    "decorator",
+                ]
+                .into_iter()
+                .map(lsp_types::SemanticTokenType::new)
+                .collect(),
+                token_modifiers: [
+                    "declaration",
+                    "definition",
+                    "readonly",
+                    "static",
+                    "deprecated",
+                    "abstract",
+                    "async",
+                    "modification",
+                    "documentation",
+                    "defaultLibrary",
+                ]
+                .into_iter()
+                .map(lsp_types::SemanticTokenModifier::new)
+                .collect(),
+                formats: vec![lsp_types::TokenFormat::RELATIVE],
+                overlapping_token_support: Some(false),
+                multiline_token_support: Some(false),
+                server_cancel_support: Some(true),
+                augments_syntax_tokens: Some(true),
+            }),
             moniker: None,
-            type_hierarchy: None,
+            type_hierarchy: Some(lsp_types::TypeHierarchyClientCapabilities {
+                dynamic_registration: Some(false),
+            }),
             inline_value: None,
-            inlay_hint: None,
+            inlay_hint: Some(lsp_types::InlayHintClientCapabilities {
+                dynamic_registration: Some(false),
+                resolve_support: Some(lsp_types::InlayHintResolveClientCapabilities {
+                    properties: vec![String::from("tooltip")],
+                }),
+            }),
             diagnostic: None,
         }),
         window: Some(lsp_types::WindowClientCapabilities {
@@ -146,29 +358,92 @@ pub fn default_client_capabilities() -> ClientCapabilities {
             stale_request_support: None,
             regular_expressions: None,
             markdown: None,
-            position_encodings: Some(vec![lsp_types::PositionEncodingKind::UTF16]),
+            position_encodings: Some(vec![
+                lsp_types::PositionEncodingKind::UTF32,
+                lsp_types::PositionEncodingKind::UTF8,
+                lsp_types::PositionEncodingKind::UTF16,
+            ]),
         }),
         experimental: None,
         notebook_document: None,
     }
 }
 
-pub fn negotiate(server: &InitializeResult) -> NegotiatedCapabilities {
+/// `raw` is the untyped initialize result; lsp-types has no field for
+/// `typeHierarchyProvider`.
+pub fn negotiate(server: &InitializeResult, raw: &Value) -> NegotiatedCapabilities {
     let capabilities = &server.capabilities;
     NegotiatedCapabilities {
         completion: capabilities.completion_provider.is_some(),
         completion_trigger_characters: completion_trigger_characters(capabilities),
+        completion_resolve: capabilities
+            .completion_provider
+            .as_ref()
+            .is_some_and(|options| options.resolve_provider == Some(true)),
         hover: hover_supported(capabilities),
         signature_help: capabilities.signature_help_provider.is_some(),
         goto_definition: one_of_supported(&capabilities.definition_provider),
+        declaration: !matches!(
+            capabilities.declaration_provider,
+            None | Some(DeclarationCapability::Simple(false))
+        ),
+        type_definition: !matches!(
+            capabilities.type_definition_provider,
+            None | Some(TypeDefinitionProviderCapability::Simple(false))
+        ),
+        implementation: !matches!(
+            capabilities.implementation_provider,
+            None | Some(ImplementationProviderCapability::Simple(false))
+        ),
         references: one_of_supported(&capabilities.references_provider),
         rename: one_of_supported(&capabilities.rename_provider),
         code_action: code_action_supported(&capabilities.code_action_provider),
+        code_action_resolve: matches!(
+            &capabilities.code_action_provider,
+            Some(CodeActionProviderCapability::Options(options))
+                if options.resolve_provider == Some(true)
+        ),
         formatting: one_of_supported(&capabilities.document_formatting_provider),
         workspace_symbols: one_of_supported(&capabilities.workspace_symbol_provider),
+        inlay_hints: one_of_supported(&capabilities.inlay_hint_provider),
+        inlay_hint_resolve: matches!(
+            &capabilities.inlay_hint_provider,
+            Some(OneOf::Right(lsp_types::InlayHintServerCapabilities::Options(options)))
+                if options.resolve_provider == Some(true)
+        ),
+        semantic_tokens: semantic_tokens_support(capabilities),
+        document_symbols: one_of_supported(&capabilities.document_symbol_provider),
+        document_highlight: one_of_supported(&capabilities.document_highlight_provider),
+        call_hierarchy: matches!(
+            &capabilities.call_hierarchy_provider,
+            Some(CallHierarchyServerCapability::Simple(true))
+                | Some(CallHierarchyServerCapability::Options(_))
+        ),
+        type_hierarchy: raw
+            .pointer("/capabilities/typeHierarchyProvider")
+            .is_some_and(|provider| provider.is_object() || provider == &Value::Bool(true)),
+        incremental_sync: incremental_sync_supported(&capabilities.text_document_sync),
+        position_encoding: PositionEncoding::from_kind(capabilities.position_encoding.as_ref()),
+    }
+}
+
+/// Goto requests may answer with `LocationLink`s.
+fn goto_capability() -> lsp_types::GotoCapability {
+    lsp_types::GotoCapability {
+        dynamic_registration: Some(false),
+        link_support: Some(true),
     }
 }
 
+fn incremental_sync_supported(value: &Option<TextDocumentSyncCapability>) -> bool {
+    let kind = match value {
+        Some(TextDocumentSyncCapability::Kind(kind)) => Some(*kind),
+        Some(TextDocumentSyncCapability::Options(options)) => options.change,
+        None => None,
+    };
+    kind == Some(TextDocumentSyncKind::INCREMENTAL)
+}
+
 fn completion_trigger_characters(capabilities: &ServerCapabilities) -> Vec<String> {
     capabilities
         .completion_provider
@@ -200,3 +475,24 @@ fn code_action_supported(value: &Option<CodeActionProviderCapability>) -> bool {
         None => false,
     }
 }
+
+fn semantic_tokens_support(capabilities: &ServerCapabilities) -> Option<SemanticTokensSupport> {
+    let options = match capabilities.semantic_tokens_provider.as_ref()? {
+        SemanticTokensServerCapabilities::SemanticTokensOptions(options) => options,
+        SemanticTokensServerCapabilities::SemanticTokensRegistrationOptions(registration) => {
+            &registration.semantic_tokens_options
+        }
+    };
+    let (full, delta) = match options.full {
+        Some(SemanticTokensFullOptions::Bool(full)) => (full, false),
+        Some(SemanticTokensFullOptions::Delta { delta }) => (true, delta == Some(true)),
+        None => (false, false),
+    };
+    let range = options.range == Some(true);
+    (full || range).then(|| SemanticTokensSupport {
+        legend: SemanticLegend::from_lsp(&options.legend),
+        full,
+        delta,
+        range,
+    })
+}
@@ -1,19 +1,27 @@
 use std::collections::HashMap;
 use std::path::Path;
 use std::process::{Command, Stdio};
+use std::time::Instant;
 
 use crate::lsp::capabilities::NegotiatedCapabilities;
+use crate::lsp::position::PositionEncoding;
 use crate::lsp::client::response_loop::spawn_response_loop;
 use crate::lsp::client::session::LspClient;
+use crate::lsp::client::stderr_loop::spawn_stderr_loop;
+use crate::lsp::client::traffic::TrafficLog;
 use crate::lsp::client::write_loop::spawn_write_loop;
 use crate::lsp::client::ServerConfig;
 
-pub(super) fn launch_client(config: &ServerConfig, root_path: &Path) -> Result<LspClient, String> {
+pub(super) fn launch_client(
+    config: &ServerConfig,
+    root_path: &Path,
+    traffic: &TrafficLog,
+) -> Result<LspClient, String> {
     let mut child = Command::new(&config.command)
         .args(&config.args)
         .stdin(Stdio::piped())
         .stdout(Stdio::piped())
-        .stderr(Stdio::null())
+        .stderr(Stdio::piped())
         .spawn()
         .map_err(|error| format!("failed to spawn {}: {error}", config.command))?;
 
@@ -25,9 +33,14 @@ pub(super) fn launch_client(config: &ServerConfig, root_path: &Path) -> Result<L
         .stdout
         .take()
         .ok_or_else(|| String::from("missing language server stdout"))?;
+    let stderr = child
+        .stderr
+        .take()
+        .ok_or_else(|| String::from("missing language server stderr"))?;
 
-    let sender = spawn_write_loop(stdin);
-    let receiver = spawn_response_loop(stdout);
+    let sender = spawn_write_loop(stdin, traffic.clone(), config.name.clone());
+    let receiver = spawn_response_loop(stdout, traffic.clone(), config.name.clone());
+    let stderr = spawn_stderr_loop(stderr);
 
     Ok(LspClient {
         language_id: config.language_id.clone(),
@@ -40,7 +53,10 @@ pub(super) fn launch_client(config: &ServerConfig, root_path: &Path) -> Result<L
         receiver,
         queued_notifications: Vec::new(),
         queued_responses: HashMap::new(),
+        queued_server_requests: Vec::new(),
         next_request_id: 1_000_000,
+        stderr,
+        started: Instant::now(),
     })
 }
 
@@ -48,13 +64,27 @@ fn default_capabilities() -> NegotiatedCapabilities {
     NegotiatedCapabilities {
         completion: false,
         completion_trigger_characters: Vec::new(),
+        completion_resolve: false,
         hover: false,
         signature_help: false,
         goto_definition: false,
+        declaration: false,
+        type_definition: false,
+        implementation: false,
         references: false,
         rename: false,
         code_action: false,
+        code_action_resolve: false,
         formatting: false,
         workspace_symbols: false,
+        inlay_hints: false,
+        inlay_hint_resolve: false,
+        semantic_tokens: None,
+        document_symbols: false,
+        document_highlight: false,
+        call_hierarchy: false,
+        type_hierarchy: false,
+        incremental_sync: false,
+        position_encoding: PositionEncoding::default(),
     }
 }
@@ -1,9 +1,50 @@
+use serde::Deserialize;
 use serde_json::Value;
 
+/// Features a server can be routed; a server only gets the ones it is
+/// configured for and advertises.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LspFeature {
+    Completion,
+    Hover,
+    SignatureHelp,
+    Definition,
+    Declaration,
+    TypeDefinition,
+    Implementation,
+    References,
+    Rename,
+    CodeAction,
+    Formatting,
+    WorkspaceSymbols,
+    InlayHints,
+    SemanticTokens,
+    DocumentSymbols,
+    DocumentHighlight,
+    CallHierarchy,
+    TypeHierarchy,
+    Diagnostics,
+}
+
 #[derive(Debug, Clone)]
 pub struct ServerConfig {
+    /// Name shown in `:lsp-status` and the traffic log, e.g. `ruff`.
+    pub name: String,
     pub language_id: String,
     pub command: String,
     pub args: Vec<String>,
     pub initialization_options: Option<Value>,
+    /// Features this server is used for; `None` means all of them.
+    pub features: Option<Vec<LspFeature>>,
+    /// Formatting goes to this server even when an earlier one supports it.
+    pub formatter: bool,
+}
+
+impl ServerConfig {
+    pub fn handles(&self, feature: LspFeature) -> bool {
+        self.features
+            .as_ref()
+            .is_none_or(|features| features.contains(&feature))
+    }
 }
@@ -4,10 +4,13 @@ mod notifications;
 mod requests;
 mod response_loop;
 mod session;
+mod stderr_loop;
+mod traffic;
 mod uri;
 mod response;
 mod write_loop;
 
 pub use session::LspClient;
-pub use config::ServerConfig;
+pub use config::{LspFeature, ServerConfig};
 pub use response::RpcResponse;
+pub use traffic::{render_traffic, TrafficFilter, TrafficLog};
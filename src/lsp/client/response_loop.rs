@@ -6,6 +6,8 @@ use std::thread;
 use serde_json::Value;
 
 use crate::lsp::client::RpcResponse;
+use crate::lsp::client::traffic::{TrafficDirection, TrafficLog};
+use crate::lsp::protocol::{parse_message, read_message};
 
 pub(super) enum IncomingMessage {
     Notification(Value),
@@ -13,14 +15,22 @@ pub(super) enum IncomingMessage {
     Response(RpcResponse),
 }
 
-pub(super) fn spawn_response_loop(stdout: ChildStdout) -> Receiver<IncomingMessage> {
+pub(super) fn spawn_response_loop(
+    stdout: ChildStdout,
+    traffic: TrafficLog,
+    server: String,
+) -> Receiver<IncomingMessage> {
     let (tx, receiver) = mpsc::channel::<IncomingMessage>();
     thread::spawn(move || {
         let mut reader = BufReader::new(stdout);
-        while let Ok(message) = crate::lsp::protocol::read_message(&mut reader) {
-            let Some(payload) = message else {
+        while let Ok(message) = read_message(&mut reader) {
+            let Some(body) = message else {
                 break;
             };
+            let Ok(payload) = parse_message(&body) else {
+                break;
+            };
+            traffic.record(&server, TrafficDirection::Incoming, body);
 
             if payload.get("method").is_some() && payload.get("id").is_some() {
                 let _ = tx.send(IncomingMessage::ServerRequest(payload));
@@ -2,18 +2,26 @@ use std::collections::HashMap;
 use std::io;
 use std::path::{Path, PathBuf};
 use std::process::Child;
-use std::sync::mpsc::{Receiver, Sender};
-use std::time::Duration;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
 
 use lsp_types::{InitializeResult, InitializedParams};
 use serde_json::Value;
+use tokio::runtime::Builder;
 
 use crate::lsp::capabilities::{default_client_capabilities, negotiate, NegotiatedCapabilities};
 use crate::lsp::client::bootstrap::launch_client;
 use crate::lsp::client::response_loop::IncomingMessage;
+use crate::lsp::client::stderr_loop::StderrLog;
+use crate::lsp::client::TrafficLog;
 use crate::lsp::client::uri::file_uri;
 use crate::lsp::client::{RpcResponse, ServerConfig};
 
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_millis(500);
+/// How often a server told to exit is checked for having done so.
+const EXIT_POLL: Duration = Duration::from_millis(10);
+
 #[derive(Debug)]
 pub struct LspClient {
     pub language_id: String,
@@ -26,16 +34,51 @@ pub struct LspClient {
     pub(super) receiver: Receiver<IncomingMessage>,
     pub(super) queued_notifications: Vec<Value>,
     pub(super) queued_responses: HashMap<u64, RpcResponse>,
+    /// Server requests the app has to answer, such as `workspace/applyEdit`.
+    pub(super) queued_server_requests: Vec<Value>,
     pub(super) next_request_id: u64,
+    pub(super) stderr: StderrLog,
+    pub(super) started: Instant,
 }
 
 impl LspClient {
-    pub async fn launch(config: &ServerConfig, root_path: &Path) -> Result<Self, String> {
-        let mut client = launch_client(config, root_path)?;
+    pub async fn launch(
+        config: &ServerConfig,
+        root_path: &Path,
+        traffic: &TrafficLog,
+    ) -> Result<Self, String> {
+        let mut client = launch_client(config, root_path, traffic)?;
         client.initialize().await?;
         Ok(client)
     }
 
+    /// Launches on a thread of its own so a slow `initialize` leaves the
+    /// editor responsive; the receiver yields the outcome once.
+    pub fn launch_in_background(
+        config: ServerConfig,
+        root_path: PathBuf,
+        traffic: TrafficLog,
+    ) -> Receiver<Result<Self, String>> {
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            let result = match Builder::new_current_thread().enable_all().build() {
+                Ok(runtime) => runtime.block_on(Self::launch(&config, &root_path, &traffic)),
+                Err(error) => Err(error.to_string()),
+            };
+            let _ = sender.send(result);
+        });
+        receiver
+    }
+
+    /// Runs `shutdown` on a thread of its own; join the handle to wait for it.
+    pub fn shutdown_in_background(self) -> JoinHandle<()> {
+        std::thread::spawn(move || {
+            if let Ok(runtime) = Builder::new_current_thread().enable_all().build() {
+                runtime.block_on(self.shutdown());
+            }
+        })
+    }
+
     async fn initialize(&mut self) -> Result<(), String> {
         let init_value = serde_json::json!({
             "processId": std::process::id(),
@@ -54,9 +97,9 @@ impl LspClient {
             }
         });
         let result = self.request("initialize", init_value).await?;
-        let init: InitializeResult = serde_json::from_value(result)
+        let init: InitializeResult = serde_json::from_value(result.clone())
             .map_err(|error| format!("invalid initialize response: {error}"))?;
-        self.capabilities = negotiate(&init);
+        self.capabilities = negotiate(&init, &result);
         let initialized_value = serde_json::to_value(InitializedParams {})
             .map_err(|error| format!("failed to serialize initialized params: {error}"))?;
         self.notify("initialized", initialized_value).await?;
@@ -73,15 +116,74 @@ impl LspClient {
         Ok(())
     }
 
+    pub fn pid(&self) -> u32 {
+        self.child.id()
+    }
+
+    pub fn uptime(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// The last `count` lines the server wrote to stderr.
+    pub fn stderr_tail(&self, count: usize) -> Vec<String> {
+        self.stderr
+            .lock()
+            .map(|lines| {
+                let skip = lines.len().saturating_sub(count);
+                lines.iter().skip(skip).cloned().collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Describes how the server process ended, or `None` while it runs.
+    pub fn exit_status(&mut self) -> Option<String> {
+        match self.child.try_wait() {
+            Ok(Some(status)) => Some(status.to_string()),
+            Ok(None) => None,
+            Err(error) => Some(error.to_string()),
+        }
+    }
+
+    /// Sends `shutdown` then `exit`, giving the server a moment to quit before
+    /// `Drop` kills it.
+    async fn shutdown(mut self) {
+        if self.exit_status().is_some() {
+            return;
+        }
+        let _ = self
+            .request_with_timeout("shutdown", Value::Null, SHUTDOWN_TIMEOUT)
+            .await;
+        let _ = self.notify("exit", Value::Null).await;
+        let _ = tokio::time::timeout(SHUTDOWN_TIMEOUT, self.wait_for_exit()).await;
+    }
+
+    async fn wait_for_exit(&mut self) {
+        while self.exit_status().is_none() {
+            tokio::time::sleep(EXIT_POLL).await;
+        }
+    }
+
     pub async fn request(&mut self, method: &str, params: Value) -> Result<Value, String> {
+        self.request_with_timeout(method, params, Duration::from_secs(5))
+            .await
+    }
+
+    pub async fn request_with_timeout(
+        &mut self,
+        method: &str,
+        params: Value,
+        timeout: Duration,
+    ) -> Result<Value, String> {
         let id = self.send_request(method, params)?;
+        let deadline = std::time::Instant::now() + timeout;
 
         if let Some(response) = self.queued_responses.remove(&id) {
             return response.into_result(method);
         }
 
         loop {
-            let incoming = self.receiver.recv_timeout(Duration::from_secs(5));
+            let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+            let incoming = self.receiver.recv_timeout(remaining);
             let incoming = match incoming {
                 Ok(message) => message,
                 Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {
@@ -119,6 +221,16 @@ impl LspClient {
         Ok(id)
     }
 
+    /// Asks the server to drop a request whose answer is no longer wanted.
+    pub fn cancel_request(&mut self, id: u64) {
+        self.queued_responses.remove(&id);
+        let payload = super::notifications::build_notification(
+            "$/cancelRequest",
+            serde_json::json!({ "id": id }),
+        );
+        let _ = self.write_jsonrpc(&payload.to_string());
+    }
+
     pub async fn notify(&mut self, method: &str, params: Value) -> Result<(), String> {
         let payload = super::notifications::build_notification(method, params);
         self.write_jsonrpc(&payload.to_string())
@@ -131,6 +243,17 @@ impl LspClient {
         std::mem::take(&mut self.queued_notifications)
     }
 
+    pub fn drain_server_requests(&mut self) -> Vec<Value> {
+        self.pump_incoming();
+        std::mem::take(&mut self.queued_server_requests)
+    }
+
+    /// Answers a request taken from `drain_server_requests`.
+    pub fn respond(&mut self, id: u64, result: Value) {
+        let payload = super::requests::build_response(id, result);
+        let _ = self.write_jsonrpc(&payload.to_string());
+    }
+
     pub fn drain_responses(&mut self) -> Vec<RpcResponse> {
         self.pump_incoming();
         self.queued_responses.drain().map(|(_, response)| response).collect()
@@ -160,6 +283,10 @@ impl LspClient {
             .get("method")
             .and_then(Value::as_str)
             .unwrap_or_default();
+        if method == "workspace/applyEdit" {
+            self.queued_server_requests.push(request);
+            return Ok(());
+        }
         let result = match method {
             "workspace/configuration" => configuration_response(
                 request.get("params"),
@@ -0,0 +1,31 @@
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader};
+use std::process::ChildStderr;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Lines of server stderr kept for `:lsp-status`.
+const STDERR_RING_LINES: usize = 200;
+
+pub(super) type StderrLog = Arc<Mutex<VecDeque<String>>>;
+
+pub(super) fn spawn_stderr_loop(stderr: ChildStderr) -> StderrLog {
+    let log: StderrLog = Arc::new(Mutex::new(VecDeque::with_capacity(STDERR_RING_LINES)));
+    let writer = Arc::clone(&log);
+    thread::spawn(move || {
+        let reader = BufReader::new(stderr);
+        for line in reader.lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            let Ok(mut lines) = writer.lock() else {
+                break;
+            };
+            if lines.len() == STDERR_RING_LINES {
+                lines.pop_front();
+            }
+            lines.push_back(line);
+        }
+    });
+    log
+}
@@ -0,0 +1,278 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Messages kept in memory for `:lsp-log`; older ones are dropped.
+const MAX_TRAFFIC_ENTRIES: usize = 2000;
+/// Bytes of message bodies kept in memory, across all entries.
+const MAX_TRAFFIC_BYTES: usize = 8 * 1024 * 1024;
+/// Longer bodies are cut to this many bytes in memory; the trace file
+/// still gets them whole.
+const MAX_BODY_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TrafficDirection {
+    Outgoing,
+    Incoming,
+}
+
+impl TrafficDirection {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Outgoing => "send",
+            Self::Incoming => "recv",
+        }
+    }
+
+    fn arrow(self) -> &'static str {
+        match self {
+            Self::Outgoing => "->",
+            Self::Incoming => "<-",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TrafficEntry {
+    pub at: Duration,
+    pub server: String,
+    pub direction: TrafficDirection,
+    /// Responses borrow the method of the request they answer.
+    pub method: Option<String>,
+    /// Message text as sent, parsed only when rendered.
+    pub body: String,
+    /// Full length of a body cut to `MAX_BODY_BYTES`.
+    pub truncated_from: Option<usize>,
+}
+
+/// The fields of a message the log indexes by; the rest is skipped unparsed.
+#[derive(Deserialize)]
+struct Envelope {
+    id: Option<Value>,
+    method: Option<String>,
+}
+
+#[derive(Debug)]
+struct TrafficState {
+    entries: VecDeque<TrafficEntry>,
+    /// Methods of unanswered requests keyed by server, sender and id.
+    open_requests: HashMap<(String, TrafficDirection, u64), String>,
+    /// Body bytes held by `entries`.
+    bytes: usize,
+    trace: Option<File>,
+}
+
+/// Shared, bounded record of every JSON-RPC message exchanged with servers.
+#[derive(Debug, Clone)]
+pub struct TrafficLog {
+    started: Instant,
+    state: Arc<Mutex<TrafficState>>,
+}
+
+impl Default for TrafficLog {
+    fn default() -> Self {
+        Self {
+            started: Instant::now(),
+            state: Arc::new(Mutex::new(TrafficState {
+                entries: VecDeque::new(),
+                open_requests: HashMap::new(),
+                bytes: 0,
+                trace: None,
+            })),
+        }
+    }
+}
+
+impl TrafficLog {
+    /// Also appends every message to `path`, one line each.
+    pub fn with_trace_file(path: &Path) -> Self {
+        let log = Self::default();
+        if let Ok(file) = OpenOptions::new().append(true).create(true).open(path)
+            && let Ok(mut state) = log.state.lock()
+        {
+            state.trace = Some(file);
+        }
+        log
+    }
+
+    pub fn record(&self, server: &str, direction: TrafficDirection, mut body: String) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        let envelope = serde_json::from_str::<Envelope>(&body).ok();
+        let id = envelope
+            .as_ref()
+            .and_then(|envelope| envelope.id.as_ref()?.as_u64());
+        let method = envelope.and_then(|envelope| envelope.method);
+        let method = match (id, method) {
+            (Some(id), Some(method)) => {
+                state
+                    .open_requests
+                    .insert((server.to_owned(), direction, id), method.clone());
+                Some(method)
+            }
+            (Some(id), None) => {
+                let requester = match direction {
+                    TrafficDirection::Outgoing => TrafficDirection::Incoming,
+                    TrafficDirection::Incoming => TrafficDirection::Outgoing,
+                };
+                state.open_requests.remove(&(server.to_owned(), requester, id))
+            }
+            (None, method) => method,
+        };
+        let at = self.started.elapsed();
+        if let Some(trace) = state.trace.as_mut() {
+            let arrow = direction.arrow();
+            let _ = writeln!(trace, "{:.3}s {server} {arrow} {body}", at.as_secs_f64());
+        }
+        let truncated_from = (body.len() > MAX_BODY_BYTES).then(|| {
+            let length = body.len();
+            body.truncate(body.floor_char_boundary(MAX_BODY_BYTES));
+            body.shrink_to_fit();
+            length
+        });
+        state.bytes += body.len();
+        state.entries.push_back(TrafficEntry {
+            at,
+            server: server.to_owned(),
+            direction,
+            method,
+            body,
+            truncated_from,
+        });
+        while state.entries.len() > MAX_TRAFFIC_ENTRIES || state.bytes > MAX_TRAFFIC_BYTES {
+            let Some(dropped) = state.entries.pop_front() else {
+                break;
+            };
+            state.bytes -= dropped.body.len();
+        }
+    }
+
+    pub fn entries(&self, filter: &TrafficFilter) -> Vec<TrafficEntry> {
+        self.state
+            .lock()
+            .map(|state| {
+                state
+                    .entries
+                    .iter()
+                    .filter(|entry| filter.matches(entry))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// `server=<name>` and `method=<substring>` terms from `:lsp-log`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrafficFilter {
+    pub server: Option<String>,
+    pub method: Option<String>,
+}
+
+impl TrafficFilter {
+    pub fn parse(args: &str) -> Result<Self, String> {
+        let mut filter = Self::default();
+        for term in args.split_whitespace() {
+            match term.split_once('=') {
+                Some(("server", value)) => filter.server = Some(value.to_owned()),
+                Some(("method", value)) => filter.method = Some(value.to_owned()),
+                _ => return Err(format!("Unknown filter '{term}'. Use server=NAME or method=TEXT")),
+            }
+        }
+        Ok(filter)
+    }
+
+    fn matches(&self, entry: &TrafficEntry) -> bool {
+        self.server.as_ref().is_none_or(|server| &entry.server == server)
+            && self.method.as_ref().is_none_or(|method| {
+                entry
+                    .method
+                    .as_ref()
+                    .is_some_and(|entry_method| entry_method.contains(method.as_str()))
+            })
+    }
+}
+
+/// Renders entries as a sequence of JSON objects for the log buffer.
+pub fn render_traffic(entries: &[TrafficEntry]) -> String {
+    let mut text = String::new();
+    for entry in entries {
+        // Cut bodies are no longer JSON and show as a string.
+        let message = match entry.truncated_from {
+            None => serde_json::from_str::<Value>(&entry.body)
+                .unwrap_or_else(|_| Value::from(entry.body.as_str())),
+            Some(_) => Value::from(entry.body.as_str()),
+        };
+        let message = serde_json::to_string_pretty(&message)
+            .unwrap_or_default()
+            .replace('\n', "\n  ");
+        let method = entry.method.as_deref().map_or(Value::Null, Value::from);
+        text.push_str("{\n");
+        text.push_str(&format!("  \"at\": \"{:.3}s\",\n", entry.at.as_secs_f64()));
+        text.push_str(&format!("  \"server\": {},\n", Value::from(entry.server.as_str())));
+        text.push_str(&format!("  \"direction\": \"{}\",\n", entry.direction.label()));
+        text.push_str(&format!("  \"method\": {method},\n"));
+        if let Some(length) = entry.truncated_from {
+            text.push_str(&format!("  \"truncated_from\": {length},\n"));
+        }
+        text.push_str(&format!("  \"message\": {message}\n}}\n"));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{MAX_BODY_BYTES, TrafficDirection, TrafficFilter, TrafficLog, render_traffic};
+
+    #[test]
+    fn responses_inherit_request_methods_and_filter() {
+        let log = TrafficLog::default();
+        let hover = json!({ "jsonrpc": "2.0", "id": 7, "method": "textDocument/hover" });
+        log.record("rust", TrafficDirection::Outgoing, hover.to_string());
+        let reply = json!({ "id": 7, "result": null });
+        log.record("rust", TrafficDirection::Incoming, reply.to_string());
+        let message = json!({ "method": "window/logMessage" });
+        log.record("python", TrafficDirection::Incoming, message.to_string());
+
+        let filter = TrafficFilter::parse("method=hover").unwrap_or_default();
+        let entries = log.entries(&filter);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].method.as_deref(), Some("textDocument/hover"));
+
+        let filter = TrafficFilter::parse("server=python").unwrap_or_default();
+        let rendered = render_traffic(&log.entries(&filter));
+        assert!(rendered.contains("\"method\": \"window/logMessage\""));
+        assert!(serde_json::from_str::<serde_json::Value>(&rendered).is_ok());
+        assert!(TrafficFilter::parse("bogus").is_err());
+    }
+
+    #[test]
+    fn cuts_long_bodies_and_appends_to_the_trace_file() {
+        let dir = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let path = dir.path().join("trace.log");
+        std::fs::write(&path, "earlier session\n").unwrap_or_else(|e| panic!("{e}"));
+        let log = TrafficLog::with_trace_file(&path);
+        let text = "x".repeat(MAX_BODY_BYTES * 2);
+        let long = json!({ "id": 1, "method": "workspace/symbol", "params": { "query": text } });
+        log.record("rust", TrafficDirection::Outgoing, long.to_string());
+
+        let entries = log.entries(&TrafficFilter::default());
+        assert_eq!(entries[0].method.as_deref(), Some("workspace/symbol"));
+        assert_eq!(entries[0].body.len(), MAX_BODY_BYTES);
+        assert_eq!(entries[0].truncated_from, Some(long.to_string().len()));
+        assert!(render_traffic(&entries).contains("\"truncated_from\""));
+
+        let trace = std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("{e}"));
+        assert!(trace.starts_with("earlier session\n"));
+        assert!(trace.contains(&text));
+    }
+}
@@ -3,17 +3,28 @@ use std::process::ChildStdin;
 use std::sync::mpsc::{self, Sender};
 use std::thread;
 
-pub(super) fn spawn_write_loop(stdin: ChildStdin) -> Sender<String> {
+use crate::lsp::client::traffic::{TrafficDirection, TrafficLog};
+
+pub(super) fn spawn_write_loop(
+    stdin: ChildStdin,
+    traffic: TrafficLog,
+    server: String,
+) -> Sender<String> {
     let (tx, receiver) = mpsc::channel::<String>();
     thread::spawn(move || {
         let mut stdin = stdin;
-        while let Ok(message) = receiver.recv() {
+        while let Ok(mut message) = receiver.recv() {
             if stdin.write_all(message.as_bytes()).is_err() {
                 break;
             }
             if stdin.flush().is_err() {
                 break;
             }
+            // Log the body alone, reusing the sent buffer.
+            if let Some(header) = message.find("\r\n\r\n") {
+                message.drain(..header + 4);
+                traffic.record(&server, TrafficDirection::Outgoing, message);
+            }
         }
     });
     tx
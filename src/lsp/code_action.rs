@@ -1,4 +1,134 @@
+use std::collections::BTreeSet;
+
+use serde_json::Value;
+
 use crate::app::{App, MessageKind};
+use crate::lsp::position::PositionEncoding;
+use crate::lsp::workspace::CodeActionRun;
+use crate::lsp::workspace_edit::{EditOperation, EditOrigin, WorkspaceEditPlan};
+use crate::ui::{PickerItem, PickerKind, PickerState};
+
+/// Longest replacement text shown in a quick-fix preview.
+const PREVIEW_TEXT_LIMIT: usize = 40;
+
+/// One entry of the code action menu.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeActionView {
+    pub title: String,
+    pub kind: Option<String>,
+    pub preferred: bool,
+    /// Why the server says the action cannot run right now.
+    pub disabled: Option<String>,
+    /// What the action changes, e.g. `main.rs: "use std::fmt;"`.
+    pub preview: String,
+}
+
+impl CodeActionView {
+    /// Reads a `CodeAction` literal or a bare `Command`.
+    pub fn parse(value: &Value) -> Option<Self> {
+        let title = value.get("title")?.as_str()?.to_owned();
+        // A bare `Command` names its command with a string.
+        if let Some(command) = value.get("command").and_then(Value::as_str) {
+            return Some(Self {
+                title,
+                kind: None,
+                preferred: false,
+                disabled: None,
+                preview: format!("runs {command}"),
+            });
+        }
+        let preview = match (value.get("edit"), value.get("command")) {
+            // Only line counts and file names are read, so any encoding works.
+            (Some(edit), _) => {
+                edit_preview(&WorkspaceEditPlan::parse(edit, PositionEncoding::Utf8))
+            }
+            (None, Some(command)) => command
+                .get("command")
+                .and_then(Value::as_str)
+                .map(|command| format!("runs {command}"))
+                .unwrap_or_default(),
+            (None, None) => String::new(),
+        };
+        Some(Self {
+            title,
+            kind: value.get("kind").and_then(Value::as_str).map(ToOwned::to_owned),
+            preferred: value.get("isPreferred").and_then(Value::as_bool) == Some(true),
+            disabled: value
+                .get("disabled")
+                .and_then(|disabled| disabled.get("reason"))
+                .and_then(Value::as_str)
+                .map(ToOwned::to_owned),
+            preview,
+        })
+    }
+
+    fn picker_item(&self, index: usize) -> PickerItem {
+        let marker = if self.preferred { "\u{2605} " } else { "  " };
+        let mut subtitle = self
+            .kind
+            .as_deref()
+            .filter(|kind| !kind.is_empty())
+            .map(|kind| format!("[{kind}]"))
+            .unwrap_or_default();
+        let detail = match &self.disabled {
+            Some(reason) => format!("disabled: {reason}"),
+            None => self.preview.clone(),
+        };
+        if !detail.is_empty() {
+            if !subtitle.is_empty() {
+                subtitle.push(' ');
+            }
+            subtitle.push_str(&detail);
+        }
+        PickerItem {
+            title: format!("{marker}{}", self.title),
+            subtitle,
+            path: None,
+            buffer_id: None,
+            line: None,
+            column: None,
+            entry: Some(index),
+        }
+    }
+}
+
+/// Summarises an edit as the single replacement it makes, or as counts.
+fn edit_preview(plan: &WorkspaceEditPlan) -> String {
+    let mut files = BTreeSet::new();
+    let mut edits = Vec::new();
+    let mut file_operations = 0;
+    for operation in &plan.operations {
+        match operation {
+            EditOperation::Text { path, edits: list, .. } if !list.is_empty() => {
+                files.insert(path.file_name().unwrap_or_default().to_string_lossy());
+                edits.extend(list);
+            }
+            EditOperation::Text { .. } => {}
+            _ => file_operations += 1,
+        }
+    }
+    let mut preview = match (edits.as_slice(), files.first()) {
+        ([], _) | (_, None) => String::new(),
+        ([edit], Some(file)) if edit.new_text.is_empty() => format!("{file}: delete text"),
+        ([edit], Some(file)) => {
+            let text = edit.new_text.lines().find(|line| !line.trim().is_empty());
+            let mut text = text.unwrap_or_default().trim().to_owned();
+            if text.chars().count() > PREVIEW_TEXT_LIMIT {
+                text = text.chars().take(PREVIEW_TEXT_LIMIT).collect::<String>() + "\u{2026}";
+            }
+            format!("{file}: \"{text}\"")
+        }
+        (edits, Some(file)) if files.len() == 1 => format!("{} edits in {file}", edits.len()),
+        (edits, Some(_)) => format!("{} edits in {} files", edits.len(), files.len()),
+    };
+    if file_operations > 0 {
+        if !preview.is_empty() {
+            preview.push_str(", ");
+        }
+        preview.push_str(&format!("{file_operations} file operation(s)"));
+    }
+    preview
+}
 
 impl App {
     pub(crate) fn show_code_actions(&mut self) {
@@ -6,27 +136,125 @@ impl App {
             return;
         };
         let cursor = self.active_pane().cursor();
-        let actions =
-            self.lsp
-                .request_code_actions(&path, &self.workspace_root, cursor.line, cursor.column);
-        if actions.is_empty() {
+        let (start, end) = self
+            .active_pane()
+            .selection()
+            .normalized()
+            .unwrap_or((cursor, cursor));
+        if !self
+            .lsp
+            .request_code_actions(&path, &self.workspace_root, start, end, &self.buffers)
+        {
             self.set_message("No code actions", MessageKind::Info);
+        }
+    }
+
+    /// Opens the menu, or refreshes it when another server's actions arrive.
+    pub(crate) fn show_code_action_menu(&mut self) {
+        if self.lsp.code_actions().is_empty() {
+            if !self.lsp.code_actions_pending() {
+                self.set_message("No code actions", MessageKind::Info);
+            }
             return;
         }
-        let mut picker = crate::ui::PickerState::new(crate::ui::PickerKind::Files);
-        picker.set_buffer_items(
-            actions
-                .into_iter()
-                .map(|title| crate::ui::PickerItem {
-                    title,
-                    subtitle: String::from("code action"),
-                    path: None,
-                    buffer_id: None,
-                    line: None,
-                    column: None,
-                })
-                .collect(),
-        );
+        if self.picker.as_ref().map(PickerState::kind) == Some(PickerKind::CodeActions) {
+            self.refresh_picker();
+            return;
+        }
+        let mut picker = PickerState::new(PickerKind::CodeActions);
+        picker.set_buffer_items(self.code_action_picker_items(""));
         self.picker = Some(picker);
     }
+
+    pub(crate) fn code_action_picker_items(&self, query: &str) -> Vec<PickerItem> {
+        let query = query.to_lowercase();
+        self.lsp
+            .code_actions()
+            .into_iter()
+            .enumerate()
+            .filter(|(_, action)| action.title.to_lowercase().contains(&query))
+            .map(|(index, action)| action.picker_item(index))
+            .collect()
+    }
+
+    /// Runs the menu entry at `index`, resolving its edit first if needed.
+    pub(crate) fn run_code_action(&mut self, index: usize) {
+        let disabled = self
+            .lsp
+            .code_actions()
+            .get(index)
+            .and_then(|action| action.disabled.clone());
+        if let Some(reason) = disabled {
+            self.set_message(&format!("Code action unavailable: {reason}"), MessageKind::Warning);
+            return;
+        }
+        if !self.lsp.run_code_action(index) {
+            self.set_message("Code action is no longer available", MessageKind::Warning);
+        }
+    }
+
+    /// Applies the action's edit, then asks the server to run its command.
+    pub(crate) fn apply_code_action(&mut self, run: CodeActionRun) {
+        self.begin_workspace_edit(run.edit.clone(), EditOrigin::CodeAction(run));
+    }
+
+    /// Asks for code actions on the cursor line so the gutter can show a
+    /// lightbulb; repeated calls on the same line and version are no-ops.
+    pub(crate) fn refresh_code_action_hint(&mut self) {
+        if self.active_buffer().large_file {
+            return;
+        }
+        let Some(path) = self.active_document().path().map(|path| path.to_path_buf()) else {
+            return;
+        };
+        let cursor = self.active_pane().cursor();
+        self.lsp.request_code_action_hint(&path, cursor, &self.buffers);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::CodeActionView;
+
+    #[test]
+    fn parses_kind_preference_and_previews() {
+        let quick_fix = json!({
+            "title": "Import `fmt`",
+            "kind": "quickfix",
+            "isPreferred": true,
+            "edit": { "changes": { "file:///tmp/main.rs": [{
+                "range": {
+                    "start": { "line": 0, "character": 0 },
+                    "end": { "line": 0, "character": 0 }
+                },
+                "newText": "use std::fmt;\n"
+            }] } }
+        });
+        let view = CodeActionView::parse(&quick_fix).unwrap_or_else(|| panic!("not parsed"));
+        assert_eq!(view.kind.as_deref(), Some("quickfix"));
+        assert!(view.preferred);
+        assert_eq!(view.preview, "main.rs: \"use std::fmt;\"");
+        let item = view.picker_item(3);
+        assert_eq!(item.title, "\u{2605} Import `fmt`");
+        assert_eq!(item.subtitle, "[quickfix] main.rs: \"use std::fmt;\"");
+        assert_eq!(item.entry, Some(3));
+
+        let command = json!({ "title": "Run test", "command": "rust-analyzer.runSingle" });
+        let view = CodeActionView::parse(&command).unwrap_or_else(|| panic!("not parsed"));
+        assert_eq!(view.preview, "runs rust-analyzer.runSingle");
+
+        let disabled = json!({
+            "title": "Extract function",
+            "kind": "refactor.extract",
+            "disabled": { "reason": "Select an expression" }
+        });
+        let view = CodeActionView::parse(&disabled).unwrap_or_else(|| panic!("not parsed"));
+        assert_eq!(view.disabled.as_deref(), Some("Select an expression"));
+        assert_eq!(
+            view.picker_item(0).subtitle,
+            "[refactor.extract] disabled: Select an expression"
+        );
+    }
 }
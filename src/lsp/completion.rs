@@ -1,46 +1,381 @@
+use lsp_types::{Range, TextEdit};
+use nucleo::pattern::{CaseMatching, Normalization, Pattern};
+use nucleo::{Config, Matcher, Utf32String};
+use serde_json::Value;
+
+use crate::core::Cursor;
+use crate::lsp::position::PositionEncoding;
+
+/// Where an item's text goes, in the server's positions at request time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompletionEdit {
+    /// Range to use when inserting; ends at the request position.
+    pub insert: Range,
+    /// Range that also covers the rest of the word after the cursor.
+    pub replace: Range,
+}
+
+/// Where a completion item came from, in ranking order for equal scores.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CompletionSource {
+    #[default]
+    Server,
+    /// The user's snippet library or a choice tabstop.
+    Snippet,
+    /// A word from an open buffer.
+    Word,
+    /// A file or directory next to the buffer.
+    Path,
+}
+
+impl CompletionSource {
+    /// Tag shown after the item in the list.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Server => "lsp",
+            Self::Snippet => "snippet",
+            Self::Word => "buffer",
+            Self::Path => "path",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct CompletionItemView {
+    /// Names the raw item the workspace keeps for `completionItem/resolve`.
+    pub id: usize,
     pub label: String,
     pub detail: String,
+    /// Markdown or plain text shown next to the list.
+    pub documentation: String,
+    /// Set once the server filled in the lazy fields.
+    pub resolved: bool,
+    /// `CompletionItemKind` number; 0 when the server sent none.
+    pub kind: u32,
     pub insert_text: String,
     pub is_snippet: bool,
+    pub filter_text: String,
+    pub sort_text: String,
+    pub preselect: bool,
+    /// Typing one of these accepts the item before the character is inserted.
+    pub commit_characters: Vec<char>,
+    pub edit: Option<CompletionEdit>,
+    /// Edits elsewhere in the document, such as an auto-import.
+    pub additional_edits: Vec<TextEdit>,
+    pub encoding: PositionEncoding,
+    pub source: CompletionSource,
+}
+
+impl CompletionItemView {
+    /// Short kind label shown in front of the item.
+    pub fn icon(&self) -> &'static str {
+        match self.kind {
+            1 => "text",
+            2 => "meth",
+            3 => "fn",
+            4 => "ctor",
+            5 => "field",
+            6 => "var",
+            7 => "class",
+            8 => "iface",
+            9 => "mod",
+            10 => "prop",
+            11 => "unit",
+            12 => "value",
+            13 => "enum",
+            14 => "kw",
+            15 => "snip",
+            16 => "color",
+            17 => "file",
+            18 => "ref",
+            19 => "dir",
+            20 => "member",
+            21 => "const",
+            22 => "struct",
+            23 => "event",
+            24 => "op",
+            25 => "type",
+            _ => "",
+        }
+    }
+
+    /// Edits that insert `text` for this item: its own edit, or else the
+    /// word from `start`, followed by the additional edits. `line_text` is
+    /// the cursor line, where `typed` moved the cursor since `request`.
+    /// With `replace` the item's edit also covers the rest of the word
+    /// after the cursor.
+    pub fn text_edits(
+        &self,
+        text: String,
+        line_text: &str,
+        request: Cursor,
+        start: Cursor,
+        typed: Cursor,
+        replace: bool,
+    ) -> Vec<TextEdit> {
+        let cursor = self.encoding.to_position(line_text, typed);
+        let range = match &self.edit {
+            Some(edit) => {
+                let requested = self.encoding.to_position(line_text, request);
+                let mut range = if replace { edit.replace } else { edit.insert };
+                if range.end.line == requested.line {
+                    let shift = i64::from(cursor.character) - i64::from(requested.character);
+                    let end = i64::from(range.end.character) + shift;
+                    range.end.character = end.max(i64::from(range.start.character)) as u32;
+                }
+                range
+            }
+            None => Range::new(self.encoding.to_position(line_text, start), cursor),
+        };
+        let mut edits = vec![TextEdit::new(range, text)];
+        edits.extend(self.additional_edits.iter().cloned());
+        edits
+    }
+}
+
+/// Reads a `CompletionItem[]` or `CompletionList`, filling in the list's
+/// `itemDefaults`. Returns the items paired with the raw `CompletionItem`
+/// and whether the list is incomplete.
+pub fn parse_completion_items(
+    value: &Value,
+    encoding: PositionEncoding,
+) -> (Vec<(CompletionItemView, Value)>, bool) {
+    let incomplete = value
+        .get("isIncomplete")
+        .and_then(Value::as_bool)
+        .unwrap_or(false);
+    let defaults = value.get("itemDefaults");
+    let default = |key: &str| defaults.and_then(|defaults| defaults.get(key));
+    let list = value.get("items").unwrap_or(value);
+    let Some(list) = list.as_array() else {
+        return (Vec::new(), incomplete);
+    };
+
+    let items = list
+        .iter()
+        .filter_map(|item| {
+            let label = item.get("label").and_then(Value::as_str)?;
+            let text = |key: &str| item.get(key).and_then(Value::as_str);
+            let text_edit = item.get("textEdit");
+            let edit = match text_edit {
+                Some(edit) => parse_edit_range(edit),
+                None => default("editRange").and_then(parse_edit_range),
+            };
+            let insert_text = text_edit
+                .and_then(|edit| edit.get("newText"))
+                .and_then(Value::as_str)
+                .or_else(|| text("textEditText"))
+                .or_else(|| text("insertText"))
+                .unwrap_or(label);
+            let format = item
+                .get("insertTextFormat")
+                .or_else(|| default("insertTextFormat"))
+                .and_then(Value::as_u64);
+            let commit_characters = item
+                .get("commitCharacters")
+                .or_else(|| default("commitCharacters"))
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(|character| character.as_str()?.chars().next())
+                .collect();
+            let additional_edits = item
+                .get("additionalTextEdits")
+                .and_then(|edits| serde_json::from_value(edits.clone()).ok())
+                .unwrap_or_default();
+
+            let view = CompletionItemView {
+                id: 0,
+                label: label.to_owned(),
+                detail: text("detail").unwrap_or_default().to_owned(),
+                documentation: item
+                    .get("documentation")
+                    .and_then(parse_documentation)
+                    .unwrap_or_default(),
+                resolved: false,
+                kind: item.get("kind").and_then(Value::as_u64).unwrap_or(0) as u32,
+                insert_text: insert_text.to_owned(),
+                is_snippet: format == Some(2),
+                filter_text: text("filterText").unwrap_or(label).to_owned(),
+                sort_text: text("sortText").unwrap_or(label).to_owned(),
+                preselect: item.get("preselect").and_then(Value::as_bool) == Some(true),
+                commit_characters,
+                edit,
+                additional_edits,
+                encoding,
+                source: CompletionSource::Server,
+            };
+            Some((view, item.clone()))
+        })
+        .collect();
+    (items, incomplete)
+}
+
+/// A plain string or `MarkupContent`.
+fn parse_documentation(value: &Value) -> Option<String> {
+    value
+        .as_str()
+        .or_else(|| value.get("value")?.as_str())
+        .map(ToOwned::to_owned)
+}
+
+/// The range of a `TextEdit` or an `editRange` default, or the two ranges
+/// of an `InsertReplaceEdit`.
+fn parse_edit_range(value: &Value) -> Option<CompletionEdit> {
+    let range = |key: &str| serde_json::from_value::<Range>(value.get(key)?.clone()).ok();
+    let single = range("range").or_else(|| serde_json::from_value(value.clone()).ok());
+    match single {
+        Some(range) => Some(CompletionEdit {
+            insert: range,
+            replace: range,
+        }),
+        None => Some(CompletionEdit {
+            insert: range("insert")?,
+            replace: range("replace")?,
+        }),
+    }
 }
 
 #[derive(Debug, Clone, Default)]
 pub struct CompletionContext {
     pub active: bool,
-    pub trigger_column: usize,
+    /// Where the request was made.
+    pub cursor: Cursor,
+    /// Start of the word being completed.
+    pub start: Cursor,
     pub items: Vec<CompletionItemView>,
+    /// Indices into `items` that match `filter`, best first.
+    pub matches: Vec<usize>,
+    /// Index into `matches`.
     pub selected: usize,
+    /// Word typed before the cursor that the items are narrowed by.
     pub filter: String,
+    /// Some server asked to be queried again as the word grows.
+    pub incomplete: bool,
 }
 
 impl CompletionContext {
     pub fn close(&mut self) {
         self.active = false;
         self.items.clear();
+        self.matches.clear();
         self.selected = 0;
         self.filter.clear();
+        self.incomplete = false;
     }
 
-    pub fn set_items(&mut self, trigger_column: usize, mut items: Vec<CompletionItemView>) {
-        items.sort_by(|left, right| left.label.cmp(&right.label));
-        self.active = !items.is_empty();
-        self.trigger_column = trigger_column;
+    pub fn set_items(&mut self, cursor: Cursor, items: Vec<CompletionItemView>, incomplete: bool) {
+        self.cursor = cursor;
         self.items = items;
-        self.selected = 0;
+        self.incomplete = incomplete;
+        self.refilter();
+    }
+
+    /// Adds another source's items to the list, skipping duplicates. A
+    /// duplicate of a buffer word replaces the word.
+    pub fn merge_items(
+        &mut self,
+        cursor: Cursor,
+        items: Vec<CompletionItemView>,
+        incomplete: bool,
+    ) {
+        let mut merged = std::mem::take(&mut self.items);
+        for item in items {
+            let duplicate = merged.iter().position(|existing| {
+                existing.label == item.label && existing.insert_text == item.insert_text
+            });
+            match duplicate {
+                Some(index) if merged[index].source == CompletionSource::Word => {
+                    merged[index] = item;
+                }
+                Some(_) => {}
+                None => merged.push(item),
+            }
+        }
+        self.set_items(cursor, merged, self.incomplete || incomplete);
+    }
+
+    /// Fills in the lazy fields of item `id` from a `completionItem/resolve`
+    /// result; `Null` just marks it resolved.
+    pub fn resolve(&mut self, id: usize, value: &Value) {
+        // Snippet items share ids with server items but never need resolving.
+        let Some(item) = self.items.iter_mut().find(|item| item.id == id && !item.resolved)
+        else {
+            return;
+        };
+        item.resolved = true;
+        if let Some(documentation) = value.get("documentation").and_then(parse_documentation) {
+            item.documentation = documentation;
+        }
+        if let Some(detail) = value.get("detail").and_then(Value::as_str) {
+            item.detail = detail.to_owned();
+        }
+        if item.additional_edits.is_empty()
+            && let Some(edits) = value.get("additionalTextEdits")
+        {
+            item.additional_edits = serde_json::from_value(edits.clone()).unwrap_or_default();
+        }
+    }
+
+    pub fn set_filter(&mut self, filter: &str) {
+        if self.filter != filter {
+            self.filter = filter.to_owned();
+            self.refilter();
+        }
+    }
+
+    /// Ranks the items against `filter` by fuzzy score, then by source and
+    /// the server's `sortText`, and selects the preselected match if any.
+    fn refilter(&mut self) {
+        let mut ranked = if self.filter.is_empty() {
+            (0..self.items.len()).map(|index| (0, index)).collect::<Vec<_>>()
+        } else {
+            let pattern = Pattern::parse(&self.filter, CaseMatching::Smart, Normalization::Smart);
+            let mut matcher = Matcher::new(Config::DEFAULT);
+            self.items
+                .iter()
+                .enumerate()
+                .filter_map(|(index, item)| {
+                    let haystack = Utf32String::from(item.filter_text.as_str());
+                    let score = pattern.score(haystack.slice(..), &mut matcher)?;
+                    Some((score, index))
+                })
+                .collect()
+        };
+        ranked.sort_by(|(left_score, left), (right_score, right)| {
+            let (left, right) = (&self.items[*left], &self.items[*right]);
+            right_score
+                .cmp(left_score)
+                .then_with(|| left.source.cmp(&right.source))
+                .then_with(|| left.sort_text.cmp(&right.sort_text))
+                .then_with(|| left.label.cmp(&right.label))
+        });
+        self.matches = ranked.into_iter().map(|(_, index)| index).collect();
+        self.selected = self
+            .matches
+            .iter()
+            .position(|index| self.items[*index].preselect)
+            .unwrap_or(0);
+        self.active = !self.matches.is_empty();
+    }
+
+    /// Items that match the filter, best first.
+    pub fn visible_items(&self) -> impl Iterator<Item = &CompletionItemView> {
+        self.matches.iter().map(|index| &self.items[*index])
     }
 
     pub fn selected_item(&self) -> Option<&CompletionItemView> {
-        self.items.get(self.selected)
+        self.matches
+            .get(self.selected)
+            .map(|index| &self.items[*index])
     }
 
     pub fn move_selection(&mut self, delta: isize) {
-        if self.items.is_empty() {
+        if self.matches.is_empty() {
             self.selected = 0;
             return;
         }
-        let max_index = self.items.len().saturating_sub(1);
+        let max_index = self.matches.len().saturating_sub(1);
         self.selected = if delta.is_negative() {
             self.selected.saturating_sub(delta.unsigned_abs())
         } else {
@@ -48,3 +383,87 @@ impl CompletionContext {
         };
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{CompletionContext, CompletionItemView, parse_completion_items};
+    use crate::core::Cursor;
+    use crate::lsp::position::PositionEncoding;
+
+    #[test]
+    fn parses_defaults_and_ranks_by_score_then_sort_text() {
+        let range = |start: u32, end: u32| {
+            json!({
+                "start": { "line": 0, "character": start },
+                "end": { "line": 0, "character": end }
+            })
+        };
+        let list = json!({
+            "isIncomplete": false,
+            "itemDefaults": { "editRange": range(4, 6), "commitCharacters": ["("] },
+            "items": [
+                { "label": "to_string", "kind": 2, "sortText": "b" },
+                { "label": "to_owned", "kind": 2, "sortText": "a", "preselect": true },
+                {
+                    "label": "HashMap",
+                    "kind": 22,
+                    "textEdit": {
+                        "newText": "HashMap",
+                        "insert": range(4, 6),
+                        "replace": range(4, 9)
+                    },
+                    "additionalTextEdits": [
+                        { "range": range(0, 0), "newText": "use std::collections::HashMap;\n" }
+                    ]
+                }
+            ]
+        });
+        let (items, incomplete) = parse_completion_items(&list, PositionEncoding::Utf16);
+        let items = items
+            .into_iter()
+            .enumerate()
+            .map(|(id, (item, _))| CompletionItemView { id, ..item })
+            .collect::<Vec<_>>();
+        assert!(!incomplete);
+        let default_edit = items[0].edit.as_ref().map(|edit| (edit.insert, edit.replace));
+        assert_eq!(default_edit.map(|(insert, replace)| insert == replace), Some(true));
+        assert_eq!(items[0].commit_characters, vec!['(']);
+        let map = &items[2];
+        assert_eq!((map.icon(), map.additional_edits.len()), ("struct", 1));
+        assert_eq!(map.edit.as_ref().map(|edit| edit.replace.end.character), Some(9));
+        // One more character typed since the request moves both range ends.
+        let line = "    HasMap";
+        let (request, start, typed) = (Cursor::new(0, 6), Cursor::new(0, 4), Cursor::new(0, 7));
+        let end = |replace| {
+            map.text_edits(String::from("HashMap"), line, request, start, typed, replace)[0]
+                .range
+                .end
+                .character
+        };
+        assert_eq!((end(false), end(true)), (7, 10));
+
+        let mut completion = CompletionContext::default();
+        completion.set_items(Cursor::new(0, 6), items, incomplete);
+        let labels = |completion: &CompletionContext| {
+            completion
+                .visible_items()
+                .map(|item| item.label.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(labels(&completion), vec!["HashMap", "to_owned", "to_string"]);
+        assert_eq!(completion.selected_item().map(|item| item.label.as_str()), Some("to_owned"));
+
+        completion.set_filter("tstr");
+        assert_eq!(labels(&completion), vec!["to_string"]);
+        assert_eq!(completion.selected, 0);
+        let resolved = json!({ "documentation": { "kind": "markdown", "value": "Converts." } });
+        completion.resolve(0, &resolved);
+        let item = completion.selected_item();
+        let documentation = item.map(|item| (item.resolved, item.documentation.as_str()));
+        assert_eq!(documentation, Some((true, "Converts.")));
+        completion.set_filter("zz");
+        assert!(!completion.active);
+    }
+}
@@ -1,8 +1,10 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 
 use lsp_types::DiagnosticSeverity;
 
+use crate::lsp::position::{LineSource, PositionEncoding};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DiagnosticSeverityView {
     Error,
@@ -23,21 +25,41 @@ impl DiagnosticSeverityView {
 }
 
 impl DiagnosticStore {
+    /// Replaces what `server` reported for `path`; other servers' diagnostics
+    /// for the file are kept.
     pub fn apply_publish(
         &mut self,
         path: PathBuf,
+        server: &str,
         diagnostics: impl IntoIterator<Item = lsp_types::Diagnostic>,
+        encoding: PositionEncoding,
+        lines: &dyn LineSource,
     ) {
         let mapped = diagnostics
             .into_iter()
-            .map(|d| DiagnosticItem {
-                line: d.range.start.line as usize,
-                column: d.range.start.character as usize,
-                severity: map_severity(d.severity),
-                message: d.message,
+            .map(|d| {
+                let start = d.range.start;
+                let column = lines
+                    .line_text(&path, start.line as usize)
+                    .map_or(start.character as usize, |line_text| {
+                        encoding.to_cursor(&line_text, start).column
+                    });
+                DiagnosticItem {
+                    line: start.line as usize,
+                    column,
+                    severity: map_severity(d.severity),
+                    message: d.message.clone(),
+                    source: d.source.clone().unwrap_or_else(|| server.to_owned()),
+                    server: server.to_owned(),
+                    published: d,
+                }
             })
             .collect::<Vec<_>>();
-        self.set(path, mapped);
+        let by_server = self.by_server.entry(path.clone()).or_default();
+        by_server.insert(server.to_owned(), mapped);
+        let mut combined = by_server.values().flatten().cloned().collect::<Vec<_>>();
+        combined.sort_by_key(|diagnostic| (diagnostic.line, diagnostic.column));
+        self.set(path, combined);
     }
 }
 
@@ -47,11 +69,26 @@ pub struct DiagnosticItem {
     pub column: usize,
     pub severity: DiagnosticSeverityView,
     pub message: String,
+    /// Tool that reported it: the diagnostic's own source or the server name.
+    pub source: String,
+    /// Name of the server that published it.
+    pub server: String,
+    /// The diagnostic as published, sent back as code action context.
+    pub published: lsp_types::Diagnostic,
+}
+
+impl DiagnosticItem {
+    /// Whether the published range touches any line in `first..=last`.
+    pub fn overlaps_lines(&self, first: usize, last: usize) -> bool {
+        let range = self.published.range;
+        range.start.line as usize <= last && range.end.line as usize >= first
+    }
 }
 
 #[derive(Debug, Default)]
 pub struct DiagnosticStore {
     by_path: HashMap<PathBuf, Vec<DiagnosticItem>>,
+    by_server: HashMap<PathBuf, BTreeMap<String, Vec<DiagnosticItem>>>,
 }
 
 impl DiagnosticStore {
@@ -95,3 +132,46 @@ pub fn map_severity(severity: Option<DiagnosticSeverity>) -> DiagnosticSeverityV
         _ => DiagnosticSeverityView::Hint,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use lsp_types::{Diagnostic, Position, Range};
+
+    use super::DiagnosticStore;
+    use crate::lsp::position::PositionEncoding;
+
+    fn diagnostic(line: u32, message: &str, source: Option<&str>) -> Diagnostic {
+        Diagnostic {
+            range: Range::new(Position::new(line, 0), Position::new(line, 1)),
+            message: message.to_owned(),
+            source: source.map(ToOwned::to_owned),
+            ..Diagnostic::default()
+        }
+    }
+
+    #[test]
+    fn servers_publish_independently_and_are_tagged() {
+        let mut store = DiagnosticStore::default();
+        let path = PathBuf::from("/nonexistent/main.py");
+        let lines = Vec::new();
+        let encoding = PositionEncoding::Utf16;
+        let pyright = vec![diagnostic(3, "type error", Some("Pyright"))];
+        store.apply_publish(path.clone(), "pyright", pyright, encoding, &lines);
+        let ruff = vec![diagnostic(1, "unused import", None)];
+        store.apply_publish(path.clone(), "ruff", ruff, encoding, &lines);
+
+        let sources = |store: &DiagnosticStore| {
+            store
+                .for_path(Path::new(&path))
+                .iter()
+                .map(|item| item.source.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(sources(&store), ["ruff", "Pyright"]);
+
+        store.apply_publish(path.clone(), "ruff", Vec::new(), encoding, &lines);
+        assert_eq!(sources(&store), ["Pyright"]);
+    }
+}
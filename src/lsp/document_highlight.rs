@@ -0,0 +1,315 @@
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use lsp_types::Position;
+use serde_json::Value;
+
+use crate::app::{App, MessageKind};
+use crate::core::{Cursor, Selection};
+use crate::lsp::position::PositionEncoding;
+use crate::syntax::{LanguageRegistry, LocalBindings};
+
+/// How long the cursor rests on a name before its occurrences are looked up.
+const HIGHLIGHT_DELAY: Duration = Duration::from_millis(150);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HighlightKind {
+    Text,
+    Read,
+    Write,
+}
+
+/// One occurrence of the symbol under the cursor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocumentHighlight {
+    pub start: Cursor,
+    pub end: Cursor,
+    pub kind: HighlightKind,
+}
+
+impl DocumentHighlight {
+    pub fn contains(&self, line: usize, column: usize) -> bool {
+        (self.start.line, self.start.column) <= (line, column)
+            && (line, column) < (self.end.line, self.end.column)
+    }
+}
+
+/// Reads a `DocumentHighlight[]` result, ordered by position.
+pub fn parse_document_highlights(
+    value: &Value,
+    encoding: PositionEncoding,
+    line_text: impl Fn(usize) -> Option<String>,
+) -> Vec<DocumentHighlight> {
+    let cursor = |position: Position| match line_text(position.line as usize) {
+        Some(text) => encoding.to_cursor(&text, position),
+        None => Cursor::new(position.line as usize, position.character as usize),
+    };
+    let mut highlights = value
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|item| {
+            let range = item.get("range")?;
+            let start = serde_json::from_value::<Position>(range.get("start")?.clone()).ok()?;
+            let end = serde_json::from_value::<Position>(range.get("end")?.clone()).ok()?;
+            let kind = match item.get("kind").and_then(Value::as_u64) {
+                Some(2) => HighlightKind::Read,
+                Some(3) => HighlightKind::Write,
+                _ => HighlightKind::Text,
+            };
+            Some(DocumentHighlight {
+                start: cursor(start),
+                end: cursor(end),
+                kind,
+            })
+        })
+        .collect::<Vec<_>>();
+    highlights.sort_by_key(|highlight| (highlight.start.line, highlight.start.column));
+    highlights
+}
+
+/// Name the cursor rests on: its buffer, syntax revision and span.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct HighlightTarget {
+    buffer_id: u64,
+    revision: u64,
+    start: Cursor,
+    end: Cursor,
+}
+
+/// Occurrences of the name under the cursor, looked up once the cursor has
+/// rested on it for `HIGHLIGHT_DELAY`.
+#[derive(Debug, Default)]
+pub struct DocumentHighlights {
+    target: Option<HighlightTarget>,
+    since: Option<Instant>,
+    requested: bool,
+    highlights: Vec<DocumentHighlight>,
+}
+
+impl DocumentHighlights {
+    /// Occurrences in `buffer_id`, ordered by position.
+    pub fn for_buffer(&self, buffer_id: u64) -> &[DocumentHighlight] {
+        match &self.target {
+            Some(target) if target.buffer_id == buffer_id => &self.highlights,
+            _ => &[],
+        }
+    }
+
+    /// Occurrences starting or ending on `line` of `buffer_id`.
+    pub fn on_line(&self, buffer_id: u64, line: usize) -> Vec<&DocumentHighlight> {
+        self.for_buffer(buffer_id)
+            .iter()
+            .filter(|highlight| highlight.start.line <= line && line <= highlight.end.line)
+            .collect()
+    }
+
+    /// Switches to `target`; moving onto another occurrence of the same
+    /// name keeps the highlights instead of looking them up again.
+    fn retarget(&mut self, target: Option<HighlightTarget>) {
+        let same_name = match (&self.target, &target) {
+            (Some(old), Some(new)) => {
+                old.buffer_id == new.buffer_id
+                    && old.revision == new.revision
+                    && self
+                        .highlights
+                        .iter()
+                        .any(|highlight| highlight.start == new.start && highlight.end == new.end)
+            }
+            _ => false,
+        };
+        if !same_name {
+            self.highlights.clear();
+            self.requested = false;
+            self.since = Some(Instant::now());
+        }
+        self.target = target;
+    }
+}
+
+impl App {
+    pub fn document_highlights(&self) -> &DocumentHighlights {
+        &self.document_highlights
+    }
+
+    /// Looks up the occurrences of the name under the cursor once it rested
+    /// there: from the language server, or else the `locals.scm` query.
+    pub(crate) fn refresh_document_highlights(&mut self) {
+        let target = self.highlight_target();
+        if target != self.document_highlights.target {
+            self.document_highlights.retarget(target);
+            return;
+        }
+        let highlights = &mut self.document_highlights;
+        let Some(target) = highlights.target.clone() else {
+            return;
+        };
+        if highlights.requested
+            || highlights
+                .since
+                .is_some_and(|since| since.elapsed() < HIGHLIGHT_DELAY)
+        {
+            return;
+        }
+        highlights.requested = true;
+        if let Some(path) = self.active_document().path().map(Path::to_path_buf)
+            && self.lsp.request_document_highlights(
+                &path,
+                &self.workspace_root,
+                target.start,
+                &self.buffers,
+            )
+        {
+            return;
+        }
+        self.document_highlights.highlights = self.local_highlights(&target);
+    }
+
+    /// Takes a server's answer if the cursor is still on the name it was
+    /// asked about.
+    pub(crate) fn show_document_highlights(
+        &mut self,
+        path: &Path,
+        cursor: Cursor,
+        highlights: Vec<DocumentHighlight>,
+    ) {
+        let Some(target) = self.document_highlights.target.as_ref() else {
+            return;
+        };
+        let same_document = self
+            .buffer_by_id(target.buffer_id)
+            .and_then(|buffer| buffer.document.path())
+            == Some(path);
+        if same_document && target.start == cursor {
+            self.document_highlights.highlights = highlights;
+        }
+    }
+
+    /// Moves to the next or previous occurrence, wrapping around the file.
+    pub(crate) fn jump_to_document_highlight(&mut self, forward: bool) {
+        let cursor = self.active_pane().cursor();
+        let here = (cursor.line, cursor.column);
+        let highlights = self.document_highlights.for_buffer(self.active_buffer_id);
+        let anchor = highlights
+            .iter()
+            .find(|highlight| {
+                highlight.contains(cursor.line, cursor.column)
+                    || (highlight.end.line, highlight.end.column) == here
+            })
+            .map_or(here, |highlight| (highlight.start.line, highlight.start.column));
+        let start = |highlight: &DocumentHighlight| (highlight.start.line, highlight.start.column);
+        let next = if forward {
+            highlights
+                .iter()
+                .find(|highlight| start(highlight) > anchor)
+                .or(highlights.first())
+        } else {
+            highlights
+                .iter()
+                .rev()
+                .find(|highlight| start(highlight) < anchor)
+                .or(highlights.last())
+        };
+        let Some(target) = next.map(|highlight| highlight.start) else {
+            self.set_message("No highlighted occurrences", MessageKind::Info);
+            return;
+        };
+        let pane = self.active_pane_mut();
+        pane.set_cursor(target);
+        pane.set_selection(Selection::caret(target));
+        self.ensure_cursor_visible();
+    }
+
+    fn highlight_target(&self) -> Option<HighlightTarget> {
+        let buffer = self.active_buffer();
+        if buffer.large_file || buffer.hex.is_some() {
+            return None;
+        }
+        let (start, end) = buffer
+            .document
+            .word_range_at(self.active_pane().cursor())?;
+        Some(HighlightTarget {
+            buffer_id: buffer.id,
+            revision: buffer.syntax.revision(),
+            start,
+            end,
+        })
+    }
+
+    fn local_highlights(&self, target: &HighlightTarget) -> Vec<DocumentHighlight> {
+        let Some(buffer) = self.buffer_by_id(target.buffer_id) else {
+            return Vec::new();
+        };
+        let (Some(tree), Some(language_id)) = (buffer.syntax.tree(), buffer.syntax.language_id())
+        else {
+            return Vec::new();
+        };
+        let Some(query) = LanguageRegistry::global().locals_query(language_id) else {
+            return Vec::new();
+        };
+        let document = &buffer.document;
+        let revision = buffer.syntax.revision();
+        let mut cache = buffer.locals_cache.borrow_mut();
+        if cache.revision != revision || cache.bindings.is_none() {
+            cache.revision = revision;
+            let source = document.text();
+            cache.bindings = Some(LocalBindings::new(tree, query, source.as_bytes()));
+        }
+        let Some(bindings) = cache.bindings.as_ref() else {
+            return Vec::new();
+        };
+        bindings
+            .occurrences_at(document.cursor_to_byte(target.start))
+            .into_iter()
+            .map(|occurrence| DocumentHighlight {
+                start: document.byte_to_cursor(occurrence.range.start),
+                end: document.byte_to_cursor(occurrence.range.end),
+                kind: if occurrence.definition {
+                    HighlightKind::Write
+                } else {
+                    HighlightKind::Read
+                },
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{HighlightKind, parse_document_highlights};
+    use crate::core::Cursor;
+    use crate::lsp::position::PositionEncoding;
+
+    #[test]
+    fn parses_kinds_in_document_order() {
+        let range = |line: u32, start: u32, end: u32| {
+            json!({
+                "start": { "line": line, "character": start },
+                "end": { "line": line, "character": end }
+            })
+        };
+        let value = json!([
+            { "range": range(4, 2, 7), "kind": 2 },
+            { "range": range(1, 6, 11), "kind": 3 },
+            { "range": range(4, 10, 15) }
+        ]);
+        let line = |_| Some(String::from("é value = value;"));
+        let highlights = parse_document_highlights(&value, PositionEncoding::Utf16, line);
+        let found = highlights
+            .iter()
+            .map(|highlight| (highlight.start, highlight.end.column, highlight.kind))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            vec![
+                (Cursor::new(1, 6), 11, HighlightKind::Write),
+                (Cursor::new(4, 2), 7, HighlightKind::Read),
+                (Cursor::new(4, 10), 15, HighlightKind::Text),
+            ]
+        );
+        assert!(highlights[1].contains(4, 6));
+        assert!(!highlights[1].contains(4, 7));
+    }
+}
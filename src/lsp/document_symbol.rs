@@ -0,0 +1,136 @@
+use lsp_types::{Position, SymbolKind};
+use serde_json::Value;
+
+use crate::lsp::position::PositionEncoding;
+use crate::ui::{OutlineSymbol, nest_symbols};
+
+/// Reads `DocumentSymbol[]` (nested) or `SymbolInformation[]` (flat) into
+/// outline entries.
+pub fn parse_document_symbols(
+    value: &Value,
+    encoding: PositionEncoding,
+    line_text: impl Fn(usize) -> Option<String>,
+) -> Vec<OutlineSymbol> {
+    let mut symbols = Vec::new();
+    for item in value.as_array().into_iter().flatten() {
+        collect_symbols(item, encoding, &line_text, &mut symbols);
+    }
+    nest_symbols(symbols)
+}
+
+fn collect_symbols(
+    item: &Value,
+    encoding: PositionEncoding,
+    line_text: &impl Fn(usize) -> Option<String>,
+    symbols: &mut Vec<OutlineSymbol>,
+) {
+    let range = item
+        .get("range")
+        .or_else(|| item.get("location").and_then(|location| location.get("range")));
+    let (Some(name), Some(range)) = (item.get("name").and_then(Value::as_str), range) else {
+        return;
+    };
+    let position = |value: Option<&Value>| {
+        value.and_then(|value| serde_json::from_value::<Position>(value.clone()).ok())
+    };
+    let (Some(start), Some(end)) = (position(range.get("start")), position(range.get("end")))
+    else {
+        return;
+    };
+    let target = position(item.get("selectionRange").and_then(|range| range.get("start")))
+        .unwrap_or(start);
+    let column = line_text(target.line as usize)
+        .map_or(target.character as usize, |text| encoding.to_cursor(&text, target).column);
+    let kind = item
+        .get("kind")
+        .and_then(|kind| serde_json::from_value::<SymbolKind>(kind.clone()).ok())
+        .map_or("variable", symbol_kind);
+    symbols.push(OutlineSymbol {
+        name: name.lines().next().unwrap_or_default().to_owned(),
+        kind,
+        depth: 0,
+        start_line: start.line as usize,
+        end_line: end.line as usize,
+        line: target.line as usize,
+        column,
+    });
+    for child in item
+        .get("children")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        collect_symbols(child, encoding, line_text, symbols);
+    }
+}
+
+pub(super) fn symbol_kind(kind: SymbolKind) -> &'static str {
+    match kind {
+        SymbolKind::FILE | SymbolKind::MODULE | SymbolKind::NAMESPACE | SymbolKind::PACKAGE => {
+            "module"
+        }
+        SymbolKind::CLASS => "class",
+        SymbolKind::METHOD | SymbolKind::CONSTRUCTOR => "method",
+        SymbolKind::PROPERTY | SymbolKind::FIELD | SymbolKind::KEY | SymbolKind::EVENT => "field",
+        SymbolKind::ENUM => "enum",
+        SymbolKind::INTERFACE => "interface",
+        SymbolKind::FUNCTION | SymbolKind::OPERATOR => "function",
+        SymbolKind::CONSTANT => "constant",
+        SymbolKind::OBJECT => "object",
+        SymbolKind::ENUM_MEMBER => "variant",
+        SymbolKind::STRUCT => "struct",
+        SymbolKind::TYPE_PARAMETER => "type",
+        _ => "variable",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::parse_document_symbols;
+    use crate::lsp::position::PositionEncoding;
+
+    #[test]
+    fn parses_nested_and_flat_symbols() {
+        let range = |start: u32, end: u32| {
+            json!({
+                "start": { "line": start, "character": 0 },
+                "end": { "line": end, "character": 1 }
+            })
+        };
+        let nested = json!([{
+            "name": "impl Outline for App",
+            "kind": 19,
+            "range": range(2, 9),
+            "selectionRange": range(2, 2),
+            "children": [{
+                "name": "refresh",
+                "kind": 12,
+                "range": range(3, 8),
+                "selectionRange": {
+                    "start": { "line": 4, "character": 9 },
+                    "end": { "line": 4, "character": 16 }
+                }
+            }]
+        }]);
+        let line = |_| Some(String::from("    fn é_refresh() {"));
+        let symbols = parse_document_symbols(&nested, PositionEncoding::Utf16, line);
+        assert_eq!(symbols.len(), 2);
+        assert_eq!(symbols[0].label(), "obj");
+        assert_eq!((symbols[1].kind, symbols[1].depth), ("method", 1));
+        assert_eq!((symbols[1].line, symbols[1].column), (4, 9));
+
+        let location = |start, end| json!({ "uri": "file:///a.rs", "range": range(start, end) });
+        let flat = json!([
+            { "name": "Config", "kind": 23, "location": location(0, 5) },
+            { "name": "load", "kind": 12, "location": location(7, 9) }
+        ]);
+        let symbols = parse_document_symbols(&flat, PositionEncoding::Utf16, |_| None);
+        let kinds = symbols
+            .iter()
+            .map(|symbol| (symbol.kind, symbol.depth))
+            .collect::<Vec<_>>();
+        assert_eq!(kinds, vec![("struct", 0), ("function", 0)]);
+    }
+}
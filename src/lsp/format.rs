@@ -1,29 +1,39 @@
+use std::path::Path;
+
+use lsp_types::TextEdit;
+
 use crate::app::{App, MessageKind};
+use crate::lsp::position::PositionEncoding;
 
 impl App {
     pub(crate) fn format_document(&mut self) {
         let Some(path) = self.active_document().path().map(|path| path.to_path_buf()) else {
             return;
         };
-        let current = self.active_document().text();
-        if let Some(formatted) = self
-            .lsp
-            .format_document(&path, &self.workspace_root, &current)
-        {
-            let cursor = self.active_pane().cursor();
-            if let Some(buffer) = self.buffer_by_id_mut(self.active_buffer_id) {
-                buffer.document = crate::core::Document::from_text(Some(path), &formatted);
-                buffer.document.set_dirty(true);
-                buffer.syntax.mark_dirty();
-            }
-            if let Some(pane) = self.layout.focused_pane_mut() {
-                pane.set_cursor(cursor);
-                pane.set_selection(crate::core::Selection::caret(cursor));
-            }
-            self.ensure_cursor_visible();
-            self.set_message("Document formatted", MessageKind::Info);
-        } else {
+        if !self.lsp.request_formatting(&path, &self.workspace_root) {
             self.set_message("No formatting edits", MessageKind::Info);
         }
     }
+
+    /// Applies formatting edits to the buffer for `path` if it is still open.
+    pub(crate) fn apply_formatting(
+        &mut self,
+        path: &Path,
+        edits: &[TextEdit],
+        encoding: PositionEncoding,
+    ) {
+        let Some(buffer_id) = self
+            .buffers
+            .iter()
+            .find(|buffer| buffer.document.path() == Some(path))
+            .map(|buffer| buffer.id)
+        else {
+            return;
+        };
+        match self.apply_buffer_text_edits(buffer_id, edits, encoding) {
+            Ok(true) => self.set_message("Document formatted", MessageKind::Info),
+            Ok(false) => self.set_message("No formatting edits", MessageKind::Info),
+            Err(error) => self.set_message(&format!("Format failed: {error}"), MessageKind::Error),
+        }
+    }
 }
@@ -1,25 +1,130 @@
+use std::path::PathBuf;
+
+use lsp_types::Position;
+use serde_json::Value;
+
 use crate::app::{App, MessageKind};
+use crate::core::{Cursor, Selection};
+use crate::lsp::position::{LineSource, PositionEncoding};
+use crate::lsp::workspace::parse_file_uri;
+use crate::ui::{PickerItem, PickerKind, PickerState};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GotoKind {
+    Definition,
+    Declaration,
+    TypeDefinition,
+    Implementation,
+}
+
+impl GotoKind {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Definition => "definition",
+            Self::Declaration => "declaration",
+            Self::TypeDefinition => "type definition",
+            Self::Implementation => "implementation",
+        }
+    }
+}
+
+/// A goto or references result with the trimmed text of its line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    pub path: PathBuf,
+    pub cursor: Cursor,
+    pub preview: String,
+}
+
+/// Reads the start of a `Location`, or the name of a `LocationLink`'s
+/// target, as an editor cursor in its file.
+pub(crate) fn parse_location(
+    value: &Value,
+    encoding: PositionEncoding,
+    lines: &dyn LineSource,
+) -> Option<(PathBuf, Cursor)> {
+    let (uri, range) = match value.get("targetUri") {
+        Some(uri) => (
+            uri,
+            value
+                .get("targetSelectionRange")
+                .or_else(|| value.get("targetRange"))?,
+        ),
+        None => (value.get("uri")?, value.get("range")?),
+    };
+    let path = parse_file_uri(uri.as_str()?)?;
+    let start = serde_json::from_value::<Position>(range.get("start")?.clone()).ok()?;
+    let cursor = match lines.line_text(&path, start.line as usize) {
+        Some(line_text) => encoding.to_cursor(&line_text, start),
+        None => Cursor::new(start.line as usize, start.character as usize),
+    };
+    Some((path, cursor))
+}
+
+/// Reads a goto result: a single `Location`, `Location[]` or `LocationLink[]`.
+/// Previews come from `lines`, which should read each file only once.
+pub(crate) fn parse_locations(
+    value: &Value,
+    encoding: PositionEncoding,
+    lines: &dyn LineSource,
+) -> Vec<Location> {
+    let mut locations = match value.as_array() {
+        Some(list) => list
+            .iter()
+            .filter_map(|item| parse_location(item, encoding, lines))
+            .collect::<Vec<_>>(),
+        None => parse_location(value, encoding, lines).into_iter().collect(),
+    };
+    locations.dedup();
+    locations
+        .into_iter()
+        .map(|(path, cursor)| {
+            let preview = lines.line_text(&path, cursor.line).unwrap_or_default();
+            Location {
+                preview: preview.trim().to_owned(),
+                path,
+                cursor,
+            }
+        })
+        .collect()
+}
 
 impl App {
     pub(crate) fn goto_definition(&mut self) {
+        self.goto(GotoKind::Definition);
+    }
+
+    pub(crate) fn goto(&mut self, kind: GotoKind) {
         let Some(path) = self.active_document().path().map(|path| path.to_path_buf()) else {
             return;
         };
         let cursor = self.active_pane().cursor();
-        if let Some((target_path, line, column)) =
-            self.lsp
-                .goto_definition(&path, &self.workspace_root, cursor.line, cursor.column)
+        if !self
+            .lsp
+            .request_goto(kind, &path, &self.workspace_root, cursor, &self.buffers)
         {
-            if self.open_path_in_active_pane(&target_path).is_ok() {
-                let c = crate::core::Cursor::new(line, column);
-                if let Some(pane) = self.layout.focused_pane_mut() {
-                    pane.set_cursor(c);
-                    pane.set_selection(crate::core::Selection::caret(c));
-                }
-                self.ensure_cursor_visible();
+            self.set_message(&format!("No {} found", kind.label()), MessageKind::Info);
+        }
+    }
+
+    /// Jumps straight to a single result and lists several in a picker.
+    pub(crate) fn open_goto_results(&mut self, target: &str, locations: Vec<Location>) {
+        let [location] = locations.as_slice() else {
+            if locations.is_empty() {
+                self.set_message(&format!("No {target} found"), MessageKind::Info);
+            } else {
+                self.show_location_picker(locations);
             }
-        } else {
-            self.set_message("No definition found", MessageKind::Info);
+            return;
+        };
+        self.record_jump();
+        let target = location.cursor;
+        if self.open_path_in_active_pane(&location.path).is_ok() {
+            if let Some(pane) = self.layout.focused_pane_mut() {
+                pane.set_cursor(target);
+                pane.set_selection(Selection::caret(target));
+            }
+            self.ensure_cursor_visible();
         }
     }
 
@@ -28,26 +133,150 @@ impl App {
             return;
         };
         let cursor = self.active_pane().cursor();
-        let refs =
-            self.lsp
-                .goto_references(&path, &self.workspace_root, cursor.line, cursor.column);
+        if !self
+            .lsp
+            .request_references(&path, &self.workspace_root, cursor, &self.buffers)
+        {
+            self.set_message("No references found", MessageKind::Info);
+        }
+    }
+
+    pub(crate) fn show_references(&mut self, refs: Vec<Location>) {
         if refs.is_empty() {
             self.set_message("No references found", MessageKind::Info);
             return;
         }
-        let mut picker = crate::ui::PickerState::new(crate::ui::PickerKind::Files);
-        picker.set_buffer_items(
-            refs.into_iter()
-                .map(|(p, l, c)| crate::ui::PickerItem {
-                    title: format!("{}:{}", p.display(), l + 1),
-                    subtitle: String::from("reference"),
-                    path: Some(p),
+        self.show_location_picker(refs);
+    }
+
+    /// Lists locations as `path:line` with the source line as preview.
+    fn show_location_picker(&mut self, locations: Vec<Location>) {
+        let items = locations
+            .into_iter()
+            .map(|location| {
+                let shown = location
+                    .path
+                    .strip_prefix(&self.workspace_root)
+                    .unwrap_or(&location.path);
+                PickerItem {
+                    title: format!("{}:{}", shown.display(), location.cursor.line + 1),
+                    subtitle: location.preview,
+                    path: Some(location.path),
                     buffer_id: None,
-                    line: Some(l),
-                    column: Some(c),
-                })
-                .collect(),
-        );
+                    line: Some(location.cursor.line),
+                    column: Some(location.cursor.column),
+                    entry: None,
+                }
+            })
+            .collect();
+        let mut picker = PickerState::new(PickerKind::Locations);
+        picker.set_source(items);
         self.picker = Some(picker);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use serde_json::json;
+
+    use super::{Location, parse_locations};
+    use crate::app::{App, BufferState};
+    use crate::core::Cursor;
+    use crate::lsp::position::{BufferLines, PositionEncoding};
+
+    #[test]
+    fn parses_locations_and_location_links() {
+        let range = |line: u32, character: u32| {
+            json!({
+                "start": { "line": line, "character": character },
+                "end": { "line": line, "character": character + 4 }
+            })
+        };
+        let buffers: Vec<BufferState> = Vec::new();
+        let encoding = PositionEncoding::Utf16;
+
+        let spots = |value| {
+            parse_locations(&value, encoding, &buffers)
+                .into_iter()
+                .map(|location| (location.path, location.cursor))
+                .collect::<Vec<_>>()
+        };
+
+        let single = json!({ "uri": "file:///src/a.rs", "range": range(3, 4) });
+        assert_eq!(spots(single), vec![(PathBuf::from("/src/a.rs"), Cursor::new(3, 4))]);
+
+        let links = json!([
+            {
+                "targetUri": "file:///src/b.rs",
+                "targetRange": range(10, 0),
+                "targetSelectionRange": range(11, 7)
+            },
+            { "uri": "file:///src/a.rs", "range": range(3, 4) },
+            { "uri": "file:///src/a.rs", "range": range(3, 4) }
+        ]);
+        assert_eq!(
+            spots(links),
+            vec![
+                (PathBuf::from("/src/b.rs"), Cursor::new(11, 7)),
+                (PathBuf::from("/src/a.rs"), Cursor::new(3, 4)),
+            ]
+        );
+        assert!(parse_locations(&json!(null), encoding, &buffers).is_empty());
+    }
+
+    #[test]
+    fn previews_unopened_files_from_disk() {
+        let suffix = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let path = std::env::temp_dir().join(format!("codx-goto-{suffix}.rs"));
+        std::fs::write(&path, "fn main() {\n    run();\n}\n").unwrap_or_else(|e| panic!("{e}"));
+        let uri = format!("file://{}", path.display());
+        let at = |line: u32| {
+            json!({
+                "uri": uri,
+                "range": {
+                    "start": { "line": line, "character": 4 },
+                    "end": { "line": line, "character": 7 }
+                }
+            })
+        };
+        let buffers: Vec<BufferState> = Vec::new();
+        let lines = BufferLines::new(&buffers);
+        let locations = parse_locations(&json!([at(1), at(0)]), PositionEncoding::Utf16, &lines);
+        let _ = std::fs::remove_file(&path);
+        let previews = locations
+            .iter()
+            .map(|location| location.preview.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(previews, vec!["run();", "fn main() {"]);
+    }
+
+    #[test]
+    fn location_picker_filters_its_own_items() {
+        let mut app = App::open(None).unwrap_or_else(|error| panic!("{error}"));
+        let location = |name: &str, preview: &str| Location {
+            path: app.workspace_root.join(name),
+            cursor: Cursor::new(3, 0),
+            preview: preview.to_owned(),
+        };
+        let locations = vec![location("a.rs", "run();"), location("b.rs", "fn main() {")];
+        app.show_location_picker(locations);
+        let shown = |app: &App| app.picker.as_ref().map_or(0, |picker| picker.items().len());
+        assert_eq!(shown(&app), 2);
+        if let Some(picker) = app.picker.as_mut() {
+            picker.set_query(String::from("MAIN"));
+        }
+        app.refresh_picker();
+        assert_eq!(shown(&app), 1);
+        if let Some(picker) = app.picker.as_mut() {
+            picker.set_query(String::new());
+        }
+        app.refresh_picker();
+        assert_eq!(shown(&app), 2);
+    }
+}
@@ -0,0 +1,262 @@
+use std::path::{Path, PathBuf};
+
+use lsp_types::{Position, SymbolKind};
+use serde_json::Value;
+
+use crate::app::{App, FocusTarget, MessageKind};
+use crate::core::{Cursor, Selection};
+use crate::lsp::document_symbol::symbol_kind;
+use crate::lsp::position::PositionEncoding;
+use crate::lsp::workspace::parse_file_uri;
+use crate::ui::HierarchyTree;
+
+/// Which way a call or type hierarchy is walked from the symbol under the cursor.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HierarchyDirection {
+    #[default]
+    IncomingCalls,
+    OutgoingCalls,
+    Supertypes,
+    Subtypes,
+}
+
+impl HierarchyDirection {
+    pub fn title(self) -> &'static str {
+        match self {
+            Self::IncomingCalls => "Incoming calls",
+            Self::OutgoingCalls => "Outgoing calls",
+            Self::Supertypes => "Supertypes",
+            Self::Subtypes => "Subtypes",
+        }
+    }
+
+    pub fn is_call(self) -> bool {
+        matches!(self, Self::IncomingCalls | Self::OutgoingCalls)
+    }
+}
+
+/// A node of a call or type hierarchy; `id` names the raw item the
+/// workspace keeps for loading its children.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HierarchyItem {
+    pub id: usize,
+    pub name: String,
+    pub detail: String,
+    pub kind: &'static str,
+    pub path: PathBuf,
+    /// Start of the item's name.
+    pub cursor: Cursor,
+    /// First call between the item and its parent, where Enter jumps.
+    pub call_site: Option<(PathBuf, Cursor)>,
+}
+
+impl HierarchyItem {
+    pub fn target(&self) -> (&Path, Cursor) {
+        match &self.call_site {
+            Some((path, cursor)) => (path, *cursor),
+            None => (&self.path, self.cursor),
+        }
+    }
+}
+
+/// Reads a prepare or children result into items paired with the raw
+/// `CallHierarchyItem`/`TypeHierarchyItem`. `parent` is the uri of the item
+/// whose outgoing calls were asked for, as those ranges point into it.
+pub fn parse_hierarchy_items(
+    value: &Value,
+    direction: Option<HierarchyDirection>,
+    parent: Option<&Path>,
+    encoding: PositionEncoding,
+    line_text: impl Fn(&Path, usize) -> Option<String>,
+) -> Vec<(HierarchyItem, Value)> {
+    let cursor = |path: &Path, position: Position| match line_text(path, position.line as usize) {
+        Some(text) => encoding.to_cursor(&text, position),
+        None => Cursor::new(position.line as usize, position.character as usize),
+    };
+    value
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| {
+            let (raw, call_path) = match direction {
+                Some(HierarchyDirection::IncomingCalls) => (entry.get("from")?, None),
+                Some(HierarchyDirection::OutgoingCalls) => (entry.get("to")?, parent),
+                _ => (entry, None),
+            };
+            let path = parse_file_uri(raw.get("uri")?.as_str()?)?;
+            let start = raw.get("selectionRange")?.get("start")?;
+            let start = serde_json::from_value::<Position>(start.clone()).ok()?;
+            let call_site = entry
+                .get("fromRanges")
+                .and_then(Value::as_array)
+                .and_then(|ranges| ranges.first())
+                .and_then(|range| range.get("start"))
+                .and_then(|start| serde_json::from_value::<Position>(start.clone()).ok())
+                .map(|start| {
+                    let path = call_path.map_or_else(|| path.clone(), Path::to_path_buf);
+                    let call = cursor(&path, start);
+                    (path, call)
+                });
+            let kind = raw
+                .get("kind")
+                .and_then(|kind| serde_json::from_value::<SymbolKind>(kind.clone()).ok())
+                .map_or("function", symbol_kind);
+            let item = HierarchyItem {
+                id: 0,
+                name: raw.get("name")?.as_str()?.to_owned(),
+                detail: raw
+                    .get("detail")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_owned(),
+                kind,
+                cursor: cursor(&path, start),
+                path,
+                call_site,
+            };
+            Some((item, raw.clone()))
+        })
+        .collect()
+}
+
+impl App {
+    pub(crate) fn open_hierarchy(&mut self, direction: HierarchyDirection) {
+        let Some(path) = self.active_document().path().map(|path| path.to_path_buf()) else {
+            return;
+        };
+        let cursor = self.active_pane().cursor();
+        if !self
+            .lsp
+            .request_hierarchy(direction, &path, &self.workspace_root, cursor, &self.buffers)
+        {
+            let message = format!("{} unavailable", direction.title());
+            self.set_message(&message, MessageKind::Info);
+        }
+    }
+
+    /// Opens the panel on prepared roots, or fills in a node's children.
+    pub(crate) fn show_hierarchy(
+        &mut self,
+        direction: HierarchyDirection,
+        parent: Option<usize>,
+        items: Vec<HierarchyItem>,
+    ) {
+        let Some(parent) = parent else {
+            if items.is_empty() {
+                let message = format!("No {} found", direction.title().to_lowercase());
+                self.set_message(&message, MessageKind::Info);
+                return;
+            }
+            self.hierarchy = Some(HierarchyTree::new(direction, items));
+            self.focus = FocusTarget::Hierarchy;
+            self.ensure_hierarchy_selection_visible();
+            return;
+        };
+        if let Some(tree) = self.hierarchy.as_mut() {
+            tree.set_children(parent, items);
+        }
+        self.ensure_hierarchy_selection_visible();
+    }
+
+    pub(crate) fn expand_hierarchy_selection(&mut self) {
+        let Some(load) = self.hierarchy.as_mut().and_then(HierarchyTree::expand_selected) else {
+            return;
+        };
+        if !self.lsp.request_hierarchy_children(load)
+            && let Some(tree) = self.hierarchy.as_mut()
+        {
+            tree.set_children(load, Vec::new());
+        }
+    }
+
+    pub(crate) fn close_hierarchy(&mut self) {
+        self.hierarchy = None;
+        self.focus = FocusTarget::Editor;
+    }
+
+    pub(crate) fn jump_to_hierarchy_selection(&mut self) {
+        let Some((path, cursor)) = self
+            .hierarchy
+            .as_ref()
+            .and_then(HierarchyTree::selected_item)
+            .map(|item| {
+                let (path, cursor) = item.target();
+                (path.to_path_buf(), cursor)
+            })
+        else {
+            return;
+        };
+        self.record_jump();
+        if self.open_path_in_active_pane(&path).is_ok() {
+            self.focus = FocusTarget::Editor;
+            if let Some(pane) = self.layout.focused_pane_mut() {
+                pane.set_cursor(cursor);
+                pane.set_selection(Selection::caret(cursor));
+            }
+            self.ensure_cursor_visible();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use serde_json::json;
+
+    use super::{HierarchyDirection, parse_hierarchy_items};
+    use crate::core::Cursor;
+    use crate::lsp::position::PositionEncoding;
+
+    fn range(line: u32, start: u32, end: u32) -> serde_json::Value {
+        json!({
+            "start": { "line": line, "character": start },
+            "end": { "line": line, "character": end }
+        })
+    }
+
+    fn item(name: &str, uri: &str, line: u32) -> serde_json::Value {
+        let range = range(line, 3, 9);
+        json!({ "name": name, "kind": 12, "uri": uri, "range": range, "selectionRange": range })
+    }
+
+    #[test]
+    fn parses_call_sites_for_each_direction() {
+        let call = json!([range(20, 8, 12)]);
+        let no_lines = |_: &Path, _| None;
+        let caller = item("caller", "file:///src/a.rs", 18);
+        let incoming = json!([{ "from": caller, "fromRanges": call }]);
+        let items = parse_hierarchy_items(
+            &incoming,
+            Some(HierarchyDirection::IncomingCalls),
+            None,
+            PositionEncoding::Utf16,
+            no_lines,
+        );
+        assert_eq!(items.len(), 1);
+        let (caller, raw) = &items[0];
+        assert_eq!((caller.name.as_str(), caller.kind), ("caller", "function"));
+        assert_eq!(raw["name"], "caller");
+        let (path, cursor) = caller.target();
+        assert_eq!((path, cursor), (Path::new("/src/a.rs"), Cursor::new(20, 8)));
+
+        let callee = item("callee", "file:///src/b.rs", 2);
+        let outgoing = json!([{ "to": callee, "fromRanges": call }]);
+        let parent = PathBuf::from("/src/a.rs");
+        let items = parse_hierarchy_items(
+            &outgoing,
+            Some(HierarchyDirection::OutgoingCalls),
+            Some(&parent),
+            PositionEncoding::Utf16,
+            no_lines,
+        );
+        let (callee, _) = &items[0];
+        assert_eq!(callee.path, PathBuf::from("/src/b.rs"));
+        assert_eq!(callee.target(), (parent.as_path(), Cursor::new(20, 8)));
+
+        let types = json!([item("Base", "file:///src/c.rs", 5)]);
+        let line = |_: &Path, _| Some(String::from("é  struct Base;"));
+        let items = parse_hierarchy_items(&types, None, None, PositionEncoding::Utf16, line);
+        assert_eq!(items[0].0.target(), (Path::new("/src/c.rs"), Cursor::new(5, 3)));
+    }
+}
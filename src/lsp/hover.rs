@@ -26,7 +26,7 @@ impl App {
         let cursor = self.active_pane().cursor();
         if !self
             .lsp
-            .request_hover(&path, &self.workspace_root, cursor.line, cursor.column)
+            .request_hover(&path, &self.workspace_root, cursor, &self.buffers)
         {
             self.set_message("No hover information", MessageKind::Info);
         }
@@ -0,0 +1,225 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use serde_json::Value;
+use unicode_width::UnicodeWidthStr;
+
+use crate::app::{App, MessageKind};
+use crate::core::Cursor;
+use crate::lsp::position::PositionEncoding;
+
+/// A server label drawn between the characters of a line, e.g. `: i32`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InlayHintView {
+    pub line: usize,
+    /// Character column the label is drawn in front of.
+    pub column: usize,
+    /// Label text with the server's padding applied.
+    pub label: String,
+    pub tooltip: Option<String>,
+}
+
+impl InlayHintView {
+    /// Reads an `InlayHint`; `line_text` supplies lines for position conversion.
+    pub fn parse(
+        value: &Value,
+        encoding: PositionEncoding,
+        line_text: impl Fn(usize) -> Option<String>,
+    ) -> Option<Self> {
+        let position = serde_json::from_value::<lsp_types::Position>(value.get("position")?.clone())
+            .ok()?;
+        let column = line_text(position.line as usize)
+            .map_or(position.character as usize, |text| {
+                encoding.to_cursor(&text, position).column
+            });
+        let parts = match value.get("label")? {
+            Value::String(label) => vec![(label.clone(), None)],
+            Value::Array(parts) => parts
+                .iter()
+                .filter_map(|part| {
+                    let text = part.get("value")?.as_str()?.to_owned();
+                    Some((text, part.get("tooltip").and_then(parse_tooltip)))
+                })
+                .collect(),
+            _ => return None,
+        };
+        let mut label = parts.iter().map(|(text, _)| text.as_str()).collect::<String>();
+        if value.get("paddingLeft").and_then(Value::as_bool) == Some(true) {
+            label.insert(0, ' ');
+        }
+        if value.get("paddingRight").and_then(Value::as_bool) == Some(true) {
+            label.push(' ');
+        }
+        let tooltip = value
+            .get("tooltip")
+            .and_then(parse_tooltip)
+            .or_else(|| parts.into_iter().find_map(|(_, tooltip)| tooltip));
+        Some(Self {
+            line: position.line as usize,
+            column,
+            label,
+            tooltip,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.label.width()
+    }
+}
+
+/// Tooltips are a plain string or `MarkupContent`.
+pub fn parse_tooltip(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => Some(text.clone()),
+        value => value.get("value")?.as_str().map(ToOwned::to_owned),
+    }
+}
+
+/// Width of the hints drawn before the character at `column`.
+pub fn hint_width_before(hints: &[&InlayHintView], column: usize) -> usize {
+    hints
+        .iter()
+        .filter(|hint| hint.column <= column)
+        .map(|hint| hint.width())
+        .sum()
+}
+
+impl App {
+    /// Asks for hints covering every pane's visible lines; documents whose
+    /// cached hints already cover them are skipped.
+    pub(crate) fn refresh_inlay_hints(&mut self) {
+        if !self.lsp.inlay_hints_enabled() {
+            return;
+        }
+        for (path, (first, last, line_count)) in self.visible_line_ranges() {
+            self.lsp
+                .request_inlay_hints(&path, first..=last, line_count, &self.buffers);
+        }
+    }
+
+    /// First and last visible line plus the line count of every file shown
+    /// in a pane, merged across panes showing the same file.
+    pub(crate) fn visible_line_ranges(&self) -> HashMap<PathBuf, (usize, usize, usize)> {
+        let mut ranges: HashMap<PathBuf, (usize, usize, usize)> = HashMap::new();
+        for pane_id in self.layout.pane_ids() {
+            let Some(pane) = self.layout.pane(pane_id) else {
+                continue;
+            };
+            let Some(buffer) = self
+                .buffer_by_id(pane.buffer_id())
+                .filter(|buffer| !buffer.large_file && buffer.hex.is_none())
+            else {
+                continue;
+            };
+            let Some(path) = buffer.document.path() else {
+                continue;
+            };
+            let line_count = buffer.document.line_count();
+            let first = pane.viewport().top_line();
+            let last = (first + pane.viewport().text_height()).min(line_count.saturating_sub(1));
+            let range = ranges
+                .entry(path.to_path_buf())
+                .or_insert((first, last, line_count));
+            range.0 = range.0.min(first);
+            range.1 = range.1.max(last);
+        }
+        ranges
+    }
+
+    pub(crate) fn toggle_inlay_hints(&mut self) {
+        let enabled = !self.lsp.inlay_hints_enabled();
+        self.lsp.set_inlay_hints_enabled(enabled);
+        let state = if enabled { "shown" } else { "hidden" };
+        self.set_message(&format!("Inlay hints {state}"), MessageKind::Info);
+    }
+
+    /// Shows the tooltip of the hint nearest the cursor, resolving it first
+    /// when the server sends tooltips lazily.
+    pub(crate) fn show_inlay_hint_tooltip(&mut self) {
+        let Some(path) = self.active_document().path().map(|path| path.to_path_buf()) else {
+            return;
+        };
+        let cursor = self.active_pane().cursor();
+        if !self.lsp.request_inlay_hint_tooltip(&path, cursor) {
+            self.set_message("No inlay hint tooltip here", MessageKind::Info);
+        }
+    }
+
+    pub(crate) fn inlay_hints_on_line(&self, buffer_id: u64, line: usize) -> Vec<&InlayHintView> {
+        self.buffer_by_id(buffer_id)
+            .and_then(|buffer| buffer.document.path())
+            .map(|path| self.lsp.inlay_hints_for_line(path, line))
+            .unwrap_or_default()
+    }
+
+    /// Screen column of `cursor` counted from the line start, hints included.
+    pub(crate) fn visual_column(&self, buffer_id: u64, cursor: Cursor) -> usize {
+        let Some(buffer) = self.buffer_by_id(buffer_id) else {
+            return 0;
+        };
+        let hints = self.inlay_hints_on_line(buffer_id, cursor.line);
+        buffer.document.display_column(cursor) + hint_width_before(&hints, cursor.column)
+    }
+
+    /// Character column under screen column `visual`; clicks on a hint land
+    /// on the text next to it.
+    pub(crate) fn column_for_visual(&self, buffer_id: u64, line: usize, visual: usize) -> usize {
+        let Some(buffer) = self.buffer_by_id(buffer_id) else {
+            return 0;
+        };
+        let document = &buffer.document;
+        let hints = self.inlay_hints_on_line(buffer_id, line);
+        if hints.is_empty() {
+            return document.column_for_display(line, visual);
+        }
+        let length = document.line_text(line).chars().count();
+        let visual_at = |column: usize| {
+            document.display_column(Cursor::new(line, column)) + hint_width_before(&hints, column)
+        };
+        let mut column = 0;
+        while column < length && visual_at(column + 1) <= visual {
+            column += 1;
+        }
+        column
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{InlayHintView, hint_width_before};
+    use crate::lsp::position::PositionEncoding;
+
+    #[test]
+    fn parses_labels_padding_and_tooltips() {
+        let line = |_| Some(String::from("let é = compute(1);"));
+        let hint = json!({
+            "position": { "line": 0, "character": 6 },
+            "label": [{ "value": ": " }, { "value": "f64", "tooltip": "primitive" }],
+            "paddingLeft": false,
+            "paddingRight": true
+        });
+        let view = InlayHintView::parse(&hint, PositionEncoding::Utf8, line)
+            .unwrap_or_else(|| panic!("hint not parsed"));
+        assert_eq!(view.column, 5);
+        assert_eq!(view.label, ": f64 ");
+        assert_eq!(view.tooltip.as_deref(), Some("primitive"));
+
+        let parameter = json!({
+            "position": { "line": 0, "character": 16 },
+            "label": "value:",
+            "paddingRight": true,
+            "tooltip": { "kind": "markdown", "value": "`value: u32`" }
+        });
+        let parameter = InlayHintView::parse(&parameter, PositionEncoding::Utf16, line)
+            .unwrap_or_else(|| panic!("hint not parsed"));
+        assert_eq!(parameter.column, 16);
+        assert_eq!(parameter.tooltip.as_deref(), Some("`value: u32`"));
+
+        let hints = [&view, &parameter];
+        assert_eq!(hint_width_before(&hints, 4), 0);
+        assert_eq!(hint_width_before(&hints, 5), 6);
+        assert_eq!(hint_width_before(&hints, 20), 13);
+    }
+}
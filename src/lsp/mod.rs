@@ -3,14 +3,28 @@ mod client;
 mod code_action;
 mod completion;
 mod diagnostics;
+mod document_highlight;
+mod document_symbol;
 mod format;
 mod goto;
+mod hierarchy;
 mod hover;
+mod inlay_hint;
+mod position;
 mod progress;
 mod protocol;
 mod rename;
+mod semantic_tokens;
 mod signature;
+mod sync;
 mod workspace;
+mod workspace_edit;
 
+pub use completion::{CompletionItemView, CompletionSource};
 pub use diagnostics::{DiagnosticItem, DiagnosticSeverityView};
+pub use document_highlight::{DocumentHighlights, HighlightKind};
+pub use goto::GotoKind;
+pub use hierarchy::{HierarchyDirection, HierarchyItem};
+pub use progress::spinner_frame;
 pub use workspace::LspWorkspace;
+pub(crate) use workspace_edit::{EditOrigin, WorkspaceEditPlan};
@@ -0,0 +1,254 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use lsp_types::{Position, PositionEncodingKind, TextEdit};
+
+use crate::app::BufferState;
+use crate::core::Cursor;
+
+/// Unit the server counts `Position::character` in. Editor columns are chars.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum PositionEncoding {
+    Utf8,
+    #[default]
+    Utf16,
+    Utf32,
+}
+
+impl PositionEncoding {
+    /// Maps the server's choice; servers that say nothing use UTF-16.
+    pub fn from_kind(kind: Option<&PositionEncodingKind>) -> Self {
+        match kind.map(PositionEncodingKind::as_str) {
+            Some("utf-8") => Self::Utf8,
+            Some("utf-32") => Self::Utf32,
+            _ => Self::Utf16,
+        }
+    }
+
+    fn units(self, ch: char) -> u32 {
+        match self {
+            Self::Utf8 => ch.len_utf8() as u32,
+            Self::Utf16 => ch.len_utf16() as u32,
+            Self::Utf32 => 1,
+        }
+    }
+
+    /// Length of `text` in this encoding's code units.
+    pub fn len(self, text: &str) -> u32 {
+        text.chars().map(|ch| self.units(ch)).sum()
+    }
+
+    pub fn to_position(self, line_text: &str, cursor: Cursor) -> Position {
+        let prefix = line_text.chars().take(cursor.column);
+        Position::new(cursor.line as u32, prefix.map(|ch| self.units(ch)).sum())
+    }
+
+    /// Converts a server position to a char column, clamping offsets that fall
+    /// inside a character or past the end of the line.
+    pub fn to_cursor(self, line_text: &str, position: Position) -> Cursor {
+        let mut units = 0u32;
+        let mut column = 0usize;
+        for ch in line_text.chars().take_while(|ch| *ch != '\n') {
+            units += self.units(ch);
+            if units > position.character {
+                break;
+            }
+            column += 1;
+        }
+        Cursor::new(position.line as usize, column)
+    }
+
+    /// Byte offset of `position` within the full document `text`.
+    pub fn byte_offset(self, text: &str, position: Position) -> Option<usize> {
+        let mut byte = 0usize;
+        for (line, segment) in text.split_inclusive('\n').enumerate() {
+            if line == position.line as usize {
+                let column = self.to_cursor(segment, position).column;
+                let offset = segment
+                    .char_indices()
+                    .nth(column)
+                    .map_or(segment.trim_end_matches('\n').len(), |(offset, _)| offset);
+                return Some(byte + offset);
+            }
+            byte += segment.len();
+        }
+        let line_count = text.split_inclusive('\n').count();
+        (position.line as usize == line_count).then_some(text.len())
+    }
+}
+
+/// A server edit as a byte range of the original text and its replacement.
+pub type ResolvedEdit = (Range<usize>, String);
+
+/// Converts server edits to byte ranges of `text`, in document order. Edits
+/// at the same position keep the order the server sent them in. Returns
+/// `None` when a range is inverted, out of bounds or overlaps another.
+pub fn resolve_text_edits(
+    text: &str,
+    edits: &[TextEdit],
+    encoding: PositionEncoding,
+) -> Option<Vec<ResolvedEdit>> {
+    let mut resolved = edits
+        .iter()
+        .map(|edit| {
+            let start = encoding.byte_offset(text, edit.range.start)?;
+            let end = encoding.byte_offset(text, edit.range.end)?;
+            (start <= end).then(|| (start..end, edit.new_text.clone()))
+        })
+        .collect::<Option<Vec<_>>>()?;
+    resolved.sort_by_key(|(range, _)| (range.start, range.end));
+    let overlapping = resolved
+        .windows(2)
+        .any(|pair| pair[0].0.end > pair[1].0.start);
+    (!overlapping).then_some(resolved)
+}
+
+/// Where byte `offset` of the original text ends up after `edits`. Offsets
+/// inside a replaced range move to the end of its replacement.
+pub fn map_offset(offset: usize, edits: &[ResolvedEdit]) -> usize {
+    let mut mapped = offset;
+    for (range, new_text) in edits {
+        if range.end <= offset {
+            mapped = mapped + new_text.len() - range.len();
+        } else if range.start < offset {
+            return mapped - (offset - range.start) + new_text.len();
+        } else {
+            break;
+        }
+    }
+    mapped
+}
+
+/// Looks up line text for position conversion, preferring unsaved buffers.
+pub trait LineSource {
+    fn line_text(&self, path: &Path, line: usize) -> Option<String>;
+
+    /// Full text of an open document, used to re-open it after a restart.
+    fn document_text(&self, path: &Path) -> Option<String>;
+}
+
+/// Lines of the open buffers, falling back to files on disk, each of which
+/// is read once for as long as this lives, e.g. one batch of responses.
+pub struct BufferLines<'a> {
+    buffers: &'a [BufferState],
+    files: RefCell<HashMap<PathBuf, Option<Vec<String>>>>,
+}
+
+impl<'a> BufferLines<'a> {
+    pub fn new(buffers: &'a [BufferState]) -> Self {
+        Self {
+            buffers,
+            files: RefCell::new(HashMap::new()),
+        }
+    }
+}
+
+impl LineSource for BufferLines<'_> {
+    fn line_text(&self, path: &Path, line: usize) -> Option<String> {
+        if let Some(buffer) = self
+            .buffers
+            .iter()
+            .find(|buffer| buffer.document.path() == Some(path))
+        {
+            return (line < buffer.document.line_count())
+                .then(|| buffer.document.line_text(line));
+        }
+        let mut files = self.files.borrow_mut();
+        let lines = files.entry(path.to_path_buf()).or_insert_with(|| {
+            let text = std::fs::read_to_string(path).ok()?;
+            Some(text.lines().map(ToOwned::to_owned).collect())
+        });
+        lines.as_ref()?.get(line).cloned()
+    }
+
+    fn document_text(&self, path: &Path) -> Option<String> {
+        self.buffers
+            .iter()
+            .find(|buffer| buffer.document.path() == Some(path))
+            .map(|buffer| buffer.document.text())
+    }
+}
+
+impl LineSource for Vec<BufferState> {
+    fn line_text(&self, path: &Path, line: usize) -> Option<String> {
+        BufferLines::new(self).line_text(path, line)
+    }
+
+    fn document_text(&self, path: &Path) -> Option<String> {
+        BufferLines::new(self).document_text(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::{Position, Range, TextEdit};
+
+    use super::{PositionEncoding, ResolvedEdit, map_offset, resolve_text_edits};
+    use crate::core::Cursor;
+
+    /// Applies resolved edits to the text they were resolved against.
+    fn splice_edits(text: &str, edits: &[ResolvedEdit]) -> String {
+        let mut output = String::with_capacity(text.len());
+        let mut copied = 0;
+        for (range, new_text) in edits {
+            output.push_str(&text[copied..range.start]);
+            output.push_str(new_text);
+            copied = range.end;
+        }
+        output.push_str(&text[copied..]);
+        output
+    }
+
+    #[test]
+    fn columns_round_trip_through_each_encoding() {
+        let line = "a😀日b";
+        let cursor = Cursor::new(3, 3);
+        for (encoding, character) in [
+            (PositionEncoding::Utf8, 8),
+            (PositionEncoding::Utf16, 4),
+            (PositionEncoding::Utf32, 3),
+        ] {
+            let position = encoding.to_position(line, cursor);
+            assert_eq!(position, Position::new(3, character));
+            assert_eq!(encoding.to_cursor(line, position), cursor);
+        }
+        assert_eq!(
+            PositionEncoding::Utf16.to_cursor(line, Position::new(0, 2)),
+            Cursor::new(0, 1)
+        );
+    }
+
+    #[test]
+    fn byte_offsets_respect_encoding() {
+        let text = "é😀x\nab";
+        let utf16 = PositionEncoding::Utf16;
+        assert_eq!(utf16.byte_offset(text, Position::new(0, 3)), Some(6));
+        assert_eq!(utf16.byte_offset(text, Position::new(0, 99)), Some(7));
+        assert_eq!(utf16.byte_offset(text, Position::new(1, 1)), Some(9));
+        assert_eq!(utf16.byte_offset(text, Position::new(5, 0)), None);
+        assert_eq!(PositionEncoding::Utf8.byte_offset(text, Position::new(0, 6)), Some(6));
+    }
+
+    #[test]
+    fn resolved_edits_splice_and_map_offsets() {
+        let text = "let a = 1;\nlet b = a;\n";
+        let edit = |line, start, end, new_text: &str| TextEdit {
+            range: Range::new(Position::new(line, start), Position::new(line, end)),
+            new_text: new_text.to_owned(),
+        };
+        let edits = [edit(1, 8, 9, "value"), edit(0, 4, 5, "value")];
+        let resolved = resolve_text_edits(text, &edits, PositionEncoding::Utf16)
+            .unwrap_or_default();
+        assert_eq!(splice_edits(text, &resolved), "let value = 1;\nlet b = value;\n");
+        // Before, inside and after the first edit.
+        assert_eq!(map_offset(2, &resolved), 2);
+        assert_eq!(map_offset(5, &resolved), 9);
+        assert_eq!(map_offset(11, &resolved), 15);
+        assert_eq!(map_offset(text.len(), &resolved), text.len() + 8);
+
+        let overlapping = [edit(0, 0, 5, ""), edit(0, 3, 6, "")];
+        assert!(resolve_text_edits(text, &overlapping, PositionEncoding::Utf16).is_none());
+    }
+}
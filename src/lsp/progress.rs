@@ -1,3 +1,8 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SPINNER_FRAMES: [char; 8] = ['⣾', '⣽', '⣻', '⢿', '⡿', '⣟', '⣯', '⣷'];
+const SPINNER_FRAME_MS: u128 = 125;
+
 #[derive(Debug, Clone)]
 pub struct ProgressState {
     pub title: String,
@@ -43,3 +48,28 @@ impl ProgressState {
         text
     }
 }
+
+/// Spinner glyph for in-flight requests, advanced by wall-clock time so it
+/// keeps moving on every redraw.
+pub fn spinner_frame() -> char {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis());
+    spinner_frame_at(millis)
+}
+
+fn spinner_frame_at(millis: u128) -> char {
+    SPINNER_FRAMES[(millis / SPINNER_FRAME_MS) as usize % SPINNER_FRAMES.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SPINNER_FRAMES, spinner_frame_at};
+
+    #[test]
+    fn spinner_cycles_through_frames() {
+        assert_eq!(spinner_frame_at(0), SPINNER_FRAMES[0]);
+        assert_eq!(spinner_frame_at(130), SPINNER_FRAMES[1]);
+        assert_eq!(spinner_frame_at(125 * 8), SPINNER_FRAMES[0]);
+    }
+}
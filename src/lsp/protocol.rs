@@ -2,7 +2,8 @@ use std::io::{self, BufRead};
 
 use serde_json::Value;
 
-pub(crate) fn read_message<R: BufRead>(reader: &mut R) -> io::Result<Option<Value>> {
+/// Reads one message body, undecoded, so it can be logged as received.
+pub(crate) fn read_message<R: BufRead>(reader: &mut R) -> io::Result<Option<String>> {
     let mut content_length = None;
     loop {
         let mut line = String::new();
@@ -23,7 +24,12 @@ pub(crate) fn read_message<R: BufRead>(reader: &mut R) -> io::Result<Option<Valu
     let length = content_length.ok_or_else(|| io::Error::other("Missing Content-Length"))?;
     let mut body = vec![0_u8; length];
     std::io::Read::read_exact(reader, &mut body)?;
-    let payload: Value = serde_json::from_slice(&body)
-        .map_err(|error| io::Error::other(format!("Invalid LSP JSON: {error}")))?;
-    Ok(Some(payload))
+    String::from_utf8(body)
+        .map(Some)
+        .map_err(|error| io::Error::other(format!("Invalid LSP body: {error}")))
+}
+
+pub(crate) fn parse_message(body: &str) -> io::Result<Value> {
+    serde_json::from_str(body)
+        .map_err(|error| io::Error::other(format!("Invalid LSP JSON: {error}")))
 }
@@ -1,4 +1,5 @@
 use crate::app::{App, MessageKind};
+use crate::lsp::workspace_edit::{EditOrigin, WorkspaceEditPlan};
 
 impl App {
     pub(crate) fn rename_symbol(&mut self) {
@@ -11,36 +12,22 @@ impl App {
             return;
         };
         let cursor = self.active_pane().cursor();
-        let edits = self.lsp.rename(
+        if !self.lsp.request_rename(
             &path,
             &self.workspace_root,
-            cursor.line,
-            cursor.column,
+            cursor,
+            &self.buffers,
             new_name,
-        );
-        if edits.is_empty() {
+        ) {
             self.set_message("Rename returned no edits", MessageKind::Info);
-            return;
         }
-        for (edit_path, text) in edits {
-            let document = crate::core::Document::from_text(Some(edit_path.clone()), &text);
-            if let Some(index) = self
-                .buffers
-                .iter()
-                .position(|buffer| buffer.document.path().is_some_and(|p| p == edit_path))
-            {
-                self.buffers[index].document = document;
-                self.buffers[index].saved_snapshot = self.buffers[index].document.text();
-                self.buffers[index].document.set_dirty(true);
-                continue;
-            }
-            let _ = self.push_buffer(
-                document,
-                crate::core::History::default(),
-                String::new(),
-                crate::util::DetectedEncoding::default(),
-            );
+    }
+
+    pub(crate) fn apply_rename_edits(&mut self, plan: WorkspaceEditPlan) {
+        if plan.is_empty() {
+            self.set_message("Rename returned no edits", MessageKind::Info);
+            return;
         }
-        self.set_message("Rename applied", MessageKind::Info);
+        self.begin_workspace_edit(plan, EditOrigin::Rename);
     }
 }
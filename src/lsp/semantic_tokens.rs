@@ -0,0 +1,278 @@
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use lsp_types::Position;
+use serde_json::Value;
+
+use crate::app::App;
+use crate::lsp::position::PositionEncoding;
+use crate::syntax::HighlightSpan;
+
+/// Token type and modifier names a server indexes its tokens into.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SemanticLegend {
+    pub types: Vec<String>,
+    pub modifiers: Vec<String>,
+}
+
+impl SemanticLegend {
+    pub fn from_lsp(legend: &lsp_types::SemanticTokensLegend) -> Self {
+        Self {
+            types: legend
+                .token_types
+                .iter()
+                .map(|kind| kind.as_str().to_owned())
+                .collect(),
+            modifiers: legend
+                .token_modifiers
+                .iter()
+                .map(|modifier| modifier.as_str().to_owned())
+                .collect(),
+        }
+    }
+
+    /// Theme capture for a token, or `None` to keep the tree-sitter colour.
+    fn capture(&self, token_type: u32, modifier_bits: u32) -> Option<&'static str> {
+        let token_type = self.types.get(token_type as usize)?;
+        let modifiers = self
+            .modifiers
+            .iter()
+            .enumerate()
+            .filter(|(bit, _)| *bit < 32 && modifier_bits & (1 << bit) != 0)
+            .map(|(_, name)| name.as_str())
+            .collect::<Vec<_>>();
+        capture_for(token_type, &modifiers)
+    }
+}
+
+/// Maps a standard (or rust-analyzer) token type and its modifiers to the
+/// capture names themes already style.
+pub fn capture_for(token_type: &str, modifiers: &[&str]) -> Option<&'static str> {
+    let has = |name: &str| modifiers.contains(&name);
+    let capture = match token_type {
+        "variable" | "parameter" if has("mutable") => "variable.mutable",
+        "variable" if has("defaultLibrary") => "variable.builtin",
+        "variable" if has("readonly") && has("static") => "constant",
+        "variable" => "variable",
+        "parameter" => "parameter",
+        "selfKeyword" | "selfTypeKeyword" => "variable.builtin",
+        "property" | "event" => "property",
+        "enumMember" => "constant",
+        "namespace" => "namespace",
+        "type" | "class" | "struct" | "enum" | "interface" | "typeAlias" | "union"
+            if has("defaultLibrary") =>
+        {
+            "type.builtin"
+        }
+        "type" | "class" | "struct" | "enum" | "interface" | "typeAlias" | "union" => "type",
+        "builtinType" => "type.builtin",
+        "typeParameter" => "type.parameter",
+        "function" if has("defaultLibrary") => "function.builtin",
+        "function" => "function",
+        "method" => "function.method",
+        "macro" => "function.macro",
+        "keyword" => "keyword",
+        "modifier" => "keyword.storage",
+        "comment" if has("documentation") => "comment.documentation",
+        "comment" => "comment",
+        "string" => "string",
+        "escapeSequence" => "string.escape",
+        "formatSpecifier" | "regexp" => "string.special",
+        "number" => "number",
+        "boolean" => "boolean",
+        "operator" => "operator",
+        "decorator" | "attribute" | "derive" => "attribute",
+        "label" | "lifetime" => "label",
+        _ => return None,
+    };
+    Some(capture)
+}
+
+/// Applies `semanticTokens/full/delta` edits to the previous token data.
+/// Returns `false` when an edit falls outside the data.
+pub fn apply_delta(data: &mut Vec<u32>, edits: &[Value]) -> bool {
+    let mut edits = edits
+        .iter()
+        .filter_map(|edit| {
+            let start = edit.get("start")?.as_u64()? as usize;
+            let delete = edit.get("deleteCount")?.as_u64()? as usize;
+            let insert = edit
+                .get("data")
+                .and_then(Value::as_array)
+                .map(|data| {
+                    data.iter()
+                        .filter_map(|value| value.as_u64().map(|value| value as u32))
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default();
+            Some((start, delete, insert))
+        })
+        .collect::<Vec<_>>();
+    // Later edits first, so earlier offsets stay valid.
+    edits.sort_by_key(|(start, _, _)| std::cmp::Reverse(*start));
+    for (start, delete, insert) in edits {
+        if start + delete > data.len() {
+            return false;
+        }
+        data.splice(start..start + delete, insert);
+    }
+    true
+}
+
+/// Reads the `data` array of a `SemanticTokens` result.
+pub fn token_data(value: &Value) -> Option<Vec<u32>> {
+    let data = value.get("data")?.as_array()?;
+    Some(
+        data.iter()
+            .filter_map(|value| value.as_u64().map(|value| value as u32))
+            .collect(),
+    )
+}
+
+/// Highlight spans for one line, valid while the line still has `hash`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SemanticLine {
+    pub hash: u64,
+    pub spans: Vec<(usize, usize, &'static str)>,
+}
+
+pub fn line_hash(text: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    text.trim_end_matches(['\r', '\n']).hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Decodes relative token data into byte spans per line; tokens without a
+/// capture are dropped so tree-sitter keeps colouring them.
+pub fn decode(
+    data: &[u32],
+    legend: &SemanticLegend,
+    encoding: PositionEncoding,
+    line_text: impl Fn(usize) -> Option<String>,
+) -> HashMap<usize, SemanticLine> {
+    let mut lines: HashMap<usize, SemanticLine> = HashMap::new();
+    let mut text: Option<(usize, String)> = None;
+    let mut line = 0usize;
+    let mut start = 0u32;
+    for token in data.chunks_exact(5) {
+        let [delta_line, delta_start, length, token_type, modifiers] = token else {
+            continue;
+        };
+        if *delta_line > 0 {
+            line += *delta_line as usize;
+            start = *delta_start;
+        } else {
+            start += *delta_start;
+        }
+        let Some(capture) = legend.capture(*token_type, *modifiers) else {
+            continue;
+        };
+        if text.as_ref().is_none_or(|(cached, _)| *cached != line) {
+            let Some(current) = line_text(line) else {
+                continue;
+            };
+            text = Some((line, current));
+        }
+        let Some((_, current)) = text.as_ref() else {
+            continue;
+        };
+        let byte = |character| encoding.byte_offset(current, Position::new(0, character));
+        let (Some(start_byte), Some(end_byte)) = (byte(start), byte(start + length)) else {
+            continue;
+        };
+        if start_byte < end_byte {
+            lines
+                .entry(line)
+                .or_insert_with(|| SemanticLine {
+                    hash: line_hash(current),
+                    spans: Vec::new(),
+                })
+                .spans
+                .push((start_byte, end_byte, capture));
+        }
+    }
+    lines
+}
+
+impl App {
+    /// Keeps semantic tokens current for every document shown in a pane.
+    pub(crate) fn refresh_semantic_tokens(&mut self) {
+        for (path, (first, last, line_count)) in self.visible_line_ranges() {
+            self.lsp
+                .request_semantic_tokens(&path, first..=last, line_count, &self.buffers);
+        }
+    }
+
+    /// Semantic token spans for a line, empty until the server answered or
+    /// once the line was edited after the tokens were computed.
+    pub(crate) fn semantic_spans_for_line(
+        &self,
+        buffer_id: u64,
+        line: usize,
+    ) -> Vec<HighlightSpan> {
+        let Some(buffer) = self.buffer_by_id(buffer_id) else {
+            return Vec::new();
+        };
+        let Some(path) = buffer.document.path() else {
+            return Vec::new();
+        };
+        let text = buffer.document.line_text(line);
+        self.lsp
+            .semantic_line(path, line, line_hash(&text))
+            .map(|semantic| {
+                semantic
+                    .spans
+                    .iter()
+                    .map(|(start_byte, end_byte, capture)| HighlightSpan {
+                        start_byte: *start_byte,
+                        end_byte: *end_byte,
+                        capture,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{SemanticLegend, apply_delta, capture_for, decode, line_hash};
+    use crate::lsp::position::PositionEncoding;
+
+    #[test]
+    fn decodes_tokens_and_applies_deltas() {
+        let legend = SemanticLegend {
+            types: ["variable", "macro", "parameter", "punctuation"]
+                .map(String::from)
+                .to_vec(),
+            modifiers: ["declaration", "mutable"].map(String::from).to_vec(),
+        };
+        assert_eq!(capture_for("variable", &["mutable"]), Some("variable.mutable"));
+        assert_eq!(capture_for("typeParameter", &[]), Some("type.parameter"));
+        assert_eq!(capture_for("punctuation", &[]), None);
+
+        let text = ["let mut é = 1;", "    println!(\"{é}\", x);"];
+        let line_text = |line: usize| text.get(line).map(|text| text.to_string());
+        // `é` (mutable declaration), `println` (macro), `x` (parameter) and `;`.
+        let mut data = vec![0, 8, 1, 0, 3, 1, 4, 7, 1, 0, 0, 16, 1, 2, 0, 0, 1, 1, 3, 0];
+        let lines = decode(&data, &legend, PositionEncoding::Utf16, line_text);
+        assert_eq!(lines[&0].spans, vec![(8, 10, "variable.mutable")]);
+        assert_eq!(lines[&0].hash, line_hash(text[0]));
+        assert_eq!(
+            lines[&1].spans,
+            vec![(4, 11, "function.macro"), (21, 22, "parameter")]
+        );
+
+        // Drop the `x` token and retype the last one.
+        let edits = [
+            json!({ "start": 10, "deleteCount": 5 }),
+            json!({ "start": 18, "deleteCount": 1, "data": [0] }),
+        ];
+        assert!(apply_delta(&mut data, &edits));
+        assert_eq!(data, vec![0, 8, 1, 0, 3, 1, 4, 7, 1, 0, 0, 1, 1, 0, 0]);
+        assert!(!apply_delta(&mut data, &[json!({ "start": 14, "deleteCount": 2 })]));
+    }
+}
@@ -22,6 +22,6 @@ impl App {
         };
         let cursor = self.active_pane().cursor();
         self.lsp
-            .request_signature(&path, &self.workspace_root, cursor.line, cursor.column);
+            .request_signature(&path, &self.workspace_root, cursor, &self.buffers);
     }
 }
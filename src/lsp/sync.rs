@@ -0,0 +1,99 @@
+use lsp_types::{Position, Range, TextDocumentContentChangeEvent};
+
+use crate::app::App;
+use crate::core::Cursor;
+use crate::lsp::position::PositionEncoding;
+
+/// Builds the ranged change for replacing `deleted` with `inserted` at a
+/// position whose line starts with `line_prefix`, in the server's encoding.
+pub fn ranged_change(
+    encoding: PositionEncoding,
+    line_prefix: &str,
+    start_line: usize,
+    deleted: &str,
+    inserted: &str,
+) -> TextDocumentContentChangeEvent {
+    let start = Position::new(start_line as u32, encoding.len(line_prefix));
+    let end = match deleted.rfind('\n') {
+        Some(index) => Position::new(
+            (start_line + deleted.matches('\n').count()) as u32,
+            encoding.len(&deleted[index + 1..]),
+        ),
+        None => Position::new(start.line, start.character + encoding.len(deleted)),
+    };
+    TextDocumentContentChangeEvent {
+        range: Some(Range::new(start, end)),
+        range_length: None,
+        text: inserted.to_owned(),
+    }
+}
+
+impl App {
+    /// Reports one applied edit to the language server. The document must
+    /// already contain `inserted` at `start`.
+    pub(crate) fn notify_lsp_edit(&mut self, start: Cursor, deleted: &str, inserted: &str) {
+        self.notify_lsp_buffer_edit(self.active_buffer_id, start, deleted, inserted);
+    }
+
+    pub(crate) fn notify_lsp_buffer_edit(
+        &mut self,
+        buffer_id: u64,
+        start: Cursor,
+        deleted: &str,
+        inserted: &str,
+    ) {
+        let Some(buffer) = self.buffer_by_id(buffer_id) else {
+            return;
+        };
+        if buffer.large_file || buffer.hex.is_some() {
+            return;
+        }
+        let Some(path) = buffer.document.path().map(|path| path.to_path_buf()) else {
+            return;
+        };
+        let prefix = buffer
+            .document
+            .line_text(start.line)
+            .chars()
+            .take(start.column)
+            .collect::<String>();
+        let change = |encoding| ranged_change(encoding, &prefix, start.line, deleted, inserted);
+        let buffers = &self.buffers;
+        self.lsp.record_change(
+            &path,
+            change,
+            || {
+                buffers
+                    .iter()
+                    .find(|buffer| buffer.id == buffer_id)
+                    .map(|buffer| buffer.document.text())
+                    .unwrap_or_default()
+            },
+            &self.workspace_root,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::{Position, Range};
+
+    use super::ranged_change;
+    use crate::lsp::position::PositionEncoding;
+
+    #[test]
+    fn ranges_use_server_units_and_span_deleted_lines() {
+        let change = ranged_change(PositionEncoding::Utf16, "a😀", 2, "é\nxy", "z");
+        assert_eq!(
+            change.range,
+            Some(Range::new(Position::new(2, 3), Position::new(3, 2)))
+        );
+        assert_eq!(change.text, "z");
+
+        let change = ranged_change(PositionEncoding::Utf8, "é", 0, "ab", "");
+        assert_eq!(
+            change.range,
+            Some(Range::new(Position::new(0, 2), Position::new(0, 4)))
+        );
+    }
+}
@@ -1,4 +1,9 @@
 use crate::app::{App, MessageKind};
+use crate::lsp::client::{render_traffic, TrafficFilter};
+use crate::lsp::workspace::LspResponse;
+use crate::lsp::workspace_edit::EditOrigin;
+use crate::syntax::{language_for_path, LanguageId};
+use crate::ui::{PickerItem, PickerKind, PickerState};
 
 impl App {
     pub(crate) fn open_workspace_symbols(&mut self) {
@@ -7,10 +12,135 @@ impl App {
     }
 
     pub(crate) fn open_workspace_symbols_query(&mut self, query: &str) {
-        self.lsp
-            .open_workspace_symbols(query, &self.workspace_root, &mut self.picker);
-        if self.picker.is_none() {
+        if !self.lsp.request_workspace_symbols(query, &self.workspace_root) {
+            self.set_message("No workspace symbols found", MessageKind::Info);
+        }
+    }
+
+    fn show_workspace_symbols(&mut self, items: Vec<PickerItem>) {
+        if items.is_empty() {
             self.set_message("No workspace symbols found", MessageKind::Info);
+            return;
+        }
+        let mut state = PickerState::new(PickerKind::Locations);
+        state.set_source(items);
+        self.picker = Some(state);
+    }
+
+    /// Restarts the active buffer's server, or every known server when the
+    /// buffer has none.
+    pub(crate) fn restart_language_servers(&mut self) {
+        let languages = match self.active_document().path().and_then(language_for_path) {
+            Some(language) => vec![language],
+            None => self.lsp.active_server_languages(),
+        };
+        if languages.is_empty() {
+            self.set_message("No language server to restart", MessageKind::Info);
+            return;
+        }
+        let started = languages
+            .into_iter()
+            .map(|language| self.lsp.restart_server(language))
+            .sum::<usize>();
+        if started == 0 {
+            self.set_message("Language server failed to start", MessageKind::Warning);
+        } else {
+            self.set_message(
+                &format!("Restarting {started} language server(s)"),
+                MessageKind::Info,
+            );
+        }
+    }
+
+    pub(crate) fn show_lsp_status(&mut self) {
+        let items = self.lsp.status_items();
+        if items.is_empty() {
+            self.set_message("No language servers running", MessageKind::Info);
+            return;
+        }
+        let mut state = PickerState::new(PickerKind::LspStatus);
+        state.set_buffer_items(items);
+        self.picker = Some(state);
+    }
+
+    /// Status lines mentioning `query`, for filtering the status picker.
+    pub(crate) fn lsp_status_items(&self, query: &str) -> Vec<PickerItem> {
+        let query = query.to_lowercase();
+        self.lsp
+            .status_items()
+            .into_iter()
+            .filter(|item| {
+                item.title.to_lowercase().contains(&query)
+                    || item.subtitle.to_lowercase().contains(&query)
+            })
+            .collect()
+    }
+
+    /// Opens recorded JSON-RPC traffic, optionally filtered by
+    /// `server=NAME` and `method=TEXT`, in a read-only buffer.
+    pub(crate) fn open_lsp_log(&mut self, args: &str) {
+        let filter = match TrafficFilter::parse(args) {
+            Ok(filter) => filter,
+            Err(error) => {
+                self.set_message(&error, MessageKind::Warning);
+                return;
+            }
+        };
+        let entries = self.lsp.traffic().entries(&filter);
+        if entries.is_empty() {
+            self.set_message("LSP log is empty", MessageKind::Info);
+            return;
+        }
+        let text = render_traffic(&entries);
+        self.push_scratch_buffer("lsp-log", &text, Some(LanguageId::Json));
+        self.set_message(&format!("{} LSP messages", entries.len()), MessageKind::Info);
+    }
+
+    /// Acts on requests the language servers finished since the last tick.
+    pub(crate) fn handle_lsp_responses(&mut self) {
+        for response in self.lsp.take_responses() {
+            match response {
+                LspResponse::Goto { target, locations } => {
+                    self.open_goto_results(target, locations)
+                }
+                LspResponse::References(refs) => self.show_references(refs),
+                LspResponse::CodeActions => self.show_code_action_menu(),
+                LspResponse::CodeAction(run) => self.apply_code_action(run),
+                LspResponse::Formatting {
+                    path,
+                    edits,
+                    encoding,
+                } => self.apply_formatting(&path, &edits, encoding),
+                LspResponse::Rename(edits) => self.apply_rename_edits(edits),
+                LspResponse::WorkspaceSymbols(items) => self.show_workspace_symbols(items),
+                LspResponse::Hierarchy {
+                    direction,
+                    parent,
+                    items,
+                } => self.show_hierarchy(direction, parent, items),
+                LspResponse::DocumentHighlights {
+                    path,
+                    cursor,
+                    highlights,
+                } => self.show_document_highlights(&path, cursor, highlights),
+                LspResponse::TimedOut(method) => {
+                    self.set_message(&format!("LSP {method} timed out"), MessageKind::Warning);
+                }
+                LspResponse::ServerNotice(notice) => {
+                    self.set_message(&notice, MessageKind::Warning);
+                }
+                LspResponse::ApplyEdit { reply, label, edit } => {
+                    self.begin_workspace_edit(edit, EditOrigin::Server { reply, label });
+                }
+                LspResponse::ShowMessage { message, level } => {
+                    let kind = match level {
+                        1 => MessageKind::Error,
+                        2 => MessageKind::Warning,
+                        _ => MessageKind::Info,
+                    };
+                    self.set_message(&message, kind);
+                }
+            }
         }
     }
 
@@ -0,0 +1,295 @@
+use std::path::Path;
+
+use crate::core::Cursor;
+use crate::lsp::client::LspFeature;
+use crate::lsp::code_action::CodeActionView;
+use crate::lsp::position::{LineSource, PositionEncoding};
+use crate::lsp::workspace::LspWorkspace;
+use crate::lsp::workspace::pending::{
+    CodeActionEntry, CodeActionRun, LspResponse, PendingRequest, RequestKind,
+};
+use crate::lsp::workspace::routing::ServerId;
+use crate::lsp::workspace::state::file_uri;
+use crate::lsp::workspace_edit::WorkspaceEditPlan;
+
+/// Menu entries merged from every server, and the gutter lightbulb.
+#[derive(Debug, Default)]
+pub(super) struct CodeActionState {
+    entries: Vec<CodeActionEntry>,
+    /// Line with code actions available, shown as a gutter lightbulb.
+    hint: Option<(std::path::PathBuf, usize)>,
+    /// Path, line and version the last lightbulb query was sent for.
+    hint_key: Option<(std::path::PathBuf, usize, i32)>,
+}
+
+impl CodeActionState {
+    /// Asks again for the lightbulb, e.g. after new diagnostics.
+    pub(super) fn forget_hint(&mut self) {
+        self.hint_key = None;
+    }
+}
+
+impl LspWorkspace {
+    /// Asks every code action server for actions on `start..end`, each along
+    /// with its own diagnostics there; the answers are merged as they arrive.
+    pub fn request_code_actions(
+        &mut self,
+        path: &Path,
+        workspace_root: &Path,
+        start: Cursor,
+        end: Cursor,
+        lines: &dyn LineSource,
+    ) -> bool {
+        self.bootstrap_workspace(workspace_root);
+        self.ensure_client_for_path(path, workspace_root);
+        if !self.progress.done {
+            return false;
+        }
+        self.cancel_kind(RequestKind::CodeActions);
+        self.code_actions.entries.clear();
+        let mut sent = false;
+        for id in self.route_all(path, LspFeature::CodeAction) {
+            let params = self.code_action_params(id, path, (start, end), lines, false);
+            sent |= self.send_request(RequestKind::CodeActions, id, Some(path), start, params);
+        }
+        sent
+    }
+
+    /// Whether a server has yet to answer the last code action request.
+    pub fn code_actions_pending(&self) -> bool {
+        self.pending
+            .values()
+            .any(|request| request.kind == RequestKind::CodeActions)
+    }
+
+    /// Queries the cursor line in the background for the lightbulb. Only
+    /// servers that are already running are asked; any of them can light it.
+    pub fn request_code_action_hint(
+        &mut self,
+        path: &Path,
+        cursor: Cursor,
+        lines: &dyn LineSource,
+    ) {
+        let key = (path.to_path_buf(), cursor.line, self.document_version(path));
+        if !self.progress.done || self.code_actions.hint_key.as_ref() == Some(&key) {
+            return;
+        }
+        self.code_actions.hint_key = Some(key);
+        self.code_actions.hint = None;
+        self.cancel_kind(RequestKind::CodeActionHint);
+        for id in self.route_all(path, LspFeature::CodeAction) {
+            let params = self.code_action_params(id, path, (cursor, cursor), lines, true);
+            self.send_request(RequestKind::CodeActionHint, id, Some(path), cursor, params);
+        }
+    }
+
+    pub fn code_action_hint_line(&self, path: &Path) -> Option<usize> {
+        self.code_actions
+            .hint
+            .as_ref()
+            .filter(|(hint_path, _)| hint_path == path)
+            .map(|(_, line)| *line)
+    }
+
+    fn code_action_params(
+        &self,
+        id: ServerId,
+        path: &Path,
+        (start, end): (Cursor, Cursor),
+        lines: &dyn LineSource,
+        automatic: bool,
+    ) -> serde_json::Value {
+        let server = self
+            .server_config(id)
+            .map(|server| server.name.as_str())
+            .unwrap_or_default();
+        let diagnostics = self
+            .diagnostics
+            .for_path(path)
+            .iter()
+            .filter(|item| item.server == server && item.overlaps_lines(start.line, end.line))
+            .map(|item| item.published.clone())
+            .collect::<Vec<_>>();
+        serde_json::json!({
+            "textDocument": { "uri": file_uri(path) },
+            "range": {
+                "start": self.lsp_position(id, path, start, lines),
+                "end": self.lsp_position(id, path, end, lines)
+            },
+            "context": {
+                "diagnostics": diagnostics,
+                "triggerKind": if automatic { 2 } else { 1 }
+            }
+        })
+    }
+
+    /// Menu entries from the last code action request, from every server that
+    /// has answered so far; preferred first.
+    pub fn code_actions(&self) -> Vec<&CodeActionView> {
+        self.code_actions
+            .entries
+            .iter()
+            .map(|entry| &entry.view)
+            .collect()
+    }
+
+    /// Merges a server's actions into the menu entries.
+    pub(super) fn store_code_actions(&mut self, id: ServerId, value: &serde_json::Value) {
+        let entries = value
+            .as_array()
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|raw| {
+                        Some(CodeActionEntry {
+                            server: id,
+                            view: CodeActionView::parse(raw)?,
+                            raw: raw.clone(),
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        self.code_actions.entries.extend(entries);
+        self.code_actions
+            .entries
+            .sort_by_key(|entry| (entry.view.disabled.is_some(), !entry.view.preferred));
+    }
+
+    /// Queues the menu entry at `index` as a `CodeAction` response, sending
+    /// `codeAction/resolve` first when the server left out the edit.
+    pub fn run_code_action(&mut self, index: usize) -> bool {
+        let Some(entry) = self.code_actions.entries.get(index).cloned() else {
+            return false;
+        };
+        let literal = !entry
+            .raw
+            .get("command")
+            .is_some_and(serde_json::Value::is_string);
+        let resolve = self
+            .clients
+            .get(&entry.server)
+            .is_some_and(|client| client.capabilities.code_action_resolve);
+        if literal && resolve && entry.raw.get("edit").is_none() {
+            return self.dispatch(
+                RequestKind::ResolveCodeAction,
+                entry.server,
+                None,
+                Cursor::default(),
+                entry.raw,
+            );
+        }
+        let encoding = self.server_encoding(entry.server);
+        let Some(run) = code_action_run(entry.server, &entry.raw, encoding) else {
+            return false;
+        };
+        self.responses.push(LspResponse::CodeAction(run));
+        true
+    }
+
+    /// Sends the action's command to the server that offered it.
+    pub fn execute_command(&mut self, run: &CodeActionRun) -> bool {
+        let Some(command) = &run.command else {
+            return false;
+        };
+        let params = serde_json::json!({
+            "command": command.command,
+            "arguments": command.arguments.clone().unwrap_or_default()
+        });
+        self.send_request(
+            RequestKind::ExecuteCommand,
+            run.server,
+            None,
+            Cursor::default(),
+            params,
+        )
+    }
+
+    /// Routes the answer to a code action request: menu entries are merged,
+    /// any server with an enabled action lights the lightbulb, and resolved
+    /// actions are queued to run.
+    pub(super) fn handle_code_action_response(
+        &mut self,
+        id: ServerId,
+        request: &PendingRequest,
+        result: Result<serde_json::Value, String>,
+    ) {
+        match request.kind {
+            RequestKind::CodeActions => {
+                self.store_code_actions(id, &result.unwrap_or(serde_json::Value::Null));
+                self.responses.push(LspResponse::CodeActions);
+            }
+            RequestKind::CodeActionHint => {
+                let available = result
+                    .ok()
+                    .as_ref()
+                    .and_then(serde_json::Value::as_array)
+                    .is_some_and(|items| items.iter().any(|item| item.get("disabled").is_none()));
+                if available && let Some(path) = request.path.clone() {
+                    self.code_actions.hint = Some((path, request.cursor.line));
+                }
+            }
+            RequestKind::ResolveCodeAction => {
+                let value = result.unwrap_or(serde_json::Value::Null);
+                if let Some(run) = code_action_run(id, &value, request.encoding) {
+                    self.responses.push(LspResponse::CodeAction(run));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Reads the edit and command of a `CodeAction`, or a bare `Command`.
+fn code_action_run(
+    server: ServerId,
+    value: &serde_json::Value,
+    encoding: PositionEncoding,
+) -> Option<CodeActionRun> {
+    let title = value.get("title")?.as_str()?.to_owned();
+    let command = match value.get("command") {
+        Some(serde_json::Value::String(_)) => Some(value),
+        command => command,
+    };
+    Some(CodeActionRun {
+        server,
+        command: command.and_then(|command| serde_json::from_value(command.clone()).ok()),
+        title,
+        edit: value
+            .get("edit")
+            .map(|edit| WorkspaceEditPlan::parse(edit, encoding))
+            .unwrap_or_default(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::LspWorkspace;
+    use crate::lsp::workspace::routing::ServerId;
+    use crate::syntax::LanguageId;
+
+    #[test]
+    fn code_actions_from_every_server_are_merged() {
+        let root = tempfile::tempdir().unwrap_or_else(|error| panic!("{error}"));
+        let mut workspace = LspWorkspace::new(root.path());
+        let first = ServerId::new(LanguageId::Rust, 0);
+        let second = ServerId::new(LanguageId::Rust, 1);
+        workspace.store_code_actions(first, &json!([{ "title": "Rename file" }]));
+        workspace.store_code_actions(
+            second,
+            &json!([
+                { "title": "Fix lint", "isPreferred": true },
+                { "title": "Extract", "disabled": { "reason": "Select code" } }
+            ]),
+        );
+        let titles = workspace
+            .code_actions()
+            .into_iter()
+            .map(|action| action.title.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(titles, ["Fix lint", "Rename file", "Extract"]);
+        assert_eq!(workspace.code_actions.entries[0].server, second);
+    }
+}
@@ -0,0 +1,145 @@
+use std::path::Path;
+
+use crate::core::Cursor;
+use crate::lsp::client::LspFeature;
+use crate::lsp::completion::{CompletionItemView, parse_completion_items};
+use crate::lsp::position::LineSource;
+use crate::lsp::workspace::LspWorkspace;
+use crate::lsp::workspace::pending::{PendingRequest, RequestKind};
+use crate::lsp::workspace::routing::ServerId;
+use crate::lsp::workspace::state::file_uri;
+
+/// Raw items behind the merged completion list.
+#[derive(Debug, Default)]
+pub(super) struct CompletionItemState {
+    /// Server and raw item behind each completion item id.
+    raw: Vec<(ServerId, serde_json::Value)>,
+    /// Item an in-flight `completionItem/resolve` fills in.
+    resolving: Option<usize>,
+}
+
+impl LspWorkspace {
+    pub fn request_completion(
+        &mut self,
+        path: &Path,
+        workspace_root: &Path,
+        cursor: Cursor,
+        lines: &dyn LineSource,
+        trigger: Option<char>,
+    ) {
+        self.bootstrap_workspace(workspace_root);
+        self.ensure_client_for_path(path, workspace_root);
+        self.close_completion();
+        // Every completion server is asked; the answers are merged as they arrive.
+        for id in self.route_all(path, LspFeature::Completion) {
+            let Some(client) = self.clients.get(&id) else {
+                continue;
+            };
+            let context =
+                completion_context(trigger, &client.capabilities.completion_trigger_characters);
+            let params = serde_json::json!({
+                "textDocument": { "uri": file_uri(path) },
+                "position": self.lsp_position(id, path, cursor, lines),
+                "context": context
+            });
+            self.send_request(RequestKind::Completion, id, Some(path), cursor, params);
+        }
+    }
+
+    /// Closes the list and drops the answers still on their way.
+    pub fn close_completion(&mut self) {
+        self.completion.close();
+        self.completion_items.raw.clear();
+        self.cancel_kind(RequestKind::Completion);
+        self.cancel_kind(RequestKind::ResolveCompletion);
+        self.completion_items.resolving = None;
+    }
+
+    /// Asks the server to fill in the selected completion item's
+    /// documentation the first time it is selected.
+    pub fn resolve_completion_selection(&mut self) {
+        let Some(item) = self.completion.selected_item() else {
+            return;
+        };
+        if item.resolved || self.completion_items.resolving == Some(item.id) {
+            return;
+        }
+        let item = item.id;
+        let Some((server, raw)) = self.completion_items.raw.get(item).cloned() else {
+            return;
+        };
+        let resolve = self
+            .clients
+            .get(&server)
+            .is_some_and(|client| client.capabilities.completion_resolve);
+        if resolve
+            && self.dispatch(
+                RequestKind::ResolveCompletion,
+                server,
+                None,
+                Cursor::default(),
+                raw,
+            )
+        {
+            self.completion_items.resolving = Some(item);
+        } else {
+            self.completion.resolve(item, &serde_json::Value::Null);
+        }
+    }
+
+    /// Numbers a server's completion items and merges them into the list.
+    fn store_completion_items(
+        &mut self,
+        id: ServerId,
+        request: &PendingRequest,
+        value: &serde_json::Value,
+    ) {
+        let (parsed, incomplete) = parse_completion_items(value, request.encoding);
+        let items = parsed
+            .into_iter()
+            .map(|(item, raw)| {
+                let item_id = self.completion_items.raw.len();
+                self.completion_items.raw.push((id, raw));
+                CompletionItemView {
+                    id: item_id,
+                    ..item
+                }
+            })
+            .collect();
+        self.completion
+            .merge_items(request.cursor, items, incomplete);
+    }
+
+    /// Merges a server's items into the list, or fills in the documentation
+    /// of the item being resolved.
+    pub(super) fn handle_completion_response(
+        &mut self,
+        id: ServerId,
+        request: &PendingRequest,
+        result: Result<serde_json::Value, String>,
+    ) {
+        if request.kind == RequestKind::ResolveCompletion {
+            if let Some(item) = self.completion_items.resolving.take() {
+                let value = result.unwrap_or(serde_json::Value::Null);
+                self.completion.resolve(item, &value);
+            }
+        } else if let Ok(value) = result {
+            self.store_completion_items(id, request, &value);
+        }
+    }
+}
+
+fn completion_context(trigger: Option<char>, trigger_characters: &[String]) -> serde_json::Value {
+    let Some(trigger) = trigger else {
+        return serde_json::json!({ "triggerKind": 1 });
+    };
+    let trigger_text = trigger.to_string();
+    if trigger_characters.iter().any(|item| item == &trigger_text) {
+        serde_json::json!({
+            "triggerKind": 2,
+            "triggerCharacter": trigger_text
+        })
+    } else {
+        serde_json::json!({ "triggerKind": 1 })
+    }
+}
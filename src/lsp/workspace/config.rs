@@ -1,9 +1,9 @@
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use serde::Deserialize;
 
-use crate::lsp::client::ServerConfig;
+use crate::lsp::client::{LspFeature, ServerConfig};
 use crate::syntax::LanguageId;
 
 #[derive(Debug, Deserialize)]
@@ -15,44 +15,78 @@ struct LanguagesConfig {
 #[derive(Debug, Default, Deserialize)]
 struct LspSection {
     #[serde(default)]
-    servers: HashMap<String, ServerTomlConfig>,
+    servers: HashMap<String, ServerEntries>,
+    /// Appends every JSON-RPC message to this file, relative to the workspace.
+    #[serde(default)]
+    trace_file: Option<PathBuf>,
+}
+
+/// `[lsp.servers.python]` for one server or `[[lsp.servers.python]]` for an
+/// ordered list, the first being the primary server.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ServerEntries {
+    One(ServerTomlConfig),
+    Many(Vec<ServerTomlConfig>),
 }
 
 #[derive(Debug, Deserialize)]
 struct ServerTomlConfig {
+    #[serde(default)]
+    name: Option<String>,
     command: String,
     #[serde(default)]
     args: Vec<String>,
     #[serde(default)]
     initialization_options: Option<serde_json::Value>,
+    #[serde(default)]
+    features: Option<Vec<LspFeature>>,
+    #[serde(default)]
+    formatter: bool,
 }
 
-pub fn load_server_config(workspace_root: &Path) -> HashMap<LanguageId, ServerConfig> {
-    let mut map = default_servers();
+fn read_languages_config(workspace_root: &Path) -> Option<LanguagesConfig> {
     let config_path = workspace_root.join("assets/languages.toml");
-    let text = match std::fs::read_to_string(config_path) {
-        Ok(text) => text,
-        Err(_) => return map,
-    };
+    let text = std::fs::read_to_string(config_path).ok()?;
+    toml::from_str(&text).ok()
+}
 
-    let parsed: LanguagesConfig = match toml::from_str(&text) {
-        Ok(parsed) => parsed,
-        Err(_) => return map,
-    };
+pub fn load_trace_file(workspace_root: &Path) -> Option<PathBuf> {
+    let path = read_languages_config(workspace_root)?.lsp.trace_file?;
+    Some(workspace_root.join(path))
+}
+
+pub fn load_server_config(workspace_root: &Path) -> HashMap<LanguageId, Vec<ServerConfig>> {
+    let mut map = default_servers();
+    if let Some(parsed) = read_languages_config(workspace_root) {
+        map.extend(servers_from_config(parsed.lsp));
+    }
+    map
+}
 
-    for (language_name, server) in parsed.lsp.servers {
+fn servers_from_config(section: LspSection) -> HashMap<LanguageId, Vec<ServerConfig>> {
+    let mut map = HashMap::new();
+    for (language_name, entries) in section.servers {
         let Some(language) = language_name_to_id(&language_name) else {
             continue;
         };
-        map.insert(
-            language,
-            ServerConfig {
-                language_id: language_name,
+        let entries = match entries {
+            ServerEntries::One(server) => vec![server],
+            ServerEntries::Many(servers) => servers,
+        };
+        let servers = entries
+            .into_iter()
+            .map(|server| ServerConfig {
+                name: server.name.unwrap_or_else(|| server.command.clone()),
+                language_id: language_name.clone(),
                 command: server.command,
                 args: server.args,
                 initialization_options: server.initialization_options,
-            },
-        );
+                features: server.features,
+                formatter: server.formatter,
+            })
+            .collect();
+        map.insert(language, servers);
     }
     map
 }
@@ -78,56 +112,81 @@ fn language_name_to_id(name: &str) -> Option<LanguageId> {
     }
 }
 
-fn default_servers() -> HashMap<LanguageId, ServerConfig> {
-    let mut map = HashMap::new();
-    map.insert(
-        LanguageId::Rust,
-        ServerConfig {
-            language_id: String::from("rust"),
-            command: String::from("rust-analyzer"),
-            args: Vec::new(),
-            initialization_options: Some(serde_json::json!({
-                "cargo": {
-                    "autoreload": true,
-                    "buildScripts": {
-                        "enable": true
-                    }
-                },
-                "procMacro": {
-                    "enable": true
-                },
-                "checkOnSave": {
-                    "enable": true
-                }
-            })),
+fn default_server(language_id: &str, command: &str, args: &[&str]) -> ServerConfig {
+    ServerConfig {
+        name: command.to_owned(),
+        language_id: language_id.to_owned(),
+        command: command.to_owned(),
+        args: args.iter().map(|arg| (*arg).to_owned()).collect(),
+        initialization_options: None,
+        features: None,
+        formatter: false,
+    }
+}
+
+fn default_servers() -> HashMap<LanguageId, Vec<ServerConfig>> {
+    let mut rust = default_server("rust", "rust-analyzer", &[]);
+    rust.initialization_options = Some(serde_json::json!({
+        "cargo": {
+            "autoreload": true,
+            "buildScripts": {
+                "enable": true
+            }
         },
-    );
-    map.insert(
-        LanguageId::Python,
-        ServerConfig {
-            language_id: String::from("python"),
-            command: String::from("pylsp"),
-            args: Vec::new(),
-            initialization_options: None,
+        "procMacro": {
+            "enable": true
         },
-    );
+        "checkOnSave": {
+            "enable": true
+        }
+    }));
+    let mut map = HashMap::new();
+    map.insert(LanguageId::Rust, vec![rust]);
+    map.insert(LanguageId::Python, vec![default_server("python", "pylsp", &[])]);
     map.insert(
         LanguageId::TypeScript,
-        ServerConfig {
-            language_id: String::from("typescript"),
-            command: String::from("typescript-language-server"),
-            args: vec![String::from("--stdio")],
-            initialization_options: None,
-        },
+        vec![default_server("typescript", "typescript-language-server", &["--stdio"])],
     );
     map.insert(
         LanguageId::JavaScript,
-        ServerConfig {
-            language_id: String::from("javascript"),
-            command: String::from("typescript-language-server"),
-            args: vec![String::from("--stdio")],
-            initialization_options: None,
-        },
+        vec![default_server("javascript", "typescript-language-server", &["--stdio"])],
     );
     map
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{LanguagesConfig, servers_from_config};
+    use crate::lsp::client::LspFeature;
+    use crate::syntax::LanguageId;
+
+    #[test]
+    fn servers_accept_a_single_table_or_an_ordered_list() {
+        let text = r#"
+            [lsp.servers.rust]
+            command = "rust-analyzer"
+
+            [[lsp.servers.python]]
+            command = "pyright-langserver"
+            args = ["--stdio"]
+
+            [[lsp.servers.python]]
+            name = "ruff"
+            command = "ruff"
+            args = ["server"]
+            features = ["diagnostics", "formatting", "code-action"]
+            formatter = true
+        "#;
+        let parsed: LanguagesConfig =
+            toml::from_str(text).unwrap_or_else(|error| panic!("{error}"));
+        let servers = servers_from_config(parsed.lsp);
+        assert_eq!(servers[&LanguageId::Rust].len(), 1);
+        let python = &servers[&LanguageId::Python];
+        assert_eq!(python[0].name, "pyright-langserver");
+        assert!(python[0].handles(LspFeature::Hover));
+        assert_eq!(python[1].name, "ruff");
+        assert!(python[1].formatter);
+        assert!(!python[1].handles(LspFeature::Completion));
+        assert!(python[1].handles(LspFeature::Diagnostics));
+    }
+}
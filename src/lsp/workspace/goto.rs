@@ -0,0 +1,78 @@
+use std::path::Path;
+
+use crate::core::Cursor;
+use crate::lsp::goto::{GotoKind, parse_locations};
+use crate::lsp::position::LineSource;
+use crate::lsp::workspace::LspWorkspace;
+use crate::lsp::workspace::pending::{LspResponse, PendingRequest, RequestKind};
+
+impl LspWorkspace {
+    /// Sends a definition, declaration, type definition or implementation request.
+    pub fn request_goto(
+        &mut self,
+        kind: GotoKind,
+        path: &Path,
+        workspace_root: &Path,
+        cursor: Cursor,
+        lines: &dyn LineSource,
+    ) -> bool {
+        let kind = match kind {
+            GotoKind::Definition => RequestKind::Definition,
+            GotoKind::Declaration => RequestKind::Declaration,
+            GotoKind::TypeDefinition => RequestKind::TypeDefinition,
+            GotoKind::Implementation => RequestKind::Implementation,
+        };
+        self.request_at_cursor(
+            kind,
+            path,
+            workspace_root,
+            cursor,
+            lines,
+            serde_json::Map::new(),
+        )
+    }
+
+    pub fn request_references(
+        &mut self,
+        path: &Path,
+        workspace_root: &Path,
+        cursor: Cursor,
+        lines: &dyn LineSource,
+    ) -> bool {
+        let mut extra = serde_json::Map::new();
+        extra.insert(
+            String::from("context"),
+            serde_json::json!({ "includeDeclaration": true }),
+        );
+        self.request_at_cursor(
+            RequestKind::References,
+            path,
+            workspace_root,
+            cursor,
+            lines,
+            extra,
+        )
+    }
+
+    /// Queues the locations a goto or references request found.
+    pub(super) fn handle_goto_response(
+        &mut self,
+        request: &PendingRequest,
+        result: Result<serde_json::Value, String>,
+        lines: &dyn LineSource,
+    ) {
+        let value = result.unwrap_or(serde_json::Value::Null);
+        let locations = parse_locations(&value, request.encoding, lines);
+        let target = match request.kind {
+            RequestKind::Definition => "definition",
+            RequestKind::Declaration => "declaration",
+            RequestKind::TypeDefinition => "type definition",
+            RequestKind::Implementation => "implementation",
+            _ => {
+                self.responses.push(LspResponse::References(locations));
+                return;
+            }
+        };
+        self.responses.push(LspResponse::Goto { target, locations });
+    }
+}
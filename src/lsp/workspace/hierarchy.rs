@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::core::Cursor;
+use crate::lsp::hierarchy::{HierarchyDirection, parse_hierarchy_items};
+use crate::lsp::position::LineSource;
+use crate::lsp::workspace::LspWorkspace;
+use crate::lsp::workspace::pending::{LspResponse, PendingRequest, RequestKind};
+use crate::lsp::workspace::routing::ServerId;
+use crate::lsp::workspace::state::parse_file_uri;
+
+/// Nodes of the hierarchy being browsed.
+#[derive(Debug, Default)]
+pub(super) struct HierarchyState {
+    direction: HierarchyDirection,
+    /// Server and raw item behind each hierarchy node id.
+    items: HashMap<usize, (ServerId, serde_json::Value)>,
+    /// Node whose children an in-flight request loads.
+    requests: HashMap<(ServerId, u64), usize>,
+    next_id: usize,
+}
+
+impl LspWorkspace {
+    /// Prepares a call or type hierarchy at the cursor; children are loaded
+    /// per node through `request_hierarchy_children`.
+    pub fn request_hierarchy(
+        &mut self,
+        direction: HierarchyDirection,
+        path: &Path,
+        workspace_root: &Path,
+        cursor: Cursor,
+        lines: &dyn LineSource,
+    ) -> bool {
+        let kind = if direction.is_call() {
+            RequestKind::PrepareCallHierarchy
+        } else {
+            RequestKind::PrepareTypeHierarchy
+        };
+        self.hierarchy.direction = direction;
+        self.hierarchy.items.clear();
+        self.hierarchy.requests.clear();
+        self.cancel_where(|request| request.kind.feature() == kind.feature());
+        self.request_at_cursor(kind, path, workspace_root, cursor, lines, serde_json::Map::new())
+    }
+
+    pub fn request_hierarchy_children(&mut self, node: usize) -> bool {
+        let Some((id, item)) = self.hierarchy.items.get(&node).cloned() else {
+            return false;
+        };
+        let kind = match self.hierarchy.direction {
+            HierarchyDirection::IncomingCalls => RequestKind::IncomingCalls,
+            HierarchyDirection::OutgoingCalls => RequestKind::OutgoingCalls,
+            HierarchyDirection::Supertypes => RequestKind::Supertypes,
+            HierarchyDirection::Subtypes => RequestKind::Subtypes,
+        };
+        let params = serde_json::json!({ "item": item });
+        let Some(request_id) = self.send_tracked_request(kind, id, None, Cursor::new(0, 0), params)
+        else {
+            return false;
+        };
+        self.hierarchy.requests.insert((id, request_id), node);
+        true
+    }
+
+    /// Gives each parsed item an id and keeps its raw form for later requests.
+    pub(super) fn store_hierarchy_items(
+        &mut self,
+        id: ServerId,
+        request: &PendingRequest,
+        parent: Option<usize>,
+        value: &serde_json::Value,
+        lines: &dyn LineSource,
+    ) {
+        let direction = self.hierarchy.direction;
+        let parent_path = parent
+            .and_then(|node| self.hierarchy.items.get(&node))
+            .and_then(|(_, raw)| raw.get("uri")?.as_str())
+            .and_then(parse_file_uri);
+        let children = parent.map(|_| direction);
+        let line_text = |path: &Path, line| lines.line_text(path, line);
+        let parsed = parse_hierarchy_items(
+            value,
+            children,
+            parent_path.as_deref(),
+            request.encoding,
+            line_text,
+        );
+        let mut items = Vec::with_capacity(parsed.len());
+        for (mut item, raw) in parsed {
+            self.hierarchy.next_id += 1;
+            item.id = self.hierarchy.next_id;
+            self.hierarchy.items.insert(item.id, (id, raw));
+            items.push(item);
+        }
+        self.responses.push(LspResponse::Hierarchy {
+            direction,
+            parent,
+            items,
+        });
+    }
+
+    /// Node whose children the answer to `request_id` holds, if any.
+    pub(super) fn take_hierarchy_parent(&mut self, id: ServerId, request_id: u64) -> Option<usize> {
+        self.hierarchy.requests.remove(&(id, request_id))
+    }
+}
@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+
+use crate::core::Cursor;
+use crate::lsp::client::LspFeature;
+use crate::lsp::inlay_hint::{InlayHintView, parse_tooltip};
+use crate::lsp::position::LineSource;
+use crate::lsp::workspace::LspWorkspace;
+use crate::lsp::workspace::pending::{LspResponse, PendingRequest, RequestKind};
+use crate::lsp::workspace::routing::ServerId;
+use crate::lsp::workspace::state::file_uri;
+
+/// Inlay hints for one version of a document, sorted by position.
+#[derive(Debug)]
+struct InlayHintCache {
+    version: i32,
+    server: ServerId,
+    /// Parsed hints next to the raw `InlayHint` sent back for resolving.
+    hints: Vec<(InlayHintView, serde_json::Value)>,
+}
+
+/// Cached hints per document and the ranges already asked for.
+#[derive(Debug)]
+pub(super) struct InlayHintState {
+    cache: HashMap<PathBuf, InlayHintCache>,
+    /// Version and line range of the last inlay hint request per document.
+    requests: HashMap<PathBuf, (i32, RangeInclusive<usize>)>,
+    enabled: bool,
+}
+
+impl Default for InlayHintState {
+    fn default() -> Self {
+        Self {
+            cache: HashMap::new(),
+            requests: HashMap::new(),
+            enabled: true,
+        }
+    }
+}
+
+impl InlayHintState {
+    pub(super) fn forget(&mut self, path: &Path) {
+        self.cache.remove(path);
+        self.requests.remove(path);
+    }
+}
+
+impl LspWorkspace {
+    pub fn inlay_hints_enabled(&self) -> bool {
+        self.inlay_hints.enabled
+    }
+
+    pub fn set_inlay_hints_enabled(&mut self, enabled: bool) {
+        self.inlay_hints.enabled = enabled;
+        if !enabled {
+            self.inlay_hints.cache.clear();
+            self.inlay_hints.requests.clear();
+            self.cancel_kind(RequestKind::InlayHints);
+        }
+    }
+
+    /// Requests hints for `visible` plus a screen of margin either side,
+    /// unless the last request for this version already covers it.
+    pub fn request_inlay_hints(
+        &mut self,
+        path: &Path,
+        visible: RangeInclusive<usize>,
+        line_count: usize,
+        lines: &dyn LineSource,
+    ) {
+        if !self.inlay_hints.enabled || !self.progress.done {
+            return;
+        }
+        let version = self.document_version(path);
+        if self.inlay_hints.requests.get(path).is_some_and(|(requested, range)| {
+            *requested == version
+                && range.contains(visible.start())
+                && range.contains(visible.end())
+        }) {
+            return;
+        }
+        let Some(id) = self.route(path, LspFeature::InlayHints) else {
+            return;
+        };
+        let margin = visible.end() - visible.start() + 1;
+        let first = visible.start().saturating_sub(margin);
+        let last = (visible.end() + margin).min(line_count.saturating_sub(1));
+        let last_length = lines
+            .line_text(path, last)
+            .map_or(0, |text| text.chars().count());
+        let params = serde_json::json!({
+            "textDocument": { "uri": file_uri(path) },
+            "range": {
+                "start": { "line": first, "character": 0 },
+                "end": self.lsp_position(id, path, Cursor::new(last, last_length), lines)
+            }
+        });
+        self.inlay_hints.requests.insert(path.to_path_buf(), (version, first..=last));
+        self.cancel_where(|request| {
+            request.kind == RequestKind::InlayHints && request.path.as_deref() == Some(path)
+        });
+        let start = Cursor::new(first, 0);
+        self.send_request(RequestKind::InlayHints, id, Some(path), start, params);
+    }
+
+    /// Hints to draw on `line`; empty once the document changed since they
+    /// were computed.
+    pub fn inlay_hints_for_line(&self, path: &Path, line: usize) -> Vec<&InlayHintView> {
+        let version = self.document_version(path);
+        let Some(cache) = self
+            .inlay_hints
+            .cache
+            .get(path)
+            .filter(|cache| self.inlay_hints.enabled && cache.version == version)
+        else {
+            return Vec::new();
+        };
+        let start = cache.hints.partition_point(|(hint, _)| hint.line < line);
+        cache.hints[start..]
+            .iter()
+            .take_while(|(hint, _)| hint.line == line)
+            .map(|(hint, _)| hint)
+            .collect()
+    }
+
+    fn store_inlay_hints(
+        &mut self,
+        id: ServerId,
+        request: &PendingRequest,
+        value: &serde_json::Value,
+        lines: &dyn LineSource,
+    ) {
+        let Some(path) = request.path.clone() else {
+            return;
+        };
+        let mut hints = value
+            .as_array()
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|raw| {
+                        let line_text = |line| lines.line_text(&path, line);
+                        let hint = InlayHintView::parse(raw, request.encoding, line_text)?;
+                        Some((hint, raw.clone()))
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        hints.sort_by_key(|(hint, _)| (hint.line, hint.column));
+        self.inlay_hints.cache.insert(
+            path,
+            InlayHintCache {
+                version: request.version,
+                server: id,
+                hints,
+            },
+        );
+    }
+
+    /// Shows the tooltip of the hint closest to `cursor` on its line in the
+    /// hover popup, sending `inlayHint/resolve` first if the server defers it.
+    pub fn request_inlay_hint_tooltip(&mut self, path: &Path, cursor: Cursor) -> bool {
+        let version = self.document_version(path);
+        let Some(cache) = self
+            .inlay_hints
+            .cache
+            .get(path)
+            .filter(|cache| cache.version == version)
+        else {
+            return false;
+        };
+        let Some((hint, raw)) = cache
+            .hints
+            .iter()
+            .filter(|(hint, _)| hint.line == cursor.line)
+            .min_by_key(|(hint, _)| hint.column.abs_diff(cursor.column))
+            .cloned()
+        else {
+            return false;
+        };
+        let position = Cursor::new(hint.line, hint.column);
+        if let Some(tooltip) = hint.tooltip {
+            self.show_inlay_tooltip(tooltip, position);
+            return true;
+        }
+        let server = cache.server;
+        let resolve = self
+            .clients
+            .get(&server)
+            .is_some_and(|client| client.capabilities.inlay_hint_resolve);
+        resolve && self.dispatch(RequestKind::ResolveInlayHint, server, Some(path), position, raw)
+    }
+
+    fn show_inlay_tooltip(&mut self, tooltip: String, position: Cursor) {
+        self.hover.visible = true;
+        self.hover.title = String::from("Inlay hint");
+        self.hover.contents = tooltip;
+        self.hover.line = position.line;
+        self.hover.column = position.column;
+    }
+
+    /// Caches the hints of a document, or shows a resolved hint's tooltip.
+    pub(super) fn handle_inlay_hint_response(
+        &mut self,
+        id: ServerId,
+        request: &PendingRequest,
+        result: Result<serde_json::Value, String>,
+        lines: &dyn LineSource,
+    ) {
+        if request.kind == RequestKind::InlayHints {
+            if let Ok(value) = result {
+                self.store_inlay_hints(id, request, &value, lines);
+            }
+            return;
+        }
+        match result.ok().as_ref().and_then(|value| value.get("tooltip")) {
+            Some(tooltip) => {
+                let tooltip = parse_tooltip(tooltip).unwrap_or_default();
+                self.show_inlay_tooltip(tooltip, request.cursor);
+            }
+            None => self.responses.push(LspResponse::ShowMessage {
+                message: String::from("Inlay hint has no tooltip"),
+                level: 3,
+            }),
+        }
+    }
+}
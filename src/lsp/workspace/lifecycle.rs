@@ -0,0 +1,119 @@
+use std::sync::mpsc::Receiver;
+use std::time::{Duration, Instant};
+
+use crate::lsp::client::LspClient;
+
+/// Restarts given up on after this many consecutive failures.
+pub(super) const MAX_RESTART_ATTEMPTS: u32 = 5;
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+/// A server that stayed up this long starts its backoff from scratch.
+const HEALTHY_UPTIME: Duration = Duration::from_secs(60);
+
+/// Backoff bookkeeping for a server that crashed or failed to launch.
+#[derive(Debug, Clone)]
+pub(super) struct RestartState {
+    pub attempts: u32,
+    pub next_attempt: Instant,
+    pub last_error: String,
+}
+
+impl RestartState {
+    pub(super) fn record_failure(previous: Option<&Self>, uptime: Duration, error: String) -> Self {
+        let attempts = match previous {
+            Some(state) if uptime < HEALTHY_UPTIME => state.attempts + 1,
+            _ => 1,
+        };
+        Self {
+            attempts,
+            next_attempt: Instant::now() + backoff(attempts),
+            last_error: error,
+        }
+    }
+
+    pub(super) fn gave_up(&self) -> bool {
+        self.attempts >= MAX_RESTART_ATTEMPTS
+    }
+
+    pub(super) fn ready(&self, now: Instant) -> bool {
+        !self.gave_up() && now >= self.next_attempt
+    }
+}
+
+/// A server starting on a background thread.
+#[derive(Debug)]
+pub(super) struct PendingLaunch {
+    pub result: Receiver<Result<LspClient, String>>,
+    /// Report a failure to the user, as for an explicit restart.
+    pub announce: bool,
+}
+
+/// 1s, 2s, 4s, ... capped at thirty seconds.
+fn backoff(attempts: u32) -> Duration {
+    Duration::from_secs(1u64 << attempts.saturating_sub(1).min(5)).min(MAX_BACKOFF)
+}
+
+pub(super) fn format_uptime(uptime: Duration) -> String {
+    let seconds = uptime.as_secs();
+    match seconds {
+        0..60 => format!("{seconds}s"),
+        60..3600 => format!("{}m{:02}s", seconds / 60, seconds % 60),
+        _ => format!("{}h{:02}m", seconds / 3600, seconds / 60 % 60),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{RestartState, backoff, format_uptime};
+    use crate::lsp::workspace::{LspResponse, LspWorkspace};
+    use crate::syntax::LanguageId;
+
+    #[test]
+    fn backoff_grows_until_giving_up() {
+        assert_eq!(backoff(1), Duration::from_secs(1));
+        assert_eq!(backoff(3), Duration::from_secs(4));
+        assert_eq!(backoff(9), Duration::from_secs(30));
+
+        let mut state = RestartState::record_failure(None, Duration::ZERO, String::from("exit 1"));
+        for _ in 0..4 {
+            assert!(!state.gave_up());
+            state = RestartState::record_failure(Some(&state), Duration::ZERO, String::new());
+        }
+        assert!(state.gave_up());
+        let uptime = Duration::from_secs(90);
+        let healthy = RestartState::record_failure(Some(&state), uptime, String::new());
+        assert_eq!(healthy.attempts, 1);
+        assert_eq!(format_uptime(Duration::from_secs(125)), "2m05s");
+    }
+
+    #[test]
+    fn restart_launches_in_the_background_and_reports_failure() {
+        let root = tempfile::tempdir().unwrap_or_else(|e| panic!("{e}"));
+        let assets = root.path().join("assets");
+        std::fs::create_dir_all(&assets).unwrap_or_else(|e| panic!("{e}"));
+        let config = "[lsp.servers.rust]\nname = \"ghost\"\ncommand = \"codx-no-such-server\"\n";
+        std::fs::write(assets.join("languages.toml"), config).unwrap_or_else(|e| panic!("{e}"));
+
+        let mut workspace = LspWorkspace::new(root.path());
+        assert_eq!(workspace.restart_server(LanguageId::Rust), 1);
+        assert!(workspace.status_items()[0].title.ends_with("starting"));
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let notice = loop {
+            workspace.poll_server_messages(&[]);
+            let notice = workspace.take_responses().into_iter().find_map(|response| {
+                match response {
+                    LspResponse::ServerNotice(notice) => Some(notice),
+                    _ => None,
+                }
+            });
+            if let Some(notice) = notice {
+                break notice;
+            }
+            assert!(Instant::now() < deadline, "launch never finished");
+            std::thread::sleep(Duration::from_millis(10));
+        };
+        assert!(notice.starts_with("ghost failed to start"), "{notice}");
+        assert!(workspace.status_items()[0].title.contains("restarting in"));
+    }
+}
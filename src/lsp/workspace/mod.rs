@@ -1,6 +1,18 @@
 mod app_actions;
+mod code_actions;
+mod completions;
 mod config;
 mod discovery;
+mod goto;
+mod hierarchy;
+mod inlay_hints;
+mod lifecycle;
+mod pending;
+mod routing;
+mod semantic_tokens;
 mod state;
+mod symbols;
 
+pub use pending::{CodeActionRun, LspResponse, ServerReply};
 pub use state::LspWorkspace;
+pub(crate) use state::parse_file_uri;
@@ -0,0 +1,218 @@
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use lsp_types::TextEdit;
+
+use crate::core::Cursor;
+use crate::lsp::client::LspFeature;
+use crate::lsp::code_action::CodeActionView;
+use crate::lsp::document_highlight::DocumentHighlight;
+use crate::lsp::goto::Location;
+use crate::lsp::hierarchy::{HierarchyDirection, HierarchyItem};
+use crate::lsp::position::PositionEncoding;
+use crate::lsp::workspace::routing::ServerId;
+use crate::lsp::workspace_edit::WorkspaceEditPlan;
+use crate::ui::PickerItem;
+
+/// In-flight requests older than this are cancelled and reported as timed out.
+pub(super) const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum RequestKind {
+    Completion,
+    ResolveCompletion,
+    Hover,
+    SignatureHelp,
+    Definition,
+    Declaration,
+    TypeDefinition,
+    Implementation,
+    References,
+    CodeActions,
+    /// Background code action query behind the gutter lightbulb.
+    CodeActionHint,
+    ResolveCodeAction,
+    ExecuteCommand,
+    Formatting,
+    Rename,
+    WorkspaceSymbols,
+    InlayHints,
+    ResolveInlayHint,
+    SemanticTokensFull,
+    /// Full tokens sent as edits to the previous result.
+    SemanticTokensDelta,
+    SemanticTokensRange,
+    DocumentSymbols,
+    DocumentHighlights,
+    PrepareCallHierarchy,
+    IncomingCalls,
+    OutgoingCalls,
+    PrepareTypeHierarchy,
+    Supertypes,
+    Subtypes,
+}
+
+impl RequestKind {
+    pub(super) fn method(self) -> &'static str {
+        match self {
+            Self::Completion => "textDocument/completion",
+            Self::ResolveCompletion => "completionItem/resolve",
+            Self::Hover => "textDocument/hover",
+            Self::SignatureHelp => "textDocument/signatureHelp",
+            Self::Definition => "textDocument/definition",
+            Self::Declaration => "textDocument/declaration",
+            Self::TypeDefinition => "textDocument/typeDefinition",
+            Self::Implementation => "textDocument/implementation",
+            Self::References => "textDocument/references",
+            Self::CodeActions | Self::CodeActionHint => "textDocument/codeAction",
+            Self::ResolveCodeAction => "codeAction/resolve",
+            Self::ExecuteCommand => "workspace/executeCommand",
+            Self::Formatting => "textDocument/formatting",
+            Self::Rename => "textDocument/rename",
+            Self::WorkspaceSymbols => "workspace/symbol",
+            Self::InlayHints => "textDocument/inlayHint",
+            Self::ResolveInlayHint => "inlayHint/resolve",
+            Self::SemanticTokensFull => "textDocument/semanticTokens/full",
+            Self::SemanticTokensDelta => "textDocument/semanticTokens/full/delta",
+            Self::SemanticTokensRange => "textDocument/semanticTokens/range",
+            Self::DocumentSymbols => "textDocument/documentSymbol",
+            Self::DocumentHighlights => "textDocument/documentHighlight",
+            Self::PrepareCallHierarchy => "textDocument/prepareCallHierarchy",
+            Self::IncomingCalls => "callHierarchy/incomingCalls",
+            Self::OutgoingCalls => "callHierarchy/outgoingCalls",
+            Self::PrepareTypeHierarchy => "textDocument/prepareTypeHierarchy",
+            Self::Supertypes => "typeHierarchy/supertypes",
+            Self::Subtypes => "typeHierarchy/subtypes",
+        }
+    }
+
+    pub(super) fn feature(self) -> LspFeature {
+        match self {
+            Self::Completion | Self::ResolveCompletion => LspFeature::Completion,
+            Self::Hover => LspFeature::Hover,
+            Self::SignatureHelp => LspFeature::SignatureHelp,
+            Self::Definition => LspFeature::Definition,
+            Self::Declaration => LspFeature::Declaration,
+            Self::TypeDefinition => LspFeature::TypeDefinition,
+            Self::Implementation => LspFeature::Implementation,
+            Self::References => LspFeature::References,
+            Self::CodeActions
+            | Self::CodeActionHint
+            | Self::ResolveCodeAction
+            | Self::ExecuteCommand => LspFeature::CodeAction,
+            Self::Formatting => LspFeature::Formatting,
+            Self::Rename => LspFeature::Rename,
+            Self::WorkspaceSymbols => LspFeature::WorkspaceSymbols,
+            Self::InlayHints | Self::ResolveInlayHint => LspFeature::InlayHints,
+            Self::SemanticTokensFull | Self::SemanticTokensDelta | Self::SemanticTokensRange => {
+                LspFeature::SemanticTokens
+            }
+            Self::DocumentSymbols => LspFeature::DocumentSymbols,
+            Self::DocumentHighlights => LspFeature::DocumentHighlight,
+            Self::PrepareCallHierarchy | Self::IncomingCalls | Self::OutgoingCalls => {
+                LspFeature::CallHierarchy
+            }
+            Self::PrepareTypeHierarchy | Self::Supertypes | Self::Subtypes => {
+                LspFeature::TypeHierarchy
+            }
+        }
+    }
+
+    /// Requests fired while typing time out quietly.
+    pub(super) fn is_automatic(self) -> bool {
+        matches!(
+            self,
+            Self::Completion
+                | Self::ResolveCompletion
+                | Self::SignatureHelp
+                | Self::CodeActionHint
+                | Self::InlayHints
+                | Self::SemanticTokensFull
+                | Self::SemanticTokensDelta
+                | Self::SemanticTokensRange
+                | Self::DocumentSymbols
+                | Self::DocumentHighlights
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct PendingRequest {
+    pub kind: RequestKind,
+    /// Document the request is about; `None` for workspace-wide requests.
+    pub path: Option<PathBuf>,
+    pub version: i32,
+    pub cursor: Cursor,
+    pub encoding: PositionEncoding,
+    pub started: Instant,
+}
+
+/// Identifies a server request that is waiting for the app's answer.
+#[derive(Debug, Clone, Copy)]
+pub struct ServerReply {
+    pub(super) server: ServerId,
+    pub(super) id: u64,
+}
+
+/// A code action the server listed, kept so the menu can run it later.
+#[derive(Debug, Clone)]
+pub(super) struct CodeActionEntry {
+    pub server: ServerId,
+    /// The `CodeAction` or `Command` literal, sent back for resolving.
+    pub raw: serde_json::Value,
+    pub view: CodeActionView,
+}
+
+/// A chosen code action, resolved and ready to apply.
+#[derive(Debug, Clone)]
+pub struct CodeActionRun {
+    pub(super) server: ServerId,
+    pub(super) command: Option<lsp_types::Command>,
+    pub title: String,
+    pub edit: WorkspaceEditPlan,
+}
+
+/// Result of a finished request that the app has to act on.
+#[derive(Debug, Clone)]
+pub enum LspResponse {
+    /// Targets of a definition, declaration, type definition or
+    /// implementation request, named by `target`.
+    Goto {
+        target: &'static str,
+        locations: Vec<Location>,
+    },
+    References(Vec<Location>),
+    /// The code action menu was refreshed; read it through `code_actions()`.
+    CodeActions,
+    CodeAction(CodeActionRun),
+    Formatting {
+        path: PathBuf,
+        edits: Vec<TextEdit>,
+        encoding: PositionEncoding,
+    },
+    Rename(WorkspaceEditPlan),
+    WorkspaceSymbols(Vec<PickerItem>),
+    /// Prepared roots when `parent` is `None`, otherwise that node's children.
+    Hierarchy {
+        direction: HierarchyDirection,
+        parent: Option<usize>,
+        items: Vec<HierarchyItem>,
+    },
+    /// Occurrences of the symbol at `cursor`, the start of the name asked about.
+    DocumentHighlights {
+        path: PathBuf,
+        cursor: Cursor,
+        highlights: Vec<DocumentHighlight>,
+    },
+    TimedOut(&'static str),
+    /// Server crashed, restarted or gave up.
+    ServerNotice(String),
+    /// `workspace/applyEdit` from a server, answered through `reply`.
+    ApplyEdit {
+        reply: ServerReply,
+        label: Option<String>,
+        edit: WorkspaceEditPlan,
+    },
+    /// `window/showMessage`; `level` is the LSP `MessageType` number.
+    ShowMessage { message: String, level: u64 },
+}
@@ -0,0 +1,20 @@
+use crate::syntax::LanguageId;
+
+/// One configured server: its language and its place in that language's
+/// ordered server list. Slot 0 is the primary server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(super) struct ServerId {
+    pub language: LanguageId,
+    pub slot: usize,
+}
+
+impl ServerId {
+    pub(super) fn new(language: LanguageId, slot: usize) -> Self {
+        Self { language, slot }
+    }
+
+    /// Stable order for status listings and workspace-wide requests.
+    pub(super) fn sort_key(self) -> (String, usize) {
+        (format!("{:?}", self.language), self.slot)
+    }
+}
@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+
+use crate::core::Cursor;
+use crate::lsp::client::LspFeature;
+use crate::lsp::position::LineSource;
+use crate::lsp::semantic_tokens::{SemanticLine, apply_delta, decode, token_data};
+use crate::lsp::workspace::LspWorkspace;
+use crate::lsp::workspace::pending::{PendingRequest, RequestKind};
+use crate::lsp::workspace::routing::ServerId;
+use crate::lsp::workspace::state::file_uri;
+
+/// Semantic tokens of one document, decoded per line.
+#[derive(Debug)]
+struct SemanticTokenCache {
+    server: ServerId,
+    /// `resultId` of the last full result; `data` is the base for deltas.
+    result_id: Option<String>,
+    data: Vec<u32>,
+    lines: HashMap<usize, SemanticLine>,
+}
+
+/// Decoded tokens per document and the ranges already asked for.
+#[derive(Debug, Default)]
+pub(super) struct SemanticTokenState {
+    cache: HashMap<PathBuf, SemanticTokenCache>,
+    /// Version and line range of the last semantic token request per document.
+    requests: HashMap<PathBuf, (i32, RangeInclusive<usize>)>,
+}
+
+impl SemanticTokenState {
+    pub(super) fn forget(&mut self, path: &Path) {
+        self.cache.remove(path);
+        self.requests.remove(path);
+    }
+}
+
+impl LspWorkspace {
+    /// Requests semantic tokens for a new document version: a delta against
+    /// the last full result when the server supports it, then full tokens,
+    /// and `visible` plus a screen of margin from range-only servers.
+    pub fn request_semantic_tokens(
+        &mut self,
+        path: &Path,
+        visible: RangeInclusive<usize>,
+        line_count: usize,
+        lines: &dyn LineSource,
+    ) {
+        if !self.progress.done {
+            return;
+        }
+        let version = self.document_version(path);
+        if self.semantic_tokens.requests.get(path).is_some_and(|(requested, range)| {
+            *requested == version
+                && range.contains(visible.start())
+                && range.contains(visible.end())
+        }) {
+            return;
+        }
+        let Some(id) = self.route(path, LspFeature::SemanticTokens) else {
+            return;
+        };
+        let Some(support) = self
+            .clients
+            .get(&id)
+            .and_then(|client| client.capabilities.semantic_tokens.clone())
+        else {
+            return;
+        };
+        let document = serde_json::json!({ "uri": file_uri(path) });
+        let previous = self
+            .semantic_tokens
+            .cache
+            .get(path)
+            .filter(|cache| support.delta && cache.server == id)
+            .and_then(|cache| cache.result_id.clone());
+        let mut first = 0;
+        let (kind, params, last) = match previous {
+            Some(previous) if support.full => {
+                let params = serde_json::json!({
+                    "textDocument": document,
+                    "previousResultId": previous
+                });
+                (RequestKind::SemanticTokensDelta, params, usize::MAX)
+            }
+            _ if support.full => {
+                let params = serde_json::json!({ "textDocument": document });
+                (RequestKind::SemanticTokensFull, params, usize::MAX)
+            }
+            _ if support.range => {
+                let margin = visible.end() - visible.start() + 1;
+                first = visible.start().saturating_sub(margin);
+                let last = (visible.end() + margin).min(line_count.saturating_sub(1));
+                let last_length = lines
+                    .line_text(path, last)
+                    .map_or(0, |text| text.trim_end_matches(['\r', '\n']).chars().count());
+                let params = serde_json::json!({
+                    "textDocument": document,
+                    "range": {
+                        "start": { "line": first, "character": 0 },
+                        "end": self.lsp_position(id, path, Cursor::new(last, last_length), lines)
+                    }
+                });
+                (RequestKind::SemanticTokensRange, params, last)
+            }
+            _ => return,
+        };
+        self.semantic_tokens.requests.insert(path.to_path_buf(), (version, first..=last));
+        self.cancel_where(|request| {
+            request.kind.feature() == LspFeature::SemanticTokens
+                && request.path.as_deref() == Some(path)
+        });
+        self.send_request(kind, id, Some(path), Cursor::new(first, 0), params);
+    }
+
+    /// Semantic spans for `line`, provided its text still hashes to `hash`.
+    pub fn semantic_line(&self, path: &Path, line: usize, hash: u64) -> Option<&SemanticLine> {
+        self.semantic_tokens
+            .cache
+            .get(path)?
+            .lines
+            .get(&line)
+            .filter(|semantic| semantic.hash == hash)
+    }
+
+    pub(super) fn store_semantic_tokens(
+        &mut self,
+        id: ServerId,
+        request: &PendingRequest,
+        value: &serde_json::Value,
+        lines: &dyn LineSource,
+    ) {
+        let Some(path) = request.path.clone() else {
+            return;
+        };
+        let Some(legend) = self
+            .clients
+            .get(&id)
+            .and_then(|client| client.capabilities.semantic_tokens.as_ref())
+            .map(|support| support.legend.clone())
+        else {
+            return;
+        };
+        let data = match value.get("edits").and_then(serde_json::Value::as_array) {
+            Some(edits) => {
+                let mut data = self
+                    .semantic_tokens
+                    .cache
+                    .get(&path)
+                    .filter(|cache| cache.server == id)
+                    .map(|cache| cache.data.clone());
+                if !data.as_mut().is_some_and(|data| apply_delta(data, edits)) {
+                    // Lost the base the delta refers to; start over with full tokens.
+                    self.semantic_tokens.cache.remove(&path);
+                    self.semantic_tokens.requests.remove(&path);
+                    return;
+                }
+                data.unwrap_or_default()
+            }
+            None => {
+                let Some(data) = token_data(value) else {
+                    return;
+                };
+                data
+            }
+        };
+        let line_text = |line| lines.line_text(&path, line);
+        let decoded = decode(&data, &legend, request.encoding, line_text);
+        // Range results cannot serve as a delta base.
+        let full = request.kind != RequestKind::SemanticTokensRange;
+        let result_id = value
+            .get("resultId")
+            .and_then(serde_json::Value::as_str)
+            .filter(|_| full)
+            .map(ToOwned::to_owned);
+        self.semantic_tokens.cache.insert(
+            path,
+            SemanticTokenCache {
+                server: id,
+                result_id,
+                data: if full { data } else { Vec::new() },
+                lines: decoded,
+            },
+        );
+    }
+}
@@ -1,48 +1,75 @@
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::TryRecvError;
+use std::time::{Duration, Instant};
 
-use lsp_types::Position;
+use lsp_types::{Position, TextDocumentContentChangeEvent, TextEdit};
 use tokio::runtime::{Builder, Runtime};
 
-use crate::lsp::client::LspClient;
-use crate::lsp::completion::{CompletionContext, CompletionItemView};
+use crate::app::BufferState;
+use crate::core::Cursor;
+use crate::lsp::capabilities::NegotiatedCapabilities;
+use crate::lsp::client::{LspClient, LspFeature, ServerConfig, TrafficLog};
+use crate::lsp::completion::CompletionContext;
 use crate::lsp::diagnostics::{DiagnosticCounts, DiagnosticItem, DiagnosticStore};
+use crate::lsp::document_highlight::parse_document_highlights;
 use crate::lsp::hover::HoverView;
+use crate::lsp::position::{BufferLines, LineSource, PositionEncoding};
 use crate::lsp::progress::ProgressState;
 use crate::lsp::signature::SignatureHelpView;
-use crate::lsp::workspace::config::load_server_config;
+use crate::lsp::workspace::code_actions::CodeActionState;
+use crate::lsp::workspace::completions::CompletionItemState;
+use crate::lsp::workspace::config::{load_server_config, load_trace_file};
 use crate::lsp::workspace::discovery::WorkspaceDiscovery;
+use crate::lsp::workspace::lifecycle::{
+    MAX_RESTART_ATTEMPTS, PendingLaunch, RestartState, format_uptime,
+};
+use crate::lsp::workspace::hierarchy::HierarchyState;
+use crate::lsp::workspace::inlay_hints::InlayHintState;
+use crate::lsp::workspace::pending::{
+    LspResponse, PendingRequest, REQUEST_TIMEOUT, RequestKind, ServerReply,
+};
+use crate::lsp::workspace::routing::ServerId;
+use crate::lsp::workspace::semantic_tokens::SemanticTokenState;
+use crate::lsp::workspace::symbols::{DocumentSymbolState, parse_workspace_symbols};
+use crate::lsp::workspace_edit::WorkspaceEditPlan;
 use crate::syntax::{language_for_path, LanguageId};
-use crate::ui::{PickerItem, PickerState};
-
-#[derive(Debug, Clone)]
-struct PendingRequest {
-    language: LanguageId,
-    request_id: u64,
-    path: std::path::PathBuf,
-    version: i32,
-    line: usize,
-    character: usize,
-}
+use crate::ui::PickerItem;
 
-#[derive(Debug, Clone)]
-struct PendingCompletionRequest {
-    request: PendingRequest,
-    trigger_column: usize,
+/// Edits made to one document since the last flush.
+#[derive(Debug, Default)]
+struct PendingSync {
+    /// Ranged changes, one list per encoding an incremental server uses.
+    ranged: HashMap<PositionEncoding, Vec<TextDocumentContentChangeEvent>>,
+    /// Latest full text, kept while a full-sync server is attached.
+    full_text: Option<String>,
 }
 
 #[derive(Debug, Default)]
 pub struct LspWorkspace {
     runtime: Option<Runtime>,
-    clients: HashMap<LanguageId, LspClient>,
-    servers: HashMap<LanguageId, crate::lsp::client::ServerConfig>,
+    pub(super) clients: HashMap<ServerId, LspClient>,
+    /// Ordered servers per language; the first is the primary server.
+    servers: HashMap<LanguageId, Vec<ServerConfig>>,
     open_versions: HashMap<std::path::PathBuf, i32>,
-    pending_completion: Option<PendingCompletionRequest>,
-    pending_hover: Option<PendingRequest>,
-    pending_signature: Option<PendingRequest>,
-    diagnostics: DiagnosticStore,
+    /// Edits made since the last flush, sent as one didChange per server.
+    pending_changes: HashMap<std::path::PathBuf, PendingSync>,
+    pub(super) pending: HashMap<(ServerId, u64), PendingRequest>,
+    pub(super) responses: Vec<LspResponse>,
+    pub(super) diagnostics: DiagnosticStore,
     discovery: WorkspaceDiscovery,
     workspace_bootstrapped: bool,
+    workspace_root: PathBuf,
+    restarts: HashMap<ServerId, RestartState>,
+    /// Servers still launching; they join `clients` once initialized.
+    launches: HashMap<ServerId, PendingLaunch>,
+    traffic: TrafficLog,
+    pub(super) code_actions: CodeActionState,
+    pub(super) inlay_hints: InlayHintState,
+    pub(super) semantic_tokens: SemanticTokenState,
+    pub(super) document_symbols: DocumentSymbolState,
+    pub(super) hierarchy: HierarchyState,
+    pub(super) completion_items: CompletionItemState,
     pub completion: CompletionContext,
     pub hover: HoverView,
     pub signature: SignatureHelpView,
@@ -55,17 +82,30 @@ impl LspWorkspace {
         let runtime = Builder::new_current_thread().enable_all().build().ok();
         let servers = load_server_config(workspace_root);
         let discovery = WorkspaceDiscovery::discover(workspace_root);
+        let traffic = load_trace_file(workspace_root)
+            .map(|path| TrafficLog::with_trace_file(&path))
+            .unwrap_or_default();
         Self {
             runtime,
             clients: HashMap::new(),
             servers,
             open_versions: HashMap::new(),
-            pending_completion: None,
-            pending_hover: None,
-            pending_signature: None,
+            pending_changes: HashMap::new(),
+            pending: HashMap::new(),
+            responses: Vec::new(),
             diagnostics: DiagnosticStore::default(),
             discovery,
             workspace_bootstrapped: false,
+            workspace_root: workspace_root.to_path_buf(),
+            restarts: HashMap::new(),
+            launches: HashMap::new(),
+            traffic,
+            code_actions: CodeActionState::default(),
+            inlay_hints: InlayHintState::default(),
+            semantic_tokens: SemanticTokenState::default(),
+            document_symbols: DocumentSymbolState::default(),
+            hierarchy: HierarchyState::default(),
+            completion_items: CompletionItemState::default(),
             completion: CompletionContext::default(),
             hover: HoverView::default(),
             signature: SignatureHelpView::default(),
@@ -74,6 +114,10 @@ impl LspWorkspace {
         }
     }
 
+    pub fn traffic(&self) -> &TrafficLog {
+        &self.traffic
+    }
+
     pub fn diagnostics_for_path(&self, path: &Path) -> &[DiagnosticItem] {
         self.diagnostics.for_path(path)
     }
@@ -92,6 +136,7 @@ impl LspWorkspace {
             return;
         };
         self.ensure_client_for_language(language, workspace_root);
+        self.flush_pending_changes(path);
     }
 
     pub fn bootstrap_workspace(&mut self, workspace_root: &Path) {
@@ -106,96 +151,211 @@ impl LspWorkspace {
     }
 
     fn ensure_client_for_language(&mut self, language: LanguageId, workspace_root: &Path) {
-        if self.clients.contains_key(&language) {
+        let count = self.servers.get(&language).map_or(0, Vec::len);
+        for slot in 0..count {
+            self.ensure_client(ServerId::new(language, slot), workspace_root);
+        }
+    }
+
+    fn ensure_client(&mut self, id: ServerId, workspace_root: &Path) {
+        if self.clients.contains_key(&id) || self.launches.contains_key(&id) {
             return;
         }
-        let Some(server) = self.servers.get(&language).cloned() else {
+        let Some(server) = self.server_config(id).cloned() else {
             return;
         };
-        let Some(runtime) = self.runtime.as_mut() else {
+        if self
+            .restarts
+            .get(&id)
+            .is_some_and(|state| !state.ready(Instant::now()))
+        {
             return;
-        };
-        if let Ok(client) = runtime.block_on(LspClient::launch(&server, workspace_root)) {
-            self.clients.insert(language, client);
         }
+        self.spawn_launch(id, server, workspace_root, false);
     }
 
-    pub fn request_completion(
-        &mut self,
-        path: &Path,
-        workspace_root: &Path,
-        line: usize,
-        character: usize,
-        trigger: Option<char>,
-    ) {
-        self.bootstrap_workspace(workspace_root);
-        self.ensure_client_for_path(path, workspace_root);
-        self.completion.close();
-        self.pending_completion = None;
+    fn spawn_launch(&mut self, id: ServerId, server: ServerConfig, root: &Path, announce: bool) {
+        let traffic = self.traffic.clone();
+        let result = LspClient::launch_in_background(server, root.to_path_buf(), traffic);
+        self.launches.insert(id, PendingLaunch { result, announce });
+    }
+
+    pub(super) fn server_config(&self, id: ServerId) -> Option<&ServerConfig> {
+        self.servers.get(&id.language)?.get(id.slot)
+    }
+
+    /// Running servers for `path`'s language, primary first.
+    fn servers_for_path(&self, path: &Path) -> Vec<ServerId> {
         let Some(language) = language_for_path(path) else {
-            return;
-        };
-        let version = self.document_version(path);
-        let Some(client) = self.clients.get_mut(&language) else {
-            return;
+            return Vec::new();
         };
-        if !client.capabilities.completion {
-            return;
+        let count = self.servers.get(&language).map_or(0, Vec::len);
+        (0..count)
+            .map(|slot| ServerId::new(language, slot))
+            .filter(|id| self.clients.contains_key(id))
+            .collect()
+    }
+
+    pub(super) fn serves(&self, id: ServerId, feature: LspFeature) -> bool {
+        self.server_config(id)
+            .is_some_and(|server| server.handles(feature))
+            && self
+                .clients
+                .get(&id)
+                .is_some_and(|client| client.capabilities.supports(feature))
+    }
+
+    /// Every running server for `path` that handles `feature`, in order.
+    pub(super) fn route_all(&self, path: &Path, feature: LspFeature) -> Vec<ServerId> {
+        self.servers_for_path(path)
+            .into_iter()
+            .filter(|id| self.serves(*id, feature))
+            .collect()
+    }
+
+    /// The server that answers `feature` for `path`. Formatting prefers the
+    /// server marked `formatter`, everything else the first capable server.
+    pub(super) fn route(&self, path: &Path, feature: LspFeature) -> Option<ServerId> {
+        let candidates = self.route_all(path, feature);
+        if feature == LspFeature::Formatting
+            && let Some(id) = candidates
+                .iter()
+                .find(|id| self.server_config(**id).is_some_and(|server| server.formatter))
+        {
+            return Some(*id);
         }
+        candidates.first().copied()
+    }
 
-        let context =
-            completion_context(trigger, &client.capabilities.completion_trigger_characters);
-        let params = serde_json::json!({
-            "textDocument": { "uri": file_uri(path) },
-            "position": { "line": line, "character": character },
-            "context": context
-        });
+    /// Sends a request without waiting for it; the response is routed by
+    /// `poll_server_messages`. An older request of the same kind is cancelled.
+    pub(super) fn dispatch(
+        &mut self,
+        kind: RequestKind,
+        id: ServerId,
+        path: Option<&Path>,
+        cursor: Cursor,
+        params: serde_json::Value,
+    ) -> bool {
+        self.cancel_kind(kind);
+        self.send_request(kind, id, path, cursor, params)
+    }
 
-        let Ok(request_id) = client.send_request("textDocument/completion", params) else {
-            return;
-        };
+    pub(super) fn send_request(
+        &mut self,
+        kind: RequestKind,
+        id: ServerId,
+        path: Option<&Path>,
+        cursor: Cursor,
+        params: serde_json::Value,
+    ) -> bool {
+        self.send_tracked_request(kind, id, path, cursor, params)
+            .is_some()
+    }
 
-        self.pending_completion = Some(PendingCompletionRequest {
-            request: PendingRequest {
-                language,
-                request_id,
-                path: path.to_path_buf(),
+    /// Like `send_request`, returning the request id for matching the response.
+    pub(super) fn send_tracked_request(
+        &mut self,
+        kind: RequestKind,
+        id: ServerId,
+        path: Option<&Path>,
+        cursor: Cursor,
+        params: serde_json::Value,
+    ) -> Option<u64> {
+        let version = path.map_or(0, |path| self.document_version(path));
+        let client = self.clients.get_mut(&id)?;
+        let encoding = client.capabilities.position_encoding;
+        let request_id = client.send_request(kind.method(), params).ok()?;
+        self.pending.insert(
+            (id, request_id),
+            PendingRequest {
+                kind,
+                path: path.map(Path::to_path_buf),
                 version,
-                line,
-                character,
+                cursor,
+                encoding,
+                started: Instant::now(),
             },
-            trigger_column: character,
-        });
+        );
+        Some(request_id)
+    }
+
+    pub(super) fn cancel_kind(&mut self, kind: RequestKind) {
+        self.cancel_where(|request| request.kind == kind);
+    }
+
+    /// Drops matching in-flight requests and tells their servers to stop.
+    pub(super) fn cancel_where(&mut self, stale: impl Fn(&PendingRequest) -> bool) {
+        let keys = self
+            .pending
+            .iter()
+            .filter(|(_, request)| stale(request))
+            .map(|(key, _)| *key)
+            .collect::<Vec<_>>();
+        for (id, request_id) in keys {
+            self.pending.remove(&(id, request_id));
+            if let Some(client) = self.clients.get_mut(&id) {
+                client.cancel_request(request_id);
+            }
+        }
+    }
+
+    /// Requests sent but not yet answered, for the statusline spinner.
+    pub fn in_flight_requests(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn take_responses(&mut self) -> Vec<LspResponse> {
+        std::mem::take(&mut self.responses)
+    }
+
+    /// Tells the server whether its `workspace/applyEdit` went through.
+    pub fn answer_apply_edit(&mut self, reply: ServerReply, result: Result<usize, String>) {
+        let Some(client) = self.clients.get_mut(&reply.server) else {
+            return;
+        };
+        let answer = match result {
+            Ok(_) => serde_json::json!({ "applied": true }),
+            Err(reason) => serde_json::json!({ "applied": false, "failureReason": reason }),
+        };
+        client.respond(reply.id, answer);
     }
 }
 
-fn file_uri(path: &Path) -> String {
+pub(super) fn file_uri(path: &Path) -> String {
     format!("file://{}", path.to_string_lossy())
 }
 
-fn parse_file_uri(uri: &str) -> Option<std::path::PathBuf> {
+pub(crate) fn parse_file_uri(uri: &str) -> Option<std::path::PathBuf> {
     uri.strip_prefix("file://").map(std::path::PathBuf::from)
 }
 
-fn parse_position(value: &serde_json::Value) -> (usize, usize) {
-    let line = value
-        .get("line")
-        .and_then(serde_json::Value::as_u64)
-        .unwrap_or(0) as usize;
-    let character = value
-        .get("character")
-        .and_then(serde_json::Value::as_u64)
-        .unwrap_or(0) as usize;
-    (line, character)
-}
-
 impl LspWorkspace {
-    fn document_version(&self, path: &Path) -> i32 {
-        self.open_versions.get(path).copied().unwrap_or(1)
+    pub(super) fn server_encoding(&self, id: ServerId) -> PositionEncoding {
+        self.clients
+            .get(&id)
+            .map(|client| client.capabilities.position_encoding)
+            .unwrap_or_default()
+    }
+
+    pub(super) fn lsp_position(
+        &self,
+        id: ServerId,
+        path: &Path,
+        cursor: Cursor,
+        lines: &dyn LineSource,
+    ) -> Position {
+        let line_text = lines.line_text(path, cursor.line).unwrap_or_default();
+        self.server_encoding(id).to_position(&line_text, cursor)
+    }
+
+    /// Version the servers were last told `path` is at, while it is open.
+    pub fn open_version(&self, path: &Path) -> Option<i32> {
+        self.open_versions.get(path).copied()
     }
 
-    fn request_still_current(&self, request: &PendingRequest) -> bool {
-        self.document_version(&request.path) == request.version
+    pub(super) fn document_version(&self, path: &Path) -> i32 {
+        self.open_versions.get(path).copied().unwrap_or(1)
     }
 }
 
@@ -203,10 +363,14 @@ impl LspWorkspace {
     pub fn did_open(&mut self, path: &Path, text: &str, workspace_root: &Path) {
         self.bootstrap_workspace(workspace_root);
         self.ensure_client_for_path(path, workspace_root);
-        let Some(language) = language_for_path(path) else {
-            return;
-        };
-        let Some(client) = self.clients.get_mut(&language) else {
+        self.open_versions.insert(path.to_path_buf(), 1);
+        for id in self.servers_for_path(path) {
+            self.open_on_server(id, path, text, 1);
+        }
+    }
+
+    fn open_on_server(&mut self, id: ServerId, path: &Path, text: &str, version: i32) {
+        let Some(client) = self.clients.get_mut(&id) else {
             return;
         };
         let Some(runtime) = self.runtime.as_mut() else {
@@ -216,53 +380,152 @@ impl LspWorkspace {
             "textDocument": {
                 "uri": file_uri(path),
                 "languageId": client.language_id,
-                "version": 1,
+                "version": version,
                 "text": text
             }
         });
-        self.open_versions.insert(path.to_path_buf(), 1);
         let _ = runtime.block_on(client.notify("textDocument/didOpen", params));
     }
 
+    /// Tells the servers a document is gone, e.g. after it was renamed.
+    pub fn did_close(&mut self, path: &Path) {
+        if self.open_versions.remove(path).is_none() {
+            return;
+        }
+        self.pending_changes.remove(path);
+        self.inlay_hints.forget(path);
+        self.semantic_tokens.forget(path);
+        self.document_symbols.forget(path);
+        self.cancel_where(|request| request.path.as_deref() == Some(path));
+        let servers = self.servers_for_path(path);
+        let Some(runtime) = self.runtime.as_mut() else {
+            return;
+        };
+        let params = serde_json::json!({ "textDocument": { "uri": file_uri(path) } });
+        for id in servers {
+            if let Some(client) = self.clients.get_mut(&id) {
+                let _ = runtime.block_on(client.notify("textDocument/didClose", params.clone()));
+            }
+        }
+    }
+
     pub fn did_change(&mut self, path: &Path, text: &str, workspace_root: &Path) {
         if !self.open_versions.contains_key(path) {
             self.did_open(path, text, workspace_root);
             return;
         }
+        self.pending_changes.remove(path);
         self.bootstrap_workspace(workspace_root);
         self.ensure_client_for_path(path, workspace_root);
-        let Some(language) = language_for_path(path) else {
-            return;
-        };
-        let Some(client) = self.clients.get_mut(&language) else {
+        let version = self.document_version(path).saturating_add(1);
+        let changes = serde_json::json!([ { "text": text } ]);
+        for id in self.servers_for_path(path) {
+            self.send_did_change(id, path, version, changes.clone());
+        }
+        self.open_versions.insert(path.to_path_buf(), version);
+        self.cancel_where(|request| request.path.as_deref() == Some(path));
+    }
+
+    fn send_did_change(
+        &mut self,
+        id: ServerId,
+        path: &Path,
+        version: i32,
+        changes: serde_json::Value,
+    ) {
+        let Some(client) = self.clients.get_mut(&id) else {
             return;
         };
         let Some(runtime) = self.runtime.as_mut() else {
             return;
         };
-        let version = self
-            .open_versions
-            .get(path)
-            .copied()
-            .unwrap_or(1)
-            .saturating_add(1);
         let params = serde_json::json!({
             "textDocument": { "uri": file_uri(path), "version": version },
-            "contentChanges": [ { "text": text } ]
+            "contentChanges": changes
         });
-        self.open_versions.insert(path.to_path_buf(), version);
         let _ = runtime.block_on(client.notify("textDocument/didChange", params));
     }
 
-    pub fn did_save(&mut self, path: &Path, text: &str, workspace_root: &Path) {
-        self.bootstrap_workspace(workspace_root);
-        self.ensure_client_for_path(path, workspace_root);
+    /// Records one edit for every server of `path`. Incremental servers get
+    /// `change` built in their encoding; full-sync servers get `full_text()`.
+    /// Both are batched with the other edits of this tick.
+    pub fn record_change(
+        &mut self,
+        path: &Path,
+        change: impl Fn(PositionEncoding) -> TextDocumentContentChangeEvent,
+        full_text: impl FnOnce() -> String,
+        workspace_root: &Path,
+    ) {
+        if !self.open_versions.contains_key(path) {
+            self.did_open(path, &full_text(), workspace_root);
+            return;
+        }
         let Some(language) = language_for_path(path) else {
             return;
         };
-        let Some(client) = self.clients.get_mut(&language) else {
+        self.ensure_client_for_language(language, workspace_root);
+        let servers = self.servers_for_path(path);
+        let mut needs_full_text = false;
+        let mut encodings = Vec::new();
+        for id in servers {
+            let Some(client) = self.clients.get(&id) else {
+                continue;
+            };
+            if client.capabilities.incremental_sync {
+                let encoding = client.capabilities.position_encoding;
+                if !encodings.contains(&encoding) {
+                    encodings.push(encoding);
+                }
+            } else {
+                needs_full_text = true;
+            }
+        }
+        let pending = self.pending_changes.entry(path.to_path_buf()).or_default();
+        for encoding in encodings {
+            pending.ranged.entry(encoding).or_default().push(change(encoding));
+        }
+        if needs_full_text {
+            pending.full_text = Some(full_text());
+        }
+    }
+
+    /// Sends the queued edits for `path` as a single didChange per server.
+    pub fn flush_pending_changes(&mut self, path: &Path) {
+        let Some(mut pending) = self.pending_changes.remove(path) else {
             return;
         };
+        let version = self.document_version(path).saturating_add(1);
+        for id in self.servers_for_path(path) {
+            let Some(client) = self.clients.get(&id) else {
+                continue;
+            };
+            let capabilities = &client.capabilities;
+            let ranged = capabilities
+                .incremental_sync
+                .then(|| pending.ranged.remove(&capabilities.position_encoding))
+                .flatten();
+            let changes = match (ranged, &pending.full_text) {
+                (Some(ranged), _) => serde_json::json!(ranged),
+                (None, Some(text)) => serde_json::json!([ { "text": text } ]),
+                (None, None) => continue,
+            };
+            self.send_did_change(id, path, version, changes);
+        }
+        self.open_versions.insert(path.to_path_buf(), version);
+        self.cancel_where(|request| request.path.as_deref() == Some(path));
+    }
+
+    fn flush_all_pending_changes(&mut self) {
+        let paths = self.pending_changes.keys().cloned().collect::<Vec<_>>();
+        for path in paths {
+            self.flush_pending_changes(&path);
+        }
+    }
+
+    pub fn did_save(&mut self, path: &Path, text: &str, workspace_root: &Path) {
+        self.bootstrap_workspace(workspace_root);
+        self.ensure_client_for_path(path, workspace_root);
+        let servers = self.servers_for_path(path);
         let Some(runtime) = self.runtime.as_mut() else {
             return;
         };
@@ -270,564 +533,471 @@ impl LspWorkspace {
             "textDocument": { "uri": file_uri(path) },
             "text": text
         });
-        let _ = runtime.block_on(client.notify("textDocument/didSave", params));
+        for id in servers {
+            if let Some(client) = self.clients.get_mut(&id) {
+                let _ = runtime.block_on(client.notify("textDocument/didSave", params.clone()));
+            }
+        }
     }
 
     pub fn request_hover(
         &mut self,
         path: &Path,
         workspace_root: &Path,
-        line: usize,
-        character: usize,
+        cursor: Cursor,
+        lines: &dyn LineSource,
     ) -> bool {
         self.bootstrap_workspace(workspace_root);
         self.ensure_client_for_path(path, workspace_root);
-        self.pending_hover = None;
+        self.cancel_kind(RequestKind::Hover);
         if !self.progress.done {
             return false;
         }
-        let Some(language) = language_for_path(path) else {
-            return false;
-        };
-        let version = self.document_version(path);
-        let Some(client) = self.clients.get_mut(&language) else {
+        let Some(id) = self.route(path, LspFeature::Hover) else {
             return false;
         };
-        if !client.capabilities.hover {
-            return false;
-        }
         let params = serde_json::json!({
             "textDocument": { "uri": file_uri(path) },
-            "position": { "line": line, "character": character }
-        });
-        let Ok(request_id) = client.send_request("textDocument/hover", params) else {
-            return false;
-        };
-        self.pending_hover = Some(PendingRequest {
-            language,
-            request_id,
-            path: path.to_path_buf(),
-            version,
-            line,
-            character,
+            "position": self.lsp_position(id, path, cursor, lines)
         });
-        true
+        self.dispatch(RequestKind::Hover, id, Some(path), cursor, params)
     }
 
     pub fn request_signature(
         &mut self,
         path: &Path,
         workspace_root: &Path,
-        line: usize,
-        character: usize,
+        cursor: Cursor,
+        lines: &dyn LineSource,
     ) {
         self.bootstrap_workspace(workspace_root);
         self.ensure_client_for_path(path, workspace_root);
         self.signature.clear();
-        self.pending_signature = None;
+        self.cancel_kind(RequestKind::SignatureHelp);
         if !self.progress.done {
             return;
         }
-        let Some(language) = language_for_path(path) else {
+        let Some(id) = self.route(path, LspFeature::SignatureHelp) else {
             return;
         };
-        let version = self.document_version(path);
-        let Some(client) = self.clients.get_mut(&language) else {
-            return;
-        };
-        if !client.capabilities.signature_help {
-            return;
-        }
         let params = serde_json::json!({
             "textDocument": { "uri": file_uri(path) },
-            "position": { "line": line, "character": character }
-        });
-        let Ok(request_id) = client.send_request("textDocument/signatureHelp", params) else {
-            return;
-        };
-        self.pending_signature = Some(PendingRequest {
-            language,
-            request_id,
-            path: path.to_path_buf(),
-            version,
-            line,
-            character,
+            "position": self.lsp_position(id, path, cursor, lines)
         });
+        self.dispatch(RequestKind::SignatureHelp, id, Some(path), cursor, params);
     }
 
-    pub fn goto_definition(
+    /// Asks for the occurrences of the symbol at `cursor`; `false` when no
+    /// ready server offers them.
+    pub fn request_document_highlights(
         &mut self,
         path: &Path,
         workspace_root: &Path,
-        line: usize,
-        character: usize,
-    ) -> Option<(std::path::PathBuf, usize, usize)> {
-        let supported = language_for_path(path)
-            .and_then(|language| self.clients.get(&language))
-            .map(|client| client.capabilities.goto_definition)
-            .unwrap_or(true);
-        self.goto_request(
-            "textDocument/definition",
-            supported,
+        cursor: Cursor,
+        lines: &dyn LineSource,
+    ) -> bool {
+        self.request_at_cursor(
+            RequestKind::DocumentHighlights,
             path,
             workspace_root,
-            line,
-            character,
+            cursor,
+            lines,
+            serde_json::Map::new(),
         )
     }
 
-    pub fn goto_references(
-        &mut self,
-        path: &Path,
-        workspace_root: &Path,
-        line: usize,
-        character: usize,
-    ) -> Vec<(std::path::PathBuf, usize, usize)> {
+    pub fn request_formatting(&mut self, path: &Path, workspace_root: &Path) -> bool {
         self.bootstrap_workspace(workspace_root);
         self.ensure_client_for_path(path, workspace_root);
         if !self.progress.done {
-            return Vec::new();
-        }
-        let Some(language) = language_for_path(path) else {
-            return Vec::new();
-        };
-        let Some(client) = self.clients.get_mut(&language) else {
-            return Vec::new();
-        };
-        if !client.capabilities.references {
-            return Vec::new();
-        }
-        let Some(runtime) = self.runtime.as_mut() else {
-            return Vec::new();
-        };
-        let params = serde_json::json!({
-            "textDocument": { "uri": file_uri(path) },
-            "position": { "line": line, "character": character },
-            "context": { "includeDeclaration": true }
-        });
-        let value = match runtime.block_on(client.request("textDocument/references", params)) {
-            Ok(value) => value,
-            Err(_) => return Vec::new(),
-        };
-        value
-            .as_array()
-            .map(|list| {
-                list.iter()
-                    .filter_map(|item| {
-                        let uri = item.get("uri")?.as_str()?;
-                        let path = parse_file_uri(uri)?;
-                        let start = item.get("range")?.get("start")?;
-                        let (line, column) = parse_position(start);
-                        Some((path, line, column))
-                    })
-                    .collect::<Vec<_>>()
-            })
-            .unwrap_or_default()
-    }
-
-    pub fn workspace_symbols(&mut self, query: &str, workspace_root: &Path) -> Vec<PickerItem> {
-        self.bootstrap_workspace(workspace_root);
-        let Some(language) = self.clients.keys().next().copied() else {
-            return Vec::new();
-        };
-        let Some(client) = self.clients.get_mut(&language) else {
-            return Vec::new();
-        };
-        if !client.capabilities.workspace_symbols {
-            return Vec::new();
+            return false;
         }
-        let Some(runtime) = self.runtime.as_mut() else {
-            return Vec::new();
-        };
-        let value = match runtime
-            .block_on(client.request("workspace/symbol", serde_json::json!({ "query": query })))
-        {
-            Ok(value) => value,
-            Err(_) => return Vec::new(),
+        let Some(id) = self.route(path, LspFeature::Formatting) else {
+            return false;
         };
-        value
-            .as_array()
-            .map(|items| {
-                items
-                    .iter()
-                    .filter_map(|item| {
-                        let name = item.get("name")?.as_str()?.to_owned();
-                        let loc = item.get("location")?;
-                        let uri = loc.get("uri")?.as_str()?;
-                        let path = parse_file_uri(uri)?;
-                        let start = loc.get("range")?.get("start")?;
-                        let (line, column) = parse_position(start);
-                        Some(PickerItem {
-                            title: name,
-                            subtitle: path.display().to_string(),
-                            path: Some(path),
-                            buffer_id: None,
-                            line: Some(line),
-                            column: Some(column),
-                        })
-                    })
-                    .collect::<Vec<_>>()
-            })
-            .unwrap_or_default()
-    }
-
-    pub fn open_workspace_symbols(
-        &mut self,
-        query: &str,
-        workspace_root: &Path,
-        picker: &mut Option<PickerState>,
-    ) {
-        let mut state = PickerState::new(crate::ui::PickerKind::Files);
-        state.set_buffer_items(self.workspace_symbols(query, workspace_root));
-        if state.items().is_empty() {
-            *picker = None;
-        } else {
-            *picker = Some(state);
-        }
+        let params = formatting_params(path);
+        self.dispatch(
+            RequestKind::Formatting,
+            id,
+            Some(path),
+            Cursor::new(0, 0),
+            params,
+        )
     }
 
-    pub fn format_document(
+    /// Requests formatting edits synchronously within `budget`. Save has to
+    /// wait for the edits, so this is the one request that still blocks.
+    pub fn format_edits_within(
         &mut self,
         path: &Path,
         workspace_root: &Path,
-        text: &str,
-    ) -> Option<String> {
+        budget: Duration,
+    ) -> Option<(Vec<TextEdit>, PositionEncoding)> {
         self.bootstrap_workspace(workspace_root);
         self.ensure_client_for_path(path, workspace_root);
         if !self.progress.done {
             return None;
         }
-        let language = language_for_path(path)?;
-        let client = self.clients.get_mut(&language)?;
-        if !client.capabilities.formatting {
-            return None;
-        }
+        let id = self.route(path, LspFeature::Formatting)?;
+        let encoding = self.server_encoding(id);
+        let client = self.clients.get_mut(&id)?;
         let runtime = self.runtime.as_mut()?;
-        let params = serde_json::json!({
-            "textDocument": { "uri": file_uri(path) },
-            "options": { "tabSize": 4, "insertSpaces": true }
-        });
+        let params = formatting_params(path);
         let edits = runtime
-            .block_on(client.request("textDocument/formatting", params))
+            .block_on(client.request_with_timeout("textDocument/formatting", params, budget))
             .ok()?;
-        apply_text_edits(text, &edits)
-    }
-
-    pub fn request_code_actions(
-        &mut self,
-        path: &Path,
-        workspace_root: &Path,
-        line: usize,
-        character: usize,
-    ) -> Vec<String> {
-        self.bootstrap_workspace(workspace_root);
-        self.ensure_client_for_path(path, workspace_root);
-        if !self.progress.done {
-            return Vec::new();
-        }
-        let Some(language) = language_for_path(path) else {
-            return Vec::new();
-        };
-        let Some(client) = self.clients.get_mut(&language) else {
-            return Vec::new();
-        };
-        if !client.capabilities.code_action {
-            return Vec::new();
-        }
-        let Some(runtime) = self.runtime.as_mut() else {
-            return Vec::new();
-        };
-        let range = serde_json::json!({
-            "start": { "line": line, "character": character },
-            "end": { "line": line, "character": character }
-        });
-        let params = serde_json::json!({
-            "textDocument": { "uri": file_uri(path) },
-            "range": range,
-            "context": { "diagnostics": [] }
-        });
-        let value = match runtime.block_on(client.request("textDocument/codeAction", params)) {
-            Ok(value) => value,
-            Err(_) => return Vec::new(),
-        };
-        value
-            .as_array()
-            .map(|items| {
-                items
-                    .iter()
-                    .filter_map(|i| i.get("title").and_then(serde_json::Value::as_str))
-                    .map(ToOwned::to_owned)
-                    .collect::<Vec<_>>()
-            })
-            .unwrap_or_default()
+        Some((parse_text_edits(&edits), encoding))
     }
-
-    pub fn rename(
+    pub fn request_rename(
         &mut self,
         path: &Path,
         workspace_root: &Path,
-        line: usize,
-        character: usize,
+        cursor: Cursor,
+        lines: &dyn LineSource,
         new_name: &str,
-    ) -> Vec<(std::path::PathBuf, String)> {
-        self.bootstrap_workspace(workspace_root);
-        self.ensure_client_for_path(path, workspace_root);
-        if !self.progress.done {
-            return Vec::new();
-        }
-        let Some(language) = language_for_path(path) else {
-            return Vec::new();
-        };
-        let Some(client) = self.clients.get_mut(&language) else {
-            return Vec::new();
-        };
-        if !client.capabilities.rename {
-            return Vec::new();
-        }
-        let Some(runtime) = self.runtime.as_mut() else {
-            return Vec::new();
-        };
-        let params = serde_json::json!({
-            "textDocument": { "uri": file_uri(path) },
-            "position": { "line": line, "character": character },
-            "newName": new_name
-        });
-        let value = match runtime.block_on(client.request("textDocument/rename", params)) {
-            Ok(value) => value,
-            Err(_) => return Vec::new(),
-        };
-        let changes = value.get("changes").and_then(serde_json::Value::as_object);
-        let Some(changes) = changes else {
-            return Vec::new();
-        };
-        changes
-            .iter()
-            .filter_map(|(uri, edits)| {
-                let path = parse_file_uri(uri)?;
-                let original = std::fs::read_to_string(&path).ok()?;
-                let updated = apply_text_edits(&original, edits)?;
-                Some((path, updated))
-            })
-            .collect()
+    ) -> bool {
+        let mut extra = serde_json::Map::new();
+        extra.insert(String::from("newName"), serde_json::json!(new_name));
+        self.request_at_cursor(
+            RequestKind::Rename,
+            path,
+            workspace_root,
+            cursor,
+            lines,
+            extra,
+        )
     }
-
-    pub fn poll_server_messages(&mut self) {
+    pub fn poll_server_messages(&mut self, buffers: &[BufferState]) {
+        // Shared by every response below so each unopened file is read once.
+        let lines = &BufferLines::new(buffers);
+        // Flush first so servers relaunched below re-open up-to-date text.
+        self.flush_all_pending_changes();
+        self.detect_crashed_servers();
+        self.restart_due_servers();
+        self.finish_launches(lines);
         let mut updates = Vec::new();
         let mut responses = Vec::new();
-        for (language, client) in self.clients.iter_mut() {
-            updates.extend(client.drain_notifications());
+        let mut server_requests = Vec::new();
+        for (id, client) in self.clients.iter_mut() {
+            let encoding = client.capabilities.position_encoding;
+            updates.extend(
+                client
+                    .drain_notifications()
+                    .into_iter()
+                    .map(|update| (*id, encoding, update)),
+            );
+            server_requests.extend(
+                client
+                    .drain_server_requests()
+                    .into_iter()
+                    .map(|request| (*id, encoding, request)),
+            );
             responses.extend(
                 client
                     .drain_responses()
                     .into_iter()
-                    .map(|response| (*language, response)),
+                    .map(|response| (*id, response)),
             );
         }
-        for update in updates {
-            if let Some(method) = update.get("method").and_then(serde_json::Value::as_str) {
-                match method {
-                    "textDocument/publishDiagnostics" => {
-                        let Some(params) = update.get("params") else {
-                            continue;
-                        };
-                        let Some(uri) = params.get("uri").and_then(serde_json::Value::as_str)
-                        else {
-                            continue;
-                        };
-                        let Some(path) = parse_file_uri(uri) else {
-                            continue;
-                        };
-                        let Some(diags) = params
-                            .get("diagnostics")
-                            .and_then(serde_json::Value::as_array)
-                            .cloned()
-                        else {
-                            continue;
-                        };
-                        let parsed = diags
-                            .into_iter()
-                            .filter_map(|d| serde_json::from_value::<lsp_types::Diagnostic>(d).ok())
-                            .collect::<Vec<_>>();
-                        self.diagnostics.apply_publish(path, parsed);
+        for (id, encoding, update) in updates {
+            self.handle_notification(id, encoding, &update, lines);
+        }
+
+        for (server, encoding, request) in server_requests {
+            let Some(id) = request.get("id").and_then(serde_json::Value::as_u64) else {
+                continue;
+            };
+            let params = request.get("params");
+            let edit = params
+                .and_then(|params| params.get("edit"))
+                .map(|edit| WorkspaceEditPlan::parse(edit, encoding))
+                .unwrap_or_default();
+            let label = params
+                .and_then(|params| params.get("label"))
+                .and_then(serde_json::Value::as_str)
+                .map(ToOwned::to_owned);
+            self.responses.push(LspResponse::ApplyEdit {
+                reply: ServerReply { server, id },
+                label,
+                edit,
+            });
+        }
+
+        self.expire_requests();
+        for (id, response) in responses {
+            let Some(request) = self.pending.remove(&(id, response.id)) else {
+                continue;
+            };
+            let hierarchy_parent = self.take_hierarchy_parent(id, response.id);
+            // Completions stay usable while typing, as the list is filtered locally.
+            if request.kind != RequestKind::Completion
+                && request
+                    .path
+                    .as_deref()
+                    .is_some_and(|path| self.document_version(path) != request.version)
+            {
+                continue;
+            }
+            let result = response.into_result(request.kind.method());
+            match request.kind.feature() {
+                LspFeature::Completion => self.handle_completion_response(id, &request, result),
+                LspFeature::CodeAction => self.handle_code_action_response(id, &request, result),
+                LspFeature::InlayHints => {
+                    self.handle_inlay_hint_response(id, &request, result, lines);
+                }
+                LspFeature::Definition
+                | LspFeature::Declaration
+                | LspFeature::TypeDefinition
+                | LspFeature::Implementation
+                | LspFeature::References => self.handle_goto_response(&request, result, lines),
+                LspFeature::CallHierarchy | LspFeature::TypeHierarchy => {
+                    let value = result.unwrap_or(serde_json::Value::Null);
+                    self.store_hierarchy_items(id, &request, hierarchy_parent, &value, lines);
+                }
+                LspFeature::SemanticTokens => {
+                    if let Ok(value) = result {
+                        self.store_semantic_tokens(id, &request, &value, lines);
                     }
-                    "$/progress" => {
-                        let Some(params) = update.get("params") else {
-                            continue;
-                        };
-                        if let Some(value) = params.get("value") {
-                            if let Some(kind) =
-                                value.get("kind").and_then(serde_json::Value::as_str)
-                            {
-                                match kind {
-                                    "begin" | "report" => self.progress.done = false,
-                                    "end" => self.progress.done = true,
-                                    _ => {}
-                                }
-                            }
-                            self.progress.title = value
-                                .get("title")
-                                .and_then(serde_json::Value::as_str)
-                                .unwrap_or_default()
-                                .to_owned();
-                            self.progress.message = value
-                                .get("message")
-                                .and_then(serde_json::Value::as_str)
-                                .unwrap_or_default()
-                                .to_owned();
-                            self.progress.percentage = value
-                                .get("percentage")
-                                .and_then(serde_json::Value::as_u64)
-                                .map(|v| v as u32);
-                        }
+                }
+                LspFeature::DocumentSymbols => {
+                    if let Ok(value) = result {
+                        self.store_document_symbols(&request, &value, lines);
+                    }
+                }
+                LspFeature::DocumentHighlight => {
+                    if let (Ok(value), Some(path)) = (result, request.path.clone()) {
+                        let line_text = |line| lines.line_text(&path, line);
+                        let highlights =
+                            parse_document_highlights(&value, request.encoding, line_text);
+                        self.responses.push(LspResponse::DocumentHighlights {
+                            path,
+                            cursor: request.cursor,
+                            highlights,
+                        });
+                    }
+                }
+                LspFeature::Hover | LspFeature::SignatureHelp => {
+                    if let Ok(value) = result {
+                        self.apply_view_response(&request, value);
+                    }
+                }
+                LspFeature::Formatting
+                | LspFeature::Rename
+                | LspFeature::WorkspaceSymbols
+                | LspFeature::Diagnostics => {
+                    let value = result.unwrap_or(serde_json::Value::Null);
+                    if let Some(response) = parse_response(&request, &value, lines) {
+                        self.responses.push(response);
                     }
-                    _ => {}
                 }
             }
         }
+    }
 
-        for (language, response) in responses {
-            if self.pending_completion.as_ref().is_some_and(|pending| {
-                pending.request.language == language && pending.request.request_id == response.id
-            }) {
-                let Some(pending) = self.pending_completion.take() else {
-                    continue;
+    /// Applies a notification: diagnostics, progress or a message to show.
+    fn handle_notification(
+        &mut self,
+        id: ServerId,
+        encoding: PositionEncoding,
+        update: &serde_json::Value,
+        lines: &dyn LineSource,
+    ) {
+        let Some(method) = update.get("method").and_then(serde_json::Value::as_str) else {
+            return;
+        };
+        match method {
+            "textDocument/publishDiagnostics" => {
+                let Some(server) = self
+                    .server_config(id)
+                    .filter(|server| server.handles(LspFeature::Diagnostics))
+                    .map(|server| server.name.clone())
+                else {
+                    return;
                 };
-                if !self.request_still_current(&pending.request) {
-                    continue;
-                }
-                if let Ok(value) = response.into_result("textDocument/completion") {
-                    self.completion
-                        .set_items(pending.trigger_column, parse_completion_items(value));
-                }
-                continue;
+                let Some(params) = update.get("params") else {
+                    return;
+                };
+                let Some(uri) = params.get("uri").and_then(serde_json::Value::as_str) else {
+                    return;
+                };
+                let Some(path) = parse_file_uri(uri) else {
+                    return;
+                };
+                let Some(diags) = params
+                    .get("diagnostics")
+                    .and_then(serde_json::Value::as_array)
+                    .cloned()
+                else {
+                    return;
+                };
+                let parsed = diags
+                    .into_iter()
+                    .filter_map(|d| serde_json::from_value::<lsp_types::Diagnostic>(d).ok())
+                    .collect::<Vec<_>>();
+                self.diagnostics.apply_publish(path, &server, parsed, encoding, lines);
+                // New diagnostics can bring new quick fixes.
+                self.code_actions.forget_hint();
             }
-
-            if self.pending_hover.as_ref().is_some_and(|pending| {
-                pending.language == language && pending.request_id == response.id
-            }) {
-                let Some(pending) = self.pending_hover.take() else {
-                    continue;
+            "$/progress" => {
+                let Some(params) = update.get("params") else {
+                    return;
                 };
-                if !self.request_still_current(&pending) {
-                    continue;
-                }
-                if let Ok(value) = response.into_result("textDocument/hover") {
-                    if let Some(contents) = parse_hover_contents(&value) {
-                        self.hover.visible = true;
-                        self.hover.title = String::from("Hover");
-                        self.hover.contents = contents;
-                        self.hover.line = pending.line;
-                        self.hover.column = pending.character;
-                    } else {
-                        self.hover.clear();
+                if let Some(value) = params.get("value") {
+                    if let Some(kind) = value.get("kind").and_then(serde_json::Value::as_str) {
+                        match kind {
+                            "begin" | "report" => self.progress.done = false,
+                            "end" => self.progress.done = true,
+                            _ => {}
+                        }
                     }
+                    self.progress.title = value
+                        .get("title")
+                        .and_then(serde_json::Value::as_str)
+                        .unwrap_or_default()
+                        .to_owned();
+                    self.progress.message = value
+                        .get("message")
+                        .and_then(serde_json::Value::as_str)
+                        .unwrap_or_default()
+                        .to_owned();
+                    self.progress.percentage = value
+                        .get("percentage")
+                        .and_then(serde_json::Value::as_u64)
+                        .map(|v| v as u32);
                 }
-                continue;
             }
-
-            if self.pending_signature.as_ref().is_some_and(|pending| {
-                pending.language == language && pending.request_id == response.id
-            }) {
-                let Some(pending) = self.pending_signature.take() else {
-                    continue;
+            "window/showMessage" => {
+                let Some(params) = update.get("params") else {
+                    return;
                 };
-                if !self.request_still_current(&pending) {
-                    continue;
+                let message = params
+                    .get("message")
+                    .and_then(serde_json::Value::as_str)
+                    .unwrap_or_default()
+                    .to_owned();
+                let level = params
+                    .get("type")
+                    .and_then(serde_json::Value::as_u64)
+                    .unwrap_or(3);
+                self.responses.push(LspResponse::ShowMessage { message, level });
+            }
+            _ => {}
+        }
+    }
+
+    fn apply_view_response(&mut self, request: &PendingRequest, value: serde_json::Value) {
+        match request.kind {
+            RequestKind::Hover => {
+                if let Some(contents) = parse_hover_contents(&value) {
+                    self.hover.visible = true;
+                    self.hover.title = String::from("Hover");
+                    self.hover.contents = contents;
+                    self.hover.line = request.cursor.line;
+                    self.hover.column = request.cursor.column;
+                } else {
+                    self.hover.clear();
                 }
-                if let Ok(value) = response.into_result("textDocument/signatureHelp") {
-                    if let Some((label, active_parameter)) = parse_signature_help(&value) {
-                        self.signature.visible = true;
-                        self.signature.label = label;
-                        self.signature.active_parameter = active_parameter;
-                    } else {
-                        self.signature.clear();
-                    }
+            }
+            RequestKind::SignatureHelp => {
+                if let Some((label, active_parameter)) = parse_signature_help(&value) {
+                    self.signature.visible = true;
+                    self.signature.label = label;
+                    self.signature.active_parameter = active_parameter;
+                } else {
+                    self.signature.clear();
                 }
             }
+            _ => {}
         }
     }
 
-    fn goto_request(
+    fn expire_requests(&mut self) {
+        let now = Instant::now();
+        let expired = self
+            .pending
+            .values()
+            .filter(|request| now.duration_since(request.started) >= REQUEST_TIMEOUT)
+            .map(|request| request.kind)
+            .collect::<Vec<_>>();
+        if expired.is_empty() {
+            return;
+        }
+        self.cancel_where(|request| now.duration_since(request.started) >= REQUEST_TIMEOUT);
+        for kind in expired {
+            if !kind.is_automatic() {
+                self.responses.push(LspResponse::TimedOut(kind.method()));
+            }
+        }
+    }
+
+    pub(super) fn request_at_cursor(
         &mut self,
-        method: &str,
-        capability_supported: bool,
+        kind: RequestKind,
         path: &Path,
         workspace_root: &Path,
-        line: usize,
-        character: usize,
-    ) -> Option<(std::path::PathBuf, usize, usize)> {
+        cursor: Cursor,
+        lines: &dyn LineSource,
+        extra: serde_json::Map<String, serde_json::Value>,
+    ) -> bool {
         self.bootstrap_workspace(workspace_root);
         self.ensure_client_for_path(path, workspace_root);
         if !self.progress.done {
-            return None;
-        }
-        let language = language_for_path(path)?;
-        let client = self.clients.get_mut(&language)?;
-        if !capability_supported {
-            return None;
+            return false;
         }
-        let runtime = self.runtime.as_mut()?;
-        let params = serde_json::json!({
+        let Some(id) = self.route(path, kind.feature()) else {
+            return false;
+        };
+        let mut params = serde_json::json!({
             "textDocument": { "uri": file_uri(path) },
-            "position": { "line": line, "character": character }
+            "position": self.lsp_position(id, path, cursor, lines)
         });
-        let value = runtime.block_on(client.request(method, params)).ok()?;
-        let first = value.as_array()?.first()?.clone();
-        let uri = first.get("uri")?.as_str()?;
-        let p = parse_file_uri(uri)?;
-        let start = first.get("range")?.get("start")?;
-        let (line, column) = parse_position(start);
-        Some((p, line, column))
+        if let Some(object) = params.as_object_mut() {
+            object.extend(extra);
+        }
+        self.dispatch(kind, id, Some(path), cursor, params)
     }
 }
 
-fn apply_text_edits(text: &str, edits_value: &serde_json::Value) -> Option<String> {
-    let edits = edits_value.as_array()?;
-    let mut parsed = edits
-        .iter()
-        .filter_map(|v| serde_json::from_value::<lsp_types::TextEdit>(v.clone()).ok())
-        .collect::<Vec<_>>();
-    parsed.sort_by(|a, b| {
-        let al = (a.range.start.line, a.range.start.character);
-        let bl = (b.range.start.line, b.range.start.character);
-        bl.cmp(&al)
-    });
-    let mut output = text.to_owned();
-    for edit in parsed {
-        let start = position_to_byte(&output, edit.range.start)?;
-        let end = position_to_byte(&output, edit.range.end)?;
-        if start <= end && end <= output.len() {
-            output.replace_range(start..end, &edit.new_text);
-        }
-    }
-    Some(output)
+/// Turns a formatting, rename or workspace symbol result into the typed
+/// response the app acts on.
+fn parse_response(
+    request: &PendingRequest,
+    value: &serde_json::Value,
+    lines: &dyn LineSource,
+) -> Option<LspResponse> {
+    let encoding = request.encoding;
+    let response = match request.kind {
+        RequestKind::Formatting => LspResponse::Formatting {
+            path: request.path.clone()?,
+            edits: parse_text_edits(value),
+            encoding,
+        },
+        RequestKind::Rename => LspResponse::Rename(WorkspaceEditPlan::parse(value, encoding)),
+        RequestKind::WorkspaceSymbols => {
+            LspResponse::WorkspaceSymbols(parse_workspace_symbols(value, encoding, lines))
+        }
+        _ => return None,
+    };
+    Some(response)
 }
 
-fn position_to_byte(text: &str, pos: Position) -> Option<usize> {
-    let mut byte = 0usize;
-    let mut line = 0u32;
-    for segment in text.split_inclusive('\n') {
-        if line == pos.line {
-            let mut col = 0u32;
-            for (offset, ch) in segment.char_indices() {
-                if col == pos.character {
-                    return Some(byte + offset);
-                }
-                if ch == '\n' {
-                    break;
-                }
-                col += 1;
-            }
-            return Some(byte + segment.len());
-        }
-        byte += segment.len();
-        line += 1;
-    }
-    if line == pos.line {
-        Some(text.len())
-    } else {
-        None
-    }
+fn formatting_params(path: &Path) -> serde_json::Value {
+    serde_json::json!({
+        "textDocument": { "uri": file_uri(path) },
+        "options": { "tabSize": 4, "insertSpaces": true }
+    })
+}
+
+fn parse_text_edits(value: &serde_json::Value) -> Vec<TextEdit> {
+    value
+        .as_array()
+        .map(|edits| {
+            edits
+                .iter()
+                .filter_map(|v| serde_json::from_value::<TextEdit>(v.clone()).ok())
+                .collect()
+        })
+        .unwrap_or_default()
 }
 
 fn parse_hover_contents(value: &serde_json::Value) -> Option<String> {
@@ -860,56 +1030,232 @@ fn parse_signature_help(value: &serde_json::Value) -> Option<(String, Option<u32
     Some((label, active_parameter))
 }
 
-fn completion_context(trigger: Option<char>, trigger_characters: &[String]) -> serde_json::Value {
-    let Some(trigger) = trigger else {
-        return serde_json::json!({ "triggerKind": 1 });
-    };
-    let trigger_text = trigger.to_string();
-    if trigger_characters.iter().any(|item| item == &trigger_text) {
-        serde_json::json!({
-            "triggerKind": 2,
-            "triggerCharacter": trigger_text
-        })
-    } else {
-        serde_json::json!({ "triggerKind": 1 })
+impl LspWorkspace {
+    fn detect_crashed_servers(&mut self) {
+        let crashed = self
+            .clients
+            .iter_mut()
+            .filter_map(|(id, client)| Some((*id, client.exit_status()?)))
+            .collect::<Vec<_>>();
+        for (id, status) in crashed {
+            let Some(client) = self.clients.remove(&id) else {
+                continue;
+            };
+            self.pending.retain(|(pending_id, _), _| *pending_id != id);
+            let last_line = client.stderr_tail(1).pop().unwrap_or_default();
+            let error = if last_line.is_empty() {
+                status
+            } else {
+                format!("{status}: {last_line}")
+            };
+            let previous = self.restarts.get(&id);
+            let state = RestartState::record_failure(previous, client.uptime(), error);
+            let name = self
+                .server_config(id)
+                .map_or_else(|| client.language_id.clone(), |server| server.name.clone());
+            let notice = if state.gave_up() {
+                format!("{name} crashed; giving up after {} attempts", state.attempts)
+            } else {
+                format!("{name} crashed ({}); restarting", state.last_error)
+            };
+            self.restarts.insert(id, state);
+            self.responses.push(LspResponse::ServerNotice(notice));
+        }
     }
-}
 
-fn parse_completion_items(value: serde_json::Value) -> Vec<CompletionItemView> {
-    let items_value = if value.get("items").is_some() {
-        value
-            .get("items")
-            .cloned()
-            .unwrap_or(serde_json::Value::Null)
-    } else {
-        value
-    };
-    let Some(list) = items_value.as_array() else {
-        return Vec::new();
-    };
+    fn restart_due_servers(&mut self) {
+        let now = Instant::now();
+        let due = self
+            .restarts
+            .iter()
+            .filter(|(id, state)| state.ready(now) && !self.clients.contains_key(*id))
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        let root = self.workspace_root.clone();
+        for id in due {
+            self.ensure_client(id, &root);
+        }
+    }
 
-    list.iter()
-        .filter_map(|item| {
-            let label = item.get("label").and_then(serde_json::Value::as_str)?;
-            let detail = item
-                .get("detail")
-                .and_then(serde_json::Value::as_str)
-                .unwrap_or_default();
-            let insert_text = item
-                .get("insertText")
-                .and_then(serde_json::Value::as_str)
-                .unwrap_or(label);
-            let is_snippet = item
-                .get("insertTextFormat")
-                .and_then(serde_json::Value::as_u64)
-                == Some(2);
-
-            Some(CompletionItemView {
-                label: label.to_owned(),
-                detail: detail.to_owned(),
-                insert_text: insert_text.to_owned(),
-                is_snippet,
+    /// Takes in servers whose launch finished, recording failures for the
+    /// restart backoff.
+    fn finish_launches(&mut self, lines: &dyn LineSource) {
+        let finished = self
+            .launches
+            .iter()
+            .filter_map(|(id, launch)| match launch.result.try_recv() {
+                Ok(result) => Some((*id, result)),
+                Err(TryRecvError::Empty) => None,
+                Err(TryRecvError::Disconnected) => {
+                    Some((*id, Err(String::from("launch thread ended"))))
+                }
             })
-        })
-        .collect()
+            .collect::<Vec<_>>();
+        for (id, result) in finished {
+            let Some(launch) = self.launches.remove(&id) else {
+                continue;
+            };
+            match result {
+                Ok(client) => {
+                    self.clients.insert(id, client);
+                    self.reopen_documents(id, lines);
+                }
+                Err(error) => {
+                    if launch.announce {
+                        let name = self
+                            .server_config(id)
+                            .map_or("Language server", |server| server.name.as_str());
+                        let notice = format!("{name} failed to start: {error}");
+                        self.responses.push(LspResponse::ServerNotice(notice));
+                    }
+                    let previous = self.restarts.get(&id);
+                    let state = RestartState::record_failure(previous, Duration::ZERO, error);
+                    self.restarts.insert(id, state);
+                }
+            }
+        }
+    }
+
+    /// Sends `didOpen` to a newly started server for every open document of
+    /// its language, at the version the other servers know.
+    fn reopen_documents(&mut self, id: ServerId, lines: &dyn LineSource) {
+        let paths = self
+            .open_versions
+            .keys()
+            .filter(|path| language_for_path(path) == Some(id.language))
+            .cloned()
+            .collect::<Vec<_>>();
+        for path in paths {
+            self.flush_pending_changes(&path);
+            if let Some(text) = lines.document_text(&path) {
+                let version = self.document_version(&path);
+                self.open_on_server(id, &path, &text, version);
+            }
+        }
+    }
+
+    /// Shuts down and relaunches every server for `language` in the
+    /// background, clearing any backoff. Returns how many were started.
+    pub fn restart_server(&mut self, language: LanguageId) -> usize {
+        let count = self.servers.get(&language).map_or(0, Vec::len);
+        let root = self.workspace_root.clone();
+        let mut started = 0;
+        for slot in 0..count {
+            let id = ServerId::new(language, slot);
+            if let Some(client) = self.clients.remove(&id) {
+                client.shutdown_in_background();
+            }
+            self.pending.retain(|(pending_id, _), _| *pending_id != id);
+            self.restarts.remove(&id);
+            self.launches.remove(&id);
+            if let Some(server) = self.server_config(id).cloned() {
+                self.spawn_launch(id, server, &root, true);
+                started += 1;
+            }
+        }
+        started
+    }
+
+    /// Running or crashed servers, by language then configured order.
+    fn active_servers(&self) -> Vec<ServerId> {
+        let mut servers = self
+            .clients
+            .keys()
+            .chain(self.restarts.keys())
+            .chain(self.launches.keys())
+            .copied()
+            .collect::<Vec<_>>();
+        servers.sort_by_key(|id| id.sort_key());
+        servers.dedup();
+        servers
+    }
+
+    /// Languages with a running or crashed server.
+    pub fn active_server_languages(&self) -> Vec<LanguageId> {
+        let mut languages = self
+            .active_servers()
+            .into_iter()
+            .map(|id| id.language)
+            .collect::<Vec<_>>();
+        languages.dedup();
+        languages
+    }
+
+    /// Gracefully stops every server, all at once; called when the editor quits.
+    pub fn shutdown_all(&mut self) {
+        self.launches.clear();
+        let stopping = self
+            .clients
+            .drain()
+            .map(|(_, client)| client.shutdown_in_background())
+            .collect::<Vec<_>>();
+        for handle in stopping {
+            let _ = handle.join();
+        }
+    }
+
+    /// One line per server (plus its recent stderr) for `:lsp-status`.
+    pub fn status_items(&self) -> Vec<PickerItem> {
+        let mut items = Vec::new();
+        for id in self.active_servers() {
+            let Some(server) = self.server_config(id) else {
+                continue;
+            };
+            let name = format!("{} [{}] ({})", server.name, server.language_id, server.command);
+            if let Some(client) = self.clients.get(&id) {
+                items.push(status_item(
+                    format!(
+                        "{name}  pid {}  up {}",
+                        client.pid(),
+                        format_uptime(client.uptime())
+                    ),
+                    status_features(server, &client.capabilities),
+                ));
+                for line in client.stderr_tail(STATUS_STDERR_LINES) {
+                    items.push(status_item(format!("  {line}"), String::from("stderr")));
+                }
+            } else if self.launches.contains_key(&id) {
+                items.push(status_item(format!("{name}  starting"), String::new()));
+            } else if let Some(state) = self.restarts.get(&id) {
+                let title = if state.gave_up() {
+                    format!("{name}  stopped after {MAX_RESTART_ATTEMPTS} failed starts")
+                } else {
+                    let wait = state.next_attempt.saturating_duration_since(Instant::now());
+                    format!(
+                        "{name}  restarting in {}s (attempt {}/{MAX_RESTART_ATTEMPTS})",
+                        wait.as_secs(),
+                        state.attempts + 1
+                    )
+                };
+                items.push(status_item(title, state.last_error.clone()));
+            }
+        }
+        items
+    }
+}
+
+/// Capability summary, noting when a server is limited to some features.
+fn status_features(server: &ServerConfig, capabilities: &NegotiatedCapabilities) -> String {
+    let mut summary = capabilities.summary();
+    if server.features.is_some() {
+        summary.push_str("  (restricted by config)");
+    }
+    if server.formatter {
+        summary.push_str("  formatter");
+    }
+    summary
+}
+
+const STATUS_STDERR_LINES: usize = 5;
+
+fn status_item(title: String, subtitle: String) -> PickerItem {
+    PickerItem {
+        title,
+        subtitle,
+        path: None,
+        buffer_id: None,
+        line: None,
+        column: None,
+        entry: None,
+    }
 }
@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::core::Cursor;
+use crate::lsp::client::LspFeature;
+use crate::lsp::document_symbol::parse_document_symbols;
+use crate::lsp::goto::parse_location;
+use crate::lsp::position::{LineSource, PositionEncoding};
+use crate::lsp::workspace::LspWorkspace;
+use crate::lsp::workspace::pending::{PendingRequest, RequestKind};
+use crate::lsp::workspace::state::file_uri;
+use crate::ui::{OutlineSymbol, PickerItem};
+
+/// Outline symbols per document.
+#[derive(Debug, Default)]
+pub(super) struct DocumentSymbolState {
+    symbols: HashMap<PathBuf, Vec<OutlineSymbol>>,
+    /// Version the last document symbol request was sent for.
+    requests: HashMap<PathBuf, i32>,
+}
+
+impl DocumentSymbolState {
+    pub(super) fn forget(&mut self, path: &Path) {
+        self.symbols.remove(path);
+        self.requests.remove(path);
+    }
+}
+
+impl LspWorkspace {
+    /// Requests the symbols of `path` once per document version.
+    pub fn request_document_symbols(&mut self, path: &Path) {
+        if !self.progress.done {
+            return;
+        }
+        let version = self.document_version(path);
+        if self.document_symbols.requests.get(path) == Some(&version) {
+            return;
+        }
+        let Some(id) = self.route(path, LspFeature::DocumentSymbols) else {
+            return;
+        };
+        self.document_symbols.requests.insert(path.to_path_buf(), version);
+        let params = serde_json::json!({ "textDocument": { "uri": file_uri(path) } });
+        self.cancel_where(|request| {
+            request.kind == RequestKind::DocumentSymbols && request.path.as_deref() == Some(path)
+        });
+        let start = Cursor::new(0, 0);
+        self.send_request(RequestKind::DocumentSymbols, id, Some(path), start, params);
+    }
+
+    /// Latest symbols a server sent for `path`; they may predate recent edits.
+    pub fn document_symbols(&self, path: &Path) -> Option<&[OutlineSymbol]> {
+        self.document_symbols.symbols.get(path).map(Vec::as_slice)
+    }
+
+    pub(super) fn store_document_symbols(
+        &mut self,
+        request: &PendingRequest,
+        value: &serde_json::Value,
+        lines: &dyn LineSource,
+    ) {
+        let Some(path) = request.path.clone() else {
+            return;
+        };
+        let line_text = |line| lines.line_text(&path, line);
+        let symbols = parse_document_symbols(value, request.encoding, line_text);
+        self.document_symbols.symbols.insert(path, symbols);
+    }
+
+    pub fn request_workspace_symbols(&mut self, query: &str, workspace_root: &Path) -> bool {
+        self.bootstrap_workspace(workspace_root);
+        let Some(id) = self
+            .clients
+            .keys()
+            .copied()
+            .filter(|id| self.serves(*id, LspFeature::WorkspaceSymbols))
+            .min_by_key(|id| id.sort_key())
+        else {
+            return false;
+        };
+        let params = serde_json::json!({ "query": query });
+        self.dispatch(
+            RequestKind::WorkspaceSymbols,
+            id,
+            None,
+            Cursor::new(0, 0),
+            params,
+        )
+    }
+}
+
+pub(super) fn parse_workspace_symbols(
+    value: &serde_json::Value,
+    encoding: PositionEncoding,
+    lines: &dyn LineSource,
+) -> Vec<PickerItem> {
+    value
+        .as_array()
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| {
+                    let name = item.get("name")?.as_str()?.to_owned();
+                    let (path, cursor) = parse_location(item.get("location")?, encoding, lines)?;
+                    Some(PickerItem {
+                        title: name,
+                        subtitle: path.display().to_string(),
+                        path: Some(path),
+                        buffer_id: None,
+                        line: Some(cursor.line),
+                        column: Some(cursor.column),
+                        entry: None,
+                    })
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default()
+}
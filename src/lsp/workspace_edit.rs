@@ -0,0 +1,815 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use lsp_types::{DocumentChangeOperation, DocumentChanges, OneOf, ResourceOp, TextEdit};
+
+use crate::app::{App, AppMode, MessageKind};
+use crate::core::Selection;
+use crate::lsp::position::{PositionEncoding, map_offset, resolve_text_edits};
+use crate::lsp::workspace::{CodeActionRun, ServerReply, parse_file_uri};
+use crate::syntax::language_for_path;
+
+/// One step of a workspace edit. Steps are applied in order.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EditOperation {
+    Text {
+        path: PathBuf,
+        /// Document version the edits were computed for, when the server said.
+        version: Option<i32>,
+        edits: Vec<TextEdit>,
+    },
+    Create {
+        path: PathBuf,
+        overwrite: bool,
+        ignore_if_exists: bool,
+    },
+    Rename {
+        from: PathBuf,
+        to: PathBuf,
+        overwrite: bool,
+        ignore_if_exists: bool,
+    },
+    Delete {
+        path: PathBuf,
+        recursive: bool,
+        ignore_if_not_exists: bool,
+    },
+}
+
+/// A server's `WorkspaceEdit`, with positions in that server's encoding.
+#[derive(Debug, Clone, Default)]
+pub struct WorkspaceEditPlan {
+    pub operations: Vec<EditOperation>,
+    pub encoding: PositionEncoding,
+}
+
+impl WorkspaceEditPlan {
+    /// Reads `documentChanges` when present and the `changes` map otherwise.
+    pub fn parse(value: &serde_json::Value, encoding: PositionEncoding) -> Self {
+        let operations = serde_json::from_value::<lsp_types::WorkspaceEdit>(value.clone())
+            .map(|edit| match edit.document_changes {
+                Some(DocumentChanges::Edits(edits)) => {
+                    edits.into_iter().filter_map(document_edit).collect()
+                }
+                Some(DocumentChanges::Operations(operations)) => operations
+                    .into_iter()
+                    .filter_map(|operation| match operation {
+                        DocumentChangeOperation::Edit(edit) => document_edit(edit),
+                        DocumentChangeOperation::Op(operation) => resource_operation(operation),
+                    })
+                    .collect(),
+                None => {
+                    let mut changes = edit
+                        .changes
+                        .unwrap_or_default()
+                        .into_iter()
+                        .filter_map(|(uri, edits)| {
+                            Some(EditOperation::Text {
+                                path: parse_file_uri(uri.as_str())?,
+                                version: None,
+                                edits,
+                            })
+                        })
+                        .collect::<Vec<_>>();
+                    changes.sort_by(|left, right| operation_path(left).cmp(operation_path(right)));
+                    changes
+                }
+            })
+            .unwrap_or_default();
+        Self {
+            operations,
+            encoding,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.operations.iter().all(|operation| {
+            matches!(operation, EditOperation::Text { edits, .. } if edits.is_empty())
+        })
+    }
+}
+
+fn document_edit(edit: lsp_types::TextDocumentEdit) -> Option<EditOperation> {
+    let edits = edit
+        .edits
+        .into_iter()
+        .map(|edit| match edit {
+            OneOf::Left(edit) => edit,
+            OneOf::Right(annotated) => annotated.text_edit,
+        })
+        .collect();
+    Some(EditOperation::Text {
+        path: parse_file_uri(edit.text_document.uri.as_str())?,
+        version: edit.text_document.version,
+        edits,
+    })
+}
+
+fn resource_operation(operation: ResourceOp) -> Option<EditOperation> {
+    Some(match operation {
+        ResourceOp::Create(create) => {
+            let options = create.options.as_ref();
+            EditOperation::Create {
+                path: parse_file_uri(create.uri.as_str())?,
+                overwrite: options.and_then(|options| options.overwrite) == Some(true),
+                ignore_if_exists: options.and_then(|options| options.ignore_if_exists)
+                    == Some(true),
+            }
+        }
+        ResourceOp::Rename(rename) => {
+            let options = rename.options.as_ref();
+            EditOperation::Rename {
+                from: parse_file_uri(rename.old_uri.as_str())?,
+                to: parse_file_uri(rename.new_uri.as_str())?,
+                overwrite: options.and_then(|options| options.overwrite) == Some(true),
+                ignore_if_exists: options.and_then(|options| options.ignore_if_exists)
+                    == Some(true),
+            }
+        }
+        ResourceOp::Delete(delete) => {
+            let options = delete.options.as_ref();
+            EditOperation::Delete {
+                path: parse_file_uri(delete.uri.as_str())?,
+                recursive: options.and_then(|options| options.recursive) == Some(true),
+                ignore_if_not_exists: options.and_then(|options| options.ignore_if_not_exists)
+                    == Some(true),
+            }
+        }
+    })
+}
+
+fn operation_path(operation: &EditOperation) -> &Path {
+    match operation {
+        EditOperation::Text { path, .. }
+        | EditOperation::Create { path, .. }
+        | EditOperation::Delete { path, .. } => path,
+        EditOperation::Rename { from, .. } => from,
+    }
+}
+
+/// Who asked for a workspace edit, which decides how its outcome is reported.
+#[derive(Debug)]
+pub(crate) enum EditOrigin {
+    Rename,
+    CodeAction(CodeActionRun),
+    /// A server's `workspace/applyEdit`, answered once the edit is settled.
+    Server {
+        reply: ServerReply,
+        label: Option<String>,
+    },
+}
+
+impl App {
+    /// Checks a server's workspace edit and applies it, asking first when it
+    /// would delete a directory tree or overwrite a file.
+    pub(crate) fn begin_workspace_edit(&mut self, plan: WorkspaceEditPlan, origin: EditOrigin) {
+        let risks = match self.check_workspace_edit(&plan) {
+            Ok(risks) => risks,
+            Err(error) => return self.finish_workspace_edit(origin, Err(error)),
+        };
+        if risks.is_empty() {
+            let result = self.apply_workspace_edit(&plan);
+            return self.finish_workspace_edit(origin, result);
+        }
+        if self.mode != AppMode::Editing || self.pending_workspace_edit.is_some() {
+            let busy = String::from("another prompt is waiting for an answer");
+            return self.finish_workspace_edit(origin, Err(busy));
+        }
+        self.picker = None;
+        self.set_message(
+            &format!("Language server edit {}: y apply, n reject", risks.join(", ")),
+            MessageKind::Warning,
+        );
+        self.pending_workspace_edit = Some((plan, origin));
+        self.mode = AppMode::ConfirmWorkspaceEdit;
+    }
+
+    /// Applies or rejects the edit waiting in `ConfirmWorkspaceEdit`.
+    pub(crate) fn confirm_workspace_edit(&mut self, accept: bool) {
+        self.mode = AppMode::Editing;
+        let Some((plan, origin)) = self.pending_workspace_edit.take() else {
+            return;
+        };
+        let result = if accept {
+            // Files may have changed while the prompt was up.
+            self.check_workspace_edit(&plan)
+                .and_then(|_| self.apply_workspace_edit(&plan))
+        } else {
+            Err(String::from("rejected by the user"))
+        };
+        self.finish_workspace_edit(origin, result);
+    }
+
+    fn finish_workspace_edit(&mut self, origin: EditOrigin, result: Result<usize, String>) {
+        match origin {
+            EditOrigin::Rename => match result {
+                Ok(changed) => self.set_message(
+                    &format!("Rename applied ({changed} change(s))"),
+                    MessageKind::Info,
+                ),
+                Err(error) => {
+                    self.set_message(&format!("Rename failed: {error}"), MessageKind::Error);
+                }
+            },
+            EditOrigin::CodeAction(run) => {
+                if let Err(error) = result {
+                    self.set_message(&format!("Code action failed: {error}"), MessageKind::Error);
+                    return;
+                }
+                // The command runs only once the edit went through.
+                if self.lsp.execute_command(&run) || !run.edit.is_empty() {
+                    self.set_message(&format!("Applied: {}", run.title), MessageKind::Info);
+                } else {
+                    self.set_message("Code action made no changes", MessageKind::Info);
+                }
+            }
+            EditOrigin::Server { reply, label } => {
+                match (&result, label) {
+                    (Ok(_), Some(label)) => self.set_message(&label, MessageKind::Info),
+                    (Ok(_), None) => {}
+                    (Err(error), _) => {
+                        self.set_message(&format!("Edit failed: {error}"), MessageKind::Error);
+                    }
+                }
+                self.lsp.answer_apply_edit(reply, result);
+            }
+        }
+    }
+
+    /// Validates every step before anything changes: paths must resolve
+    /// inside the workspace, versioned edits must match the open document
+    /// and text ranges must fit. Returns the steps that need the user's
+    /// consent, i.e. recursive deletes and overwrites.
+    fn check_workspace_edit(&self, plan: &WorkspaceEditPlan) -> Result<Vec<String>, String> {
+        let root = fs::canonicalize(&self.workspace_root)
+            .unwrap_or_else(|_| self.workspace_root.clone());
+        // Existence of paths created or removed by earlier steps.
+        let mut changes: Vec<(PathBuf, bool)> = Vec::new();
+        let mut risks = Vec::new();
+        for operation in &plan.operations {
+            for path in operation_paths(operation) {
+                if !resolve_path(path).is_some_and(|resolved| resolved.starts_with(&root)) {
+                    return Err(format!("{} is outside the workspace", path.display()));
+                }
+            }
+            match operation {
+                EditOperation::Text {
+                    path,
+                    version,
+                    edits,
+                } => {
+                    if let (Some(version), Some(open)) = (version, self.lsp.open_version(path))
+                        && *version != open
+                    {
+                        return Err(format!("{} changed since the edit was made", path.display()));
+                    }
+                    if !changes.iter().any(|(changed, _)| path.starts_with(changed)) {
+                        self.check_text_edits(path, edits, plan.encoding)?;
+                    }
+                }
+                EditOperation::Create {
+                    path,
+                    overwrite,
+                    ignore_if_exists,
+                } => {
+                    let exists = exists_after(path, &changes);
+                    if exists && !overwrite {
+                        if *ignore_if_exists {
+                            continue;
+                        }
+                        return Err(format!("{} already exists", path.display()));
+                    }
+                    if exists {
+                        risks.push(format!("overwrites {}", self.shown_path(path)));
+                    }
+                    changes.push((path.clone(), true));
+                }
+                EditOperation::Rename {
+                    from,
+                    to,
+                    overwrite,
+                    ignore_if_exists,
+                } => {
+                    if !exists_after(from, &changes) {
+                        return Err(format!("{} does not exist", from.display()));
+                    }
+                    let exists = exists_after(to, &changes);
+                    if exists && !overwrite {
+                        if *ignore_if_exists {
+                            continue;
+                        }
+                        return Err(format!("{} already exists", to.display()));
+                    }
+                    if exists {
+                        risks.push(format!("overwrites {}", self.shown_path(to)));
+                    }
+                    changes.push((from.clone(), false));
+                    changes.push((to.clone(), true));
+                }
+                EditOperation::Delete {
+                    path,
+                    recursive,
+                    ignore_if_not_exists,
+                } => {
+                    if !exists_after(path, &changes) {
+                        if *ignore_if_not_exists {
+                            continue;
+                        }
+                        return Err(format!("{} does not exist", path.display()));
+                    }
+                    if *recursive && path.is_dir() {
+                        let shown = self.shown_path(path);
+                        risks.push(format!("deletes {shown} and all it contains"));
+                    }
+                    changes.push((path.clone(), false));
+                }
+            }
+        }
+        Ok(risks)
+    }
+
+    fn check_text_edits(
+        &self,
+        path: &Path,
+        edits: &[TextEdit],
+        encoding: PositionEncoding,
+    ) -> Result<(), String> {
+        let text = match self
+            .buffers
+            .iter()
+            .find(|buffer| buffer.document.path() == Some(path))
+        {
+            Some(buffer) if buffer.scratch_name.is_some() || buffer.hex.is_some() => {
+                return Err(format!("{} cannot be edited", buffer.display_name()));
+            }
+            Some(buffer) if buffer.large_file => {
+                return Err(format!("{} cannot be edited", buffer.display_name()));
+            }
+            Some(buffer) => buffer.document.text(),
+            None => crate::file::load_document(path)
+                .map_err(|error| format!("cannot open {}: {error}", path.display()))?
+                .document
+                .text(),
+        };
+        resolve_text_edits(&text, edits, encoding)
+            .map(|_| ())
+            .ok_or_else(|| format!("invalid edit ranges for {}", path.display()))
+    }
+
+    fn shown_path(&self, path: &Path) -> String {
+        path.strip_prefix(&self.workspace_root)
+            .unwrap_or(path)
+            .display()
+            .to_string()
+    }
+
+    /// Applies a workspace edit that `check_workspace_edit` accepted. Text
+    /// edits land in buffers, opening files that are not open yet, as one
+    /// undo step per buffer; file operations run on disk. Returns how many
+    /// steps changed something.
+    fn apply_workspace_edit(&mut self, plan: &WorkspaceEditPlan) -> Result<usize, String> {
+        let mut changed = 0;
+        for operation in &plan.operations {
+            let applied = match operation {
+                EditOperation::Text { path, edits, .. } => {
+                    let buffer_id = self.buffer_for_edit(path)?;
+                    self.apply_buffer_text_edits(buffer_id, edits, plan.encoding)?
+                }
+                EditOperation::Create {
+                    path,
+                    overwrite,
+                    ignore_if_exists,
+                } => self.create_file(path, *overwrite, *ignore_if_exists)?,
+                EditOperation::Rename {
+                    from,
+                    to,
+                    overwrite,
+                    ignore_if_exists,
+                } => self.rename_file(from, to, *overwrite, *ignore_if_exists)?,
+                EditOperation::Delete {
+                    path,
+                    recursive,
+                    ignore_if_not_exists,
+                } => self.delete_file(path, *recursive, *ignore_if_not_exists)?,
+            };
+            changed += usize::from(applied);
+        }
+        Ok(changed)
+    }
+
+    fn buffer_for_edit(&mut self, path: &Path) -> Result<u64, String> {
+        if let Some(buffer) = self
+            .buffers
+            .iter()
+            .find(|buffer| buffer.document.path() == Some(path))
+        {
+            return Ok(buffer.id);
+        }
+        let buffer_id = self.load_buffer(path).map_err(|error| error.to_string())?;
+        if let Some(text) = self
+            .buffer_by_id(buffer_id)
+            .filter(|buffer| !buffer.large_file)
+            .map(|buffer| buffer.document.text())
+        {
+            self.lsp.did_open(path, &text, &self.workspace_root);
+        }
+        Ok(buffer_id)
+    }
+
+    /// Applies server text edits to one buffer as a single undo step, each
+    /// at its own range. Cursors and selections of every pane on the buffer
+    /// follow the text they were on. Returns whether the text changed.
+    pub(crate) fn apply_buffer_text_edits(
+        &mut self,
+        buffer_id: u64,
+        edits: &[TextEdit],
+        encoding: PositionEncoding,
+    ) -> Result<bool, String> {
+        let Some(buffer_index) = self.buffers.iter().position(|buffer| buffer.id == buffer_id)
+        else {
+            return Err(String::from("buffer was closed"));
+        };
+        let buffer = &self.buffers[buffer_index];
+        if buffer.scratch_name.is_some() || buffer.hex.is_some() || buffer.large_file {
+            return Err(format!("{} cannot be edited", buffer.display_name()));
+        }
+        let document = &buffer.document;
+        let text = document.text();
+        let mut resolved = resolve_text_edits(&text, edits, encoding)
+            .ok_or_else(|| format!("invalid edit ranges for {}", buffer.display_name()))?;
+        resolved.retain(|(range, new_text)| text[range.clone()] != *new_text);
+        if resolved.is_empty() {
+            return Ok(false);
+        }
+        let panes = self
+            .layout
+            .pane_ids()
+            .into_iter()
+            .filter_map(|pane_id| {
+                let pane = self.layout.pane(pane_id)?;
+                (pane.buffer_id() == buffer_id).then(|| {
+                    let anchor = document.cursor_to_byte(pane.selection().anchor());
+                    (pane_id, anchor, document.cursor_to_byte(pane.cursor()))
+                })
+            })
+            .collect::<Vec<_>>();
+        let mut cursor_before = (buffer_id == self.active_buffer_id)
+            .then(|| self.active_pane().cursor());
+
+        // Later ranges go first so the earlier ones keep their offsets.
+        let mut applied = 0;
+        for (range, new_text) in resolved.iter().rev() {
+            let document = &self.buffers[buffer_index].document;
+            let start = document.byte_to_cursor(range.start);
+            let end = document.byte_to_cursor(range.end);
+            let before = cursor_before.take().unwrap_or(start);
+            let Some((deleted, _)) =
+                self.edit_buffer(buffer_index, start, end, new_text, before, false)
+            else {
+                continue;
+            };
+            if applied > 0 {
+                self.buffers[buffer_index].history.join_last();
+            }
+            applied += 1;
+            self.notify_lsp_buffer_edit(buffer_id, start, &deleted, new_text);
+        }
+
+        let document = &self.buffers[buffer_index].document;
+        for (pane_id, anchor, cursor) in panes {
+            let anchor = document.byte_to_cursor(map_offset(anchor, &resolved));
+            let cursor = document.byte_to_cursor(map_offset(cursor, &resolved));
+            let cursor = cursor.with_preferred_column(document.display_column(cursor));
+            if let Some(pane) = self.layout.pane_mut(pane_id) {
+                pane.set_cursor(cursor);
+                pane.set_selection(Selection::caret(anchor).with_active(cursor));
+                pane.search_mut().refresh_for_document(document, cursor);
+            }
+        }
+        if buffer_id == self.active_buffer_id {
+            self.ensure_cursor_visible();
+        }
+        Ok(true)
+    }
+
+    /// Moves a file or directory and repoints the buffers inside it.
+    fn rename_file(
+        &mut self,
+        from: &Path,
+        to: &Path,
+        overwrite: bool,
+        ignore_if_exists: bool,
+    ) -> Result<bool, String> {
+        if to.exists() && !overwrite {
+            if ignore_if_exists {
+                return Ok(false);
+            }
+            return Err(format!("{} already exists", to.display()));
+        }
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|error| format!("cannot create {}: {error}", parent.display()))?;
+        }
+        fs::rename(from, to)
+            .map_err(|error| format!("cannot rename {}: {error}", from.display()))?;
+
+        for index in 0..self.buffers.len() {
+            let buffer = &self.buffers[index];
+            let Some(old_path) = buffer.document.path().map(Path::to_path_buf) else {
+                continue;
+            };
+            let Ok(suffix) = old_path.strip_prefix(from) else {
+                continue;
+            };
+            let new_path = if suffix.as_os_str().is_empty() {
+                to.to_path_buf()
+            } else {
+                to.join(suffix)
+            };
+            self.lsp.did_close(&old_path);
+            let buffer = &mut self.buffers[index];
+            buffer.document.set_path(new_path.clone());
+            if buffer.large_file {
+                continue;
+            }
+            let _ = buffer.syntax.set_language_id(language_for_path(&new_path));
+            buffer.syntax.mark_dirty();
+            let text = buffer.document.text();
+            self.lsp.did_open(&new_path, &text, &self.workspace_root);
+        }
+        Ok(true)
+    }
+
+    /// Deletes a file or directory. Open buffers keep their text, now unsaved.
+    fn delete_file(
+        &mut self,
+        path: &Path,
+        recursive: bool,
+        ignore_if_not_exists: bool,
+    ) -> Result<bool, String> {
+        let result = if path.is_dir() {
+            if recursive {
+                fs::remove_dir_all(path)
+            } else {
+                fs::remove_dir(path)
+            }
+        } else {
+            fs::remove_file(path)
+        };
+        match result {
+            Ok(()) => {}
+            Err(error) if error.kind() == ErrorKind::NotFound && ignore_if_not_exists => {
+                return Ok(false);
+            }
+            Err(error) => return Err(format!("cannot delete {}: {error}", path.display())),
+        }
+        for index in 0..self.buffers.len() {
+            let Some(buffer_path) = self.buffers[index].document.path().map(Path::to_path_buf)
+            else {
+                continue;
+            };
+            if buffer_path.starts_with(path) {
+                self.lsp.did_close(&buffer_path);
+                self.buffers[index].document.set_dirty(true);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Creates an empty file; an open buffer on an overwritten file is
+    /// reloaded so it shows what is now on disk.
+    fn create_file(
+        &mut self,
+        path: &Path,
+        overwrite: bool,
+        ignore_if_exists: bool,
+    ) -> Result<bool, String> {
+        if path.exists() && !overwrite {
+            if ignore_if_exists {
+                return Ok(false);
+            }
+            return Err(format!("{} already exists", path.display()));
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|error| format!("cannot create {}: {error}", parent.display()))?;
+        }
+        fs::write(path, "").map_err(|error| format!("cannot create {}: {error}", path.display()))?;
+        if let Some(index) = self
+            .buffers
+            .iter()
+            .position(|buffer| buffer.document.path() == Some(path))
+            && let Ok(loaded) = crate::file::load_document(path)
+        {
+            self.buffers[index].reload_from(loaded);
+            let text = self.buffers[index].document.text();
+            self.lsp.did_change(path, &text, &self.workspace_root);
+            for pane_id in self.layout.pane_ids() {
+                if let Some(pane) = self.layout.pane_mut(pane_id)
+                    && pane.buffer_id() == self.buffers[index].id
+                {
+                    pane.set_cursor(crate::core::Cursor::new(0, 0));
+                    pane.set_selection(Selection::caret(crate::core::Cursor::new(0, 0)));
+                }
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// Every path an operation touches.
+fn operation_paths(operation: &EditOperation) -> Vec<&Path> {
+    match operation {
+        EditOperation::Rename { from, to, .. } => vec![from, to],
+        operation => vec![operation_path(operation)],
+    }
+}
+
+/// `path` with links and `..` resolved, for paths that may not exist yet:
+/// the deepest existing ancestor is canonicalized and the rest appended.
+/// `None` when a missing part is `..`.
+fn resolve_path(path: &Path) -> Option<PathBuf> {
+    let mut existing = path;
+    let mut missing = Vec::new();
+    loop {
+        if let Ok(resolved) = existing.canonicalize() {
+            return Some(missing.into_iter().rev().fold(resolved, |path, part| path.join(part)));
+        }
+        missing.push(existing.file_name()?);
+        existing = existing.parent()?;
+    }
+}
+
+/// Whether `path` exists once the recorded `(path, exists)` steps ran; a
+/// removed directory takes everything inside it along.
+fn exists_after(path: &Path, changes: &[(PathBuf, bool)]) -> bool {
+    for (changed, exists) in changes.iter().rev() {
+        if path == changed {
+            return *exists;
+        }
+        if !exists && path.starts_with(changed) {
+            return false;
+        }
+    }
+    path.exists()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use serde_json::json;
+
+    use crossterm::event::{KeyCode, KeyEvent};
+    use lsp_types::{Position, Range, TextEdit};
+
+    use super::{EditOperation, EditOrigin, WorkspaceEditPlan};
+    use crate::app::{App, AppMode};
+    use crate::lsp::position::PositionEncoding;
+
+    #[test]
+    fn document_changes_keep_resource_operations_in_order() {
+        let edit = json!({
+            "changes": { "file:///ignored.rs": [] },
+            "documentChanges": [
+                { "kind": "create", "uri": "file:///tmp/new.rs" },
+                {
+                    "textDocument": { "uri": "file:///tmp/new.rs", "version": null },
+                    "edits": [{
+                        "range": {
+                            "start": { "line": 0, "character": 0 },
+                            "end": { "line": 0, "character": 0 }
+                        },
+                        "newText": "fn main() {}\n"
+                    }]
+                },
+                {
+                    "kind": "rename",
+                    "oldUri": "file:///tmp/old.rs",
+                    "newUri": "file:///tmp/renamed.rs",
+                    "options": { "overwrite": true }
+                },
+                { "kind": "delete", "uri": "file:///tmp/gone", "options": { "recursive": true } }
+            ]
+        });
+        let plan = WorkspaceEditPlan::parse(&edit, PositionEncoding::Utf8);
+        assert_eq!(plan.operations.len(), 4);
+        assert_eq!(
+            plan.operations[0],
+            EditOperation::Create {
+                path: PathBuf::from("/tmp/new.rs"),
+                overwrite: false,
+                ignore_if_exists: false,
+            }
+        );
+        assert!(matches!(
+            &plan.operations[1],
+            EditOperation::Text { edits, .. } if edits.len() == 1
+        ));
+        assert!(matches!(
+            &plan.operations[2],
+            EditOperation::Rename { overwrite: true, .. }
+        ));
+        assert!(matches!(
+            &plan.operations[3],
+            EditOperation::Delete { recursive: true, .. }
+        ));
+        assert!(!plan.is_empty());
+        assert!(WorkspaceEditPlan::parse(&json!({}), PositionEncoding::Utf8).is_empty());
+    }
+
+    #[test]
+    fn server_edits_stay_inside_the_workspace_and_ask_before_deleting_trees() {
+        let root = tempfile::tempdir().unwrap_or_else(|error| panic!("{error}"));
+        let outside = tempfile::tempdir().unwrap_or_else(|error| panic!("{error}"));
+        let kept = root.path().join("kept.rs");
+        let tree = root.path().join("tree");
+        std::fs::write(&kept, "old\n").unwrap_or_else(|error| panic!("{error}"));
+        std::fs::create_dir_all(tree.join("inner")).unwrap_or_else(|error| panic!("{error}"));
+        let mut app = App::open(None).unwrap_or_else(|error| panic!("{error}"));
+        app.workspace_root = root.path().to_path_buf();
+        let plan = |operations| WorkspaceEditPlan {
+            operations,
+            encoding: PositionEncoding::Utf8,
+        };
+        let text = EditOperation::Text {
+            path: kept.clone(),
+            version: None,
+            edits: vec![TextEdit {
+                range: Range::new(Position::new(0, 0), Position::new(0, 3)),
+                new_text: String::from("new"),
+            }],
+        };
+
+        // One step outside the workspace rejects the whole edit.
+        let escape = EditOperation::Delete {
+            path: root.path().join("..").join(outside.path().file_name().unwrap_or_default()),
+            recursive: true,
+            ignore_if_not_exists: false,
+        };
+        app.begin_workspace_edit(plan(vec![text.clone(), escape]), EditOrigin::Rename);
+        assert!(app.message().is_some_and(|message| message.contains("outside the workspace")));
+        assert!(outside.path().exists());
+        assert_eq!(std::fs::read_to_string(&kept).unwrap_or_default(), "old\n");
+
+        // Ranges past the end of the file are caught before anything changes.
+        let past_end = EditOperation::Text {
+            path: kept.clone(),
+            version: None,
+            edits: vec![TextEdit {
+                range: Range::new(Position::new(9, 0), Position::new(9, 1)),
+                new_text: String::new(),
+            }],
+        };
+        let remove = EditOperation::Delete {
+            path: tree.join("inner"),
+            recursive: true,
+            ignore_if_not_exists: false,
+        };
+        app.begin_workspace_edit(plan(vec![remove.clone(), past_end]), EditOrigin::Rename);
+        assert!(matches!(app.mode(), AppMode::Editing));
+        assert!(tree.join("inner").exists());
+
+        // A recursive delete waits for the user.
+        app.begin_workspace_edit(plan(vec![text, remove]), EditOrigin::Rename);
+        assert!(matches!(app.mode(), AppMode::ConfirmWorkspaceEdit));
+        assert!(tree.join("inner").exists());
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('y')))
+            .unwrap_or_else(|error| panic!("{error}"));
+        assert!(matches!(app.mode(), AppMode::Editing));
+        assert!(!tree.join("inner").exists());
+        let edited = app.buffers.iter().find(|buffer| buffer.document.path() == Some(&kept));
+        assert_eq!(edited.map(|buffer| buffer.document.text()).as_deref(), Some("new\n"));
+    }
+
+    #[test]
+    fn text_edits_apply_at_their_own_ranges_as_one_undo_step() {
+        let mut app = App::open(None).unwrap_or_else(|error| panic!("{error}"));
+        let buffer_id = app.active_buffer_id;
+        let edit = |line: u32, start: u32, end: u32, text: &str| TextEdit {
+            range: Range::new(Position::new(line, start), Position::new(line, end)),
+            new_text: String::from(text),
+        };
+        let encoding = PositionEncoding::Utf8;
+        let typed = [edit(0, 0, 0, "fn main() {\n    let map = Has;\n}\n")];
+        let _ = app.apply_buffer_text_edits(buffer_id, &typed, encoding);
+        let accepted = [
+            edit(1, 14, 17, "HashMap"),
+            edit(0, 0, 0, "use std::collections::HashMap;\n"),
+        ];
+        assert_eq!(app.apply_buffer_text_edits(buffer_id, &accepted, encoding), Ok(true));
+        assert_eq!(
+            app.active_document().text(),
+            "use std::collections::HashMap;\nfn main() {\n    let map = HashMap;\n}\n"
+        );
+        let history = &app.active_buffer().history;
+        let starts = history.last_applied().iter().map(|record| record.start.line);
+        assert_eq!(starts.collect::<Vec<_>>(), [1, 0]);
+
+        app.undo();
+        assert_eq!(app.active_document().text(), "fn main() {\n    let map = Has;\n}\n");
+        app.redo();
+        assert!(app.active_document().text().starts_with("use std::collections::HashMap;"));
+    }
+}
@@ -65,6 +65,50 @@ impl LanguageId {
             LanguageId::Markdown => include_str!("../../assets/queries/markdown/highlights.scm"),
         }
     }
+
+    /// Definitions query for the outline, for languages that have one.
+    pub fn tags_query_source(self) -> Option<&'static str> {
+        let source = match self {
+            LanguageId::Rust => include_str!("../../assets/queries/rust/tags.scm"),
+            LanguageId::JavaScript => include_str!("../../assets/queries/javascript/tags.scm"),
+            LanguageId::TypeScript => include_str!("../../assets/queries/typescript/tags.scm"),
+            LanguageId::Python => include_str!("../../assets/queries/python/tags.scm"),
+            LanguageId::Go => include_str!("../../assets/queries/go/tags.scm"),
+            LanguageId::C => include_str!("../../assets/queries/c/tags.scm"),
+            LanguageId::Cpp => include_str!("../../assets/queries/cpp/tags.scm"),
+            LanguageId::Bash => include_str!("../../assets/queries/bash/tags.scm"),
+            LanguageId::Lua => include_str!("../../assets/queries/lua/tags.scm"),
+            LanguageId::Markdown => include_str!("../../assets/queries/markdown/tags.scm"),
+            LanguageId::Html
+            | LanguageId::Css
+            | LanguageId::Json
+            | LanguageId::Toml
+            | LanguageId::Yaml => return None,
+        };
+        Some(source)
+    }
+
+    /// Scope and binding query for highlighting a name's occurrences.
+    pub fn locals_query_source(self) -> Option<&'static str> {
+        let source = match self {
+            LanguageId::Rust => include_str!("../../assets/queries/rust/locals.scm"),
+            LanguageId::JavaScript => include_str!("../../assets/queries/javascript/locals.scm"),
+            LanguageId::TypeScript => include_str!("../../assets/queries/typescript/locals.scm"),
+            LanguageId::Python => include_str!("../../assets/queries/python/locals.scm"),
+            LanguageId::Go => include_str!("../../assets/queries/go/locals.scm"),
+            LanguageId::C => include_str!("../../assets/queries/c/locals.scm"),
+            LanguageId::Cpp => include_str!("../../assets/queries/cpp/locals.scm"),
+            LanguageId::Yaml => include_str!("../../assets/queries/yaml/locals.scm"),
+            LanguageId::Bash => include_str!("../../assets/queries/bash/locals.scm"),
+            LanguageId::Lua => include_str!("../../assets/queries/lua/locals.scm"),
+            LanguageId::Html
+            | LanguageId::Css
+            | LanguageId::Json
+            | LanguageId::Toml
+            | LanguageId::Markdown => return None,
+        };
+        Some(source)
+    }
 }
 
 pub fn language_for_path(path: &Path) -> Option<LanguageId> {
@@ -0,0 +1,195 @@
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+use tree_sitter::{Query, QueryCursor, StreamingIterator, Tree};
+
+/// A name matched by a language's `locals.scm` query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalOccurrence {
+    pub range: Range<usize>,
+    /// Captured as `@local.definition` rather than `@local.reference`.
+    pub definition: bool,
+}
+
+/// Scopes and names of one parse, with each name's owning scopes worked
+/// out once so lookups only walk the scope chain of a single occurrence.
+#[derive(Debug, Default)]
+pub struct LocalBindings {
+    /// Sorted by start, outer scopes before the ones they contain.
+    scopes: Vec<Range<usize>>,
+    parents: Vec<Option<usize>>,
+    /// Sorted by position, each with its innermost scope and name id.
+    occurrences: Vec<(LocalOccurrence, Option<usize>, usize)>,
+    /// Every scope holding a definition of each name, by name id.
+    defined: Vec<HashSet<usize>>,
+}
+
+impl LocalBindings {
+    pub fn new(tree: &Tree, query: &Query, source: &[u8]) -> Self {
+        let names = query.capture_names();
+        let mut cursor = QueryCursor::new();
+        let mut captures = cursor.captures(query, tree.root_node(), source);
+        let mut scopes = Vec::new();
+        let mut occurrences = Vec::new();
+        while let Some((found, index)) = captures.next() {
+            let capture = found.captures[*index];
+            let range = capture.node.byte_range();
+            match names[capture.index as usize] {
+                "local.scope" => scopes.push(range),
+                "local.definition" => occurrences.push(LocalOccurrence {
+                    range,
+                    definition: true,
+                }),
+                "local.reference" => occurrences.push(LocalOccurrence {
+                    range,
+                    definition: false,
+                }),
+                _ => {}
+            }
+        }
+        scopes.sort_by_key(|scope| (scope.start, Reverse(scope.end)));
+        scopes.dedup();
+        // Syntax nodes nest, so the enclosing scope is still on the stack.
+        let mut parents = Vec::with_capacity(scopes.len());
+        let mut stack: Vec<usize> = Vec::new();
+        for (index, scope) in scopes.iter().enumerate() {
+            while stack.last().is_some_and(|&open| !contains(&scopes[open], scope)) {
+                stack.pop();
+            }
+            parents.push(stack.last().copied());
+            stack.push(index);
+        }
+        // A node captured both ways counts as a definition.
+        occurrences.sort_by_key(|item| (item.range.start, item.range.end, !item.definition));
+        occurrences.dedup_by(|next, kept| next.range == kept.range);
+
+        let mut bindings = Self {
+            scopes,
+            parents,
+            occurrences: Vec::new(),
+            defined: Vec::new(),
+        };
+        // Names are compared by id, so lookups need no source text.
+        let mut name_ids: HashMap<&[u8], usize> = HashMap::new();
+        bindings.occurrences = occurrences
+            .into_iter()
+            .map(|item| {
+                let scope = bindings.innermost_scope(&item.range);
+                let next = name_ids.len();
+                let name = *name_ids
+                    .entry(source.get(item.range.clone()).unwrap_or_default())
+                    .or_insert(next);
+                (item, scope, name)
+            })
+            .collect();
+        bindings.defined = vec![HashSet::new(); name_ids.len()];
+        for (item, scope, name) in &bindings.occurrences {
+            if !item.definition {
+                continue;
+            }
+            let holders = &mut bindings.defined[*name];
+            let mut scope = *scope;
+            while let Some(index) = scope {
+                // Outer scopes were marked by an earlier definition.
+                if !holders.insert(index) {
+                    break;
+                }
+                scope = bindings.parents[index];
+            }
+        }
+        bindings
+    }
+
+    /// Occurrences of the name at `byte` that resolve to the same binding:
+    /// each belongs to the innermost scope defining the name, or to the
+    /// whole file.
+    pub fn occurrences_at(&self, byte: usize) -> Vec<LocalOccurrence> {
+        let Some((_, target_scope, name)) = self
+            .occurrences
+            .iter()
+            .find(|(item, _, _)| item.range.contains(&byte))
+        else {
+            return Vec::new();
+        };
+        let target_owner = self.owner(*name, *target_scope);
+        self.occurrences
+            .iter()
+            .filter(|(_, scope, other)| other == name && self.owner(*name, *scope) == target_owner)
+            .map(|(item, _, _)| item.clone())
+            .collect()
+    }
+
+    /// The last scope starting at or before `range` is the innermost one
+    /// containing it or nested inside it.
+    fn innermost_scope(&self, range: &Range<usize>) -> Option<usize> {
+        let after = self.scopes.partition_point(|scope| scope.start <= range.start);
+        let mut scope = after.checked_sub(1);
+        while let Some(index) = scope {
+            if contains(&self.scopes[index], range) {
+                return Some(index);
+            }
+            scope = self.parents[index];
+        }
+        None
+    }
+
+    fn owner(&self, name: usize, mut scope: Option<usize>) -> Option<usize> {
+        let holders = self.defined.get(name)?;
+        while let Some(index) = scope {
+            if holders.contains(&index) {
+                return Some(index);
+            }
+            scope = self.parents[index];
+        }
+        None
+    }
+}
+
+fn contains(outer: &Range<usize>, inner: &Range<usize>) -> bool {
+    outer.start <= inner.start && inner.end <= outer.end
+}
+
+#[cfg(test)]
+mod tests {
+    use tree_sitter::Parser;
+
+    use super::LocalBindings;
+    use crate::syntax::{LanguageId, LanguageRegistry};
+
+    #[test]
+    fn resolves_shadowed_names_to_their_scope() {
+        let source = "local x = 1\nfunction f()\n  local x = 2\n  print(x)\nend\nprint(x)\n\
+                      function g()\n  local y = x\n  if y then\n    print(y)\n  end\nend\n";
+        let language = LanguageId::Lua;
+        let mut parser = Parser::new();
+        parser
+            .set_language(&language.ts_language())
+            .unwrap_or_else(|error| panic!("language: {error}"));
+        let tree = parser
+            .parse(source, None)
+            .unwrap_or_else(|| panic!("source should parse"));
+        let query = LanguageRegistry::global()
+            .locals_query(language)
+            .unwrap_or_else(|| panic!("lua has a locals query"));
+        let bindings = LocalBindings::new(&tree, query, source.as_bytes());
+        let found = |byte| {
+            bindings
+                .occurrences_at(byte)
+                .into_iter()
+                .map(|item| (item.range.start, item.definition))
+                .collect::<Vec<_>>()
+        };
+        let global = vec![(6, true), (60, false), (88, false)];
+        assert_eq!(found(6), global);
+        assert_eq!(found(60), global);
+        assert_eq!(found(47), vec![(33, true), (47, false)]);
+        assert_eq!(found(41), vec![(41, false), (54, false), (106, false)]);
+        // References in nested blocks reach the definition in an outer scope.
+        let y = source.find("local y").map_or(0, |at| at + 6);
+        let tested = source.find("if y").map_or(0, |at| at + 3);
+        let used = source.rfind("print(y)").map_or(0, |at| at + 6);
+        assert_eq!(found(used), vec![(y, true), (tested, false), (used, false)]);
+        assert_eq!(found(88), global);
+    }
+}
@@ -4,8 +4,10 @@ mod highlight;
 mod indent;
 mod language;
 mod layer;
+mod locals;
 mod markdown_injection;
 mod registry;
+mod tags;
 
 pub use error::SyntaxError;
 pub use fold::{FoldRange, compute_folds};
@@ -13,5 +15,7 @@ pub use highlight::{HighlightSpan, spans_for_line};
 pub use indent::compute_indent;
 pub use language::{LanguageId, language_for_name, language_for_path};
 pub use layer::SyntaxLayer;
+pub use locals::LocalBindings;
 pub use markdown_injection::markdown_code_block_spans_for_line;
 pub use registry::LanguageRegistry;
+pub use tags::{TagDefinition, compute_tags};
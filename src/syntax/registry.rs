@@ -7,6 +7,8 @@ use crate::syntax::LanguageId;
 
 struct RegistryEntry {
     highlight_query: Query,
+    tags_query: Option<Query>,
+    locals_query: Option<Query>,
 }
 
 pub struct LanguageRegistry {
@@ -44,7 +46,20 @@ impl LanguageRegistry {
             let lang = id.ts_language();
             let source = id.highlight_query_source();
             if let Ok(highlight_query) = Query::new(&lang, source) {
-                entries.insert(id, RegistryEntry { highlight_query });
+                let tags_query = id
+                    .tags_query_source()
+                    .and_then(|source| Query::new(&lang, source).ok());
+                let locals_query = id
+                    .locals_query_source()
+                    .and_then(|source| Query::new(&lang, source).ok());
+                entries.insert(
+                    id,
+                    RegistryEntry {
+                        highlight_query,
+                        tags_query,
+                        locals_query,
+                    },
+                );
             }
         }
 
@@ -54,6 +69,14 @@ impl LanguageRegistry {
     pub fn highlight_query(&self, id: LanguageId) -> Option<&Query> {
         self.entries.get(&id).map(|e| &e.highlight_query)
     }
+
+    pub fn tags_query(&self, id: LanguageId) -> Option<&Query> {
+        self.entries.get(&id).and_then(|e| e.tags_query.as_ref())
+    }
+
+    pub fn locals_query(&self, id: LanguageId) -> Option<&Query> {
+        self.entries.get(&id).and_then(|e| e.locals_query.as_ref())
+    }
 }
 
 #[cfg(test)]
@@ -90,6 +113,18 @@ mod tests {
                     failed.push((id, e));
                 }
             }
+            if let Some(tags) = id.tags_query_source()
+                && let Err(e) = Query::new(&lang, tags)
+            {
+                eprintln!("ERR {:?} tags: {:?}", id, e);
+                failed.push((id, e));
+            }
+            if let Some(locals) = id.locals_query_source()
+                && let Err(e) = Query::new(&lang, locals)
+            {
+                eprintln!("ERR {:?} locals: {:?}", id, e);
+                failed.push((id, e));
+            }
         }
         if !failed.is_empty() {
             panic!("{} queries failed — see stderr above", failed.len());
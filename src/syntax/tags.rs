@@ -0,0 +1,81 @@
+use tree_sitter::{Query, QueryCursor, StreamingIterator, Tree};
+
+/// A definition matched by a language's `tags.scm` query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagDefinition {
+    pub name: String,
+    /// Suffix of the `@definition.*` capture, e.g. `function` or `impl`.
+    pub kind: &'static str,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub name_line: usize,
+    /// Char column of the name on `name_line`.
+    pub name_column: usize,
+}
+
+fn intern_kind(name: &str) -> &'static str {
+    match name {
+        "module" => "module",
+        "class" => "class",
+        "struct" => "struct",
+        "enum" => "enum",
+        "interface" => "interface",
+        "impl" => "impl",
+        "type" => "type",
+        "method" => "method",
+        "macro" => "macro",
+        "constant" => "constant",
+        "heading" => "heading",
+        _ => "function",
+    }
+}
+
+/// Runs the tags query over the whole tree, in document order.
+pub fn compute_tags(tree: &Tree, query: &Query, source: &[u8]) -> Vec<TagDefinition> {
+    let names = query.capture_names();
+    let mut cursor = QueryCursor::new();
+    let mut matches = cursor.matches(query, tree.root_node(), source);
+    let mut tags = Vec::new();
+    while let Some(found) = matches.next() {
+        let mut definition = None;
+        let mut name = None;
+        for capture in found.captures {
+            let capture_name = names[capture.index as usize];
+            if capture_name == "name" {
+                name = Some(capture.node);
+            } else if let Some(kind) = capture_name.strip_prefix("definition.") {
+                definition = Some((capture.node, intern_kind(kind)));
+            }
+        }
+        let (Some((node, kind)), Some(name)) = (definition, name) else {
+            continue;
+        };
+        let Ok(text) = name.utf8_text(source) else {
+            continue;
+        };
+        let start = name.start_position();
+        // A node ending at column 0 stops before that line, e.g. a section
+        // that runs up to the next heading.
+        let end = node.end_position();
+        let end_line = if end.column == 0 && end.row > node.start_position().row {
+            end.row - 1
+        } else {
+            end.row
+        };
+        let line_start = name.start_byte() - start.column;
+        let name_column = String::from_utf8_lossy(&source[line_start..name.start_byte()])
+            .chars()
+            .count();
+        tags.push(TagDefinition {
+            name: text.lines().next().unwrap_or_default().trim().to_owned(),
+            kind,
+            start_line: node.start_position().row,
+            end_line,
+            name_line: start.row,
+            name_column,
+        });
+    }
+    tags.sort_by_key(|tag| (tag.start_line, std::cmp::Reverse(tag.end_line)));
+    tags.dedup_by(|next, kept| next.name_line == kept.name_line && next.name == kept.name);
+    tags
+}
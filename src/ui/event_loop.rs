@@ -6,7 +6,7 @@ use ratatui::layout::Size;
 use crate::app::{App, AppError, AppMode, CommandBarMode, FocusTarget};
 use crate::editor::Command;
 use crate::keymap::map_key_event;
-use crate::syntax::language_for_path;
+use crate::lsp::{GotoKind, HierarchyDirection};
 
 pub(crate) fn run_app(app: &mut App) -> Result<(), AppError> {
     let mut terminal_session = crate::app::TerminalSession::enter()?;
@@ -24,7 +24,9 @@ pub(crate) fn run_app(app: &mut App) -> Result<(), AppError> {
             return Ok(());
         }
 
-        if event::poll(Duration::from_millis(250))? {
+        // A chunked search keeps going between keys instead of waiting.
+        let timeout = if app.search_scan_pending() { 0 } else { 250 };
+        if event::poll(Duration::from_millis(timeout))? {
             let mut processed = 0usize;
             loop {
                 match event::read()? {
@@ -80,10 +82,14 @@ impl App {
                     self.update_search_preview();
                 }
             }
-            AppMode::Editing => {
-                if self.focus == FocusTarget::Editor {
-                    self.insert_text(&normalized, false);
-                }
+            AppMode::Editing if self.focus == FocusTarget::Editor && self.is_hex_view() => {
+                self.set_message(
+                    "Paste is not supported in the hex view",
+                    crate::app::MessageKind::Warning,
+                );
+            }
+            AppMode::Editing if self.focus == FocusTarget::Editor => {
+                self.insert_text(&normalized, false);
             }
             _ => {}
         }
@@ -95,6 +101,7 @@ impl App {
             AppMode::ConfirmQuit
                 | AppMode::ConfirmDeleteExplorerEntry
                 | AppMode::ExternalChangeConflict
+                | AppMode::ConfirmWorkspaceEdit
         ) {
             self.clear_message();
         }
@@ -109,10 +116,24 @@ impl App {
                 self.handle_confirm_delete_explorer_key(key_event)
             }
             AppMode::ExternalChangeConflict => self.handle_external_change_conflict_key(key_event),
+            AppMode::ConfirmWorkspaceEdit => {
+                match key_event.code {
+                    KeyCode::Char('y') | KeyCode::Char('Y') => self.confirm_workspace_edit(true),
+                    KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                        self.confirm_workspace_edit(false);
+                    }
+                    _ => {}
+                }
+                Ok(())
+            }
             AppMode::CommandBar(mode) => self.handle_command_bar_key(mode, key_event),
             AppMode::Editing => {
                 if self.focus == FocusTarget::Explorer && self.explorer.visible() {
                     self.handle_explorer_key(key_event)
+                } else if self.focus == FocusTarget::Outline && self.outline.visible() {
+                    self.handle_outline_key(key_event)
+                } else if self.focus == FocusTarget::Hierarchy && self.hierarchy.is_some() {
+                    self.handle_hierarchy_key(key_event)
                 } else {
                     self.handle_editing_key(key_event)
                 }
@@ -121,6 +142,10 @@ impl App {
     }
 
     fn handle_editing_key(&mut self, key_event: KeyEvent) -> Result<(), AppError> {
+        if self.is_hex_view() && self.handle_hex_key(key_event) {
+            return Ok(());
+        }
+
         if self.completion_active() {
             match key_event.code {
                 KeyCode::Up => {
@@ -132,7 +157,11 @@ impl App {
                     return Ok(());
                 }
                 KeyCode::Enter | KeyCode::Tab => {
-                    self.accept_completion();
+                    self.accept_completion(false);
+                    return Ok(());
+                }
+                KeyCode::BackTab => {
+                    self.accept_completion(true);
                     return Ok(());
                 }
                 KeyCode::Esc => {
@@ -143,6 +172,24 @@ impl App {
             }
         }
 
+        if self.snippet_active() {
+            match key_event.code {
+                KeyCode::Tab => {
+                    self.next_snippet_tabstop();
+                    return Ok(());
+                }
+                KeyCode::BackTab => {
+                    self.previous_snippet_tabstop();
+                    return Ok(());
+                }
+                KeyCode::Esc => {
+                    self.end_snippet();
+                    return Ok(());
+                }
+                _ => {}
+            }
+        }
+
         if let Some(command) = map_key_event(key_event) {
             self.apply_command(command)?;
             return Ok(());
@@ -150,15 +197,21 @@ impl App {
 
         match key_event.code {
             KeyCode::Char(ch) if !key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.commit_completion(ch);
                 self.handle_editor_char_input(ch);
-                if self.focus == FocusTarget::Editor {
-                    self.trigger_completion(Some(ch));
+                if self.focus == FocusTarget::Editor && !self.active_buffer().large_file {
+                    self.update_completion(ch);
                     self.show_signature_help();
                 }
             }
             KeyCode::Enter => self.insert_newline_with_indent(),
             KeyCode::Tab => self.insert_tab_stop(),
-            KeyCode::Backspace => self.backspace(),
+            KeyCode::Backspace => {
+                self.backspace();
+                if self.completion_active() {
+                    self.filter_completion();
+                }
+            }
             KeyCode::Delete => self.delete_forward(),
             _ => {}
         }
@@ -189,11 +242,58 @@ impl App {
             }
             KeyCode::Char('A') => self.begin_explorer_create_directory(),
             KeyCode::Char('r') => self.begin_explorer_rename(),
-            KeyCode::Char('d') => {
-                if self.explorer.selected_entry().is_some() {
-                    self.mode = AppMode::ConfirmDeleteExplorerEntry;
+            KeyCode::Char('d') if self.explorer.selected_entry().is_some() => {
+                self.mode = AppMode::ConfirmDeleteExplorerEntry;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn handle_outline_key(&mut self, key_event: KeyEvent) -> Result<(), AppError> {
+        match key_event.code {
+            KeyCode::Esc => self.focus = FocusTarget::Editor,
+            KeyCode::Char('q') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.apply_command(crate::editor::Command::Quit)?;
+            }
+            KeyCode::Char('o') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.apply_command(crate::editor::Command::ToggleOutline)?;
+            }
+            KeyCode::Up => {
+                self.outline.move_selection(-1);
+                self.ensure_outline_selection_visible();
+            }
+            KeyCode::Down => {
+                self.outline.move_selection(1);
+                self.ensure_outline_selection_visible();
+            }
+            KeyCode::Enter => self.jump_to_outline_selection(),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn handle_hierarchy_key(&mut self, key_event: KeyEvent) -> Result<(), AppError> {
+        match key_event.code {
+            KeyCode::Esc => self.close_hierarchy(),
+            KeyCode::Char('q') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.apply_command(crate::editor::Command::Quit)?;
+            }
+            KeyCode::Up | KeyCode::Down => {
+                let delta = if key_event.code == KeyCode::Up { -1 } else { 1 };
+                if let Some(tree) = self.hierarchy.as_mut() {
+                    tree.move_selection(delta);
                 }
+                self.ensure_hierarchy_selection_visible();
             }
+            KeyCode::Right => self.expand_hierarchy_selection(),
+            KeyCode::Left => {
+                if let Some(tree) = self.hierarchy.as_mut() {
+                    tree.collapse_selected();
+                }
+                self.ensure_hierarchy_selection_visible();
+            }
+            KeyCode::Enter => self.jump_to_hierarchy_selection(),
             _ => {}
         }
         Ok(())
@@ -204,19 +304,13 @@ impl App {
             KeyCode::Char('y') | KeyCode::Char('Y') => {
                 // Discard editor changes and reload from disk
                 if let Some(path) = self.pending_conflict_paths.first().cloned() {
-                    if let Ok(loaded) = crate::file::load_document(&path) {
-                        if let Some(buf) = self
+                    if let Ok(loaded) = crate::file::load_document(&path)
+                        && let Some(buf) = self
                             .buffers
                             .iter_mut()
                             .find(|b| b.document.path().is_some_and(|p| p == path))
-                        {
-                            buf.document = loaded.document;
-                            buf.encoding = loaded.encoding;
-                            buf.saved_snapshot = buf.document.text();
-                            let language_id = buf.document.path().and_then(language_for_path);
-                            let _ = buf.syntax.set_language_id(language_id);
-                            buf.syntax.mark_dirty();
-                        }
+                    {
+                        buf.reload_from(loaded);
                     }
                     self.pending_conflict_paths.remove(0);
                 }
@@ -331,7 +425,7 @@ impl App {
         Ok(())
     }
 
-    fn apply_command(&mut self, command: Command) -> Result<(), AppError> {
+    pub(crate) fn apply_command(&mut self, command: Command) -> Result<(), AppError> {
         match command {
             Command::MoveLeft { extend } => self.move_left(extend),
             Command::MoveRight { extend } => self.move_right(extend),
@@ -373,12 +467,26 @@ impl App {
             Command::Hover => self.show_hover(),
             Command::SignatureHelp => self.show_signature_help(),
             Command::GotoDefinition => self.goto_definition(),
+            Command::GotoDeclaration => self.goto(GotoKind::Declaration),
+            Command::GotoTypeDefinition => self.goto(GotoKind::TypeDefinition),
+            Command::GotoImplementation => self.goto(GotoKind::Implementation),
             Command::GotoReferences => self.goto_references(),
             Command::RenameSymbol => self.rename_symbol(),
             Command::CodeActions => self.show_code_actions(),
             Command::FormatDocument => self.format_document(),
             Command::WorkspaceSymbols => self.open_workspace_symbols(),
             Command::ToggleDiagnosticsPanel => self.toggle_diagnostics_panel(),
+            Command::ToggleOutline => self.toggle_outline(),
+            Command::IncomingCalls => self.open_hierarchy(HierarchyDirection::IncomingCalls),
+            Command::OutgoingCalls => self.open_hierarchy(HierarchyDirection::OutgoingCalls),
+            Command::Supertypes => self.open_hierarchy(HierarchyDirection::Supertypes),
+            Command::Subtypes => self.open_hierarchy(HierarchyDirection::Subtypes),
+            Command::JumpBack => self.jump_back(),
+            Command::JumpForward => self.jump_forward(),
+            Command::JumpList => self.open_jump_list_picker(),
+            Command::SnippetPicker => self.open_snippet_picker(),
+            Command::NextHighlight => self.jump_to_document_highlight(true),
+            Command::PreviousHighlight => self.jump_to_document_highlight(false),
         }
 
         self.ensure_cursor_visible();
@@ -0,0 +1,197 @@
+use crate::lsp::{HierarchyDirection, HierarchyItem};
+use crate::util::compute_scroll_offset;
+
+#[derive(Debug, Clone)]
+pub struct HierarchyNode {
+    pub item: HierarchyItem,
+    /// `None` until the server sent the children.
+    pub children: Option<Vec<HierarchyNode>>,
+    pub expanded: bool,
+}
+
+impl HierarchyNode {
+    fn new(item: HierarchyItem) -> Self {
+        Self {
+            item,
+            children: None,
+            expanded: false,
+        }
+    }
+
+    fn find_mut(&mut self, id: usize) -> Option<&mut HierarchyNode> {
+        if self.item.id == id {
+            return Some(self);
+        }
+        self.children
+            .iter_mut()
+            .flatten()
+            .find_map(|child| child.find_mut(id))
+    }
+}
+
+/// One visible line of the tree.
+#[derive(Debug, Clone, Copy)]
+pub struct HierarchyRow<'a> {
+    pub depth: usize,
+    pub node: &'a HierarchyNode,
+}
+
+/// Call or type hierarchy panel whose nodes load their children on expand.
+#[derive(Debug, Clone)]
+pub struct HierarchyTree {
+    direction: HierarchyDirection,
+    roots: Vec<HierarchyNode>,
+    selected: usize,
+    scroll_offset: usize,
+}
+
+impl HierarchyTree {
+    pub fn new(direction: HierarchyDirection, items: Vec<HierarchyItem>) -> Self {
+        Self {
+            direction,
+            roots: items.into_iter().map(HierarchyNode::new).collect(),
+            selected: 0,
+            scroll_offset: 0,
+        }
+    }
+
+    pub fn direction(&self) -> HierarchyDirection {
+        self.direction
+    }
+
+    pub fn rows(&self) -> Vec<HierarchyRow<'_>> {
+        fn walk<'a>(nodes: &'a [HierarchyNode], depth: usize, rows: &mut Vec<HierarchyRow<'a>>) {
+            for node in nodes {
+                rows.push(HierarchyRow { depth, node });
+                if node.expanded
+                    && let Some(children) = &node.children
+                {
+                    walk(children, depth + 1, rows);
+                }
+            }
+        }
+        let mut rows = Vec::new();
+        walk(&self.roots, 0, &mut rows);
+        rows
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn set_selected(&mut self, selected: usize) {
+        self.selected = selected.min(self.rows().len().saturating_sub(1));
+    }
+
+    pub fn selected_item(&self) -> Option<&HierarchyItem> {
+        self.rows().get(self.selected).map(|row| &row.node.item)
+    }
+
+    pub fn scroll_offset(&self) -> usize {
+        self.scroll_offset
+    }
+
+    pub fn sync_scroll(&mut self, viewport_height: usize) {
+        self.scroll_offset = compute_scroll_offset(
+            self.scroll_offset,
+            self.selected,
+            self.rows().len(),
+            viewport_height,
+        );
+    }
+
+    pub fn move_selection(&mut self, delta: isize) {
+        let max_index = self.rows().len().saturating_sub(1);
+        self.selected = if delta.is_negative() {
+            self.selected.saturating_sub(delta.unsigned_abs())
+        } else {
+            (self.selected + delta as usize).min(max_index)
+        };
+    }
+
+    /// Expands the selected node; returns its id when the children still
+    /// have to be requested.
+    pub fn expand_selected(&mut self) -> Option<usize> {
+        let id = self.selected_item()?.id;
+        let node = self.node_mut(id)?;
+        node.expanded = true;
+        node.children.is_none().then_some(id)
+    }
+
+    /// Collapses the selected node, or moves to its parent when it is
+    /// already collapsed.
+    pub fn collapse_selected(&mut self) {
+        let rows = self.rows();
+        let Some(row) = rows.get(self.selected).copied() else {
+            return;
+        };
+        if row.node.expanded {
+            let id = row.node.item.id;
+            if let Some(node) = self.node_mut(id) {
+                node.expanded = false;
+            }
+            return;
+        }
+        if let Some(parent) = rows[..self.selected]
+            .iter()
+            .rposition(|candidate| candidate.depth < row.depth)
+        {
+            self.selected = parent;
+        }
+    }
+
+    pub fn set_children(&mut self, id: usize, items: Vec<HierarchyItem>) {
+        if let Some(node) = self.node_mut(id) {
+            node.children = Some(items.into_iter().map(HierarchyNode::new).collect());
+        }
+    }
+
+    fn node_mut(&mut self, id: usize) -> Option<&mut HierarchyNode> {
+        self.roots.iter_mut().find_map(|root| root.find_mut(id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::HierarchyTree;
+    use crate::core::Cursor;
+    use crate::lsp::{HierarchyDirection, HierarchyItem};
+
+    fn item(id: usize, name: &str) -> HierarchyItem {
+        HierarchyItem {
+            id,
+            name: name.to_owned(),
+            detail: String::new(),
+            kind: "function",
+            path: PathBuf::from("/src/lib.rs"),
+            cursor: Cursor::new(id, 0),
+            call_site: None,
+        }
+    }
+
+    #[test]
+    fn expands_lazily_and_collapses_to_parent() {
+        let mut tree = HierarchyTree::new(HierarchyDirection::IncomingCalls, vec![item(1, "run")]);
+        assert_eq!(tree.expand_selected(), Some(1));
+        assert_eq!(tree.rows().len(), 1);
+        tree.set_children(1, vec![item(2, "main"), item(3, "test_run")]);
+        let rows = tree
+            .rows()
+            .iter()
+            .map(|row| (row.depth, row.node.item.name.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(rows, vec![(0, "run"), (1, "main"), (1, "test_run")]);
+
+        tree.move_selection(2);
+        assert_eq!(tree.selected_item().map(|item| item.id), Some(3));
+        tree.collapse_selected();
+        assert_eq!(tree.selected(), 0);
+        tree.collapse_selected();
+        assert_eq!(tree.rows().len(), 1);
+        // Children stay loaded, so expanding again needs no request.
+        assert_eq!(tree.expand_selected(), None);
+        assert_eq!(tree.rows().len(), 3);
+    }
+}
@@ -13,7 +13,7 @@ impl LayoutState {
     pub fn new(initial_buffer_id: u64) -> Self {
         let pane = Pane::new(1, initial_buffer_id);
         Self {
-            root: WindowNode::Leaf(pane),
+            root: WindowNode::Leaf(Box::new(pane)),
             focused_pane_id: 1,
             next_pane_id: 2,
         }
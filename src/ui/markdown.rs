@@ -0,0 +1,128 @@
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+
+use crate::config::Theme;
+use crate::syntax::{LanguageId, LanguageRegistry, language_for_name, spans_for_line};
+use crate::ui::renderer::find_span_style;
+
+/// Renders hover-style markdown: headings in bold and fenced code blocks
+/// highlighted in their info string's language, or `fallback` without one.
+pub(crate) fn markdown_lines(
+    text: &str,
+    fallback: Option<LanguageId>,
+    theme: &Theme,
+) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    let mut fence: Option<(Option<LanguageId>, String)> = None;
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        if let Some(info) = trimmed.strip_prefix("```") {
+            match fence.take() {
+                Some((language, code)) => lines.extend(code_lines(&code, language, theme)),
+                None => {
+                    let info = info.trim();
+                    let language = if info.is_empty() {
+                        fallback
+                    } else {
+                        language_for_name(info)
+                    };
+                    fence = Some((language, String::new()));
+                }
+            }
+            continue;
+        }
+        if let Some((_, code)) = fence.as_mut() {
+            code.push_str(line);
+            code.push('\n');
+            continue;
+        }
+        let heading = trimmed.trim_start_matches('#');
+        if heading.len() < trimmed.len() && (heading.is_empty() || heading.starts_with(' ')) {
+            let style = Style::default().add_modifier(Modifier::BOLD);
+            lines.push(Line::from(Span::styled(heading.trim().to_owned(), style)));
+        } else {
+            lines.push(Line::from(line.to_owned()));
+        }
+    }
+    if let Some((language, code)) = fence {
+        lines.extend(code_lines(&code, language, theme));
+    }
+    while lines.last().is_some_and(|line| line.width() == 0) {
+        lines.pop();
+    }
+    lines
+}
+
+/// Highlights `code` line by line with the language's highlight query.
+pub(crate) fn code_lines(
+    code: &str,
+    language: Option<LanguageId>,
+    theme: &Theme,
+) -> Vec<Line<'static>> {
+    let tree = language.and_then(|language| {
+        let mut parser = tree_sitter::Parser::new();
+        parser.set_language(&language.ts_language()).ok()?;
+        let query = LanguageRegistry::global().highlight_query(language)?;
+        Some((parser.parse(code, None)?, query))
+    });
+    let mut lines = Vec::new();
+    let mut line_start = 0;
+    for raw_line in code.split_inclusive('\n') {
+        let line = raw_line.trim_end_matches('\n');
+        let start = line_start;
+        line_start += raw_line.len();
+        let Some((tree, query)) = tree.as_ref() else {
+            lines.push(Line::from(line.to_owned()));
+            continue;
+        };
+        let spans = spans_for_line(tree, query, code.as_bytes(), start, start + line.len());
+        let mut styled: Vec<Span<'static>> = Vec::new();
+        let mut run = String::new();
+        let mut run_style = Style::default();
+        for (offset, ch) in line.char_indices() {
+            let style = find_span_style(&spans, offset, theme).unwrap_or_default();
+            if style != run_style && !run.is_empty() {
+                styled.push(Span::styled(std::mem::take(&mut run), run_style));
+            }
+            run_style = style;
+            run.push(ch);
+        }
+        if !run.is_empty() {
+            styled.push(Span::styled(run, run_style));
+        }
+        lines.push(Line::from(styled));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::markdown_lines;
+    use crate::config::Theme;
+    use crate::syntax::LanguageId;
+
+    #[test]
+    fn highlights_fenced_code_and_bolds_headings() {
+        let theme = Theme::default_theme();
+        let text = "# Examples\n\n```rust\nlet x = 1;\n```\n\nPlain `text`.\n\n";
+        let lines = markdown_lines(text, None, &theme);
+        let rendered = lines
+            .iter()
+            .map(|line| {
+                line.spans
+                    .iter()
+                    .map(|span| span.content.as_ref())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(rendered, vec!["Examples", "", "let x = 1;", "", "Plain `text`."]);
+        assert!(lines[0].spans[0].style.add_modifier.contains(ratatui::style::Modifier::BOLD));
+        // `let` is a keyword, so the code line is split into styled spans.
+        assert!(lines[2].spans.len() > 1);
+        assert_ne!(lines[2].spans[0].style, lines[4].spans[0].style);
+
+        // A fence without a language uses the fallback.
+        let bare = markdown_lines("```\nfn main() {}\n```", Some(LanguageId::Rust), &theme);
+        assert!(bare[0].spans.len() > 1);
+    }
+}
@@ -1,7 +1,10 @@
 mod error;
 mod event_loop;
+mod hierarchy;
 mod layout;
+mod markdown;
 mod mouse;
+mod outline;
 mod palette;
 mod pane;
 mod picker;
@@ -13,7 +16,9 @@ mod workspace;
 pub use error::UiError;
 pub(crate) use event_loop::run_app;
 pub(crate) use mouse::handle_mouse_event;
+pub use hierarchy::HierarchyTree;
 pub use layout::LayoutState;
+pub use outline::{OutlineState, OutlineSymbol, nest_symbols};
 pub use palette::Palette;
 pub use pane::Pane;
 pub use picker::{PickerItem, PickerKind, PickerState};
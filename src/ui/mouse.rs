@@ -3,6 +3,7 @@ use ratatui::layout::{Constraint, Layout, Rect};
 
 use crate::app::{App, AppError, AppMode, FocusTarget};
 use crate::core::{Cursor, Selection};
+use crate::ui::renderer::workspace_columns;
 
 const WHEEL_STEP: isize = 1;
 const WHEEL_SLOWDOWN: u8 = 3;
@@ -90,6 +91,19 @@ fn handle_left_pointer_event(
         return Ok(());
     }
 
+    if let Some((outer, inner)) = outline_areas(app)
+        && contains(outer, mouse_event.column, mouse_event.row)
+    {
+        if app.hierarchy.is_some() {
+            app.focus = FocusTarget::Hierarchy;
+            click_hierarchy(app, inner, mouse_event.column, mouse_event.row);
+        } else {
+            app.focus = FocusTarget::Outline;
+            click_outline(app, inner, mouse_event.column, mouse_event.row);
+        }
+        return Ok(());
+    }
+
     if let Some((pane_id, pane_area)) = pane_under_pointer(app, mouse_event.column, mouse_event.row)
     {
         focus_editor_pane(app, pane_id);
@@ -131,6 +145,38 @@ fn click_explorer(app: &mut App, column: u16, row: u16) -> Result<(), AppError>
     app.open_selected_explorer_entry()
 }
 
+fn click_outline(app: &mut App, inner: Rect, column: u16, row: u16) {
+    if !contains(inner, column, row) {
+        return;
+    }
+    let index = app
+        .outline
+        .scroll_offset()
+        .saturating_add(row.saturating_sub(inner.y) as usize);
+    if index >= app.outline.symbols().len() {
+        return;
+    }
+    app.outline.set_selected(index);
+    app.jump_to_outline_selection();
+}
+
+fn click_hierarchy(app: &mut App, inner: Rect, column: u16, row: u16) {
+    if !contains(inner, column, row) {
+        return;
+    }
+    let Some(tree) = app.hierarchy.as_mut() else {
+        return;
+    };
+    let index = tree
+        .scroll_offset()
+        .saturating_add(row.saturating_sub(inner.y) as usize);
+    if index >= tree.rows().len() {
+        return;
+    }
+    tree.set_selected(index);
+    app.jump_to_hierarchy_selection();
+}
+
 fn handle_picker_mouse_event(app: &mut App, mouse_event: MouseEvent) -> Result<(), AppError> {
     match mouse_event.kind {
         MouseEventKind::ScrollUp => {
@@ -175,10 +221,10 @@ fn hover_picker(app: &mut App, column: u16, row: u16) {
     }
 
     let index = scroll_offset.saturating_add(row.saturating_sub(list_area.y) as usize);
-    if let Some(picker) = app.picker.as_mut() {
-        if index < picker.items().len() {
-            picker.set_selected(index);
-        }
+    if let Some(picker) = app.picker.as_mut()
+        && index < picker.items().len()
+    {
+        picker.set_selected(index);
     }
 }
 
@@ -208,10 +254,10 @@ fn keep_picker_selection_under_pointer(app: &mut App, row: u16) {
     }
 
     let index = scroll_offset.saturating_add(row.saturating_sub(list_area.y) as usize);
-    if let Some(picker) = app.picker.as_mut() {
-        if index < picker.items().len() {
-            picker.set_selected(index);
-        }
+    if let Some(picker) = app.picker.as_mut()
+        && index < picker.items().len()
+    {
+        picker.set_selected(index);
     }
 }
 
@@ -315,10 +361,10 @@ fn mouse_to_cursor(app: &App, pane_area: Rect, column: u16, row: u16) -> Option<
         .viewport()
         .left_column()
         .saturating_add(column.saturating_sub(text_x) as usize);
-    let char_column = app
-        .active_document()
-        .column_for_display(line, display_column);
-    Some(Cursor::new(line, char_column).with_preferred_column(display_column))
+    let char_column = app.column_for_visual(app.active_buffer_id, line, display_column);
+    let cursor = Cursor::new(line, char_column);
+    let preferred = app.active_document().display_column(cursor);
+    Some(cursor.with_preferred_column(preferred))
 }
 
 fn pane_under_pointer(app: &App, column: u16, row: u16) -> Option<(u64, Rect)> {
@@ -337,12 +383,16 @@ fn pointer_in_explorer(app: &App, column: u16, row: u16) -> bool {
 }
 
 fn explorer_areas(app: &App) -> Option<(Rect, Rect)> {
-    if !app.explorer.visible() {
-        return None;
-    }
-    let workspace = workspace_area(app);
-    let chunks = Layout::horizontal([Constraint::Length(30), Constraint::Min(1)]).split(workspace);
-    let outer = chunks[0];
+    let (outer, _, _) = workspace_columns(app, workspace_area(app));
+    bordered_areas(outer?)
+}
+
+fn outline_areas(app: &App) -> Option<(Rect, Rect)> {
+    let (_, _, outer) = workspace_columns(app, workspace_area(app));
+    bordered_areas(outer?)
+}
+
+fn bordered_areas(outer: Rect) -> Option<(Rect, Rect)> {
     if outer.width < 3 || outer.height < 3 {
         return Some((outer, outer));
     }
@@ -356,13 +406,7 @@ fn explorer_areas(app: &App) -> Option<(Rect, Rect)> {
 }
 
 fn workspace_editor_area(app: &App) -> Option<Rect> {
-    let workspace = workspace_area(app);
-    if app.explorer.visible() {
-        let chunks =
-            Layout::horizontal([Constraint::Length(30), Constraint::Min(1)]).split(workspace);
-        return Some(chunks[1]);
-    }
-    Some(workspace)
+    Some(workspace_columns(app, workspace_area(app)).1)
 }
 
 fn workspace_area(app: &App) -> Rect {
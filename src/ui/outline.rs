@@ -0,0 +1,301 @@
+use crate::app::{App, FocusTarget};
+use crate::core::{Cursor, Selection};
+use crate::syntax::{LanguageRegistry, TagDefinition, compute_tags};
+use crate::util::compute_scroll_offset;
+
+/// One entry of the outline panel, in document order with its nesting depth.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutlineSymbol {
+    pub name: String,
+    /// Symbol kind such as `function`, `impl` or `heading`.
+    pub kind: &'static str,
+    pub depth: usize,
+    pub start_line: usize,
+    pub end_line: usize,
+    /// Where the name starts; Enter jumps here.
+    pub line: usize,
+    pub column: usize,
+}
+
+impl OutlineSymbol {
+    pub fn from_tag(tag: TagDefinition) -> Self {
+        Self {
+            name: tag.name,
+            kind: tag.kind,
+            depth: 0,
+            start_line: tag.start_line,
+            end_line: tag.end_line,
+            line: tag.name_line,
+            column: tag.name_column,
+        }
+    }
+
+    /// Short kind label drawn in front of the name.
+    pub fn label(&self) -> &'static str {
+        match self.kind {
+            "module" => "mod",
+            "interface" => "iface",
+            "function" => "fn",
+            "constant" => "const",
+            "variable" => "var",
+            "object" => "obj",
+            "heading" => "#",
+            kind => kind,
+        }
+    }
+
+    fn contains_line(&self, line: usize) -> bool {
+        self.start_line <= line && line <= self.end_line
+    }
+}
+
+/// Orders symbols by position and sets each depth from range containment.
+/// Functions directly inside a type become methods.
+pub fn nest_symbols(mut symbols: Vec<OutlineSymbol>) -> Vec<OutlineSymbol> {
+    symbols.sort_by_key(|symbol| (symbol.start_line, std::cmp::Reverse(symbol.end_line)));
+    let mut parents: Vec<(usize, &'static str)> = Vec::new();
+    for symbol in &mut symbols {
+        while parents
+            .last()
+            .is_some_and(|(end_line, _)| *end_line < symbol.end_line)
+        {
+            parents.pop();
+        }
+        let parent_is_type = parents.last().is_some_and(|(_, kind)| {
+            matches!(*kind, "impl" | "interface" | "class" | "struct" | "object")
+        });
+        if symbol.kind == "function" && parent_is_type {
+            symbol.kind = "method";
+        }
+        symbol.depth = parents.len();
+        parents.push((symbol.end_line, symbol.kind));
+    }
+    symbols
+}
+
+/// Symbols enclosing `line`, outermost first.
+pub fn symbol_path(symbols: &[OutlineSymbol], line: usize) -> Vec<&OutlineSymbol> {
+    let mut path: Vec<&OutlineSymbol> = Vec::new();
+    for symbol in symbols.iter().filter(|symbol| symbol.contains_line(line)) {
+        path.truncate(symbol.depth);
+        path.push(symbol);
+    }
+    path
+}
+
+#[derive(Debug, Default)]
+pub struct OutlineState {
+    visible: bool,
+    symbols: Vec<OutlineSymbol>,
+    selected: usize,
+    scroll_offset: usize,
+}
+
+impl OutlineState {
+    pub fn visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    pub fn symbols(&self) -> &[OutlineSymbol] {
+        &self.symbols
+    }
+
+    pub fn set_symbols(&mut self, symbols: Vec<OutlineSymbol>) {
+        self.symbols = symbols;
+        self.selected = self.selected.min(self.symbols.len().saturating_sub(1));
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn set_selected(&mut self, selected: usize) {
+        self.selected = selected.min(self.symbols.len().saturating_sub(1));
+    }
+
+    pub fn selected_symbol(&self) -> Option<&OutlineSymbol> {
+        self.symbols.get(self.selected)
+    }
+
+    pub fn scroll_offset(&self) -> usize {
+        self.scroll_offset
+    }
+
+    pub fn sync_scroll(&mut self, viewport_height: usize) {
+        self.scroll_offset = compute_scroll_offset(
+            self.scroll_offset,
+            self.selected,
+            self.symbols.len(),
+            viewport_height,
+        );
+    }
+
+    pub fn move_selection(&mut self, delta: isize) {
+        let max_index = self.symbols.len().saturating_sub(1);
+        self.selected = if delta.is_negative() {
+            self.selected.saturating_sub(delta.unsigned_abs())
+        } else {
+            (self.selected + delta as usize).min(max_index)
+        };
+    }
+
+    /// Selects the innermost symbol around `line`.
+    pub fn follow_line(&mut self, line: usize) {
+        if let Some(index) = self
+            .symbols
+            .iter()
+            .rposition(|symbol| symbol.contains_line(line))
+        {
+            self.selected = index;
+        }
+    }
+}
+
+impl App {
+    pub(crate) fn toggle_outline(&mut self) {
+        self.outline.toggle();
+        self.focus = if self.outline.visible() {
+            FocusTarget::Outline
+        } else {
+            FocusTarget::Editor
+        };
+        self.refresh_outline();
+        let line = self.active_pane().cursor().line;
+        self.outline.follow_line(line);
+        self.ensure_outline_selection_visible();
+    }
+
+    /// Asks servers for the symbols of every shown document and, while the
+    /// panel is open, reloads it and keeps the cursor's symbol selected.
+    pub(crate) fn refresh_outline(&mut self) {
+        for path in self.visible_line_ranges().into_keys() {
+            self.lsp.request_document_symbols(&path);
+        }
+        if !self.outline.visible() {
+            return;
+        }
+        let symbols = self.outline_symbols(self.active_buffer_id);
+        if symbols != self.outline.symbols() {
+            self.outline.set_symbols(symbols);
+        }
+        if self.focus != FocusTarget::Outline {
+            let line = self.active_pane().cursor().line;
+            self.outline.follow_line(line);
+            self.ensure_outline_selection_visible();
+        }
+    }
+
+    /// Server symbols when a server sent them, otherwise the tags query.
+    pub(crate) fn outline_symbols(&self, buffer_id: u64) -> Vec<OutlineSymbol> {
+        let Some(buffer) = self.buffer_by_id(buffer_id) else {
+            return Vec::new();
+        };
+        if let Some(symbols) = buffer
+            .document
+            .path()
+            .and_then(|path| self.lsp.document_symbols(path))
+        {
+            return symbols.to_vec();
+        }
+        let (Some(tree), Some(language_id)) = (buffer.syntax.tree(), buffer.syntax.language_id())
+        else {
+            return Vec::new();
+        };
+        let Some(query) = LanguageRegistry::global().tags_query(language_id) else {
+            return Vec::new();
+        };
+        let revision = buffer.syntax.revision();
+        let mut cache = buffer.outline_cache.borrow_mut();
+        if cache.revision != revision || cache.symbols.is_none() {
+            let source = buffer.document.text();
+            let tags = compute_tags(tree, query, source.as_bytes());
+            cache.revision = revision;
+            cache.symbols = Some(nest_symbols(
+                tags.into_iter().map(OutlineSymbol::from_tag).collect(),
+            ));
+        }
+        cache.symbols.clone().unwrap_or_default()
+    }
+
+    /// Names of the symbols around `line`, e.g. `outline › impl App › refresh`.
+    pub(crate) fn breadcrumbs(&self, buffer_id: u64, line: usize) -> String {
+        let symbols = self.outline_symbols(buffer_id);
+        symbol_path(&symbols, line)
+            .iter()
+            .map(|symbol| symbol.name.as_str())
+            .collect::<Vec<_>>()
+            .join(" \u{203a} ")
+    }
+
+    pub(crate) fn jump_to_outline_selection(&mut self) {
+        let Some(symbol) = self.outline.selected_symbol() else {
+            return;
+        };
+        let cursor = Cursor::new(symbol.line, symbol.column);
+        self.focus = FocusTarget::Editor;
+        self.record_jump();
+        if let Some(pane) = self.layout.focused_pane_mut() {
+            pane.set_cursor(cursor);
+            pane.set_selection(Selection::caret(cursor));
+        }
+        self.ensure_cursor_visible();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{OutlineState, OutlineSymbol, nest_symbols, symbol_path};
+
+    fn symbol(name: &str, kind: &'static str, start_line: usize, end_line: usize) -> OutlineSymbol {
+        OutlineSymbol {
+            name: name.to_owned(),
+            kind,
+            depth: 0,
+            start_line,
+            end_line,
+            line: start_line,
+            column: 0,
+        }
+    }
+
+    #[test]
+    fn nests_symbols_and_follows_the_cursor() {
+        let symbols = nest_symbols(vec![
+            symbol("render", "function", 4, 8),
+            symbol("App", "impl", 3, 12),
+            symbol("outline", "module", 0, 20),
+            symbol("toggle", "function", 9, 11),
+            symbol("helper", "function", 14, 18),
+        ]);
+        let names = symbols
+            .iter()
+            .map(|symbol| (symbol.name.as_str(), symbol.kind, symbol.depth))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                ("outline", "module", 0),
+                ("App", "impl", 1),
+                ("render", "method", 2),
+                ("toggle", "method", 2),
+                ("helper", "function", 1),
+            ]
+        );
+        let path = symbol_path(&symbols, 10)
+            .iter()
+            .map(|symbol| symbol.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(path, vec!["outline", "App", "toggle"]);
+
+        let mut outline = OutlineState::default();
+        outline.set_symbols(symbols);
+        outline.follow_line(16);
+        assert_eq!(outline.selected_symbol().map(|symbol| symbol.label()), Some("fn"));
+        outline.follow_line(13);
+        assert_eq!(outline.selected(), 0);
+    }
+}
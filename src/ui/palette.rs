@@ -22,6 +22,8 @@ pub struct PaletteStyles {
     pub selection: Style,
     pub search_match: Style,
     pub active_search_match: Style,
+    pub symbol_read: Style,
+    pub symbol_write: Style,
     pub diagnostic_error: Style,
     pub diagnostic_warning: Style,
     pub diagnostic_information: Style,
@@ -30,6 +32,7 @@ pub struct PaletteStyles {
     pub diagnostic_lens_warning: Style,
     pub diagnostic_lens_information: Style,
     pub diagnostic_lens_hint: Style,
+    pub inlay_hint: Style,
     pub statusline: Style,
     pub message: Style,
     pub warning: Style,
@@ -41,6 +44,8 @@ pub struct PaletteStyles {
     pub explorer_dir: Style,
     pub explorer_dir_selected: Style,
     pub explorer_file_selected: Style,
+    pub breadcrumb: Style,
+    pub completion_kind: Style,
 }
 
 impl Palette {
@@ -66,6 +71,10 @@ impl Palette {
             selection: Style::default().bg(Color::Rgb(69, 71, 90)).fg(self.text),
             search_match: Style::default().bg(Color::Rgb(88, 74, 102)).fg(self.text),
             active_search_match: Style::default().bg(self.blue).fg(self.mantle),
+            symbol_read: Style::default().bg(self.surface),
+            symbol_write: Style::default()
+                .bg(Color::Rgb(72, 58, 72))
+                .add_modifier(Modifier::BOLD),
             diagnostic_error: Style::default().bg(Reset).fg(self.red),
             diagnostic_warning: Style::default().bg(Reset).fg(self.yellow),
             diagnostic_information: Style::default().bg(Reset).fg(self.blue),
@@ -74,6 +83,10 @@ impl Palette {
             diagnostic_lens_warning: Style::default().bg(Reset).fg(Color::Rgb(230, 192, 118)),
             diagnostic_lens_information: Style::default().bg(Reset).fg(Color::Rgb(156, 189, 239)),
             diagnostic_lens_hint: Style::default().bg(Reset).fg(Color::Rgb(166, 173, 200)),
+            inlay_hint: Style::default()
+                .bg(Reset)
+                .fg(self.overlay)
+                .add_modifier(Modifier::ITALIC),
             statusline: Style::default()
                 .bg(self.surface)
                 .fg(self.text)
@@ -94,6 +107,8 @@ impl Palette {
             explorer_file_selected: Style::default()
                 .bg(Color::Rgb(40, 52, 87))
                 .fg(Color::Rgb(192, 202, 245)),
+            breadcrumb: Style::default().fg(self.subtle),
+            completion_kind: Style::default().fg(self.subtle),
         }
     }
 }
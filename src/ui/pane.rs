@@ -1,5 +1,5 @@
 use crate::core::{Cursor, Selection};
-use crate::editor::SearchState;
+use crate::editor::{JumpList, SearchState};
 use crate::view::Viewport;
 
 #[derive(Debug)]
@@ -10,6 +10,7 @@ pub struct Pane {
     selection: Selection,
     viewport: Viewport,
     search: SearchState,
+    jumps: JumpList,
 }
 
 impl Pane {
@@ -21,6 +22,7 @@ impl Pane {
             selection: Selection::caret(Cursor::default()),
             viewport: Viewport::default(),
             search: SearchState::default(),
+            jumps: JumpList::default(),
         }
     }
 
@@ -67,4 +69,12 @@ impl Pane {
     pub fn search_mut(&mut self) -> &mut SearchState {
         &mut self.search
     }
+
+    pub fn jumps(&self) -> &JumpList {
+        &self.jumps
+    }
+
+    pub fn jumps_mut(&mut self) -> &mut JumpList {
+        &mut self.jumps
+    }
 }
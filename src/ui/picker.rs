@@ -5,6 +5,13 @@ use crate::util::compute_scroll_offset;
 pub enum PickerKind {
     Files,
     Buffers,
+    CodeActions,
+    Jumps,
+    Snippets,
+    /// Read-only `:lsp-status` report.
+    LspStatus,
+    /// Language server results such as references and workspace symbols.
+    Locations,
 }
 
 #[derive(Debug, Clone)]
@@ -15,6 +22,8 @@ pub struct PickerItem {
     pub buffer_id: Option<u64>,
     pub line: Option<usize>,
     pub column: Option<usize>,
+    /// Index into the list a non-file picker was built from.
+    pub entry: Option<usize>,
 }
 
 #[derive(Debug)]
@@ -22,6 +31,8 @@ pub struct PickerState {
     kind: PickerKind,
     query: String,
     items: Vec<PickerItem>,
+    /// Every entry of a picker that filters its own items; see `set_source`.
+    source: Vec<PickerItem>,
     selected: usize,
     scroll_offset: usize,
 }
@@ -32,6 +43,7 @@ impl PickerState {
             kind,
             query: String::new(),
             items: Vec::new(),
+            source: Vec::new(),
             selected: 0,
             scroll_offset: 0,
         }
@@ -118,6 +130,7 @@ impl PickerState {
                 buffer_id: None,
                 line: None,
                 column: None,
+                entry: None,
             })
             .collect();
         self.selected = self.selected.min(self.items.len().saturating_sub(1));
@@ -129,4 +142,24 @@ impl PickerState {
         self.selected = self.selected.min(self.items.len().saturating_sub(1));
         self.scroll_offset = self.scroll_offset.min(self.selected);
     }
+
+    /// Keeps `items` as the full list and shows those matching the query.
+    pub fn set_source(&mut self, items: Vec<PickerItem>) {
+        self.source = items;
+        self.filter_source();
+    }
+
+    pub fn filter_source(&mut self) {
+        let query = self.query.to_lowercase();
+        let items = self
+            .source
+            .iter()
+            .filter(|item| {
+                item.title.to_lowercase().contains(&query)
+                    || item.subtitle.to_lowercase().contains(&query)
+            })
+            .cloned()
+            .collect();
+        self.set_buffer_items(items);
+    }
 }
@@ -2,15 +2,17 @@ use ratatui::buffer::Buffer;
 use ratatui::layout::{Constraint, Layout, Rect};
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
-use ratatui::widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Widget};
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Widget, Wrap};
 use ratatui::Frame;
 use unicode_segmentation::UnicodeSegmentation;
-use unicode_width::UnicodeWidthStr;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 use crate::app::{App, AppMode, FocusTarget, MessageKind};
-use crate::lsp::{DiagnosticItem, DiagnosticSeverityView};
+use crate::core::{HEX_BYTES_PER_ROW, HexDocument};
+use crate::lsp::{DiagnosticItem, DiagnosticSeverityView, HighlightKind};
 use crate::syntax::HighlightSpan;
-use crate::ui::Palette;
+use crate::ui::markdown::{code_lines, markdown_lines};
+use crate::ui::{HierarchyTree, Palette};
 use crate::view::build_statusline;
 
 pub fn render(frame: &mut Frame<'_>, app: &App) {
@@ -36,30 +38,50 @@ pub fn render(frame: &mut Frame<'_>, app: &App) {
     frame.set_cursor_position(cursor);
 }
 
+/// Splits the workspace into the explorer, the editor panes and the right
+/// side panel, which shows the hierarchy tree over the outline.
+pub(super) fn workspace_columns(app: &App, area: Rect) -> (Option<Rect>, Rect, Option<Rect>) {
+    let explorer = app.explorer().visible();
+    let outline = app.outline().visible() || app.hierarchy().is_some();
+    let chunks = Layout::horizontal([
+        Constraint::Length(if explorer { 30 } else { 0 }),
+        Constraint::Min(1),
+        Constraint::Length(if outline { 30 } else { 0 }),
+    ])
+    .split(area);
+    (
+        explorer.then_some(chunks[0]),
+        chunks[1],
+        outline.then_some(chunks[2]),
+    )
+}
+
 fn render_workspace(frame: &mut Frame<'_>, area: Rect, app: &App) {
     let palette = Palette::mocha().styles();
-    let chunks = if app.explorer().visible() {
-        Layout::horizontal([Constraint::Length(30), Constraint::Min(1)]).split(area)
-    } else {
-        Layout::horizontal([Constraint::Min(1)]).split(area)
-    };
-
-    let editor_area = if app.explorer().visible() {
-        render_explorer(frame.buffer_mut(), chunks[0], app);
-        chunks[1]
-    } else {
-        chunks[0]
-    };
+    let (explorer_area, editor_area, outline_area) = workspace_columns(app, area);
+    if let Some(explorer_area) = explorer_area {
+        render_explorer(frame.buffer_mut(), explorer_area, app);
+    }
+    if let Some(side_area) = outline_area {
+        match app.hierarchy() {
+            Some(tree) => render_hierarchy(frame.buffer_mut(), side_area, app, tree),
+            None => render_outline(frame.buffer_mut(), side_area, app),
+        }
+    }
 
     for (pane_id, pane_area, pane) in app.layout.leaves_in_area(editor_area) {
         let active = pane_id == app.active_pane_id();
-        let block = Block::default()
+        let breadcrumbs = app.breadcrumbs(pane.buffer_id(), pane.cursor().line);
+        let mut block = Block::default()
             .borders(Borders::ALL)
             .border_style(if active {
                 palette.statusline
             } else {
                 palette.editor
             });
+        if !breadcrumbs.is_empty() {
+            block = block.title(Span::styled(format!(" {breadcrumbs} "), palette.breadcrumb));
+        }
         let inner = block.inner(pane_area);
         block.render(pane_area, frame.buffer_mut());
 
@@ -140,6 +162,111 @@ fn render_explorer(buffer: &mut Buffer, area: Rect, app: &App) {
     List::new(items).render(inner, buffer);
 }
 
+fn render_outline(buffer: &mut Buffer, area: Rect, app: &App) {
+    let palette = Palette::mocha().styles();
+    let is_focused = app.focus() == FocusTarget::Outline;
+
+    let border_style = if is_focused {
+        palette.explorer_border_focused
+    } else {
+        palette.explorer_border
+    };
+
+    let title_style = if is_focused {
+        palette.explorer_border_focused.add_modifier(Modifier::BOLD)
+    } else {
+        palette.explorer_border
+    };
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(border_style)
+        .title(Span::styled(" Outline ", title_style));
+    let inner = block.inner(area);
+    block.render(area, buffer);
+
+    let outline = app.outline();
+    let items = outline
+        .symbols()
+        .iter()
+        .enumerate()
+        .skip(outline.scroll_offset())
+        .take(inner.height as usize)
+        .map(|(index, symbol)| {
+            let indent = "  ".repeat(symbol.depth);
+            let style = if index == outline.selected() {
+                palette.explorer_file_selected
+            } else {
+                palette.editor
+            };
+            ListItem::new(Line::from(vec![
+                Span::styled(indent, style),
+                Span::styled(format!("{} ", symbol.label()), palette.explorer_dir),
+                Span::styled(symbol.name.clone(), style),
+            ]))
+        })
+        .collect::<Vec<_>>();
+    List::new(items).render(inner, buffer);
+}
+
+fn render_hierarchy(buffer: &mut Buffer, area: Rect, app: &App, tree: &HierarchyTree) {
+    let palette = Palette::mocha().styles();
+    let is_focused = app.focus() == FocusTarget::Hierarchy;
+
+    let border_style = if is_focused {
+        palette.explorer_border_focused
+    } else {
+        palette.explorer_border
+    };
+
+    let title_style = if is_focused {
+        palette.explorer_border_focused.add_modifier(Modifier::BOLD)
+    } else {
+        palette.explorer_border
+    };
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(border_style)
+        .title(Span::styled(format!(" {} ", tree.direction().title()), title_style));
+    let inner = block.inner(area);
+    block.render(area, buffer);
+
+    let items = tree
+        .rows()
+        .iter()
+        .enumerate()
+        .skip(tree.scroll_offset())
+        .take(inner.height as usize)
+        .map(|(index, row)| {
+            let indent = "  ".repeat(row.depth);
+            let marker = match (&row.node.children, row.node.expanded) {
+                (Some(children), true) if children.is_empty() => "  ",
+                (Some(_), true) => "\u{25be} ",
+                (None, true) => "\u{2026} ",
+                (_, false) => "\u{25b8} ",
+            };
+            let style = if index == tree.selected() {
+                palette.explorer_file_selected
+            } else {
+                palette.editor
+            };
+            let mut spans = vec![
+                Span::styled(format!("{indent}{marker}"), palette.explorer_dir),
+                Span::styled(row.node.item.name.clone(), style),
+            ];
+            if !row.node.item.detail.is_empty() {
+                spans.push(Span::styled(
+                    format!(" {}", row.node.item.detail),
+                    palette.breadcrumb,
+                ));
+            }
+            ListItem::new(Line::from(spans))
+        })
+        .collect::<Vec<_>>();
+    List::new(items).render(inner, buffer);
+}
+
 fn render_buffer_view(buffer: &mut Buffer, area: Rect, app: &App, buffer_id: u64, pane_id: u64) {
     let palette = Palette::mocha().styles();
     let Some(buffer_state) = app.buffer_by_id(buffer_id) else {
@@ -149,6 +276,11 @@ fn render_buffer_view(buffer: &mut Buffer, area: Rect, app: &App, buffer_id: u64
         return;
     };
 
+    if let Some(hex) = buffer_state.hex.as_ref() {
+        render_hex_view(buffer, area, app, hex);
+        return;
+    }
+
     let folds = app.fold_ranges_for_buffer(buffer_id);
 
     let gutter_width =
@@ -164,6 +296,11 @@ fn render_buffer_view(buffer: &mut Buffer, area: Rect, app: &App, buffer_id: u64
         .path()
         .map(|path| app.lsp.diagnostics_for_path(path))
         .unwrap_or(&[]);
+    let lightbulb_line = buffer_state
+        .document
+        .path()
+        .and_then(|path| app.lsp.code_action_hint_line(path))
+        .filter(|line| *line == pane.cursor().line);
 
     for row in 0..area.height as usize {
         let line_index = pane.viewport().top_line() + row;
@@ -192,7 +329,14 @@ fn render_buffer_view(buffer: &mut Buffer, area: Rect, app: &App, buffer_id: u64
         } else {
             palette.tilde
         };
-        let line = Line::from(Span::styled(line_number, style));
+        let line = if lightbulb_line == Some(line_index) {
+            Line::from(vec![
+                Span::styled(line_number.trim_end().to_owned(), style),
+                Span::styled("\u{25cf}", palette.diagnostic_warning),
+            ])
+        } else {
+            Line::from(Span::styled(line_number, style))
+        };
         line.render(
             Rect {
                 x: area.x,
@@ -233,6 +377,70 @@ fn render_buffer_view(buffer: &mut Buffer, area: Rect, app: &App, buffer_id: u64
         .render(text_area, buffer);
 }
 
+const HEX_OFFSET_WIDTH: u16 = 10;
+
+fn render_hex_view(buffer: &mut Buffer, area: Rect, app: &App, hex: &HexDocument) {
+    let palette = Palette::mocha().styles();
+    let theme = app.active_theme();
+    let editor_style = Style::default()
+        .fg(Color::Rgb(
+            theme.foreground.r,
+            theme.foreground.g,
+            theme.foreground.b,
+        ))
+        .bg(Color::Rgb(
+            theme.background.r,
+            theme.background.g,
+            theme.background.b,
+        ));
+    let cursor_row = hex.cursor() / HEX_BYTES_PER_ROW;
+
+    let mut lines = Vec::with_capacity(area.height as usize);
+    for row in hex.top_row()..hex.top_row() + area.height as usize {
+        if row >= hex.row_count() || hex.is_empty() {
+            lines.push(Line::from(Span::styled("~", palette.tilde)));
+            continue;
+        }
+        let start = row * HEX_BYTES_PER_ROW;
+        let offset_style = if row == cursor_row {
+            palette.gutter_current
+        } else {
+            palette.gutter
+        };
+        let mut spans = vec![Span::styled(format!("{start:08x}  "), offset_style)];
+        let mut ascii = Vec::with_capacity(HEX_BYTES_PER_ROW + 2);
+        ascii.push(Span::styled(" |", palette.gutter));
+
+        for (column, offset) in (start..start + HEX_BYTES_PER_ROW).enumerate() {
+            if column == HEX_BYTES_PER_ROW / 2 {
+                spans.push(Span::raw(" "));
+            }
+            let Some(&byte) = hex.bytes().get(offset) else {
+                spans.push(Span::raw("   "));
+                continue;
+            };
+            let style = if offset == hex.cursor() {
+                palette.selection
+            } else {
+                editor_style
+            };
+            spans.push(Span::styled(format!("{byte:02x}"), style));
+            spans.push(Span::raw(" "));
+            let shown = if byte.is_ascii_graphic() || byte == b' ' {
+                byte as char
+            } else {
+                '.'
+            };
+            ascii.push(Span::styled(shown.to_string(), style));
+        }
+        ascii.push(Span::styled("|", palette.gutter));
+        spans.extend(ascii);
+        lines.push(Line::from(spans));
+    }
+
+    Paragraph::new(lines).style(editor_style).render(area, buffer);
+}
+
 fn render_text_line(
     app: &App,
     buffer_id: u64,
@@ -276,8 +484,16 @@ fn render_text_line(
     let mut display_column = 0usize;
     let mut char_column = 0usize;
     let mut byte_offset = 0usize;
+    let visible = pane.viewport().left_column()..pane.viewport().left_column() + width;
+    let hints = app.inlay_hints_on_line(buffer_id, line_index);
+    let occurrences = app.document_highlights().on_line(buffer_id, line_index);
+    let mut hints = hints.into_iter().peekable();
 
     for grapheme in raw_line.graphemes(true) {
+        while let Some(hint) = hints.next_if(|hint| hint.column <= char_column) {
+            let style = palette.inlay_hint;
+            push_virtual_text(&mut spans, &hint.label, style, &mut display_column, &visible);
+        }
         let expanded = if grapheme == "\t" { "    " } else { grapheme };
         let grapheme_width = expanded.width().max(1);
         let next_display = display_column + grapheme_width;
@@ -301,9 +517,20 @@ fn render_text_line(
             palette.active_search_match
         } else if pane.search().is_match_at(line_index, char_column) {
             palette.search_match
+        } else if grapheme == "\u{FFFD}" {
+            palette.error.add_modifier(Modifier::REVERSED)
         } else {
             let syntax_style = find_span_style(&syntax_spans, byte_offset, theme);
-            syntax_style.unwrap_or(plain_style)
+            let style = syntax_style.unwrap_or(plain_style);
+            match occurrences
+                .iter()
+                .find(|occurrence| occurrence.contains(line_index, char_column))
+                .map(|occurrence| occurrence.kind)
+            {
+                Some(HighlightKind::Write) => style.patch(palette.symbol_write),
+                Some(HighlightKind::Read | HighlightKind::Text) => style.patch(palette.symbol_read),
+                None => style,
+            }
         };
 
         if line_severity == Some(DiagnosticSeverityView::Error) {
@@ -315,6 +542,10 @@ fn render_text_line(
         char_column += grapheme_chars;
         byte_offset += grapheme_bytes;
     }
+    for hint in hints {
+        let style = palette.inlay_hint;
+        push_virtual_text(&mut spans, &hint.label, style, &mut display_column, &visible);
+    }
 
     if pane
         .selection()
@@ -343,7 +574,29 @@ fn render_text_line(
     Line::from(spans)
 }
 
-fn find_span_style(
+/// Appends text that is drawn but not part of the document, clipped to the
+/// visible `columns`.
+fn push_virtual_text(
+    spans: &mut Vec<Span<'static>>,
+    text: &str,
+    style: Style,
+    display_column: &mut usize,
+    columns: &std::ops::Range<usize>,
+) {
+    let mut visible = String::new();
+    for character in text.chars() {
+        let width = character.width().unwrap_or(0).max(1);
+        if *display_column >= columns.start && *display_column + width <= columns.end {
+            visible.push(character);
+        }
+        *display_column += width;
+    }
+    if !visible.is_empty() {
+        spans.push(Span::styled(visible, style));
+    }
+}
+
+pub(super) fn find_span_style(
     spans: &[HighlightSpan],
     byte_offset: usize,
     theme: &crate::config::Theme,
@@ -580,6 +833,11 @@ fn render_picker_overlay(frame: &mut Frame<'_>, app: &App) {
         .title(match picker.kind() {
             crate::ui::PickerKind::Files => " Files ",
             crate::ui::PickerKind::Buffers => " Buffers ",
+            crate::ui::PickerKind::CodeActions => " Code Actions ",
+            crate::ui::PickerKind::Jumps => " Jump List ",
+            crate::ui::PickerKind::Snippets => " Snippets ",
+            crate::ui::PickerKind::LspStatus => " Language Servers ",
+            crate::ui::PickerKind::Locations => " Locations ",
         });
     let inner = block.inner(popup);
     let areas = Layout::vertical([Constraint::Length(1), Constraint::Min(1)]).split(inner);
@@ -622,30 +880,31 @@ fn render_completion_overlay(frame: &mut Frame<'_>, app: &App) {
         return;
     }
     let area = frame.area();
+    let (_, editor_area, _) = workspace_columns(app, area);
     let Some((_, pane_area, pane)) = app
         .layout
-        .leaves_in_area(if app.explorer().visible() {
-            Layout::horizontal([Constraint::Length(30), Constraint::Min(1)]).split(area)[1]
-        } else {
-            area
-        })
+        .leaves_in_area(editor_area)
         .into_iter()
         .find(|(pane_id, _, _)| *pane_id == app.active_pane_id())
     else {
         return;
     };
 
-    let max_items = app.lsp.completion.items.len().min(8);
-    let widest = app
-        .lsp
-        .completion
-        .items
-        .iter()
+    let completion = &app.lsp.completion;
+    let max_items = completion.matches.len().min(8);
+    // Keeps the selected item on the last visible row while scrolling down.
+    let offset = (completion.selected + 1).saturating_sub(max_items);
+    let widest = completion
+        .visible_items()
+        .skip(offset)
         .take(max_items)
-        .map(|item| item.label.chars().count() + item.detail.chars().count().min(28) + 3)
+        .map(|item| {
+            let tag = item.source.label().len();
+            item.label.chars().count() + item.detail.chars().count().min(28) + tag + 12
+        })
         .max()
         .unwrap_or(12) as u16;
-    let width = widest.clamp(20, 48).min(area.width.saturating_sub(2));
+    let width = widest.clamp(20, 56).min(area.width.saturating_sub(2));
     let height = (max_items as u16 + 2).clamp(3, 10);
 
     let gutter = gutter_width(app.active_document().line_count());
@@ -654,8 +913,7 @@ fn render_completion_overlay(frame: &mut Frame<'_>, app: &App) {
         .saturating_add(1)
         .saturating_add(gutter)
         .saturating_add(
-            app.active_document()
-                .display_column(pane.cursor())
+            app.visual_column(pane.buffer_id(), pane.cursor())
                 .saturating_sub(pane.viewport().left_column()) as u16,
         );
     let cursor_y = pane_area.y.saturating_add(1).saturating_add(
@@ -679,19 +937,19 @@ fn render_completion_overlay(frame: &mut Frame<'_>, app: &App) {
     block.render(popup, frame.buffer_mut());
 
     let palette = Palette::mocha().styles();
-    let items = app
-        .lsp
-        .completion
-        .items
-        .iter()
-        .take(max_items)
+    let items = completion
+        .visible_items()
         .enumerate()
+        .skip(offset)
+        .take(max_items)
         .map(|(index, item)| {
-            let style = if index == app.lsp.completion.selected {
+            let style = if index == completion.selected {
                 palette.selection
             } else {
                 palette.editor
             };
+            let kind_style = style.patch(palette.completion_kind);
+            let kind = Span::styled(format!("{:<7}", item.icon()), kind_style);
             let mut text = item.label.clone();
             if !item.detail.is_empty() {
                 text.push_str("  ");
@@ -702,10 +960,78 @@ fn render_completion_overlay(frame: &mut Frame<'_>, app: &App) {
                 };
                 text.push_str(&detail);
             }
-            ListItem::new(Line::from(Span::styled(text, style)))
+            let source = Span::styled(format!("  {}", item.source.label()), kind_style);
+            ListItem::new(Line::from(vec![kind, Span::styled(text, style), source]))
         })
         .collect::<Vec<_>>();
     List::new(items).render(inner, frame.buffer_mut());
+    render_completion_docs(frame, app, popup);
+}
+
+/// Detail and documentation of the selected item, beside the list.
+fn render_completion_docs(frame: &mut Frame<'_>, app: &App, list: Rect) {
+    let Some(item) = app.lsp.completion.selected_item() else {
+        return;
+    };
+    if item.detail.is_empty() && item.documentation.is_empty() {
+        return;
+    }
+    let area = frame.area();
+    let right = area.right().saturating_sub(list.right());
+    let left = list.x.saturating_sub(area.x);
+    let (x, space) = if right >= 24 || right >= left {
+        (list.right(), right)
+    } else {
+        (list.x.saturating_sub(left.min(60)), left)
+    };
+    let width = space.min(60);
+    if width < 12 {
+        return;
+    }
+
+    let lines = completion_doc_lines(app, &item.detail, &item.documentation);
+    let inner_width = usize::from(width.saturating_sub(2)).max(1);
+    let rows = lines
+        .iter()
+        .map(|line| line.width().div_ceil(inner_width).max(1))
+        .sum::<usize>();
+    let height = (rows as u16 + 2).min(16).min(area.bottom().saturating_sub(list.y));
+    let popup = Rect::new(x, list.y, width, height);
+
+    let block = Block::default().borders(Borders::ALL);
+    let inner = block.inner(popup);
+    Clear.render(popup, frame.buffer_mut());
+    block.render(popup, frame.buffer_mut());
+    Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .render(inner, frame.buffer_mut());
+}
+
+/// Highlighting parses the text, so the lines are kept until the selected
+/// item, the language or the theme changes.
+fn completion_doc_lines(app: &App, detail: &str, documentation: &str) -> Vec<Line<'static>> {
+    let language = app.active_buffer().syntax.language_id();
+    let theme = app.active_theme();
+    let mut cache = app.completion_docs.borrow_mut();
+    let fresh = cache.key.as_ref().is_some_and(|(cached_detail, cached_docs, cached, name)| {
+        cached_detail == detail
+            && cached_docs == documentation
+            && *cached == language
+            && *name == theme.name
+    });
+    if !fresh {
+        let mut lines = code_lines(detail, language, theme);
+        if !documentation.is_empty() {
+            if !lines.is_empty() {
+                lines.push(Line::default());
+            }
+            lines.extend(markdown_lines(documentation, language, theme));
+        }
+        let key = (detail.to_owned(), documentation.to_owned(), language, theme.name.clone());
+        cache.key = Some(key);
+        cache.lines = lines;
+    }
+    cache.lines.clone()
 }
 
 fn render_hover_overlay(frame: &mut Frame<'_>, app: &App) {
@@ -752,10 +1078,11 @@ fn render_diagnostics_overlay(frame: &mut Frame<'_>, app: &App) {
                 .iter()
                 .map(|diag| {
                     format!(
-                        "{}:{} {:?} {}",
+                        "{}:{} {:?} [{}] {}",
                         diag.line + 1,
                         diag.column + 1,
                         diag.severity,
+                        diag.source,
                         diag.message
                     )
                 })
@@ -797,25 +1124,32 @@ fn screen_cursor_position(
 
     let pane_area = app
         .layout
-        .leaves_in_area(if app.explorer().visible() {
-            Layout::horizontal([Constraint::Length(30), Constraint::Min(1)]).split(editor_area)[1]
-        } else {
-            editor_area
-        })
+        .leaves_in_area(workspace_columns(app, editor_area).1)
         .into_iter()
         .find(|(pane_id, _, _)| *pane_id == app.active_pane_id());
 
     let Some((_, area, pane)) = pane_area else {
         return (editor_area.x, editor_area.y);
     };
+    if let Some(hex) = app.active_buffer().hex.as_ref() {
+        let column = hex.cursor() % HEX_BYTES_PER_ROW;
+        let x = HEX_OFFSET_WIDTH as usize
+            + column * 3
+            + usize::from(column >= HEX_BYTES_PER_ROW / 2)
+            + usize::from(hex.low_nibble());
+        let row = (hex.cursor() / HEX_BYTES_PER_ROW).saturating_sub(hex.top_row());
+        return (
+            area.x.saturating_add(1).saturating_add(x as u16),
+            area.y.saturating_add(1).saturating_add(row as u16),
+        );
+    }
     let gutter_width = gutter_width(app.active_document().line_count());
     let x = area
         .x
         .saturating_add(1)
         .saturating_add(gutter_width)
         .saturating_add(
-            app.active_document()
-                .display_column(pane.cursor())
+            app.visual_column(pane.buffer_id(), pane.cursor())
                 .saturating_sub(pane.viewport().left_column()) as u16,
         );
     let y = area.y.saturating_add(1).saturating_add(
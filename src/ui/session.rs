@@ -17,6 +17,34 @@ impl App {
         }
     }
 
+    pub(crate) fn ensure_outline_selection_visible(&mut self) {
+        let terminal_size = self
+            .layout
+            .focused_pane()
+            .map(|pane| pane.viewport().terminal_size())
+            .unwrap_or_else(|| Size::new(120, 30));
+        let workspace_height = terminal_size.height.saturating_sub(2);
+
+        if self.outline.visible() {
+            let outline_inner_height = workspace_height.saturating_sub(2) as usize;
+            self.outline.sync_scroll(outline_inner_height);
+        }
+    }
+
+    pub(crate) fn ensure_hierarchy_selection_visible(&mut self) {
+        let terminal_size = self
+            .layout
+            .focused_pane()
+            .map(|pane| pane.viewport().terminal_size())
+            .unwrap_or_else(|| Size::new(120, 30));
+        let workspace_height = terminal_size.height.saturating_sub(2);
+
+        if let Some(tree) = self.hierarchy.as_mut() {
+            let tree_inner_height = workspace_height.saturating_sub(2) as usize;
+            tree.sync_scroll(tree_inner_height);
+        }
+    }
+
     pub(crate) fn ensure_picker_selection_visible(&mut self) {
         let terminal_size = self
             .layout
@@ -40,7 +68,7 @@ impl App {
                 (
                     line_count,
                     pane.cursor(),
-                    self.active_document().display_column(pane.cursor()),
+                    self.visual_column(pane.buffer_id(), pane.cursor()),
                     pane.viewport().text_height(),
                     pane.viewport().text_width(line_count),
                 )
@@ -67,7 +95,7 @@ impl App {
                 (
                     line_count,
                     pane.cursor(),
-                    self.active_document().display_column(pane.cursor()),
+                    self.visual_column(pane.buffer_id(), pane.cursor()),
                     pane.viewport().text_height(),
                     pane.viewport().text_width(line_count),
                 )
@@ -111,10 +139,12 @@ impl App {
     }
 
     pub fn is_read_only(&self) -> bool {
-        self.active_document()
-            .path()
-            .and_then(|path| std::fs::metadata(path).ok())
-            .map(|metadata| metadata.permissions().readonly())
-            .unwrap_or(false)
+        self.active_buffer().scratch_name.is_some()
+            || self
+                .active_document()
+                .path()
+                .and_then(|path| std::fs::metadata(path).ok())
+                .map(|metadata| metadata.permissions().readonly())
+                .unwrap_or(false)
     }
 }
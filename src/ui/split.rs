@@ -10,7 +10,7 @@ pub enum SplitDirection {
 
 #[derive(Debug)]
 pub enum WindowNode {
-    Leaf(Pane),
+    Leaf(Box<Pane>),
     Split {
         direction: SplitDirection,
         ratio: u16,
@@ -89,12 +89,12 @@ impl WindowNode {
     ) -> bool {
         match self {
             WindowNode::Leaf(pane) if pane.id() == target_pane_id => {
-                let current = std::mem::replace(pane, Pane::new(u64::MAX, u64::MAX));
+                let current = std::mem::replace(pane, Box::new(Pane::new(u64::MAX, u64::MAX)));
                 *self = WindowNode::Split {
                     direction,
                     ratio: 50,
                     first: Box::new(WindowNode::Leaf(current)),
-                    second: Box::new(WindowNode::Leaf(new_pane)),
+                    second: Box::new(WindowNode::Leaf(Box::new(new_pane))),
                 };
                 true
             }
@@ -2,10 +2,10 @@ use std::cell::RefCell;
 use std::path::Path;
 
 use crate::app::{App, FocusTarget};
-use crate::core::{Document, History};
-use crate::syntax::{language_for_path, SyntaxLayer};
+use crate::core::{Cursor, Document, History, Selection};
+use crate::syntax::{language_for_path, LanguageId, SyntaxLayer};
 use crate::ui::{PickerItem, PickerKind, PickerState, SplitDirection};
-use crate::util::DetectedEncoding;
+use crate::util::{DetectedEncoding, LineEnding};
 
 impl App {
     pub(crate) fn open_file_picker(&mut self) {
@@ -52,6 +52,55 @@ impl App {
                     picker.set_buffer_items(items);
                 }
             }
+            PickerKind::CodeActions => {
+                let query = self
+                    .picker
+                    .as_ref()
+                    .map(|picker| picker.query())
+                    .unwrap_or_default();
+                let items = self.code_action_picker_items(query);
+                if let Some(picker) = self.picker.as_mut() {
+                    picker.set_buffer_items(items);
+                }
+            }
+            PickerKind::Jumps => {
+                let query = self
+                    .picker
+                    .as_ref()
+                    .map(|picker| picker.query())
+                    .unwrap_or_default();
+                let items = self.jump_picker_items(query);
+                if let Some(picker) = self.picker.as_mut() {
+                    picker.set_buffer_items(items);
+                }
+            }
+            PickerKind::Snippets => {
+                let query = self
+                    .picker
+                    .as_ref()
+                    .map(|picker| picker.query())
+                    .unwrap_or_default();
+                let items = self.snippet_picker_items(query);
+                if let Some(picker) = self.picker.as_mut() {
+                    picker.set_buffer_items(items);
+                }
+            }
+            PickerKind::LspStatus => {
+                let query = self
+                    .picker
+                    .as_ref()
+                    .map(|picker| picker.query())
+                    .unwrap_or_default();
+                let items = self.lsp_status_items(query);
+                if let Some(picker) = self.picker.as_mut() {
+                    picker.set_buffer_items(items);
+                }
+            }
+            PickerKind::Locations => {
+                if let Some(picker) = self.picker.as_mut() {
+                    picker.filter_source();
+                }
+            }
         }
     }
 
@@ -63,7 +112,33 @@ impl App {
         let Some(item) = selected else {
             return Ok(());
         };
+        if self.picker.as_ref().map(PickerState::kind) == Some(PickerKind::CodeActions) {
+            self.picker = None;
+            if let Some(index) = item.entry {
+                self.run_code_action(index);
+            }
+            return Ok(());
+        }
+        if self.picker.as_ref().map(PickerState::kind) == Some(PickerKind::Jumps) {
+            self.picker = None;
+            if let Some(index) = item.entry {
+                self.go_to_jump(index);
+            }
+            return Ok(());
+        }
+        if self.picker.as_ref().map(PickerState::kind) == Some(PickerKind::LspStatus) {
+            self.picker = None;
+            return Ok(());
+        }
+        if self.picker.as_ref().map(PickerState::kind) == Some(PickerKind::Snippets) {
+            self.picker = None;
+            if let Some(index) = item.entry {
+                self.insert_user_snippet(index);
+            }
+            return Ok(());
+        }
 
+        self.record_jump();
         if let Some(path) = item.path {
             self.open_path_in_active_pane(&path)?;
             if let (Some(line), Some(column)) = (item.line, item.column) {
@@ -124,6 +199,7 @@ impl App {
                 History::default(),
                 String::new(),
                 DetectedEncoding::default(),
+                LineEnding::default(),
             );
             self.switch_to_buffer(buffer_id);
         }
@@ -135,6 +211,7 @@ impl App {
         history: History,
         saved_snapshot: String,
         encoding: DetectedEncoding,
+        line_ending: LineEnding,
     ) -> u64 {
         let buffer_id = self.next_buffer_id;
         self.next_buffer_id += 1;
@@ -146,13 +223,59 @@ impl App {
             history,
             saved_snapshot,
             encoding,
+            saved_encoding: encoding,
+            line_ending,
+            saved_line_ending: line_ending,
+            hex: None,
+            large_file: false,
+            scratch_name: None,
             syntax,
             line_highlight_cache: RefCell::new(crate::app::LineHighlightCache::default()),
             fold_cache: RefCell::new(crate::app::FoldCache::default()),
+            outline_cache: RefCell::new(crate::app::OutlineCache::default()),
+            locals_cache: RefCell::new(crate::app::LocalsCache::default()),
         });
         buffer_id
     }
 
+    /// Shows `text` in a read-only buffer, replacing the previous scratch
+    /// buffer with the same name.
+    pub(crate) fn push_scratch_buffer(
+        &mut self,
+        name: &str,
+        text: &str,
+        language_id: Option<LanguageId>,
+    ) {
+        let existing = self
+            .buffers
+            .iter()
+            .find(|buffer| buffer.scratch_name.as_deref() == Some(name))
+            .map(|buffer| buffer.id);
+        let buffer_id = existing.unwrap_or_else(|| {
+            self.push_buffer(
+                Document::new_empty(None),
+                History::default(),
+                String::new(),
+                DetectedEncoding::default(),
+                LineEnding::default(),
+            )
+        });
+        if let Some(buffer) = self.buffer_by_id_mut(buffer_id) {
+            buffer.document = Document::from_text(None, text);
+            buffer.history = History::default();
+            buffer.saved_snapshot = text.to_owned();
+            buffer.scratch_name = Some(name.to_owned());
+            buffer.syntax = SyntaxLayer::new(language_id);
+            buffer.syntax.mark_dirty();
+            buffer.refresh_dirty();
+        }
+        self.switch_to_buffer(buffer_id);
+        if let Some(pane) = self.layout.focused_pane_mut() {
+            pane.set_cursor(Cursor::new(0, 0));
+            pane.set_selection(Selection::caret(Cursor::new(0, 0)));
+        }
+    }
+
     fn switch_buffer_by_offset(&mut self, delta: isize) {
         if self.buffers.is_empty() {
             return;
@@ -181,15 +304,10 @@ impl App {
                         .document
                         .path()
                         .map(|path| path.display().to_string().to_lowercase().contains(&query))
-                        .unwrap_or_else(|| String::from("[no name]").contains(&query))
+                        .unwrap_or_else(|| buffer.display_name().to_lowercase().contains(&query))
             })
             .map(|buffer| PickerItem {
-                title: buffer
-                    .document
-                    .path()
-                    .and_then(|path| path.file_name())
-                    .map(|name| name.to_string_lossy().into_owned())
-                    .unwrap_or_else(|| String::from("[No Name]")),
+                title: buffer.display_name(),
                 subtitle: buffer
                     .document
                     .path()
@@ -199,6 +317,7 @@ impl App {
                 buffer_id: Some(buffer.id),
                 line: None,
                 column: None,
+                entry: None,
             })
             .collect()
     }
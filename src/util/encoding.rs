@@ -1,10 +1,15 @@
-use encoding_rs::{Encoding, UTF_8, UTF_16BE, UTF_16LE, WINDOWS_1252};
+use encoding_rs::{CoderResult, Decoder, Encoding, UTF_8, UTF_16BE, UTF_16LE, WINDOWS_1252};
 use thiserror::Error;
 
+const UTF_8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+const UTF_16LE_BOM: &[u8] = &[0xFF, 0xFE];
+const UTF_16BE_BOM: &[u8] = &[0xFE, 0xFF];
+
 #[derive(Debug, Clone, Copy)]
 pub struct DetectedEncoding {
     encoding: &'static Encoding,
     bom_len: usize,
+    lossy: bool,
 }
 
 impl Default for DetectedEncoding {
@@ -12,11 +17,22 @@ impl Default for DetectedEncoding {
         Self {
             encoding: UTF_8,
             bom_len: 0,
+            lossy: false,
         }
     }
 }
 
 impl DetectedEncoding {
+    /// Resolves a WHATWG encoding label such as `shift_jis`, `gbk` or `iso-8859-2`.
+    pub fn for_label(label: &str) -> Option<Self> {
+        let encoding = Encoding::for_label_no_replacement(label.trim().as_bytes())?;
+        Some(Self {
+            encoding,
+            bom_len: 0,
+            lossy: false,
+        })
+    }
+
     pub fn encoding(self) -> &'static Encoding {
         self.encoding
     }
@@ -25,38 +41,69 @@ impl DetectedEncoding {
         self.bom_len
     }
 
+    pub fn has_bom(self) -> bool {
+        self.bom_len > 0
+    }
+
+    /// Whether undecodable bytes were replaced with U+FFFD while loading.
+    pub fn is_lossy(self) -> bool {
+        self.lossy
+    }
+
+    pub fn supports_bom(self) -> bool {
+        !bom_bytes(self.encoding).is_empty()
+    }
+
+    pub fn with_bom(self, bom: bool) -> Self {
+        let bom_len = if bom { bom_bytes(self.encoding).len() } else { 0 };
+        Self { bom_len, ..self }
+    }
+
+    /// Whether saving with either writes the same encoding and BOM.
+    pub fn saves_like(self, other: Self) -> bool {
+        self.encoding == other.encoding && self.has_bom() == other.has_bom()
+    }
+
     pub fn label(self) -> &'static str {
         self.encoding.name()
     }
+
+    pub fn status_label(self) -> String {
+        let mut label = String::from(self.label());
+        if self.has_bom() {
+            label.push_str(" BOM");
+        }
+        if self.lossy {
+            label.push_str(" [lossy]");
+        }
+        label
+    }
 }
 
 #[derive(Debug, Error)]
 pub enum EncodingError {
-    #[error("file content could not be decoded cleanly")]
-    Decode,
     #[error("file content could not be encoded as {0}")]
     Encode(&'static str),
 }
 
-pub fn detect_encoding(bytes: &[u8]) -> DetectedEncoding {
-    if bytes.starts_with(&[0xEF, 0xBB, 0xBF]) {
-        return DetectedEncoding {
-            encoding: UTF_8,
-            bom_len: 3,
-        };
-    }
-
-    if bytes.starts_with(&[0xFF, 0xFE]) {
-        return DetectedEncoding {
-            encoding: UTF_16LE,
-            bom_len: 2,
-        };
+fn bom_bytes(encoding: &'static Encoding) -> &'static [u8] {
+    if encoding == UTF_8 {
+        UTF_8_BOM
+    } else if encoding == UTF_16LE {
+        UTF_16LE_BOM
+    } else if encoding == UTF_16BE {
+        UTF_16BE_BOM
+    } else {
+        &[]
     }
+}
 
-    if bytes.starts_with(&[0xFE, 0xFF]) {
+pub fn detect_encoding(bytes: &[u8]) -> DetectedEncoding {
+    if let Some((encoding, bom_len)) = Encoding::for_bom(bytes) {
         return DetectedEncoding {
-            encoding: UTF_16BE,
-            bom_len: 2,
+            encoding,
+            bom_len,
+            lossy: false,
         };
     }
 
@@ -67,33 +114,160 @@ pub fn detect_encoding(bytes: &[u8]) -> DetectedEncoding {
     DetectedEncoding {
         encoding: WINDOWS_1252,
         bom_len: 0,
+        lossy: false,
     }
 }
 
-pub fn decode_text(bytes: &[u8]) -> Result<(String, DetectedEncoding), EncodingError> {
-    let detected = detect_encoding(bytes);
-    let (text, _, had_errors) = detected.encoding().decode(&bytes[detected.bom_len()..]);
-    if had_errors {
-        return Err(EncodingError::Decode);
+/// Like [`detect_encoding`] for the first chunk of a file, which may end
+/// partway through a UTF-8 sequence.
+pub fn detect_prefix_encoding(bytes: &[u8]) -> DetectedEncoding {
+    match std::str::from_utf8(bytes) {
+        Err(error) if error.error_len().is_none() => {
+            detect_encoding(&bytes[..error.valid_up_to()])
+        }
+        _ => detect_encoding(bytes),
     }
+}
 
-    Ok((text.into_owned(), detected))
+/// Decodes with the detected encoding. Undecodable bytes become U+FFFD and the
+/// result is flagged as lossy rather than rejected.
+pub fn decode_text(bytes: &[u8]) -> (String, DetectedEncoding) {
+    decode_with_encoding(bytes, detect_encoding(bytes))
+}
+
+/// Decodes with an explicitly chosen encoding, stripping its BOM if present.
+pub fn decode_with_encoding(bytes: &[u8], chosen: DetectedEncoding) -> (String, DetectedEncoding) {
+    let bom = bom_bytes(chosen.encoding());
+    let bom_len = if !bom.is_empty() && bytes.starts_with(bom) {
+        bom.len()
+    } else {
+        0
+    };
+    let (text, had_errors) = chosen
+        .encoding()
+        .decode_without_bom_handling(&bytes[bom_len..]);
+    (
+        text.into_owned(),
+        DetectedEncoding {
+            encoding: chosen.encoding(),
+            bom_len,
+            lossy: had_errors,
+        },
+    )
+}
+
+/// Decodes a file chunk by chunk. Sequences split across chunks carry over,
+/// and undecodable bytes become U+FFFD as in [`decode_with_encoding`].
+pub struct StreamDecoder {
+    decoder: Decoder,
+    detected: DetectedEncoding,
+    bom_left: usize,
+}
+
+impl StreamDecoder {
+    /// Strips the chosen encoding's BOM if `first_chunk` starts with it.
+    pub fn new(chosen: DetectedEncoding, first_chunk: &[u8]) -> Self {
+        let bom = bom_bytes(chosen.encoding());
+        let bom_len = if !bom.is_empty() && first_chunk.starts_with(bom) {
+            bom.len()
+        } else {
+            0
+        };
+        Self {
+            decoder: chosen.encoding().new_decoder_without_bom_handling(),
+            detected: DetectedEncoding {
+                encoding: chosen.encoding(),
+                bom_len,
+                lossy: false,
+            },
+            bom_left: bom_len,
+        }
+    }
+
+    /// Appends the decoded `bytes` to `text`; `last` flushes any partial
+    /// sequence left at the end of the file.
+    pub fn decode(&mut self, bytes: &[u8], last: bool, text: &mut String) {
+        let skip = self.bom_left.min(bytes.len());
+        self.bom_left -= skip;
+        let mut bytes = &bytes[skip..];
+        loop {
+            let needed = self.decoder.max_utf8_buffer_length(bytes.len());
+            text.reserve(needed.unwrap_or(bytes.len().saturating_mul(3)));
+            let (result, read, had_errors) = self.decoder.decode_to_string(bytes, text, last);
+            self.detected.lossy |= had_errors;
+            bytes = &bytes[read..];
+            if result == CoderResult::InputEmpty {
+                return;
+            }
+        }
+    }
+
+    pub fn finish(self) -> DetectedEncoding {
+        self.detected
+    }
 }
 
 pub fn encode_text(text: &str, detected: DetectedEncoding) -> Result<Vec<u8>, EncodingError> {
+    let mut bytes = Vec::with_capacity(detected.bom_len() + text.len());
+    if detected.has_bom() {
+        bytes.extend_from_slice(bom_bytes(detected.encoding()));
+    }
+
+    // encoding_rs only decodes UTF-16; its encoder falls back to UTF-8 output.
+    if detected.encoding() == UTF_16LE {
+        bytes.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+        return Ok(bytes);
+    }
+    if detected.encoding() == UTF_16BE {
+        bytes.extend(text.encode_utf16().flat_map(u16::to_be_bytes));
+        return Ok(bytes);
+    }
+
     let (encoded, _, had_errors) = detected.encoding().encode(text);
     if had_errors {
         return Err(EncodingError::Encode(detected.label()));
     }
-
-    let mut bytes = Vec::with_capacity(detected.bom_len() + encoded.len());
-    if detected.encoding() == UTF_8 && detected.bom_len() == 3 {
-        bytes.extend_from_slice(&[0xEF, 0xBB, 0xBF]);
-    } else if detected.encoding() == UTF_16LE && detected.bom_len() == 2 {
-        bytes.extend_from_slice(&[0xFF, 0xFE]);
-    } else if detected.encoding() == UTF_16BE && detected.bom_len() == 2 {
-        bytes.extend_from_slice(&[0xFE, 0xFF]);
-    }
     bytes.extend_from_slice(&encoded);
     Ok(bytes)
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::util::{DetectedEncoding, decode_text, decode_with_encoding, encode_text};
+
+    #[test]
+    fn invalid_bytes_decode_lossily() {
+        let (text, detected) = decode_text(&[0xFF, 0xFE, b'a', 0, 0x00, 0xD8, b'b']);
+        assert_eq!(detected.label(), "UTF-16LE");
+        assert!(detected.is_lossy());
+        assert!(text.starts_with('a'));
+        assert!(text.contains('\u{FFFD}'));
+
+        let utf8 = DetectedEncoding::for_label("utf-8")
+            .unwrap_or_else(|| panic!("utf-8 label should resolve"));
+        let (text, detected) = decode_with_encoding(&[b'a', 0xFF, b'b'], utf8);
+        assert!(detected.is_lossy());
+        assert_eq!(text, "a\u{FFFD}b");
+    }
+
+    #[test]
+    fn chosen_encodings_round_trip_with_bom_controls() {
+        let shift_jis = DetectedEncoding::for_label("Shift_JIS")
+            .unwrap_or_else(|| panic!("shift_jis label should resolve"));
+        let bytes = encode_text("日本", shift_jis).unwrap_or_else(|error| panic!("{error}"));
+        let (text, detected) = decode_with_encoding(&bytes, shift_jis);
+        assert_eq!(text, "日本");
+        assert!(!detected.is_lossy());
+
+        let utf16 = DetectedEncoding::for_label("utf-16le")
+            .unwrap_or_else(|| panic!("utf-16le label should resolve"))
+            .with_bom(true);
+        let bytes = encode_text("hi", utf16).unwrap_or_else(|error| panic!("{error}"));
+        assert_eq!(bytes, vec![0xFF, 0xFE, b'h', 0, b'i', 0]);
+        let (text, detected) = decode_text(&bytes);
+        assert_eq!(text, "hi");
+        assert!(detected.has_bom());
+        assert!(!DetectedEncoding::for_label("gbk")
+            .is_some_and(|encoding| encoding.with_bom(true).has_bom()));
+    }
+}
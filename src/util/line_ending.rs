@@ -0,0 +1,97 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    Crlf,
+    Cr,
+}
+
+impl LineEnding {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::Crlf => "\r\n",
+            LineEnding::Cr => "\r",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "LF",
+            LineEnding::Crlf => "CRLF",
+            LineEnding::Cr => "CR",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        match label.trim().to_ascii_lowercase().as_str() {
+            "lf" | "unix" => Some(LineEnding::Lf),
+            "crlf" | "dos" | "windows" => Some(LineEnding::Crlf),
+            "cr" | "mac" => Some(LineEnding::Cr),
+            _ => None,
+        }
+    }
+}
+
+pub fn detect_line_ending(text: &str) -> LineEnding {
+    let bytes = text.as_bytes();
+    let (mut lf, mut crlf, mut cr) = (0usize, 0usize, 0usize);
+    let mut index = 0usize;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'\r' if bytes.get(index + 1) == Some(&b'\n') => {
+                crlf += 1;
+                index += 1;
+            }
+            b'\r' => cr += 1,
+            b'\n' => lf += 1,
+            _ => {}
+        }
+        index += 1;
+    }
+
+    if crlf > lf && crlf >= cr {
+        LineEnding::Crlf
+    } else if cr > lf && cr > crlf {
+        LineEnding::Cr
+    } else {
+        LineEnding::Lf
+    }
+}
+
+pub fn normalize_line_endings(text: &str) -> String {
+    if !text.contains('\r') {
+        return text.to_owned();
+    }
+    text.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+pub fn apply_line_ending(text: &str, line_ending: LineEnding) -> String {
+    match line_ending {
+        LineEnding::Lf => text.to_owned(),
+        _ => text.replace('\n', line_ending.as_str()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::{LineEnding, apply_line_ending, detect_line_ending, normalize_line_endings};
+
+    #[test]
+    fn detects_dominant_line_ending() {
+        assert_eq!(detect_line_ending("a\nb\nc"), LineEnding::Lf);
+        assert_eq!(detect_line_ending("a\r\nb\r\nc\n"), LineEnding::Crlf);
+        assert_eq!(detect_line_ending("a\rb\rc"), LineEnding::Cr);
+        assert_eq!(detect_line_ending("no newline"), LineEnding::Lf);
+    }
+
+    #[test]
+    fn normalizes_and_restores_crlf() {
+        let normalized = normalize_line_endings("a\r\nb\rc\n");
+        assert_eq!(normalized, "a\nb\nc\n");
+        assert_eq!(
+            apply_line_ending("a\nb\n", LineEnding::Crlf),
+            String::from("a\r\nb\r\n")
+        );
+    }
+}
@@ -1,8 +1,13 @@
 mod clipboard;
 mod encoding;
+mod line_ending;
 mod scroll;
 
 #[allow(unused_imports)]
 pub use clipboard::{Clipboard, ClipboardError};
-pub use encoding::{DetectedEncoding, EncodingError, decode_text, encode_text};
+pub use encoding::{
+    DetectedEncoding, EncodingError, StreamDecoder, decode_text, decode_with_encoding,
+    detect_prefix_encoding, encode_text,
+};
+pub use line_ending::{LineEnding, apply_line_ending, detect_line_ending, normalize_line_endings};
 pub use scroll::compute_scroll_offset;
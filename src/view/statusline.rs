@@ -1,36 +1,47 @@
 use crate::app::App;
 
 pub fn build_statusline(app: &App) -> String {
-    let file_name = app
-        .active_document()
-        .path()
-        .and_then(|path| path.file_name())
-        .map(|name| name.to_string_lossy().into_owned())
-        .unwrap_or_else(|| String::from("[No Name]"));
+    let file_name = app.active_buffer().display_name();
     let dirty = if app.active_document().is_dirty() {
         " [+]"
     } else {
         ""
     };
     let read_only = if app.is_read_only() { " [RO]" } else { "" };
-    let line = app.active_pane().cursor().line + 1;
-    let column = app.display_column() + 1;
-    let total_lines = app.active_document().line_count();
+    let large = if app.active_buffer().large_file {
+        " [LARGE]"
+    } else {
+        ""
+    };
+    let position = match app.active_buffer().hex.as_ref() {
+        Some(hex) => format!("HEX 0x{:08x}  {} bytes", hex.cursor(), hex.len()),
+        None => format!(
+            "Ln {}, Col {}  {} lines",
+            app.active_pane().cursor().line + 1,
+            app.display_column() + 1,
+            app.active_document().line_count()
+        ),
+    };
     let pane_count = app.layout.pane_ids().len();
-    let encoding = app.active_buffer().encoding.label();
+    let encoding = app.active_buffer().encoding.status_label();
+    let line_ending = app.active_buffer().line_ending.label();
     let theme_name = app.active_theme_name();
     let diagnostics = app
         .lsp
         .diagnostics_counts_for_path(app.active_document().path());
     let progress = app.lsp.progress.label();
-    let progress_suffix = if progress.is_empty() {
+    let mut progress_suffix = if progress.is_empty() {
         String::new()
     } else {
         format!("  LSP {progress}")
     };
+    let in_flight = app.lsp.in_flight_requests();
+    if in_flight > 0 {
+        progress_suffix.push_str(&format!("  {} {in_flight} req", crate::lsp::spinner_frame()));
+    }
 
     format!(
-        "{file_name}{dirty}{read_only}  Ln {line}, Col {column}  {total_lines} lines  {encoding}  {theme_name}  {pane_count} pane(s)  E:{} W:{} I:{} H:{}{progress_suffix}",
+        "{file_name}{dirty}{read_only}{large}  {position}  {encoding} {line_ending}  {theme_name}  {pane_count} pane(s)  E:{} W:{} I:{} H:{}{progress_suffix}",
         diagnostics.errors,
         diagnostics.warnings,
         diagnostics.information,